@@ -224,6 +224,68 @@ fn function_call_lua_concat(c: &mut Criterion) {
     });
 }
 
+fn thread_echo(lua: &Lua) -> LuaThread {
+    let echo = lua
+        .load("function(...) local args = {...} while true do args = {coroutine.yield(unpack(args))} end end")
+        .eval::<LuaFunction>()
+        .unwrap();
+    let thread = lua.create_thread(echo).unwrap();
+    thread.resume::<()>(()).unwrap();
+    thread
+}
+
+fn thread_resume_multivalue(c: &mut Criterion) {
+    let lua = Lua::new();
+    lua.globals()
+        .set(
+            "unpack",
+            lua.load("table.unpack or unpack").eval::<LuaFunction>().unwrap(),
+        )
+        .unwrap();
+
+    let thread = thread_echo(&lua);
+    let values = (1..=10).map(LuaValue::Integer).collect::<LuaMultiValue>();
+
+    c.bench_function("thread [resume MultiValue]", |b| {
+        b.iter_batched(
+            || {
+                collect_gc_twice(&lua);
+                values.clone()
+            },
+            |values| {
+                assert_eq!(thread.resume::<LuaMultiValue>(values).unwrap().len(), 10);
+            },
+            BatchSize::SmallInput,
+        );
+    });
+}
+
+fn thread_resume_raw(c: &mut Criterion) {
+    let lua = Lua::new();
+    lua.globals()
+        .set(
+            "unpack",
+            lua.load("table.unpack or unpack").eval::<LuaFunction>().unwrap(),
+        )
+        .unwrap();
+
+    let thread = thread_echo(&lua);
+    let values = (1..=10).map(LuaValue::Integer).collect::<LuaMultiValue>();
+
+    c.bench_function("thread [resume raw]", |b| {
+        b.iter_batched(
+            || {
+                collect_gc_twice(&lua);
+                values.clone()
+            },
+            |values| {
+                assert_eq!(thread.resume_raw(values).unwrap().len(), 10);
+            },
+            BatchSize::SmallInput,
+        );
+    });
+}
+
 fn registry_value_create(c: &mut Criterion) {
     let lua = Lua::new();
     lua.gc_stop();
@@ -348,6 +410,9 @@ criterion_group! {
         function_call_concat,
         function_call_lua_concat,
 
+        thread_resume_multivalue,
+        thread_resume_raw,
+
         registry_value_create,
         registry_value_get,
 
@@ -21,9 +21,24 @@ pub fn probe_lua() {
     #[cfg(feature = "luau")]
     let artifacts = luau0_src::Build::new()
         .enable_codegen(cfg!(feature = "luau-codegen"))
+        // Check Luau internal assertions in release builds too (they are enabled without `NDEBUG`)
+        .enable_assert(cfg!(feature = "luau-assert"))
         .set_max_cstack_size(1000000)
         .set_vector_size(if cfg!(feature = "luau-vector4") { 4 } else { 3 })
         .build();
 
     artifacts.print_cargo_metadata();
+
+    // Shims to access Luau internals (C++ API) using C linkage
+    #[cfg(feature = "luau")]
+    {
+        let mut shim = cc::Build::new();
+        shim.cpp(true)
+            .std("c++17")
+            .include(artifacts.common_include_dir())
+            .file("build/luau_fflags.cpp");
+        #[cfg(feature = "luau-assert")]
+        shim.define("LUAU_ENABLE_ASSERT", None).file("build/luau_assert.cpp");
+        shim.compile("mluau_shim");
+    }
 }
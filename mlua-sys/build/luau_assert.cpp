@@ -0,0 +1,16 @@
+// Allows to route Luau internal assertions (`LUAU_ASSERT`) to a custom handler.
+//
+// Compiled against `Luau/Common.h` from luau0-src, so the handler is stored in the same slot that
+// Luau reads when an assertion fails.
+
+#include "Luau/Common.h"
+
+extern "C" void luau_setasserthandler(Luau::AssertHandler handler)
+{
+    Luau::assertHandler() = handler;
+}
+
+extern "C" Luau::AssertHandler luau_getasserthandler()
+{
+    return Luau::assertHandler();
+}
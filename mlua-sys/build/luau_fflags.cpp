@@ -0,0 +1,50 @@
+// Gives access to Luau feature flags (`Luau::FValue<T>::list`) using C linkage.
+//
+// Compiled against `Luau/Common.h` from luau0-src, so the lists are the same ones that Luau
+// registers its flags in.
+
+#include "Luau/Common.h"
+
+#include <string.h>
+
+typedef void (*luau_FlagVisitor)(void* ud, const char* name, int value);
+
+extern "C" void luau_visitfflags(luau_FlagVisitor visitor, void* ud)
+{
+    for (Luau::FValue<bool>* flag = Luau::FValue<bool>::list; flag; flag = flag->next)
+        visitor(ud, flag->name, flag->value);
+}
+
+extern "C" void luau_visitfintflags(luau_FlagVisitor visitor, void* ud)
+{
+    for (Luau::FValue<int>* flag = Luau::FValue<int>::list; flag; flag = flag->next)
+        visitor(ud, flag->name, flag->value);
+}
+
+static Luau::FValue<int>* findintflag(const char* name)
+{
+    for (Luau::FValue<int>* flag = Luau::FValue<int>::list; flag; flag = flag->next)
+    {
+        if (strcmp(flag->name, name) == 0)
+            return flag;
+    }
+    return nullptr;
+}
+
+extern "C" int luau_getfintflag(const char* name, int* value)
+{
+    Luau::FValue<int>* flag = findintflag(name);
+    if (!flag)
+        return 0;
+    *value = flag->value;
+    return 1;
+}
+
+extern "C" int luau_setfintflag(const char* name, int value)
+{
+    Luau::FValue<int>* flag = findintflag(name);
+    if (!flag)
+        return 0;
+    flag->value = value;
+    return 1;
+}
@@ -550,3 +550,31 @@ unsafe extern "C" {
     pub fn luau_setfflag(name: *const c_char, value: c_int) -> c_int;
     pub fn lua_getmetatablepointer(L: *mut lua_State, idx: c_int) -> *const c_void;
 }
+
+/// Handler of Luau internal assertions (`Luau::AssertHandler`).
+///
+/// Returns non-zero to trigger a debug break.
+pub type luau_AssertHandler = unsafe extern "C-unwind" fn(
+    expression: *const c_char,
+    file: *const c_char,
+    line: c_int,
+    function: *const c_char,
+) -> c_int;
+
+// Functions from assertion handler shim
+#[cfg(feature = "luau-assert")]
+unsafe extern "C-unwind" {
+    pub fn luau_setasserthandler(handler: Option<luau_AssertHandler>);
+    pub fn luau_getasserthandler() -> Option<luau_AssertHandler>;
+}
+
+/// Callback to visit Luau fast flags, receives the flag name and its current value.
+pub type luau_FlagVisitor = unsafe extern "C" fn(ud: *mut c_void, name: *const c_char, value: c_int);
+
+// Functions from feature flags shim
+unsafe extern "C" {
+    pub fn luau_visitfflags(visitor: luau_FlagVisitor, ud: *mut c_void);
+    pub fn luau_visitfintflags(visitor: luau_FlagVisitor, ud: *mut c_void);
+    pub fn luau_getfintflag(name: *const c_char, value: *mut c_int) -> c_int;
+    pub fn luau_setfintflag(name: *const c_char, value: c_int) -> c_int;
+}
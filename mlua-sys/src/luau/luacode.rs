@@ -48,6 +48,11 @@ pub struct lua_CompileConstant {
     _marker: PhantomData<(*mut u8, PhantomPinned)>,
 }
 
+/// Oldest bytecode version supported by the Luau VM
+pub const LBC_VERSION_MIN: u8 = 3;
+/// Newest bytecode version supported by the Luau VM
+pub const LBC_VERSION_MAX: u8 = 6;
+
 /// Type table tags
 #[doc(hidden)]
 #[repr(i32)]
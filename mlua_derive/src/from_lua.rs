@@ -25,6 +25,11 @@ pub fn from_lua(input: TokenStream) -> TokenStream {
             }),
           }
         }
+
+        #[inline]
+        fn userdata_type_id() -> ::std::option::Option<::std::any::TypeId> {
+          ::std::option::Option::Some(::std::any::TypeId::of::<Self>())
+        }
       }
     }
     .into()
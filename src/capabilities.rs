@@ -0,0 +1,123 @@
+//! Capability-based global environment.
+
+use std::string::String as StdString;
+
+use crate::error::{Error, Result};
+use crate::state::Lua;
+use crate::table::Table;
+use crate::traits::{FromLuaMulti, IntoLua, IntoLuaMulti};
+use crate::types::MaybeSend;
+use crate::value::Value;
+
+type Capability = Box<dyn FnOnce(&Lua, &Table) -> Result<Value>>;
+
+/// A set of named values granted to scripts as their only globals.
+///
+/// See [`Lua::set_capabilities`] for details.
+///
+/// # Examples
+///
+/// ```
+/// # use mlua::{CapabilityTable, Lua, Result};
+/// # fn main() -> Result<()> {
+/// let lua = Lua::new();
+/// let caps = CapabilityTable::new()
+///     .grant_global("string")
+///     .grant("VERSION", 3)
+///     .grant_function("log", |_, msg: String| {
+///         println!("{msg}");
+///         Ok(())
+///     });
+/// lua.set_capabilities(caps)?;
+///
+/// lua.load("log(string.format('v%d', VERSION))").exec()?;
+/// assert!(lua.load("os.exit()").exec().is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct CapabilityTable {
+    caps: Vec<(StdString, Capability)>,
+}
+
+impl CapabilityTable {
+    /// Creates an empty capability table, which grants nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Grants a value (eg. a function or table) under the given name.
+    #[must_use]
+    pub fn grant(mut self, name: impl Into<StdString>, value: impl IntoLua + 'static) -> Self {
+        let cap: Capability = Box::new(move |lua, _| value.into_lua(lua));
+        self.caps.push((name.into(), cap));
+        self
+    }
+
+    /// Grants a Rust function under the given name.
+    ///
+    /// The function is created when the capabilities are installed, see [`Lua::create_function`].
+    #[must_use]
+    pub fn grant_function<F, A, R>(mut self, name: impl Into<StdString>, func: F) -> Self
+    where
+        F: Fn(&Lua, A) -> Result<R> + MaybeSend + 'static,
+        A: FromLuaMulti,
+        R: IntoLuaMulti,
+    {
+        let cap: Capability = Box::new(move |lua, _| lua.create_function(func).map(Value::Function));
+        self.caps.push((name.into(), cap));
+        self
+    }
+
+    /// Keeps the current global value (eg. a standard library) with the given name.
+    ///
+    /// Granting a global that does not exist is an error.
+    #[must_use]
+    pub fn grant_global(mut self, name: impl Into<StdString>) -> Self {
+        let name = name.into();
+        let key = name.clone();
+        let cap: Capability = Box::new(move |_, globals| match globals.raw_get(key.as_str())? {
+            Value::Nil => Err(Error::runtime(format!("global '{key}' does not exist"))),
+            value => Ok(value),
+        });
+        self.caps.push((name, cap));
+        self
+    }
+}
+
+/// Replaces the content of the globals table with `caps`.
+pub(crate) fn install(lua: &Lua, caps: CapabilityTable) -> Result<()> {
+    let globals = lua.globals();
+
+    // Resolve all capabilities before touching the globals
+    let caps = (caps.caps.into_iter())
+        .map(|(name, cap)| Ok((name, cap(lua, &globals)?)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let not_granted = lua.create_function(|_, (_, key): (Value, Value)| {
+        let key = key.to_string()?;
+        Err::<(), _>(Error::runtime(format!("capability '{key}' not granted")))
+    })?;
+    let metatable = lua.create_table()?;
+    metatable.raw_set("__index", not_granted)?;
+    metatable.raw_set("__metatable", false)?;
+
+    #[cfg(feature = "luau")]
+    let readonly = globals.is_readonly();
+    #[cfg(feature = "luau")]
+    globals.set_readonly(false);
+    let result = (|| {
+        globals.clear()?;
+        for (name, value) in caps {
+            globals.raw_set(name, value)?;
+        }
+        globals.set_metatable(Some(metatable));
+        // Builtins are resolved without a globals lookup in `safeenv` environments
+        #[cfg(feature = "luau")]
+        globals.set_safeenv(false);
+        Ok(())
+    })();
+    #[cfg(feature = "luau")]
+    globals.set_readonly(readonly);
+    result
+}
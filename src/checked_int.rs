@@ -0,0 +1,155 @@
+use std::fmt;
+
+use crate::error::{Error, Result};
+use crate::function::Function;
+use crate::state::Lua;
+use crate::traits::FromLua;
+use crate::userdata::{MetaMethod, UserData, UserDataMethods};
+use crate::value::Value;
+
+/// A 64-bit integer with overflow-checked arithmetic, exposed to Lua as userdata.
+///
+/// Arithmetic operators (`+`, `-`, `*`, `/`, `//`, `%`, `^` and unary `-`) raise an error on
+/// overflow, division by zero or a negative exponent instead of wrapping around or producing
+/// floats. Operands can be other `CheckedInt` values or integral Lua numbers. Division rounds
+/// towards negative infinity and `%` has the sign of the divisor, following Lua integer semantics.
+///
+/// Values can be compared with `==`, `<` and `<=` (both operands must be `CheckedInt`, as Lua
+/// does not call comparison metamethods for mixed types on all versions) and converted to strings
+/// with `tostring`. Unlike regular Lua numbers, the full `i64` range is represented exactly on all
+/// Lua versions.
+///
+/// Converting a `CheckedInt` from Lua also accepts integral numbers, and it's passed to Lua as
+/// userdata. Use [`CheckedInt::create_constructor`] to let scripts create values.
+///
+/// # Examples
+///
+/// ```
+/// # use mlua::{CheckedInt, Lua, Result};
+/// # fn main() -> Result<()> {
+/// let lua = Lua::new();
+/// lua.globals().set("money", CheckedInt::create_constructor(&lua)?)?;
+///
+/// let total: CheckedInt = lua.load("money(100) * 3 + 5").eval()?;
+/// assert_eq!(i64::from(total), 305);
+///
+/// let overflow = lua.load("money(2^53) * money(2^53)").exec();
+/// assert!(overflow.unwrap_err().to_string().contains("integer overflow"));
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "checked-int")))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CheckedInt(pub i64);
+
+impl CheckedInt {
+    /// Creates a Lua function that converts an integral number (or a `CheckedInt`) to
+    /// `CheckedInt`.
+    pub fn create_constructor(lua: &Lua) -> Result<Function> {
+        lua.create_function(|_, value: CheckedInt| Ok(value))
+    }
+
+    fn checked(self, op: &str, rhs: CheckedInt, f: impl FnOnce(i64, i64) -> Option<i64>) -> Result<Self> {
+        match f(self.0, rhs.0) {
+            Some(n) => Ok(CheckedInt(n)),
+            None if rhs.0 == 0 && matches!(op, "/" | "//" | "%") => {
+                Err(Error::runtime(format!("attempt to perform '{self} {op} 0'")))
+            }
+            None => Err(Error::runtime(format!("integer overflow in '{self} {op} {rhs}'"))),
+        }
+    }
+}
+
+impl fmt::Display for CheckedInt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<i64> for CheckedInt {
+    fn from(n: i64) -> Self {
+        CheckedInt(n)
+    }
+}
+
+impl From<CheckedInt> for i64 {
+    fn from(n: CheckedInt) -> Self {
+        n.0
+    }
+}
+
+// Floor division and modulo with the sign of the divisor, as in Lua
+fn checked_floor_div(a: i64, b: i64) -> Option<i64> {
+    let q = a.checked_div(b)?;
+    Some(if (a % b != 0) && ((a < 0) != (b < 0)) {
+        q - 1
+    } else {
+        q
+    })
+}
+
+fn checked_floor_mod(a: i64, b: i64) -> Option<i64> {
+    // `i64::MIN % -1` overflows in Rust, but the remainder is always zero
+    if b == -1 {
+        return Some(0);
+    }
+    let r = a.checked_rem(b)?;
+    Some(if r != 0 && ((r < 0) != (b < 0)) { r + b } else { r })
+}
+
+impl UserData for CheckedInt {
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_meta_function(MetaMethod::Add, |_, (a, b): (CheckedInt, CheckedInt)| {
+            a.checked("+", b, i64::checked_add)
+        });
+        methods.add_meta_function(MetaMethod::Sub, |_, (a, b): (CheckedInt, CheckedInt)| {
+            a.checked("-", b, i64::checked_sub)
+        });
+        methods.add_meta_function(MetaMethod::Mul, |_, (a, b): (CheckedInt, CheckedInt)| {
+            a.checked("*", b, i64::checked_mul)
+        });
+        methods.add_meta_function(MetaMethod::Div, |_, (a, b): (CheckedInt, CheckedInt)| {
+            a.checked("/", b, checked_floor_div)
+        });
+        #[cfg(any(feature = "lua54", feature = "lua53", feature = "luau"))]
+        methods.add_meta_function(MetaMethod::IDiv, |_, (a, b): (CheckedInt, CheckedInt)| {
+            a.checked("//", b, checked_floor_div)
+        });
+        methods.add_meta_function(MetaMethod::Mod, |_, (a, b): (CheckedInt, CheckedInt)| {
+            a.checked("%", b, checked_floor_mod)
+        });
+        methods.add_meta_function(MetaMethod::Pow, |_, (a, b): (CheckedInt, CheckedInt)| {
+            if b.0 < 0 {
+                return Err(Error::runtime(format!("negative exponent in '{a} ^ {b}'")));
+            }
+            a.checked("^", b, |a, b| a.checked_pow(u32::try_from(b).ok()?))
+        });
+        methods.add_meta_method(MetaMethod::Unm, |_, a, ()| match a.0.checked_neg() {
+            Some(n) => Ok(CheckedInt(n)),
+            None => Err(Error::runtime(format!("integer overflow in '-{a}'"))),
+        });
+        methods.add_meta_function(MetaMethod::Eq, |_, (a, b): (CheckedInt, CheckedInt)| Ok(a == b));
+        methods.add_meta_function(MetaMethod::Lt, |_, (a, b): (CheckedInt, CheckedInt)| Ok(a < b));
+        methods.add_meta_function(MetaMethod::Le, |_, (a, b): (CheckedInt, CheckedInt)| Ok(a <= b));
+        methods.add_meta_method(MetaMethod::ToString, |_, a, ()| Ok(a.to_string()));
+    }
+}
+
+impl FromLua for CheckedInt {
+    fn from_lua(value: Value, _: &Lua) -> Result<Self> {
+        match value {
+            Value::UserData(ud) if ud.is::<CheckedInt>() => Ok(*ud.borrow::<CheckedInt>()?),
+            #[allow(clippy::useless_conversion)]
+            Value::Integer(n) => Ok(CheckedInt(n.into())),
+            // `i64::MAX as f64` is rounded up to 2^63 which does not fit into `i64`
+            Value::Number(n) if n.fract() == 0.0 && n >= i64::MIN as f64 && n < i64::MAX as f64 => {
+                Ok(CheckedInt(n as i64))
+            }
+            value => Err(Error::from_lua_conversion(
+                value.type_name(),
+                "CheckedInt",
+                "expected CheckedInt or integral number".to_string(),
+            )),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use std::borrow::Cow;
-use std::collections::HashMap;
-use std::ffi::CString;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::{CStr, CString};
+use std::fmt::Write as _;
 use std::io::Result as IoResult;
 use std::panic::Location;
 use std::path::{Path, PathBuf};
@@ -8,9 +9,11 @@ use std::string::String as StdString;
 
 use crate::error::{Error, Result};
 use crate::function::Function;
-use crate::state::{Lua, WeakLua};
+use crate::multi::MultiValue;
+use crate::state::{Lua, RawLua, WeakLua};
 use crate::table::Table;
-use crate::traits::{FromLuaMulti, IntoLua, IntoLuaMulti};
+use crate::traits::{FromLuaMulti, FromLuaTuple, IntoLua, IntoLuaMulti};
+use crate::util::short_type_name;
 use crate::value::Value;
 
 /// Trait for types [loadable by Lua] and convertible to a [`Chunk`]
@@ -142,6 +145,9 @@ pub struct Chunk<'a> {
     pub(crate) source: IoResult<Cow<'a, [u8]>>,
     #[cfg(feature = "luau")]
     pub(crate) compiler: Option<Compiler>,
+    #[cfg(feature = "luau")]
+    pub(crate) sandboxed: bool,
+    pub(crate) source_map: Option<SourceMap>,
 }
 
 /// Represents chunk mode (text or binary).
@@ -446,6 +452,196 @@ impl Compiler {
 
         Ok(bytecode)
     }
+
+    /// Compiles the `source` into a [`CompiledChunk`] with the given name.
+    ///
+    /// See [`Chunk::set_name`] for possible name prefixes.
+    pub fn compile_chunk(&self, name: impl Into<String>, source: impl AsRef<[u8]>) -> Result<CompiledChunk> {
+        Ok(CompiledChunk::from_bytecode(name, self.compile(source)?))
+    }
+}
+
+// Returns a "printable" version of the chunk name, as used by Lua in error messages
+#[cfg(feature = "luau")]
+pub(crate) fn printable_chunk_name(name: &str) -> StdString {
+    match name.strip_prefix(['=', '@']) {
+        Some(name) => name.to_string(),
+        None => {
+            let line = name.lines().next().unwrap_or_default();
+            match line.len() < name.len() {
+                true => format!("[string \"{line}...\"]"),
+                false => format!("[string \"{line}\"]"),
+            }
+        }
+    }
+}
+
+/// Luau bytecode that can be loaded into any number of [`Lua`] instances.
+///
+/// Compiling a script once and loading the result in every VM of a worker pool avoids spending
+/// CPU time on compiling the same source again. Cloning is cheap, the bytecode is shared.
+///
+/// Use [`Compiler::compile_chunk`] to create it and [`Lua::load_compiled`] to load it.
+#[cfg(any(feature = "luau", doc))]
+#[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+#[derive(Clone, Debug)]
+pub struct CompiledChunk {
+    name: String,
+    mode: ChunkMode,
+    bytecode: std::sync::Arc<[u8]>,
+}
+
+#[cfg(any(feature = "luau", doc))]
+impl CompiledChunk {
+    /// Wraps bytecode produced by [`Compiler::compile`] (eg. loaded from a cache on disk).
+    ///
+    /// The bytecode is validated when loading by [`Lua::load_compiled`].
+    pub fn from_bytecode(name: impl Into<String>, bytecode: impl Into<std::sync::Arc<[u8]>>) -> Self {
+        CompiledChunk {
+            name: name.into(),
+            mode: ChunkMode::Binary,
+            bytecode: bytecode.into(),
+        }
+    }
+
+    /// Returns the name of this chunk.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the mode of this chunk (always binary).
+    pub fn mode(&self) -> ChunkMode {
+        self.mode
+    }
+
+    /// Returns the compiled bytecode.
+    pub fn bytecode(&self) -> &[u8] {
+        &self.bytecode
+    }
+
+    /// Checks that the bytecode can be loaded by the Luau VM linked into this crate.
+    #[cfg(feature = "luau")]
+    pub(crate) fn validate(&self) -> Result<()> {
+        let name = &self.name;
+        match self.bytecode.first().copied() {
+            None => Err(Error::runtime(format!("compiled chunk '{name}' is empty"))),
+            // Bytecode holding a compilation error
+            Some(0) => Err(Error::SyntaxError {
+                message: String::from_utf8_lossy(self.bytecode.get(2..).unwrap_or_default()).into_owned(),
+                incomplete_input: false,
+            }),
+            Some(version) if !(ffi::LBC_VERSION_MIN..=ffi::LBC_VERSION_MAX).contains(&version) => {
+                Err(Error::runtime(format!(
+                    "compiled chunk '{name}' has bytecode version {version}, \
+                     but this Luau version supports versions {}..={}",
+                    ffi::LBC_VERSION_MIN,
+                    ffi::LBC_VERSION_MAX
+                )))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+#[cfg(any(feature = "luau", doc))]
+impl AsChunk for &CompiledChunk {
+    fn name(&self) -> Option<StdString> {
+        Some(self.name.clone())
+    }
+
+    fn mode(&self) -> Option<ChunkMode> {
+        Some(self.mode)
+    }
+
+    fn source<'a>(&self) -> IoResult<Cow<'a, [u8]>>
+    where
+        Self: 'a,
+    {
+        Ok(Cow::Borrowed(&self.bytecode))
+    }
+}
+
+/// Maps lines of a generated (eg. transpiled or minified) chunk back to the original source.
+///
+/// Attach it to a chunk with [`Chunk::set_source_map`]. Locations of the chunk (`name:line`) in
+/// error messages and tracebacks (including the frames of a captured [`Traceback`]) are replaced
+/// by the original locations. Lines without a mapping use the closest mapped line before them.
+///
+/// The map stays registered for the chunk name until it is replaced by another chunk with the
+/// same name or removed with [`Lua::remove_source_map`].
+///
+/// [`Traceback`]: crate::Traceback
+///
+/// # Examples
+///
+/// ```
+/// # use mlua::{Lua, Result, SourceMap};
+/// # fn main() -> Result<()> {
+/// let lua = Lua::new();
+/// let map = SourceMap::new().add_line(1, 10).add_file_line(2, "script.src", 42);
+/// let err = lua.load("local x = 1\nerror('oops')").set_name("=gen").set_source_map(map).exec();
+/// assert!(err.unwrap_err().to_string().contains("script.src:42: oops"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    lines: BTreeMap<usize, (Option<StdString>, usize)>,
+}
+
+impl SourceMap {
+    /// Creates an empty source map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps a line of the generated chunk to a line of the original source.
+    pub fn add_line(mut self, generated: usize, original: usize) -> Self {
+        self.lines.insert(generated, (None, original));
+        self
+    }
+
+    /// Maps a line of the generated chunk to a line of the original source file.
+    ///
+    /// The file name replaces the chunk name in translated locations.
+    pub fn add_file_line(mut self, generated: usize, file: impl Into<StdString>, original: usize) -> Self {
+        self.lines.insert(generated, (Some(file.into()), original));
+        self
+    }
+
+    /// Returns the original file (if set) and line of the given generated line.
+    pub fn lookup(&self, line: usize) -> Option<(Option<&str>, usize)> {
+        let (_, (file, original)) = self.lines.range(..=line).next_back()?;
+        Some((file.as_deref(), *original))
+    }
+
+    // Replaces `src:line` locations in `text` with the original locations.
+    // Returns `None` if nothing was replaced.
+    pub(crate) fn translate(&self, src: &str, text: &str) -> Option<StdString> {
+        let pattern = format!("{src}:");
+        let (mut result, mut rest, mut changed) = (StdString::new(), text, false);
+        while let Some(pos) = rest.find(&pattern) {
+            let after = &rest[pos + pattern.len()..];
+            let digits = after.bytes().take_while(u8::is_ascii_digit).count();
+            // Skip matches in the middle of another name
+            let boundary = !matches!(rest[..pos].chars().next_back(),
+                Some(c) if c.is_alphanumeric() || matches!(c, '_' | '.' | '/' | '\\'));
+            let location = (after[..digits].parse().ok())
+                .filter(|_| boundary)
+                .and_then(|line| self.lookup(line));
+            result.push_str(&rest[..pos]);
+            match location {
+                Some((file, line)) => {
+                    let _ = write!(result, "{}:{line}", file.unwrap_or(src));
+                    changed = true;
+                }
+                None => result.push_str(&rest[pos..pos + pattern.len() + digits]),
+            }
+            rest = &after[digits..];
+        }
+        result.push_str(rest);
+        changed.then_some(result)
+    }
 }
 
 impl Chunk<'_> {
@@ -500,6 +696,20 @@ impl Chunk<'_> {
         self
     }
 
+    /// Sets a source map used to translate locations of this chunk in error messages back to the
+    /// original source.
+    ///
+    /// The map applies to errors raised while loading the chunk and to runtime errors raised by
+    /// the loaded function (and functions defined in it) afterwards. It stays registered for the
+    /// lifetime of the Lua state, keyed by the chunk name, so chunks with source maps should have
+    /// unique names.
+    ///
+    /// See [`SourceMap`] for details.
+    pub fn set_source_map(mut self, map: SourceMap) -> Self {
+        self.source_map = Some(map);
+        self
+    }
+
     /// Sets or overwrites a Luau compiler used for this chunk.
     ///
     /// See [`Compiler`] for details and possible options.
@@ -510,6 +720,33 @@ impl Chunk<'_> {
         self
     }
 
+    /// Loads this chunk with its own sandboxed environment.
+    ///
+    /// The chunk gets a fresh environment table that proxies reads to the chunk environment (or
+    /// to the globals if no environment was set). Global variables assigned by the chunk are stored
+    /// in the new table, so the inherited environment is left untouched and other chunks are not
+    /// affected. This is the same isolation [`Lua::sandbox`] gives each thread, scoped to a single
+    /// chunk.
+    ///
+    /// The inherited environment is only readable through the `__index` metamethod of the new
+    /// environment, whose metatable is locked. `getfenv` and `setfenv` are hidden from the chunk,
+    /// as they give access to the globals of the running thread, and `_G` refers to the new
+    /// environment.
+    ///
+    /// The new environment has `safeenv` enabled, which lets Luau use fast paths for builtin
+    /// functions. As a result, replacing builtin libraries or functions inside the sandboxed chunk
+    /// may not affect calls that were already optimized. Tables shared with the inherited
+    /// environment (such as `string` or `math`) are not copied and can still be modified,
+    /// unless [`Lua::sandbox`] has made them read-only.
+    ///
+    /// [`Lua::sandbox`]: crate::Lua::sandbox
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn sandboxed(mut self, enabled: bool) -> Self {
+        self.sandboxed = enabled;
+        self
+    }
+
     /// Execute this chunk of code.
     ///
     /// This is equivalent to calling the chunk function with no arguments and no return values.
@@ -536,6 +773,41 @@ impl Chunk<'_> {
         }
     }
 
+    /// Evaluate the chunk as [`Chunk::eval`] does, converting the results into a tuple.
+    ///
+    /// Unlike [`Chunk::eval`], the chunk must return exactly as many values as there are elements
+    /// in the tuple. Errors name the position of the first value that cannot be converted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, Result};
+    /// # fn main() -> Result<()> {
+    /// # let lua = Lua::new();
+    /// let (a, b, c) = lua.load("return 1, 'x', true").eval_tuple::<(i64, String, bool)>()?;
+    /// assert_eq!((a, b.as_str(), c), (1, "x", true));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn eval_tuple<R: FromLuaTuple>(self) -> Result<R> {
+        let lua = self.lua.upgrade();
+        let values = self.eval::<MultiValue>()?;
+        if values.len() != R::LEN {
+            return Err(Error::FromLuaConversionError {
+                from: "values",
+                to: short_type_name::<R>(),
+                message: Some(format!("expected {} values, got {}", R::LEN, values.len())),
+            });
+        }
+        R::from_lua_args(values, 1, None, &lua).map_err(|err| match err {
+            Error::BadArgument { pos, cause, .. } => Error::WithContext {
+                context: format!("bad value #{pos} returned from chunk"),
+                cause,
+            },
+            err => err,
+        })
+    }
+
     /// Load the chunk function and call it with the given arguments.
     ///
     /// This is equivalent to `into_function` and calling the resulting function.
@@ -554,10 +826,91 @@ impl Chunk<'_> {
             self.compile();
         }
 
+        let env = self.load_environment()?;
         let name = Self::convert_name(self.name)?;
-        self.lua
-            .lock()
-            .load_chunk(Some(&name), self.env?.as_ref(), self.mode, self.source?.as_ref())
+        let result =
+            (self.lua.lock()).load_chunk(Some(&name), env.as_ref(), self.mode, self.source?.as_ref());
+        let func = Self::register_source_map(&self.lua, self.source_map.as_ref(), &name, result)?;
+        #[cfg(feature = "luau")]
+        Self::track_coverage(&self.lua, self.compiler.as_ref(), &func)?;
+        Ok(func)
+    }
+
+    /// Registers the source map for the loaded function, or translates locations in the syntax
+    /// error if loading failed.
+    fn register_source_map(
+        lua: &WeakLua,
+        map: Option<&SourceMap>,
+        name: &CStr,
+        result: Result<Function>,
+    ) -> Result<Function> {
+        let Some(map) = map else {
+            return result;
+        };
+        let lua = lua.lock();
+        match result {
+            Ok(func) => {
+                if let Some(src) = func.info().short_src {
+                    unsafe { (*lua.extra()).source_maps.insert(src, map.clone()) };
+                }
+                Ok(func)
+            }
+            Err(Error::SyntaxError {
+                message,
+                incomplete_input,
+            }) => {
+                let message = match Self::printable_name(&lua, name) {
+                    Some(src) => map.translate(&src, &message).unwrap_or(message),
+                    None => message,
+                };
+                Err(Error::SyntaxError {
+                    message,
+                    incomplete_input,
+                })
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns the chunk name as Lua prints it in error messages and tracebacks.
+    pub(crate) fn printable_name(lua: &RawLua, name: &CStr) -> Option<StdString> {
+        // Load an empty chunk with the same name to find out how Lua prints the name
+        (lua.load_chunk(Some(name), None, Some(ChunkMode::Text), b"").ok())
+            .and_then(|func| func.info().short_src)
+    }
+
+    /// Registers the loaded function for [`Lua::coverage_report`] if coverage is enabled.
+    #[cfg(feature = "luau")]
+    fn track_coverage(lua: &WeakLua, compiler: Option<&Compiler>, func: &Function) -> Result<()> {
+        if compiler.is_some_and(|c| c.coverage_level > 0) {
+            lua.upgrade().track_coverage(func)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the environment to load the chunk with.
+    fn load_environment(&self) -> Result<Option<Table>> {
+        let env = self.env.clone()?;
+        #[cfg(feature = "luau")]
+        if self.sandboxed {
+            let lua = self.lua.upgrade();
+            let env = env.unwrap_or_else(|| lua.globals());
+            // Reads go through a function, so the chunk cannot reach the inherited table
+            let index = lua.create_function(move |_, (_, key): (Value, Value)| match key {
+                Value::String(ref s) if s == "getfenv" || s == "setfenv" => Ok(Value::Nil),
+                key => env.get::<Value>(key),
+            })?;
+            let proxy = lua.create_table()?;
+            let meta = lua.create_table()?;
+            meta.raw_set("__index", index)?;
+            meta.raw_set("__metatable", false)?;
+            meta.set_readonly(true);
+            proxy.raw_set("_G", &proxy)?;
+            proxy.set_metatable(Some(meta));
+            proxy.set_safeenv(true);
+            return Ok(Some(proxy));
+        }
+        Ok(env)
     }
 
     /// Compiles the chunk and changes mode to binary.
@@ -571,8 +924,11 @@ impl Chunk<'_> {
                     self.source = Ok(Cow::Owned(data));
                     self.mode = Some(ChunkMode::Binary);
                 }
+                // Binary chunks keep the name they were compiled with
                 #[cfg(not(feature = "luau"))]
-                if let Ok(func) = self.lua.lock().load_chunk(None, None, None, source.as_ref()) {
+                let name = Self::convert_name(self.name.clone()).ok();
+                #[cfg(not(feature = "luau"))]
+                if let Ok(func) = (self.lua.lock()).load_chunk(name.as_deref(), None, None, source.as_ref()) {
                     let data = func.dump(false);
                     self.source = Ok(Cow::Owned(data));
                     self.mode = Some(ChunkMode::Binary);
@@ -638,12 +994,12 @@ impl Chunk<'_> {
             .unwrap_or(source);
 
         let name = Self::convert_name(self.name.clone())?;
-        let env = match &self.env {
-            Ok(Some(env)) => Some(env),
-            Ok(None) => None,
-            Err(err) => return Err(err.clone()),
-        };
-        self.lua.lock().load_chunk(Some(&name), env, None, &source)
+        let env = self.load_environment()?;
+        let result = (self.lua.lock()).load_chunk(Some(&name), env.as_ref(), None, &source);
+        let func = Self::register_source_map(&self.lua, self.source_map.as_ref(), &name, result)?;
+        #[cfg(feature = "luau")]
+        Self::track_coverage(&self.lua, self.compiler.as_ref(), &func)?;
+        Ok(func)
     }
 
     fn detect_mode(&self) -> ChunkMode {
@@ -1,3 +1,4 @@
+use std::any::TypeId;
 use std::borrow::Cow;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::ffi::{CStr, CString, OsStr, OsString};
@@ -18,8 +19,9 @@ use crate::string::{BorrowedBytes, BorrowedStr, String};
 use crate::table::Table;
 use crate::thread::Thread;
 use crate::traits::{FromLua, IntoLua, ShortTypeName as _};
-use crate::types::{Either, LightUserData, MaybeSend, RegistryKey};
+use crate::types::{Either, LightUserData, MaybeSend, Number, RegistryKey};
 use crate::userdata::{AnyUserData, UserData};
+use crate::util::{get_internal_userdata, WrappedFailure};
 use crate::value::{Nil, Value};
 
 impl IntoLua for Value {
@@ -341,6 +343,17 @@ impl FromLua for Error {
             val => Ok(Error::runtime(val.to_string()?)),
         }
     }
+
+    unsafe fn from_specified_stack(idx: c_int, lua: &RawLua, state: *mut ffi::lua_State) -> Result<Self> {
+        // Rust panics caught by Lua code are converted without resuming them
+        let failure_mt_ptr = (*lua.extra()).wrapped_failure_mt_ptr;
+        if let Some(WrappedFailure::Panic(_, details)) =
+            get_internal_userdata::<WrappedFailure>(state, idx, failure_mt_ptr).as_ref()
+        {
+            return Ok(details.to_error());
+        }
+        Self::from_lua(lua.stack_value_at(idx, None, state), lua.lua())
+    }
 }
 
 #[cfg(feature = "anyhow")]
@@ -835,12 +848,12 @@ macro_rules! lua_convert_int {
                 let ty = value.type_name();
                 (match value {
                     Value::Integer(i) => cast(i),
-                    Value::Number(n) => cast(n),
+                    Value::Number(n) => number_to_int(n, ty, stringify!($x), lua)?,
                     _ => {
                         if let Some(i) = lua.coerce_integer(value.clone())? {
                             cast(i)
                         } else {
-                            cast(
+                            let n =
                                 lua.coerce_number(value)?
                                     .ok_or_else(|| Error::FromLuaConversionError {
                                         from: ty,
@@ -848,8 +861,8 @@ macro_rules! lua_convert_int {
                                         message: Some(
                                             "expected number or string coercible to number".to_string(),
                                         ),
-                                    })?,
-                            )
+                                    })?;
+                            number_to_int(n, ty, stringify!($x), lua)?
                         }
                     }
                 })
@@ -869,7 +882,8 @@ macro_rules! lua_convert_int {
                 if type_id == ffi::LUA_TNUMBER {
                     let mut ok = 0;
                     let i = ffi::lua_tointegerx(state, idx, &mut ok);
-                    if ok != 0 {
+                    // Luau truncates non-integral numbers, they are handled by the default path
+                    if ok != 0 && (cfg!(not(feature = "luau")) || i as f64 == ffi::lua_tonumber(state, idx)) {
                         return cast(i).ok_or_else(|| Error::FromLuaConversionError {
                             from: "integer",
                             to: stringify!($x).to_string(),
@@ -884,6 +898,15 @@ macro_rules! lua_convert_int {
     };
 }
 
+/// Converts a float to an integer type, rejecting non-integral values in strict mode.
+fn number_to_int<T: cast::NumCast>(n: Number, from: &'static str, to: &str, lua: &Lua) -> Result<Option<T>> {
+    if n.fract() != 0.0 && lua.strict_number_coercion() {
+        let msg = "number has no integer representation";
+        return Err(Error::from_lua_conversion(from, to, msg.to_string()));
+    }
+    Ok(cast(n))
+}
+
 lua_convert_int!(i8);
 lua_convert_int!(u8);
 lua_convert_int!(i16);
@@ -1157,6 +1180,11 @@ impl<T: FromLua> FromLua for Option<T> {
             _ => Ok(Some(T::from_specified_stack(idx, lua, state)?)),
         }
     }
+
+    #[inline]
+    fn userdata_type_id() -> Option<TypeId> {
+        T::userdata_type_id()
+    }
 }
 
 impl<L: IntoLua, R: IntoLua> IntoLua for Either<L, R> {
@@ -1,3 +1,4 @@
+use std::backtrace::Backtrace;
 use std::error::Error as StdError;
 use std::fmt;
 use std::io::Error as IoError;
@@ -40,6 +41,21 @@ pub enum Error {
     /// The Lua VM returns this error when the allocator does not return the requested memory, aka
     /// it is an out-of-memory error.
     MemoryError(StdString),
+    /// An allocation failed because it would exceed the memory limit set by
+    /// [`Lua::set_memory_limit`].
+    ///
+    /// Lua code can still catch this error with `pcall`, where it appears as the usual
+    /// "not enough memory" message.
+    ///
+    /// [`Lua::set_memory_limit`]: crate::Lua::set_memory_limit
+    OutOfMemory {
+        /// Size (in bytes) of the allocation that failed.
+        requested: usize,
+        /// The memory limit (in bytes).
+        limit: usize,
+        /// Amount of memory (in bytes) used at the moment of the failure.
+        used: usize,
+    },
     /// Lua garbage collector error, aka `LUA_ERRGCMM`.
     ///
     /// The Lua VM returns this error when there is an error running a `__gc` metamethod.
@@ -116,6 +132,16 @@ pub enum Error {
     /// [`Thread::resume`]: crate::Thread::resume
     /// [`Thread::status`]: crate::Thread::status
     CoroutineUnresumable,
+    /// A coroutine yielded more times than allowed by [`Thread::resume_until_done`].
+    ///
+    /// The coroutine is left suspended at the first yield over the limit.
+    ///
+    /// [`Thread::resume_until_done`]: crate::Thread::resume_until_done
+    TooManyYields(usize),
+    /// A coroutine was resumed more times than allowed by [`Thread::set_max_resumes`].
+    ///
+    /// [`Thread::set_max_resumes`]: crate::Thread::set_max_resumes
+    ResumeLimitExceeded(u32),
     /// An [`AnyUserData`] is not the expected type in a borrow.
     ///
     /// This error can only happen when manually using [`AnyUserData`], or when implementing
@@ -180,6 +206,21 @@ pub enum Error {
     /// This error can occur only when a Rust panic resumed previously was recovered
     /// and returned again.
     PreviouslyResumedPanic,
+    /// A Rust panic that was caught by Lua code.
+    ///
+    /// When [`LuaOptions::catch_rust_panics`] is enabled, Lua code can catch Rust panics using
+    /// `pcall`. Converting the caught panic object to [`Error`] (eg. passing it to a Rust
+    /// function expecting `Error`) produces this error. Any other conversion resumes the panic.
+    ///
+    /// [`LuaOptions::catch_rust_panics`]: crate::LuaOptions::catch_rust_panics
+    RustPanic {
+        /// The panic message, or `<panic>` if the payload cannot be formatted.
+        message: StdString,
+        /// Backtrace captured where the panic was caught.
+        ///
+        /// See [`Error::panic_backtrace`] for details.
+        backtrace: Option<Arc<Backtrace>>,
+    },
     /// Serialization error.
     #[cfg(feature = "serde")]
     #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
@@ -203,6 +244,98 @@ pub enum Error {
         /// Underlying error.
         cause: Arc<Error>,
     },
+    /// An error with a structured stack traceback captured when it was raised.
+    ///
+    /// Produced only when [`LuaOptions::capture_tracebacks`] is enabled.
+    ///
+    /// [`LuaOptions::capture_tracebacks`]: crate::LuaOptions::capture_tracebacks
+    WithTraceback {
+        /// Call stack at the point where the error was raised.
+        traceback: Traceback,
+        /// Underlying error.
+        cause: Arc<Error>,
+    },
+}
+
+/// Details of a [`Error::RuntimeError`], split into position and message.
+///
+/// The position is captured from the call stack when the error is raised. See
+/// [`Error::runtime_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RuntimeErrorInfo {
+    /// A "printable" version of the chunk source where the error was raised (eg. `[string "..."]`).
+    pub source: Option<StdString>,
+    /// The line where the error was raised.
+    pub line: Option<u32>,
+    /// The error message without position and traceback.
+    pub message: StdString,
+}
+
+impl RuntimeErrorInfo {
+    fn from_message(message: &str) -> Self {
+        RuntimeErrorInfo {
+            source: None,
+            line: None,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// A structured Lua call stack traceback.
+///
+/// Frames are ordered from the innermost (where the error was raised) to the outermost.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Traceback {
+    frames: Vec<TracebackFrame>,
+    // Position and message of the runtime error, captured when it was raised
+    error_info: Option<RuntimeErrorInfo>,
+}
+
+impl Traceback {
+    pub(crate) fn new(frames: Vec<TracebackFrame>) -> Self {
+        Traceback {
+            frames,
+            error_info: None,
+        }
+    }
+
+    pub(crate) fn with_error_info(mut self, info: Option<RuntimeErrorInfo>) -> Self {
+        self.error_info = info;
+        self
+    }
+
+    /// Returns the traceback frames, starting from the innermost one.
+    pub fn frames(&self) -> &[TracebackFrame] {
+        &self.frames
+    }
+}
+
+impl<'a> IntoIterator for &'a Traceback {
+    type Item = &'a TracebackFrame;
+    type IntoIter = std::slice::Iter<'a, TracebackFrame>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.frames.iter()
+    }
+}
+
+/// A single frame of a [`Traceback`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TracebackFrame {
+    /// A "printable" version of the chunk source (eg. `[string "..."]`), if it's a Lua frame.
+    ///
+    /// For chunks with a [`SourceMap`], this is the original file name if the map has one.
+    ///
+    /// [`SourceMap`]: crate::SourceMap
+    pub source: Option<StdString>,
+    /// The line currently executed in the frame, if known (translated by the chunk source map).
+    pub line: Option<usize>,
+    /// A reasonable name of the function, if known.
+    pub name: Option<StdString>,
+    /// `true` if the frame is a C or Rust function.
+    pub is_native: bool,
 }
 
 /// A specialized `Result` type used by `mlua`'s API.
@@ -217,6 +350,12 @@ impl fmt::Display for Error {
             Error::MemoryError(msg) => {
                 write!(fmt, "memory error: {msg}")
             }
+            Error::OutOfMemory { requested, limit, used } => {
+                write!(
+                    fmt,
+                    "memory error: not enough memory (requested {requested} bytes, {used}/{limit} bytes used)"
+                )
+            }
             #[cfg(any(feature = "lua53", feature = "lua52"))]
             Error::GarbageCollectorError(msg) => {
                 write!(fmt, "garbage collector error: {msg}")
@@ -266,6 +405,10 @@ impl fmt::Display for Error {
                 }
             }
             Error::CoroutineUnresumable => write!(fmt, "coroutine is non-resumable"),
+            Error::TooManyYields(limit) => write!(fmt, "coroutine yielded more than {limit} times"),
+            Error::ResumeLimitExceeded(limit) => {
+                write!(fmt, "coroutine cannot be resumed more than {limit} times")
+            }
             Error::UserDataTypeMismatch => write!(fmt, "userdata is not expected type"),
             Error::UserDataDestructed => write!(fmt, "userdata has been destructed"),
             Error::UserDataBorrowError => write!(fmt, "error borrowing userdata"),
@@ -307,6 +450,7 @@ impl fmt::Display for Error {
             Error::PreviouslyResumedPanic => {
                 write!(fmt, "previously resumed panic returned again")
             }
+            Error::RustPanic { message, .. } => write!(fmt, "rust panic: {message}"),
             #[cfg(feature = "serde")]
             Error::SerializeError(err) => {
                 write!(fmt, "serialize error: {err}")
@@ -320,6 +464,7 @@ impl fmt::Display for Error {
                 writeln!(fmt, "{context}")?;
                 write!(fmt, "{cause}")
             },
+            Error::WithTraceback { cause, .. } => write!(fmt, "{cause}"),
         }
     }
 }
@@ -334,7 +479,7 @@ impl StdError for Error {
             // returns nothing.
             Error::CallbackError { .. } => None,
             Error::ExternalError(err) => err.source(),
-            Error::WithContext { cause, .. } => Self::source(cause),
+            Error::WithContext { cause, .. } | Error::WithTraceback { cause, .. } => Self::source(cause),
             _ => None,
         }
     }
@@ -360,11 +505,113 @@ impl Error {
     {
         match self {
             Error::ExternalError(err) => err.downcast_ref(),
-            Error::WithContext { cause, .. } => Self::downcast_ref(cause),
+            Error::WithContext { cause, .. } | Error::WithTraceback { cause, .. } => {
+                Self::downcast_ref(cause)
+            }
             _ => None,
         }
     }
 
+    /// Returns the source, line and message of a runtime error.
+    ///
+    /// Looks through callback errors, errors with context and tracebacks to find the original
+    /// [`Error::RuntimeError`]. The position and message are captured from the call stack when the
+    /// error is raised, which requires [`LuaOptions::capture_tracebacks`] to be enabled (the
+    /// structured traceback is available through [`Error::traceback`]). The position is set if the
+    /// error was raised by the Lua VM with a position prefix (eg. `error("message")` called from a
+    /// Lua function).
+    ///
+    /// Errors raised with a non-string value, created using [`Error::runtime`] or raised without
+    /// capturing tracebacks have only the message (the full text of the error, including position
+    /// and traceback if any).
+    ///
+    /// Returns `None` if the error is not a runtime error.
+    ///
+    /// [`LuaOptions::capture_tracebacks`]: crate::LuaOptions::capture_tracebacks
+    pub fn runtime_info(&self) -> Option<RuntimeErrorInfo> {
+        match self {
+            Error::RuntimeError(message) => Some(RuntimeErrorInfo::from_message(message)),
+            Error::WithTraceback { traceback, cause } => match (&traceback.error_info, &**cause) {
+                (Some(info), Error::RuntimeError(_)) => Some(info.clone()),
+                _ => cause.runtime_info(),
+            },
+            Error::CallbackError { cause, .. } | Error::WithContext { cause, .. } => cause.runtime_info(),
+            _ => None,
+        }
+    }
+
+    /// Returns the structured traceback captured when the error was raised.
+    ///
+    /// Looks through callback errors and errors with context to find the traceback of the
+    /// original error. Tracebacks are captured only if [`LuaOptions::capture_tracebacks`] is
+    /// enabled.
+    ///
+    /// [`LuaOptions::capture_tracebacks`]: crate::LuaOptions::capture_tracebacks
+    pub fn traceback(&self) -> Option<&Traceback> {
+        match self {
+            Error::WithTraceback { traceback, .. } => Some(traceback),
+            Error::CallbackError { cause, .. } | Error::WithContext { cause, .. } => cause.traceback(),
+            _ => None,
+        }
+    }
+
+    /// Returns the backtrace captured when a Rust panic was caught.
+    ///
+    /// Looks through callback errors, errors with context and tracebacks to find the original
+    /// [`Error::RustPanic`]. The backtrace is captured where mlua catches the panic (at the
+    /// boundary of the Rust callback), if enabled by the `RUST_BACKTRACE`/`RUST_LIB_BACKTRACE`
+    /// environment variables or forced with [`LuaOptions::capture_panic_backtraces`].
+    ///
+    /// [`LuaOptions::capture_panic_backtraces`]: crate::LuaOptions::capture_panic_backtraces
+    pub fn panic_backtrace(&self) -> Option<&Backtrace> {
+        match self {
+            Error::RustPanic { backtrace, .. } => backtrace.as_deref(),
+            Error::CallbackError { cause, .. }
+            | Error::WithContext { cause, .. }
+            | Error::WithTraceback { cause, .. } => cause.panic_backtrace(),
+            _ => None,
+        }
+    }
+
+    /// Returns the innermost error wrapped by this error.
+    ///
+    /// Walks through all wrapping layers ([`Error::CallbackError`], [`Error::WithContext`],
+    /// [`Error::BadArgument`], external errors, etc.) and the [`source`] chain of external errors.
+    /// Returns `self` if the error does not wrap anything.
+    ///
+    /// [`source`]: std::error::Error::source
+    pub fn root_cause(&self) -> &(dyn StdError + 'static) {
+        self.causes().last().unwrap_or(self)
+    }
+
+    /// Attempts to downcast any error in the chain of wrapped errors to a concrete type.
+    ///
+    /// Unlike [`Error::downcast_ref`], this walks through all wrapping layers (see
+    /// [`Error::root_cause`]) and returns the innermost match. This allows to recover an error
+    /// returned from a Rust callback even after it was caught and rethrown by Lua code.
+    pub fn downcast_ref_chain<T>(&self) -> Option<&T>
+    where
+        T: StdError + 'static,
+    {
+        self.causes().filter_map(|err| err.downcast_ref::<T>()).last()
+    }
+
+    // Iterates over this error and all errors wrapped by it, from the outermost one
+    fn causes(&self) -> impl Iterator<Item = &(dyn StdError + 'static)> {
+        let first: &(dyn StdError + 'static) = self;
+        std::iter::successors(Some(first), |&err| match err.downcast_ref::<Error>() {
+            Some(
+                Error::BadArgument { cause, .. }
+                | Error::CallbackError { cause, .. }
+                | Error::WithContext { cause, .. }
+                | Error::WithTraceback { cause, .. },
+            ) => Some(&**cause as &(dyn StdError + 'static)),
+            Some(Error::ExternalError(err)) => Some(&**err as &(dyn StdError + 'static)),
+            Some(_) => None,
+            None => err.source(),
+        })
+    }
+
     /// An iterator over the chain of nested errors wrapped by this Error.
     pub fn chain(&self) -> impl Iterator<Item = &(dyn StdError + 'static)> {
         Chain {
@@ -373,12 +620,38 @@ impl Error {
         }
     }
 
+    /// Converts this error into [`anyhow::Error`], keeping Lua tracebacks in the context chain.
+    ///
+    /// Callback errors and errors with context are unwrapped: their tracebacks and contexts are
+    /// attached as context layers on top of the original error. This way the root cause is the
+    /// original [`Error`] (use [`Error::downcast_ref`] to get external errors), and the
+    /// tracebacks are reported by the alternate (`{:#}`) or debug format of [`anyhow::Error`].
+    ///
+    /// The reverse conversion is available as `From<anyhow::Error>` for [`Error`].
+    #[cfg(feature = "anyhow")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "anyhow")))]
+    pub fn into_anyhow(self) -> anyhow::Error {
+        match self {
+            Error::CallbackError { traceback, cause } => {
+                let err = Arc::unwrap_or_clone(cause).into_anyhow();
+                err.context(traceback)
+            }
+            Error::WithContext { context, cause } => {
+                let err = Arc::unwrap_or_clone(cause).into_anyhow();
+                err.context(context)
+            }
+            Error::WithTraceback { cause, .. } => Arc::unwrap_or_clone(cause).into_anyhow(),
+            err => anyhow::Error::new(err),
+        }
+    }
+
     /// Returns the parent of this error.
     #[doc(hidden)]
     pub fn parent(&self) -> Option<&Error> {
         match self {
             Error::CallbackError { cause, .. } => Some(cause.as_ref()),
             Error::WithContext { cause, .. } => Some(cause.as_ref()),
+            Error::WithTraceback { cause, .. } => Some(cause.as_ref()),
             _ => None,
         }
     }
@@ -534,7 +807,8 @@ impl<'a> Iterator for Chain<'a> {
                 Some(current) => match current.downcast_ref::<Error>()? {
                     Error::BadArgument { cause, .. }
                     | Error::CallbackError { cause, .. }
-                    | Error::WithContext { cause, .. } => {
+                    | Error::WithContext { cause, .. }
+                    | Error::WithTraceback { cause, .. } => {
                         self.current = Some(&**cause);
                         self.current
                     }
@@ -546,9 +820,11 @@ impl<'a> Iterator for Chain<'a> {
                 },
             };
 
-            // Skip `ExternalError` as it only wraps the underlying error
+            // Skip `ExternalError` and `WithTraceback` as they only wrap the underlying error
             // without meaningful context
-            if let Some(Error::ExternalError(_)) = error?.downcast_ref::<Error>() {
+            if let Some(Error::ExternalError(_) | Error::WithTraceback { .. }) =
+                error?.downcast_ref::<Error>()
+            {
                 continue;
             }
 
@@ -3,14 +3,16 @@ use std::os::raw::{c_int, c_void};
 use std::{mem, ptr, slice};
 
 use crate::error::{Error, Result};
+use crate::multi::MultiValue;
 #[cfg(feature = "luau")]
 use crate::state::util::get_next_spot;
-use crate::state::Lua;
+use crate::state::{ExtraData, Lua};
 use crate::table::Table;
 use crate::traits::{FromLuaMulti, IntoLua, IntoLuaMulti, LuaNativeFn, LuaNativeFnMut};
 use crate::types::{Callback, LuaType, MaybeSend, ValueRef};
 use crate::util::{
-    assert_stack, check_stack, linenumber_to_usize, pop_error, ptr_to_lossy_str, ptr_to_str, StackGuard,
+    assert_stack, check_stack, linenumber_to_usize, pop_error, ptr_to_lossy_str, ptr_to_str,
+    with_callee_name, StackGuard,
 };
 use crate::value::Value;
 
@@ -55,6 +57,72 @@ pub struct CoverageInfo {
     pub hits: Vec<i32>,
 }
 
+/// Number of times a line of a Luau function was executed.
+#[cfg(any(feature = "luau", doc))]
+#[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineCoverage {
+    /// The line number.
+    pub line: usize,
+    /// How many times the line was executed.
+    pub hits: u64,
+}
+
+/// Line coverage of all chunks loaded with coverage enabled, returned by [`Lua::coverage_report`].
+///
+/// Chunks are identified by their name without the `@`/`=` prefix. Only lines with code are
+/// included, and counts of the same chunk loaded several times are summed up.
+#[cfg(any(feature = "luau", doc))]
+#[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CoverageReport {
+    chunks: std::collections::BTreeMap<String, std::collections::BTreeMap<usize, u64>>,
+}
+
+#[cfg(any(feature = "luau", doc))]
+impl CoverageReport {
+    /// Returns an iterator over the names of covered chunks, in sorted order.
+    pub fn chunks(&self) -> impl Iterator<Item = &str> + '_ {
+        self.chunks.keys().map(|name| name.as_str())
+    }
+
+    /// Returns the coverage of lines with code of the given chunk, ordered by line number.
+    pub fn lines(&self, chunk: &str) -> Option<Vec<LineCoverage>> {
+        let lines = self.chunks.get(chunk)?;
+        Some(
+            lines
+                .iter()
+                .map(|(&line, &hits)| LineCoverage { line, hits })
+                .collect(),
+        )
+    }
+
+    /// Renders the report in the LCOV tracefile format, with a record per chunk.
+    pub fn to_lcov(&self) -> String {
+        use std::fmt::Write as _;
+
+        let mut output = String::new();
+        for (name, lines) in &self.chunks {
+            let _ = writeln!(output, "TN:\nSF:{name}");
+            for (line, hits) in lines {
+                let _ = writeln!(output, "DA:{line},{hits}");
+            }
+            let hit = lines.values().filter(|&&hits| hits > 0).count();
+            let _ = writeln!(output, "LF:{}\nLH:{hit}\nend_of_record", lines.len());
+        }
+        output
+    }
+
+    #[cfg(feature = "luau")]
+    pub(crate) fn add(&mut self, source: &str, hits: &[c_int]) {
+        let name = source.strip_prefix(['@', '=']).unwrap_or(source);
+        let lines = self.chunks.entry(name.to_string()).or_default();
+        for (line, &hits) in hits.iter().enumerate().filter(|(_, &hits)| hits >= 0) {
+            *lines.entry(line).or_default() += hits as u64;
+        }
+    }
+}
+
 impl Function {
     /// Calls the function, passing `args` as function arguments.
     ///
@@ -111,6 +179,7 @@ impl Function {
             let nargs = args.push_into_specified_stack_multi(&lua, state)?;
             // Call the function
             let ret = ffi::lua_pcall(state, nargs, ffi::LUA_MULTRET, stack_start);
+            ExtraData::run_pending_finalizers(lua.extra());
             if ret != ffi::LUA_OK {
                 return Err(pop_error(state, ret));
             }
@@ -120,6 +189,34 @@ impl Function {
         }
     }
 
+    /// Calls the function, returning all results as a [`MultiValue`] without converting them.
+    ///
+    /// This is the same as `call::<MultiValue>(args)` and is useful when the number of results
+    /// is not known in advance, for example when forwarding them to another function.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Function, Lua, Result, Value};
+    /// # fn main() -> Result<()> {
+    /// # let lua = Lua::new();
+    /// let split: Function = lua.load("function(s) return s:match('(%w+)=(%w+)') end").eval()?;
+    ///
+    /// let results = split.call_multi("key=value")?;
+    /// assert_eq!(results.len(), 2);
+    /// assert_eq!(results[0].to_string()?, "key");
+    ///
+    /// let results = split.call_multi("invalid")?;
+    /// assert_eq!(results.len(), 1);
+    /// assert_eq!(results[0], Value::Nil);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn call_multi(&self, args: impl IntoLuaMulti) -> Result<MultiValue> {
+        self.call(args)
+    }
+
     /// Returns a function that, when called, calls `self`, passing `args` as the first set of
     /// arguments.
     ///
@@ -424,6 +521,48 @@ impl Function {
         }
     }
 
+    /// Returns per-line hit counts of this Lua function, excluding inner functions.
+    ///
+    /// Only lines with executable statements are reported, sorted by line number. Returns an empty
+    /// vector if the function was compiled without coverage support (see
+    /// [`Compiler::set_coverage_level`]).
+    ///
+    /// Returns an error if called on a C function.
+    ///
+    /// [`Compiler::set_coverage_level`]: crate::chunk::Compiler::set_coverage_level
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn line_coverage(&self) -> Result<Vec<LineCoverage>> {
+        {
+            let lua = self.0.lua.lock();
+            let state = lua.state();
+            unsafe {
+                let _sg = StackGuard::new(state);
+                assert_stack(state, 1);
+                lua.push_ref_at(&self.0, state);
+                if ffi::lua_iscfunction(state, -1) != 0 {
+                    return Err(Error::runtime("cannot get coverage of a C function"));
+                }
+            }
+        }
+
+        let mut lines = Vec::new();
+        self.coverage(|cov| {
+            if cov.depth != 0 {
+                return;
+            }
+            let hits = cov.hits.iter().enumerate();
+            lines.extend(
+                hits.filter(|(_, &hits)| hits >= 0)
+                    .map(|(line, &hits)| LineCoverage {
+                        line,
+                        hits: hits as u64,
+                    }),
+            );
+        });
+        Ok(lines)
+    }
+
     /// Converts this function to a generic C pointer.
     ///
     /// There is no way to convert the pointer back to its original value.
@@ -477,7 +616,8 @@ impl Function {
     {
         WrappedFunction(Box::new(move |lua, nargs| unsafe {
             let state = lua.state();
-            let args = A::from_specified_stack_args(nargs, 1, None, lua, state)?;
+            let args = A::from_specified_stack_args(nargs, 1, None, lua, state)
+                .map_err(|err| with_callee_name(state, err))?;
             func.call(args)?.push_into_specified_stack_multi(lua, state)
         }))
     }
@@ -493,7 +633,8 @@ impl Function {
         WrappedFunction(Box::new(move |lua, nargs| unsafe {
             let mut func = func.try_borrow_mut().map_err(|_| Error::RecursiveMutCallback)?;
             let state = lua.state();
-            let args = A::from_specified_stack_args(nargs, 1, None, lua, state)?;
+            let args = A::from_specified_stack_args(nargs, 1, None, lua, state)
+                .map_err(|err| with_callee_name(state, err))?;
             func.call(args)?.push_into_specified_stack_multi(lua, state)
         }))
     }
@@ -511,7 +652,8 @@ impl Function {
     {
         WrappedFunction(Box::new(move |lua, nargs| unsafe {
             let state = lua.state();
-            let args = A::from_specified_stack_args(nargs, 1, None, lua, state)?;
+            let args = A::from_specified_stack_args(nargs, 1, None, lua, state)
+                .map_err(|err| with_callee_name(state, err))?;
             func.call(args).push_into_specified_stack_multi(lua, state)
         }))
     }
@@ -530,7 +672,8 @@ impl Function {
         WrappedFunction(Box::new(move |lua, nargs| unsafe {
             let mut func = func.try_borrow_mut().map_err(|_| Error::RecursiveMutCallback)?;
             let state = lua.state();
-            let args = A::from_specified_stack_args(nargs, 1, None, lua, state)?;
+            let args = A::from_specified_stack_args(nargs, 1, None, lua, state)
+                .map_err(|err| with_callee_name(state, err))?;
             func.call(args).push_into_specified_stack_multi(lua, state)
         }))
     }
@@ -7,9 +7,12 @@ use std::os::raw::c_int;
 
 use ffi::lua_Debug;
 
+#[cfg(any(feature = "luau", doc))]
+use crate::function::Function;
 use crate::state::RawLua;
 use crate::types::ReentrantMutexGuard;
-use crate::util::{linenumber_to_usize, ptr_to_lossy_str, ptr_to_str};
+use crate::util::{assert_stack, linenumber_to_usize, ptr_to_lossy_str, ptr_to_str, StackGuard};
+use crate::value::Value;
 
 /// Contains information about currently executing Lua code.
 ///
@@ -205,6 +208,74 @@ impl<'a> Debug<'a> {
             stack
         }
     }
+
+    /// Returns the local variables of the function at this level, in declaration order.
+    ///
+    /// Temporaries and internal variables (eg. `(for state)`) are skipped. If the function has no
+    /// debug information (in Luau, local names require debug level 2, see
+    /// [`Compiler::set_debug_level`]), the list is empty.
+    ///
+    /// [`Compiler::set_debug_level`]: crate::Compiler::set_debug_level
+    pub fn locals(&self) -> Vec<(String, Value)> {
+        let state = self.lua.state();
+        let mut locals = Vec::new();
+        unsafe {
+            let _sg = StackGuard::new(state);
+            assert_stack(state, 1);
+
+            for n in 1.. {
+                #[cfg(not(feature = "luau"))]
+                let name = ffi::lua_getlocal(state, self.ar.get(), n);
+                #[cfg(feature = "luau")]
+                let name = ffi::lua_getlocal(state, self.level, n);
+                if name.is_null() {
+                    break;
+                }
+                let value = self.lua.pop_value_at(state);
+                match ptr_to_lossy_str(name) {
+                    Some(name) if !name.starts_with('(') => locals.push((name.into_owned(), value)),
+                    _ => {}
+                }
+            }
+        }
+        locals
+    }
+
+    /// Returns the upvalues of the function at this level.
+    ///
+    /// Upvalues without a name (of Rust/C functions or functions without debug information) are
+    /// returned with the `?` placeholder name.
+    pub fn upvalues(&self) -> Vec<(String, Value)> {
+        let state = self.lua.state();
+        let mut upvalues = Vec::new();
+        unsafe {
+            let _sg = StackGuard::new(state);
+            assert_stack(state, 2);
+
+            // Push the function running at this level
+            #[cfg(not(feature = "luau"))]
+            let found = ffi::lua_getinfo(state, cstr!("f"), self.ar.get()) != 0;
+            #[cfg(feature = "luau")]
+            let found = ffi::lua_getinfo(state, self.level, cstr!("f"), self.ar.get()) != 0;
+            if !found {
+                return upvalues;
+            }
+
+            for n in 1.. {
+                let name = ffi::lua_getupvalue(state, -1, n);
+                if name.is_null() {
+                    break;
+                }
+                let value = self.lua.pop_value_at(state);
+                let name = match ptr_to_lossy_str(name) {
+                    Some(name) if !name.is_empty() && name != "(no name)" => name.into_owned(),
+                    _ => "?".to_string(),
+                };
+                upvalues.push((name, value));
+            }
+        }
+        upvalues
+    }
 }
 
 enum ActivationRecord {
@@ -224,6 +295,56 @@ impl ActivationRecord {
     }
 }
 
+/// Action to take after a breakpoint or a step is hit.
+///
+/// Returned by the callback set with [`Lua::set_debug_callback`].
+///
+/// [`Lua::set_debug_callback`]: crate::Lua::set_debug_callback
+#[cfg(any(feature = "luau", doc))]
+#[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebugAction {
+    /// Run until the next breakpoint.
+    Continue,
+    /// Pause on the next line, including lines of called functions.
+    StepIn,
+    /// Pause on the next line of the current function (or its caller, after return).
+    StepOver,
+    /// Abort execution with a runtime error.
+    Abort,
+}
+
+/// A breakpoint set with [`Lua::set_breakpoint`].
+///
+/// [`Lua::set_breakpoint`]: crate::Lua::set_breakpoint
+#[cfg(any(feature = "luau", doc))]
+#[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct BreakpointId {
+    pub(crate) func: Function,
+    pub(crate) line: u32,
+}
+
+#[cfg(any(feature = "luau", doc))]
+impl BreakpointId {
+    /// Returns the line the breakpoint is set on.
+    ///
+    /// This is the first line with code at or after the requested line.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+}
+
+// Location of a thread paused by the debug callback.
+#[cfg(feature = "luau")]
+pub(crate) struct DebugPause {
+    pub(crate) action: DebugAction,
+    pub(crate) depth: c_int,
+    pub(crate) line: c_int,
+    // Luau re-executes the paused instruction on resume, so the next event must be ignored
+    pub(crate) skip: bool,
+}
+
 /// Represents a specific event that triggered the hook.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DebugEvent {
@@ -65,6 +65,9 @@
 mod macros;
 
 mod buffer;
+mod capabilities;
+#[cfg(feature = "checked-int")]
+mod checked_int;
 mod chunk;
 mod conversion;
 mod error;
@@ -74,6 +77,8 @@ mod hook;
 mod luau;
 mod memory;
 mod multi;
+mod random;
+mod spec;
 mod state;
 mod stdlib;
 mod string;
@@ -85,33 +90,47 @@ mod userdata;
 mod util;
 mod value;
 mod vector;
+mod vfs;
 
 pub mod prelude;
 
 pub use bstr::BString;
 pub use ffi::{self, lua_CFunction, lua_State};
 
-pub use crate::chunk::{AsChunk, Chunk, ChunkMode};
-pub use crate::error::{Error, ErrorContext, ExternalError, ExternalResult, Result};
+pub use crate::capabilities::CapabilityTable;
+pub use crate::chunk::{AsChunk, Chunk, ChunkMode, SourceMap};
+pub use crate::error::{
+    Error, ErrorContext, ExternalError, ExternalResult, Result, RuntimeErrorInfo, Traceback, TracebackFrame,
+};
 pub use crate::function::{Function, FunctionInfo};
 pub use crate::hook::{Debug, DebugEvent, DebugNames, DebugSource, DebugStack};
+pub use crate::memory::LuaAllocator;
 pub use crate::multi::{MultiValue, Variadic};
-pub use crate::state::{GCMode, Lua, LuaOptions, WeakLua};
+pub use crate::random::RandomSource;
+pub use crate::spec::{BuiltFunctionSpec, FunctionSpec, SpecArgs, SpecPush};
+pub use crate::state::{ClockKind, ForLoopSemantics, GCMode, GcGuard, Lua, LuaOptions, WeakLua};
 pub use crate::stdlib::StdLib;
 pub use crate::string::{BorrowedBytes, BorrowedStr, String};
-pub use crate::table::{Table, TablePairs, TableSequence};
-pub use crate::thread::{ContinuationStatus, Thread, ThreadStatus};
+pub use crate::table::{
+    MergeConflict, MergePolicy, Table, TableKeys, TablePairs, TablePairsRaw, TableSequence, TableValues,
+};
+pub use crate::thread::{
+    ContinuationStatus, PinnedThreadId, PooledThread, ResumeOutcome, Thread, ThreadId, ThreadPool,
+    ThreadResetOptions, ThreadStatus,
+};
 pub use crate::traits::{
-    FromLua, FromLuaMulti, IntoLua, IntoLuaMulti, LuaNativeFn, LuaNativeFnMut, ObjectLike,
+    FromLua, FromLuaMulti, FromLuaTuple, IntoLua, IntoLuaMulti, LuaNativeFn, LuaNativeFnMut, ObjectLike,
 };
 pub use crate::types::{
-    AppDataRef, AppDataRefMut, Either, Integer, LightUserData, MaybeSend, Number, RegistryKey, VmState,
+    AppDataRef, AppDataRefMut, Either, Integer, LightUserData, MaybeSend, Number, RegistryEntry, RegistryKey,
+    VmState,
 };
 pub use crate::userdata::{
     AnyUserData, MetaMethod, UserData, UserDataFields, UserDataMetatable, UserDataMethods, UserDataRef,
     UserDataRefMut, UserDataRegistry,
 };
-pub use crate::value::{Nil, Value};
+pub use crate::value::{Nil, OwnedValue, Value};
+pub use crate::vfs::{Vfs, VfsFile};
 
 #[cfg(not(feature = "luau"))]
 pub use crate::hook::HookTriggers;
@@ -120,12 +139,26 @@ pub use crate::hook::HookTriggers;
 #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
 pub use crate::{
     buffer::Buffer,
-    chunk::{CompileConstant, Compiler},
-    function::CoverageInfo,
-    luau::{NavigateError, Require, TextRequirer},
+    chunk::{CompileConstant, CompiledChunk, Compiler},
+    function::{CoverageInfo, CoverageReport, LineCoverage},
+    hook::{BreakpointId, DebugAction},
+    luau::{
+        Declaration, DeclarationKind, HeapCensus, HeapCensusEntry, NavigateError, ProfileReport,
+        ProfilerOptions, Require, TextRequirer, TypeInfo,
+    },
+    state::{AllocEvent, GcPhase, MemoryCategoryGuard},
+    thread::ThreadEvent,
     vector::Vector,
 };
 
+#[cfg(feature = "luau-assert")]
+#[cfg_attr(docsrs, doc(cfg(feature = "luau-assert")))]
+pub use crate::luau::{AssertAction, AssertionFailure};
+
+#[cfg(feature = "checked-int")]
+#[cfg_attr(docsrs, doc(cfg(feature = "checked-int")))]
+pub use crate::checked_int::CheckedInt;
+
 #[cfg(feature = "serde")]
 #[doc(inline)]
 pub use crate::serde::{de::Options as DeserializeOptions, ser::Options as SerializeOptions, LuaSerdeExt};
@@ -0,0 +1,105 @@
+//! Routing of Luau internal assertions to a Rust handler.
+
+use std::os::raw::{c_char, c_int};
+use std::sync::RwLock;
+
+use crate::state::Lua;
+use crate::util::ptr_to_lossy_str;
+
+type AssertHandler = Box<dyn Fn(&AssertionFailure) -> AssertAction + Send + Sync>;
+
+static ASSERT_HANDLER: RwLock<Option<AssertHandler>> = RwLock::new(None);
+
+/// A failed Luau internal assertion, passed to the handler set by [`Lua::set_assert_handler`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct AssertionFailure<'a> {
+    /// The asserted expression.
+    pub expression: &'a str,
+    /// Luau source file containing the assertion.
+    pub file: &'a str,
+    /// Line of the assertion in the source file.
+    pub line: u32,
+    /// Name of the function containing the assertion.
+    pub function: &'a str,
+}
+
+/// What to do after the assertion handler returns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssertAction {
+    /// Ignore the failure and continue execution.
+    Continue,
+    /// Panic with a message describing the failure.
+    ///
+    /// The panic unwinds through Luau internals, so the Lua state must not be used afterwards.
+    Panic,
+    /// Abort the process.
+    Abort,
+}
+
+impl Lua {
+    /// Sets a handler for Luau internal assertions (global setting).
+    ///
+    /// The handler is called when an assertion inside the Luau VM or compiler fails, and
+    /// receives the asserted expression and its location. It can log the failure and then
+    /// decide to [`Panic`] (which can be caught in tests), [`Abort`] the process, or
+    /// [`Continue`].
+    ///
+    /// Luau checks assertions in debug builds, and the `luau-assert` feature also enables them in
+    /// release builds (by compiling Luau with `LUAU_ENABLE_ASSERT` defined). Checking assertions
+    /// slows the VM down, so the feature is intended to catch bugs during development and should
+    /// not be enabled in production.
+    ///
+    /// [`Panic`]: AssertAction::Panic
+    /// [`Abort`]: AssertAction::Abort
+    /// [`Continue`]: AssertAction::Continue
+    #[cfg(feature = "luau-assert")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau-assert")))]
+    pub fn set_assert_handler<F>(handler: F)
+    where
+        F: Fn(&AssertionFailure) -> AssertAction + Send + Sync + 'static,
+    {
+        *ASSERT_HANDLER.write().unwrap_or_else(|err| err.into_inner()) = Some(Box::new(handler));
+        unsafe { ffi::luau_setasserthandler(Some(assert_handler_proc)) };
+    }
+
+    /// Removes the handler previously set by [`Lua::set_assert_handler`].
+    ///
+    /// Failed assertions trigger a debug break afterwards, which usually terminates the process.
+    #[cfg(feature = "luau-assert")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau-assert")))]
+    pub fn remove_assert_handler() {
+        unsafe { ffi::luau_setasserthandler(None) };
+        *ASSERT_HANDLER.write().unwrap_or_else(|err| err.into_inner()) = None;
+    }
+}
+
+unsafe extern "C-unwind" fn assert_handler_proc(
+    expression: *const c_char,
+    file: *const c_char,
+    line: c_int,
+    function: *const c_char,
+) -> c_int {
+    let expression = ptr_to_lossy_str(expression).unwrap_or_default();
+    let file = ptr_to_lossy_str(file).unwrap_or_default();
+    let function = ptr_to_lossy_str(function).unwrap_or_default();
+    let failure = AssertionFailure {
+        expression: &expression,
+        file: &file,
+        line: line.max(0) as u32,
+        function: &function,
+    };
+
+    let action = match &*ASSERT_HANDLER.read().unwrap_or_else(|err| err.into_inner()) {
+        Some(handler) => handler(&failure),
+        // Trigger a debug break
+        None => return 1,
+    };
+    match action {
+        AssertAction::Continue => 0,
+        AssertAction::Panic => {
+            panic!("Luau assertion failed: {expression} ({file}:{line} in {function})")
+        }
+        AssertAction::Abort => std::process::abort(),
+    }
+}
@@ -0,0 +1,267 @@
+//! Heap census: counts live objects reachable from the registry.
+
+use std::os::raw::{c_int, c_void};
+use std::string::String as StdString;
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::error::Result;
+use crate::state::RawLua;
+use crate::util::{check_stack, ptr_to_lossy_str, StackGuard};
+
+// Approximate sizes of object headers and their parts on 64-bit platforms
+const STRING_SIZE: usize = 24;
+const TABLE_SIZE: usize = 64;
+const TABLE_ARRAY_SLOT_SIZE: usize = 16;
+const TABLE_NODE_SIZE: usize = 32;
+const FUNCTION_SIZE: usize = 40;
+const UPVALUE_SIZE: usize = 16;
+const THREAD_SIZE: usize = 192;
+const STACK_SLOT_SIZE: usize = 16;
+const USERDATA_SIZE: usize = 16;
+const BUFFER_SIZE: usize = 16;
+
+/// Number and approximate size (in bytes) of live objects of one kind.
+///
+/// Part of [`HeapCensus`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HeapCensusEntry {
+    /// Number of objects.
+    pub count: usize,
+    /// Approximate number of bytes used by the objects.
+    pub bytes: usize,
+}
+
+impl HeapCensusEntry {
+    fn add(&mut self, bytes: usize) {
+        self.count += 1;
+        self.bytes += bytes;
+    }
+}
+
+/// Live objects of a Lua state by kind, returned by [`Lua::heap_census`].
+///
+/// Sizes are estimated from the object contents (eg. string length or number of table entries)
+/// and do not include allocator overhead, so they are not expected to add up to
+/// [`Lua::used_memory`].
+///
+/// [`Lua::heap_census`]: crate::Lua::heap_census
+/// [`Lua::used_memory`]: crate::Lua::used_memory
+#[derive(Clone, Debug, Default)]
+pub struct HeapCensus {
+    strings: HeapCensusEntry,
+    tables: HeapCensusEntry,
+    functions: HeapCensusEntry,
+    threads: HeapCensusEntry,
+    userdata: HeapCensusEntry,
+    buffers: HeapCensusEntry,
+    userdata_types: FxHashMap<StdString, HeapCensusEntry>,
+}
+
+impl HeapCensus {
+    /// Returns the live strings.
+    pub fn strings(&self) -> HeapCensusEntry {
+        self.strings
+    }
+
+    /// Returns the live tables.
+    pub fn tables(&self) -> HeapCensusEntry {
+        self.tables
+    }
+
+    /// Returns the live functions (both Lua and native).
+    pub fn functions(&self) -> HeapCensusEntry {
+        self.functions
+    }
+
+    /// Returns the live threads (coroutines), including the main thread.
+    pub fn threads(&self) -> HeapCensusEntry {
+        self.threads
+    }
+
+    /// Returns all live userdata.
+    pub fn userdata(&self) -> HeapCensusEntry {
+        self.userdata
+    }
+
+    /// Returns the live buffers.
+    pub fn buffers(&self) -> HeapCensusEntry {
+        self.buffers
+    }
+
+    /// Returns an iterator over the live userdata split by type name.
+    ///
+    /// Only userdata with a known type name (the `__type` metatable field, set for all
+    /// registered types) are included.
+    pub fn userdata_by_type(&self) -> impl Iterator<Item = (&str, HeapCensusEntry)> + '_ {
+        self.userdata_types
+            .iter()
+            .map(|(name, &entry)| (name.as_str(), entry))
+    }
+
+    /// Returns the sum of all object kinds.
+    pub fn total(&self) -> HeapCensusEntry {
+        let entries = [
+            self.strings,
+            self.tables,
+            self.functions,
+            self.threads,
+            self.userdata,
+            self.buffers,
+        ];
+        entries
+            .iter()
+            .fold(HeapCensusEntry::default(), |total, entry| HeapCensusEntry {
+                count: total.count + entry.count,
+                bytes: total.bytes + entry.bytes,
+            })
+    }
+}
+
+// Traversal state: the queue of objects to visit is kept in a Lua table to keep them reachable
+struct Walker {
+    queue: c_int,
+    len: ffi::lua_Integer,
+    visited: FxHashSet<*const c_void>,
+    census: HeapCensus,
+}
+
+impl Walker {
+    // Adds the value on top of the stack to the queue (if it's an object not visited yet), pops it
+    unsafe fn enqueue(&mut self, state: *mut ffi::lua_State) {
+        let is_object = matches!(
+            ffi::lua_type(state, -1),
+            ffi::LUA_TSTRING
+                | ffi::LUA_TTABLE
+                | ffi::LUA_TFUNCTION
+                | ffi::LUA_TUSERDATA
+                | ffi::LUA_TTHREAD
+                | ffi::LUA_TBUFFER
+        );
+        if is_object && !self.visited.contains(&ffi::lua_topointer(state, -1)) {
+            self.len += 1;
+            ffi::lua_rawseti(state, self.queue, self.len);
+        } else {
+            ffi::lua_pop(state, 1);
+        }
+    }
+
+    // Pushes the metatable of the value at `idx` to the queue
+    unsafe fn enqueue_metatable(&mut self, state: *mut ffi::lua_State, idx: c_int) {
+        if ffi::lua_getmetatable(state, idx) != 0 {
+            self.enqueue(state);
+        }
+    }
+
+    // Accounts the object on top of the stack and queues the objects it references, pops it
+    unsafe fn visit(&mut self, state: *mut ffi::lua_State) {
+        if !self.visited.insert(ffi::lua_topointer(state, -1)) {
+            ffi::lua_pop(state, 1);
+            return;
+        }
+
+        let idx = ffi::lua_gettop(state);
+        match ffi::lua_type(state, idx) {
+            ffi::LUA_TSTRING => {
+                let mut len = 0;
+                ffi::lua_tolstring(state, idx, &mut len);
+                self.census.strings.add(STRING_SIZE + len + 1);
+            }
+            ffi::LUA_TTABLE => {
+                let array_len = ffi::lua_objlen(state, idx);
+                let mut entries = 0;
+                ffi::lua_pushnil(state);
+                while ffi::lua_next(state, idx) != 0 {
+                    entries += 1;
+                    self.enqueue(state);
+                    ffi::lua_pushvalue(state, -1);
+                    self.enqueue(state);
+                }
+                let nodes = entries - array_len.min(entries);
+                let bytes = TABLE_SIZE + array_len * TABLE_ARRAY_SLOT_SIZE + nodes * TABLE_NODE_SIZE;
+                self.census.tables.add(bytes);
+                self.enqueue_metatable(state, idx);
+            }
+            ffi::LUA_TFUNCTION => {
+                let mut nupvalues = 0;
+                while !ffi::lua_getupvalue(state, idx, nupvalues + 1).is_null() {
+                    nupvalues += 1;
+                    self.enqueue(state);
+                }
+                ffi::lua_getfenv(state, idx);
+                self.enqueue(state);
+                let bytes = FUNCTION_SIZE + nupvalues as usize * UPVALUE_SIZE;
+                self.census.functions.add(bytes);
+            }
+            ffi::LUA_TTHREAD => self.visit_thread(state, ffi::lua_tothread(state, idx)),
+            ffi::LUA_TUSERDATA => {
+                let bytes = USERDATA_SIZE + ffi::lua_objlen(state, idx);
+                self.census.userdata.add(bytes);
+                if ffi::lua_getmetatable(state, idx) != 0 {
+                    if ffi::lua_rawgetfield(state, -1, cstr!("__type")) == ffi::LUA_TSTRING {
+                        if let Some(name) = ptr_to_lossy_str(ffi::lua_tostring(state, -1)) {
+                            let entry = self.census.userdata_types.entry(name.into_owned());
+                            entry.or_default().add(bytes);
+                        }
+                    }
+                    ffi::lua_pop(state, 1);
+                    self.enqueue(state);
+                }
+            }
+            ffi::LUA_TBUFFER => {
+                let bytes = BUFFER_SIZE + ffi::lua_objlen(state, idx);
+                self.census.buffers.add(bytes);
+            }
+            _ => {}
+        }
+        ffi::lua_settop(state, idx - 1);
+    }
+
+    // Accounts a thread and queues its stack values and globals
+    unsafe fn visit_thread(&mut self, state: *mut ffi::lua_State, thread: *mut ffi::lua_State) {
+        let top = ffi::lua_gettop(thread);
+        for i in 1..=top {
+            ffi::lua_xpush(thread, state, i);
+            self.enqueue(state);
+        }
+        ffi::lua_xpush(thread, state, ffi::LUA_GLOBALSINDEX);
+        self.enqueue(state);
+        let bytes = THREAD_SIZE + top as usize * STACK_SLOT_SIZE;
+        self.census.threads.add(bytes);
+    }
+}
+
+/// Walks the objects reachable from the registry and the main thread.
+pub(crate) unsafe fn heap_census(lua: &RawLua) -> Result<HeapCensus> {
+    let state = lua.state();
+    let _sg = StackGuard::new(state);
+    check_stack(state, 8)?;
+
+    let mut walker = Walker {
+        queue: 0,
+        len: 0,
+        visited: FxHashSet::default(),
+        census: HeapCensus::default(),
+    };
+    protect_lua!(state, 0, 0, |state| {
+        ffi::lua_createtable(state, 0, 0);
+        walker.queue = ffi::lua_absindex(state, -1);
+        // The queue is not a part of the heap
+        walker.visited.insert(ffi::lua_topointer(state, -1));
+
+        let main_state = ffi::lua_mainthread(state);
+        walker.visited.insert(main_state as *const c_void);
+        walker.visit_thread(state, main_state);
+        ffi::lua_pushvalue(state, ffi::LUA_REGISTRYINDEX);
+        walker.enqueue(state);
+        while walker.len > 0 {
+            ffi::lua_rawgeti(state, walker.queue, walker.len);
+            ffi::lua_pushnil(state);
+            ffi::lua_rawseti(state, walker.queue, walker.len);
+            walker.len -= 1;
+            walker.visit(state);
+        }
+    })?;
+
+    Ok(walker.census)
+}
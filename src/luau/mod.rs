@@ -8,7 +8,12 @@ use crate::function::Function;
 use crate::state::{callback_error_ext, Lua};
 use crate::traits::{FromLuaMulti, IntoLua};
 
+#[cfg(feature = "luau-assert")]
+pub use assert::{AssertAction, AssertionFailure};
+pub use census::{HeapCensus, HeapCensusEntry};
+pub use profiler::{ProfileReport, ProfilerOptions};
 pub use require::{NavigateError, Require, TextRequirer};
+pub use type_info::{Declaration, DeclarationKind, TypeInfo};
 
 // Since Luau has some missing standard functions, we re-implement them here
 
@@ -21,6 +26,73 @@ impl Lua {
         require::create_require_function(self, require)
     }
 
+    /// Returns the top-level declarations of Luau `source` with their declared types.
+    ///
+    /// This is a best-effort parser of the source text intended for editor tooling. It does not
+    /// run the Luau type checker and does not infer types: types are taken from annotations as
+    /// written in the source (functions are described by their annotated signatures, with `any`
+    /// for missing annotations), and types of unannotated variables are only known if they are
+    /// initialized with a literal value (eg. `enabled = count > 5` has no type). No type errors
+    /// are reported.
+    ///
+    /// The result does not depend on [`Compiler::set_type_info_level`], which only controls the
+    /// type information embedded in compiled bytecode.
+    ///
+    /// The source is only checked for syntax errors, which are returned as
+    /// [`Error::SyntaxError`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, Result};
+    /// # fn main() -> Result<()> {
+    /// # let lua = Lua::new();
+    /// let info = lua.type_info("local function add(a: number, b: number): number return a + b end")?;
+    /// let add = info.get("add").unwrap();
+    /// assert_eq!(add.type_name.as_deref(), Some("(a: number, b: number) -> number"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Compiler::set_type_info_level`]: crate::Compiler::set_type_info_level
+    /// [`Error::SyntaxError`]: crate::Error::SyntaxError
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn type_info(&self, source: &str) -> Result<TypeInfo> {
+        type_info::type_info(source)
+    }
+
+    /// Counts live objects of the Lua state by kind, after a full garbage collection.
+    ///
+    /// Walks all objects reachable from the registry and the main thread, reporting the number
+    /// and approximate size of strings, tables, functions, threads, userdata (also split by type
+    /// name) and buffers. Objects referenced only internally by the VM, such as constants of
+    /// compiled functions, are not included.
+    ///
+    /// This stops the world: no Lua code can run until the census is complete, which takes time
+    /// proportional to the heap size. It's intended for debugging memory growth, not for periodic
+    /// monitoring.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, Result};
+    /// # fn main() -> Result<()> {
+    /// # let lua = Lua::new();
+    /// lua.load("items = {} for i = 1, 100 do items[i] = {} end").exec()?;
+    /// let census = lua.heap_census()?;
+    /// assert!(census.tables().count >= 100);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn heap_census(&self) -> Result<HeapCensus> {
+        self.gc_collect()?;
+        let lua = self.lock();
+        unsafe { census::heap_census(&lua) }
+    }
+
     pub(crate) unsafe fn configure_luau(&self) -> Result<()> {
         let globals = self.globals();
 
@@ -83,4 +155,9 @@ unsafe extern "C-unwind" fn lua_loadstring(state: *mut ffi::lua_State) -> c_int
     })
 }
 
+#[cfg(feature = "luau-assert")]
+mod assert;
+mod census;
+pub(crate) mod profiler;
 mod require;
+mod type_info;
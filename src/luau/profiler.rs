@@ -0,0 +1,135 @@
+//! Sampling profiler driven by the Luau interrupt callback.
+
+use std::fmt::Write as _;
+use std::string::String as StdString;
+
+use rustc_hash::FxHashMap;
+
+use crate::util::capture_traceback;
+
+/// Options for [`Lua::start_profiling`].
+///
+/// [`Lua::start_profiling`]: crate::Lua::start_profiling
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ProfilerOptions {
+    /// Number of interrupts between two samples.
+    ///
+    /// Luau calls the interrupt handler at function calls and loop iterations, so lower values
+    /// give more precise reports at the cost of higher overhead. Default is `1000`.
+    pub interval_ticks: u32,
+}
+
+impl Default for ProfilerOptions {
+    fn default() -> Self {
+        ProfilerOptions { interval_ticks: 1000 }
+    }
+}
+
+impl ProfilerOptions {
+    /// Returns the default profiler options.
+    pub const fn new() -> Self {
+        ProfilerOptions { interval_ticks: 1000 }
+    }
+
+    /// Sets the number of interrupts between two samples.
+    pub const fn interval_ticks(mut self, ticks: u32) -> Self {
+        self.interval_ticks = ticks;
+        self
+    }
+}
+
+/// Samples collected by the profiler, returned by [`Lua::stop_profiling`].
+///
+/// Stacks are stored in the "folded" form, with frames ordered from the outermost one and
+/// separated by `;`. Each Lua frame is described as `chunk:line` (prefixed by the function name,
+/// if known) and each native frame as `[C] name`.
+///
+/// [`Lua::stop_profiling`]: crate::Lua::stop_profiling
+#[derive(Clone, Debug, Default)]
+pub struct ProfileReport {
+    stacks: FxHashMap<StdString, u64>,
+    total: u64,
+}
+
+impl ProfileReport {
+    /// Returns the total number of collected samples.
+    pub fn total_samples(&self) -> u64 {
+        self.total
+    }
+
+    /// Returns an iterator over the collected stacks and the number of samples of each of them.
+    pub fn stacks(&self) -> impl Iterator<Item = (&str, u64)> + '_ {
+        self.stacks.iter().map(|(stack, &count)| (stack.as_str(), count))
+    }
+
+    /// Returns the report in the collapsed stacks format understood by `inferno` and
+    /// `flamegraph.pl`, one `stack count` line per unique stack.
+    ///
+    /// Lines are sorted to make the output deterministic.
+    pub fn to_collapsed(&self) -> StdString {
+        let mut stacks = self.stacks.iter().collect::<Vec<_>>();
+        stacks.sort_unstable();
+        let mut output = StdString::new();
+        for (stack, count) in stacks {
+            let _ = writeln!(output, "{stack} {count}");
+        }
+        output
+    }
+}
+
+// State of the running profiler
+pub(crate) struct Profiler {
+    interval: u32,
+    ticks: u32,
+    report: ProfileReport,
+}
+
+impl Profiler {
+    pub(crate) fn new(options: ProfilerOptions) -> Self {
+        Profiler {
+            interval: options.interval_ticks.max(1),
+            ticks: 0,
+            report: ProfileReport::default(),
+        }
+    }
+
+    pub(crate) fn into_report(self) -> ProfileReport {
+        self.report
+    }
+
+    /// Counts an interrupt of `state` and samples its stack when the interval is reached.
+    pub(crate) unsafe fn tick(&mut self, state: *mut ffi::lua_State) {
+        self.ticks += 1;
+        if self.ticks < self.interval {
+            return;
+        }
+        self.ticks = 0;
+
+        let traceback = capture_traceback(state, 0);
+        if traceback.frames().is_empty() {
+            return;
+        }
+        let mut stack = StdString::new();
+        for frame in traceback.frames().iter().rev() {
+            if !stack.is_empty() {
+                stack.push(';');
+            }
+            let name = frame.name.as_deref();
+            let label = match (frame.is_native, name, &frame.source) {
+                (true, name, _) => format!("[C] {}", name.unwrap_or("?")),
+                (false, Some(name), Some(source)) => format!("{name} {source}:{}", frame.line.unwrap_or(0)),
+                (false, None, Some(source)) => format!("{source}:{}", frame.line.unwrap_or(0)),
+                (false, name, None) => name.unwrap_or("?").to_string(),
+            };
+            // `;` separates frames and each stack must fit on a single line
+            stack.extend(label.chars().map(|c| match c {
+                ';' => ',',
+                '\n' | '\r' => ' ',
+                c => c,
+            }));
+        }
+        *self.report.stacks.entry(stack).or_default() += 1;
+        self.report.total += 1;
+    }
+}
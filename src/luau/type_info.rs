@@ -0,0 +1,574 @@
+//! Best-effort parser of the top-level declarations in Luau source code and their declared types.
+//!
+//! This is a simple scanner of the source text: it does not run the Luau type checker, so types
+//! are never inferred.
+
+use std::collections::HashSet;
+use std::string::String as StdString;
+
+use crate::chunk::Compiler;
+use crate::error::Result;
+
+/// Top-level declarations of a Luau chunk and their declared types.
+///
+/// Returned by [`Lua::type_info`]. This is a best-effort summary parsed from the source text, not
+/// the result of type inference.
+///
+/// [`Lua::type_info`]: crate::Lua::type_info
+#[derive(Clone, Debug, Default)]
+pub struct TypeInfo {
+    declarations: Vec<Declaration>,
+}
+
+impl TypeInfo {
+    /// Returns the top-level declarations in source order.
+    pub fn declarations(&self) -> &[Declaration] {
+        &self.declarations
+    }
+
+    /// Returns the last top-level declaration of `name`.
+    pub fn get(&self, name: &str) -> Option<&Declaration> {
+        self.declarations.iter().rev().find(|decl| decl.name == name)
+    }
+}
+
+/// A top-level declaration found by [`Lua::type_info`].
+///
+/// [`Lua::type_info`]: crate::Lua::type_info
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Declaration {
+    /// Name of the declaration.
+    ///
+    /// Functions defined on tables use the full path (eg. `Point.new` or `Point:len`).
+    pub name: StdString,
+    /// Kind of the declaration.
+    pub kind: DeclarationKind,
+    /// Declared type in Luau syntax (as written in the source), or `None` if it's not annotated
+    /// and cannot be derived from a literal value.
+    ///
+    /// For type aliases this is the definition of the type.
+    pub type_name: Option<StdString>,
+    /// Line number (starting from 1) of the declaration.
+    pub line: usize,
+}
+
+/// Kind of a [`Declaration`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DeclarationKind {
+    /// A local variable (`local x = ...`).
+    Local,
+    /// A local function (`local function f() ... end`).
+    LocalFunction,
+    /// An assignment to a global variable (`x = ...`).
+    Global,
+    /// A global function or a function defined on a table (`function f() ... end`).
+    Function,
+    /// A type alias (`type T = ...`).
+    Type,
+    /// An exported type alias (`export type T = ...`).
+    ExportedType,
+}
+
+pub(crate) fn type_info(source: &str) -> Result<TypeInfo> {
+    // Report syntax errors from the compiler
+    Compiler::new().compile(source)?;
+
+    let mut scanner = Scanner {
+        source,
+        tokens: tokenize(source),
+        pos: 0,
+        locals: HashSet::new(),
+        declarations: Vec::new(),
+    };
+    scanner.scan();
+    Ok(TypeInfo {
+        declarations: scanner.declarations,
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TokenKind {
+    Name,
+    Number,
+    String,
+    Symbol,
+    Eof,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+    line: usize,
+}
+
+const SYMBOLS: [&str; 17] = [
+    "...", "//=", "..=", "..", "==", "~=", "<=", ">=", "->", "::", "+=", "-=", "*=", "/=", "%=", "^=", "//",
+];
+
+fn tokenize(source: &str) -> Vec<Token> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let (mut i, mut line) = (0, 1);
+
+    // Returns the level of a long bracket (`[[`, `[=[`, ...) starting at `i`
+    let long_bracket = |i: usize| {
+        let level = bytes[i + 1..].iter().take_while(|&&b| b == b'=').count();
+        (bytes.get(i + 1 + level) == Some(&b'[')).then_some(level)
+    };
+    // Returns the end of a long bracket with `level` starting at `i`
+    let long_bracket_end = |i: usize, level: usize| {
+        let close = format!("]{}]", "=".repeat(level));
+        match source[i..].find(&close) {
+            Some(pos) => i + pos + close.len(),
+            None => bytes.len(),
+        }
+    };
+
+    while i < bytes.len() {
+        let start = i;
+        let kind = match bytes[i] {
+            b'\n' => {
+                line += 1;
+                i += 1;
+                continue;
+            }
+            b if b.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = match bytes.get(i + 2) {
+                    Some(b'[') if long_bracket(i + 2).is_some() => {
+                        long_bracket_end(i + 2, long_bracket(i + 2).unwrap())
+                    }
+                    _ => source[i..].find('\n').map_or(bytes.len(), |pos| i + pos),
+                };
+                line += source[start..i].matches('\n').count();
+                continue;
+            }
+            b'[' if long_bracket(i).is_some() => {
+                i = long_bracket_end(i, long_bracket(i).unwrap());
+                TokenKind::String
+            }
+            quote @ (b'"' | b'\'' | b'`') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i = (i + 1).min(bytes.len());
+                TokenKind::String
+            }
+            b'0'..=b'9' => {
+                i = scan_number(bytes, i);
+                TokenKind::Number
+            }
+            b'.' if bytes.get(i + 1).is_some_and(u8::is_ascii_digit) => {
+                i = scan_number(bytes, i);
+                TokenKind::Number
+            }
+            b if b == b'_' || b.is_ascii_alphabetic() || !b.is_ascii() => {
+                while i < bytes.len()
+                    && (bytes[i] == b'_' || bytes[i].is_ascii_alphanumeric() || !bytes[i].is_ascii())
+                {
+                    i += 1;
+                }
+                TokenKind::Name
+            }
+            _ => {
+                let symbol = SYMBOLS.iter().find(|s| source[i..].starts_with(*s));
+                i += symbol.map_or(1, |s| s.len());
+                TokenKind::Symbol
+            }
+        };
+        tokens.push(Token {
+            kind,
+            start,
+            end: i,
+            line,
+        });
+        line += source[start..i].matches('\n').count();
+    }
+
+    let end = bytes.len();
+    tokens.push(Token {
+        kind: TokenKind::Eof,
+        start: end,
+        end,
+        line,
+    });
+    tokens
+}
+
+fn scan_number(bytes: &[u8], mut i: usize) -> usize {
+    let is_hex = bytes[i] == b'0' && matches!(bytes.get(i + 1), Some(b'x' | b'X'));
+    while i < bytes.len() {
+        match bytes[i] {
+            // Exponent sign
+            b'+' | b'-' if !is_hex && matches!(bytes[i - 1], b'e' | b'E') => i += 1,
+            b if b == b'_' || b == b'.' || b.is_ascii_alphanumeric() => i += 1,
+            _ => break,
+        }
+    }
+    i
+}
+
+const KEYWORDS: [&str; 21] = [
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "if", "in", "local", "nil",
+    "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+struct Scanner<'a> {
+    source: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+    locals: HashSet<&'a str>,
+    declarations: Vec<Declaration>,
+}
+
+impl<'a> Scanner<'a> {
+    fn scan(&mut self) {
+        // Depth of blocks (`function`, `do`, `if`, ...) and brackets
+        let (mut blocks, mut brackets) = (0usize, 0usize);
+        // Number of `while`/`for` loops waiting for their `do`
+        let mut loops = 0;
+
+        while self.peek().kind != TokenKind::Eof {
+            if blocks == 0 && brackets == 0 && self.at_statement_start() {
+                let start = self.pos;
+                let opens_block = self.declaration();
+                if self.pos != start {
+                    blocks += opens_block as usize;
+                    continue;
+                }
+            }
+
+            let token = self.peek();
+            match (token.kind, self.text(token)) {
+                (TokenKind::Name, "function" | "if" | "repeat") => blocks += 1,
+                (TokenKind::Name, "while" | "for") => {
+                    blocks += 1;
+                    loops += 1;
+                }
+                (TokenKind::Name, "do") if loops > 0 => loops -= 1,
+                (TokenKind::Name, "do") => blocks += 1,
+                (TokenKind::Name, "end" | "until") => blocks = blocks.saturating_sub(1),
+                (TokenKind::Symbol, "(" | "[" | "{") => brackets += 1,
+                (TokenKind::Symbol, ")" | "]" | "}") => brackets = brackets.saturating_sub(1),
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Token {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Token {
+        let last = self.tokens.len() - 1;
+        self.tokens[(self.pos + offset).min(last)]
+    }
+
+    fn text(&self, token: Token) -> &'a str {
+        &self.source[token.start..token.end]
+    }
+
+    fn is(&self, offset: usize, text: &str) -> bool {
+        let token = self.peek_at(offset);
+        token.kind != TokenKind::String && self.text(token) == text
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        let matched = self.is(0, text);
+        self.pos += matched as usize;
+        matched
+    }
+
+    fn name(&mut self) -> Option<&'a str> {
+        let token = self.peek();
+        let text = self.text(token);
+        if token.kind != TokenKind::Name || KEYWORDS.contains(&text) {
+            return None;
+        }
+        self.pos += 1;
+        Some(text)
+    }
+
+    // Checks that the previous token ends a statement, so the current token starts a new one.
+    fn at_statement_start(&self) -> bool {
+        let Some(prev) = self.pos.checked_sub(1).map(|pos| self.tokens[pos]) else {
+            return true;
+        };
+        match (prev.kind, self.text(prev)) {
+            (TokenKind::Number | TokenKind::String, _) => true,
+            (TokenKind::Symbol, text) => matches!(text, ")" | "]" | "}" | ";" | "..." | "?" | ">"),
+            (_, "end" | "true" | "false" | "nil" | "break") => true,
+            (_, text) => !KEYWORDS.contains(&text),
+        }
+    }
+
+    fn push(
+        &mut self,
+        name: impl Into<StdString>,
+        kind: DeclarationKind,
+        type_name: Option<StdString>,
+        line: usize,
+    ) {
+        self.declarations.push(Declaration {
+            name: name.into(),
+            kind,
+            type_name,
+            line,
+        });
+    }
+
+    // Parses a declaration at the current position and returns `true` if it opens a block.
+    // The position is not changed if there is no declaration.
+    fn declaration(&mut self) -> bool {
+        let line = self.peek().line;
+        match self.text(self.peek()) {
+            "local" => {
+                self.pos += 1;
+                if self.eat("function") {
+                    let Some(name) = self.name() else { return false };
+                    let type_name = self.function_signature();
+                    self.locals.insert(name);
+                    self.push(name, DeclarationKind::LocalFunction, type_name, line);
+                    return true;
+                }
+                let mut names = Vec::new();
+                while let Some(name) = self.name() {
+                    let type_name = self.eat(":").then(|| self.type_text());
+                    names.push((name, type_name));
+                    if !self.eat(",") {
+                        break;
+                    }
+                }
+                if let [(_, type_name @ None)] = names.as_mut_slice() {
+                    if self.eat("=") {
+                        *type_name = self.literal_type();
+                    }
+                }
+                for (name, type_name) in names {
+                    self.locals.insert(name);
+                    self.push(name, DeclarationKind::Local, type_name, line);
+                }
+            }
+            "function" => {
+                self.pos += 1;
+                let mut name = StdString::new();
+                while let Some(part) = self.name() {
+                    name.push_str(part);
+                    match self.text(self.peek()) {
+                        sep @ ("." | ":") => name.push_str(sep),
+                        _ => break,
+                    }
+                    self.pos += 1;
+                }
+                let type_name = self.function_signature();
+                self.push(name, DeclarationKind::Function, type_name, line);
+                return true;
+            }
+            "export" | "type" if self.is_type_alias() => {
+                let kind = match self.eat("export") {
+                    true => DeclarationKind::ExportedType,
+                    false => DeclarationKind::Type,
+                };
+                self.pos += 1;
+                if let Some(name) = self.name() {
+                    if self.is(0, "<") {
+                        self.skip_balanced("<", ">");
+                    }
+                    self.eat("=");
+                    let type_name = Some(self.type_text());
+                    self.push(name, kind, type_name, line);
+                }
+            }
+            _ => self.assignment(line),
+        }
+        false
+    }
+
+    fn is_type_alias(&self) -> bool {
+        let offset = self.is(0, "export") as usize;
+        self.is(offset, "type") && (self.is(offset + 2, "=") || self.is(offset + 2, "<"))
+    }
+
+    // Parses an assignment to global variables.
+    fn assignment(&mut self, line: usize) {
+        let start = self.pos;
+        let mut names = Vec::new();
+        while let Some(name) = self.name() {
+            names.push((name, None));
+            if !self.eat(",") {
+                break;
+            }
+        }
+        if names.is_empty() || !self.eat("=") {
+            self.pos = start;
+            return;
+        }
+        if let [(_, type_name)] = names.as_mut_slice() {
+            *type_name = self.literal_type();
+        }
+        for (name, type_name) in names {
+            let declared = self.declarations.iter().any(|decl| decl.name == name);
+            if !self.locals.contains(name) && !declared {
+                self.push(name, DeclarationKind::Global, type_name, line);
+            }
+        }
+    }
+
+    // Returns the type of a literal expression at the current position (without consuming it).
+    fn literal_type(&mut self) -> Option<StdString> {
+        let token = self.peek();
+        let type_name = match (token.kind, self.text(token)) {
+            (TokenKind::Number, _) => "number",
+            (TokenKind::String, _) => "string",
+            (TokenKind::Name, "true" | "false") => "boolean",
+            (TokenKind::Name, "function") => {
+                let start = self.pos;
+                self.pos += 1;
+                let signature = self.function_signature();
+                self.pos = start;
+                return signature;
+            }
+            _ => return None,
+        };
+        // The literal must be the whole expression
+        let next = self.peek_at(1);
+        let whole = match (next.kind, self.text(next)) {
+            (TokenKind::Eof, _) | (TokenKind::Symbol, ";" | ",") => true,
+            (TokenKind::Name, text) => text != "and" && text != "or",
+            _ => false,
+        };
+        whole.then(|| type_name.to_string())
+    }
+
+    // Parses the generic parameters, parameters and the return type of a function.
+    fn function_signature(&mut self) -> Option<StdString> {
+        let mut signature = StdString::new();
+        if self.is(0, "<") {
+            signature.push_str(&self.text_of(|this| this.skip_balanced("<", ">")));
+        }
+        if !self.eat("(") {
+            return None;
+        }
+        let mut params = Vec::new();
+        while !self.eat(")") {
+            let param = match self.name() {
+                Some(name) => name,
+                None if self.eat("...") => "...",
+                None => return None,
+            };
+            let type_name = match self.eat(":") {
+                true => self.type_text(),
+                false => "any".to_string(),
+            };
+            params.push(match param {
+                "..." => format!("...{type_name}"),
+                name => format!("{name}: {type_name}"),
+            });
+            if !self.eat(",") && !self.is(0, ")") {
+                return None;
+            }
+        }
+        let ret = match self.eat(":") {
+            true => self.type_text(),
+            false => "...any".to_string(),
+        };
+        signature.push_str(&format!("({}) -> {ret}", params.join(", ")));
+        Some(signature)
+    }
+
+    // Consumes a type and returns its source text.
+    fn type_text(&mut self) -> StdString {
+        self.text_of(Self::skip_type)
+    }
+
+    // Runs `f` and returns the source text of the consumed tokens, with normalized whitespace.
+    fn text_of(&mut self, f: impl FnOnce(&mut Self)) -> StdString {
+        let start = self.pos;
+        f(self);
+        let mut text = StdString::new();
+        for (i, token) in self.tokens[start..self.pos].iter().enumerate() {
+            if i > 0 && self.tokens[start + i - 1].end < token.start {
+                text.push(' ');
+            }
+            text.push_str(self.text(*token));
+        }
+        text
+    }
+
+    fn skip_type(&mut self) {
+        // Unions and intersections can start with a separator
+        while self.eat("|") || self.eat("&") {}
+        self.skip_simple_type();
+        while self.eat("|") || self.eat("&") {
+            self.skip_simple_type();
+        }
+    }
+
+    fn skip_simple_type(&mut self) {
+        let token = self.peek();
+        match (token.kind, self.text(token)) {
+            (TokenKind::Symbol, "...") => {
+                self.pos += 1;
+                self.skip_simple_type();
+            }
+            (TokenKind::Symbol, "<" | "(") => {
+                // Function type or parenthesized type
+                if self.is(0, "<") {
+                    self.skip_balanced("<", ">");
+                }
+                self.skip_balanced("(", ")");
+                if self.eat("->") {
+                    self.skip_type();
+                }
+            }
+            (TokenKind::Symbol, "{") => self.skip_balanced("{", "}"),
+            (TokenKind::String, _) => self.pos += 1,
+            (TokenKind::Name, "typeof") => {
+                self.pos += 1;
+                self.skip_balanced("(", ")");
+            }
+            (TokenKind::Name, _) => {
+                self.pos += 1;
+                while self.is(0, ".") && self.peek_at(1).kind == TokenKind::Name {
+                    self.pos += 2;
+                }
+                if self.is(0, "<") {
+                    self.skip_balanced("<", ">");
+                }
+            }
+            _ => return,
+        }
+        while self.eat("?") {}
+    }
+
+    fn skip_balanced(&mut self, open: &str, close: &str) {
+        let mut depth = 0;
+        loop {
+            let token = self.peek();
+            if token.kind == TokenKind::Eof {
+                return;
+            }
+            self.pos += 1;
+            if token.kind != TokenKind::Symbol {
+                continue;
+            }
+            match self.text(token) {
+                text if text == open => depth += 1,
+                text if text == close => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                return;
+            }
+        }
+    }
+}
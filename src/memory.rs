@@ -1,9 +1,57 @@
 use std::alloc::{self, Layout};
+#[cfg(feature = "luau")]
+use std::cell::Cell;
+#[cfg(feature = "luau")]
+use std::os::raw::c_int;
 use std::os::raw::c_void;
 use std::ptr;
 
+#[cfg(feature = "luau")]
+use rustc_hash::FxHashMap;
+
+use crate::error::Error;
+use crate::types::{MaybeSend, OomCallback};
+
 pub(crate) static ALLOCATOR: ffi::lua_Alloc = allocator;
 
+/// A memory allocator backing a Lua state created with [`Lua::new_with_allocator`].
+///
+/// The allocator receives the memory requests of the Lua state after mlua has applied the memory
+/// limit and usage metrics, so [`Lua::set_memory_limit`] and [`Lua::used_memory`] keep working on
+/// top of it. It's dropped after the Lua state is closed, when no more requests can be made.
+///
+/// # Safety
+///
+/// Returned blocks must be valid for reads and writes of the requested size and aligned to at
+/// least [`ffi::SYS_MIN_ALIGN`] bytes. A block must stay valid until it's resized or freed.
+///
+/// [`Lua::new_with_allocator`]: crate::Lua::new_with_allocator
+/// [`Lua::set_memory_limit`]: crate::Lua::set_memory_limit
+/// [`Lua::used_memory`]: crate::Lua::used_memory
+pub unsafe trait LuaAllocator: MaybeSend + 'static {
+    /// Allocates a new block of `size` (non-zero) bytes.
+    ///
+    /// Returns null if the memory cannot be allocated, which raises a memory error in Lua.
+    fn alloc(&mut self, size: usize) -> *mut u8;
+
+    /// Resizes the block at `ptr` from `osize` to `nsize` (both non-zero) bytes, preserving its
+    /// contents up to the smaller size.
+    ///
+    /// Returns null if the block cannot be resized, leaving the original block untouched.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` is a block of `osize` bytes returned by this allocator.
+    unsafe fn realloc(&mut self, ptr: *mut u8, osize: usize, nsize: usize) -> *mut u8;
+
+    /// Releases the block at `ptr` of `size` bytes.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` is a block of `size` bytes returned by this allocator.
+    unsafe fn free(&mut self, ptr: *mut u8, size: usize);
+}
+
 #[repr(C)]
 #[derive(Default)]
 pub(crate) struct MemoryState {
@@ -15,6 +63,24 @@ pub(crate) struct MemoryState {
     // Indicates that the memory limit was reached on the last allocation.
     #[cfg(feature = "luau")]
     limit_reached: bool,
+    // The last allocation failure caused by the memory limit
+    oom_error: Option<Error>,
+    oom_callback: Option<OomCallback>,
+    // The highest value of `used_memory` (if tracking is enabled)
+    track_peak: bool,
+    peak_memory: isize,
+    // Limits of memory categories (zero means no limit)
+    #[cfg(feature = "luau")]
+    category_limits: Vec<usize>,
+    // The thread running code (`RawLua::state`), its category is checked against the limits
+    #[cfg(feature = "luau")]
+    current_state: Option<ptr::NonNull<Cell<*mut ffi::lua_State>>>,
+    // Active memory categories of threads (`L->activememcat`), if not the default one.
+    // Luau has no API function to read it, so the category is tracked when set or inherited.
+    #[cfg(feature = "luau")]
+    memory_categories: FxHashMap<*mut ffi::lua_State, u8>,
+    // Allocator provided by the host (the system allocator is used if not set)
+    custom_allocator: Option<Box<dyn LuaAllocator>>,
 }
 
 impl MemoryState {
@@ -55,6 +121,34 @@ impl MemoryState {
         self.used_memory as usize
     }
 
+    #[inline]
+    pub(crate) fn set_custom_allocator(&mut self, allocator: Option<Box<dyn LuaAllocator>>) {
+        self.custom_allocator = allocator;
+    }
+
+    #[inline]
+    pub(crate) fn has_custom_allocator(&self) -> bool {
+        self.custom_allocator.is_some()
+    }
+
+    #[inline]
+    pub(crate) fn set_track_peak(&mut self, enabled: bool) {
+        self.track_peak = enabled;
+    }
+
+    #[inline]
+    pub(crate) fn peak_memory(&self) -> usize {
+        match self.track_peak {
+            true => self.peak_memory.max(self.used_memory) as usize,
+            false => self.used_memory as usize,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn reset_peak_memory(&mut self) {
+        self.peak_memory = self.used_memory;
+    }
+
     #[inline]
     pub(crate) fn memory_limit(&self) -> usize {
         self.memory_limit as usize
@@ -67,6 +161,88 @@ impl MemoryState {
         prev_limit as usize
     }
 
+    #[inline]
+    pub(crate) fn set_oom_callback(&mut self, callback: Option<OomCallback>) {
+        self.oom_callback = callback;
+    }
+
+    #[cfg(feature = "luau")]
+    #[inline]
+    pub(crate) fn set_current_state(&mut self, state: &Cell<*mut ffi::lua_State>) {
+        self.current_state = Some(ptr::NonNull::from(state));
+    }
+
+    #[cfg(feature = "luau")]
+    pub(crate) fn set_category_limit(&mut self, category: u8, limit: usize) -> usize {
+        if self.category_limits.is_empty() {
+            self.category_limits = vec![0; u8::MAX as usize + 1];
+        }
+        std::mem::replace(&mut self.category_limits[category as usize], limit)
+    }
+
+    // Returns the active memory category of the thread
+    #[cfg(feature = "luau")]
+    #[inline]
+    pub(crate) fn memory_category(&self, state: *mut ffi::lua_State) -> u8 {
+        self.memory_categories.get(&state).copied().unwrap_or(0)
+    }
+
+    // Sets the active memory category of the thread
+    #[cfg(feature = "luau")]
+    pub(crate) unsafe fn set_memory_category(&mut self, state: *mut ffi::lua_State, category: u8) {
+        ffi::lua_setmemcat(state, category as c_int);
+        match category {
+            0 => self.memory_categories.remove(&state),
+            _ => self.memory_categories.insert(state, category),
+        };
+    }
+
+    // Returns `true` if any thread has an active memory category other than the default one
+    #[cfg(feature = "luau")]
+    #[inline]
+    pub(crate) fn has_memory_categories(&self) -> bool {
+        !self.memory_categories.is_empty()
+    }
+
+    // Tracks a new thread, which inherits the active memory category of its parent
+    #[cfg(feature = "luau")]
+    pub(crate) fn thread_created(&mut self, parent: *mut ffi::lua_State, child: *mut ffi::lua_State) {
+        match self.memory_categories.get(&parent).copied() {
+            Some(category) => self.memory_categories.insert(child, category),
+            None => self.memory_categories.remove(&child),
+        };
+    }
+
+    // Stops tracking a destroyed thread (its address can be reused)
+    #[cfg(feature = "luau")]
+    pub(crate) fn thread_destroyed(&mut self, state: *mut ffi::lua_State) {
+        self.memory_categories.remove(&state);
+    }
+
+    // Returns the limit and usage of the active memory category of the running thread, if it has
+    // a limit
+    #[cfg(feature = "luau")]
+    #[inline]
+    unsafe fn category_usage(&self) -> Option<(usize, usize)> {
+        if self.category_limits.is_empty() {
+            return None;
+        }
+        let state = self.current_state?.as_ref().get();
+        let category = self.memory_category(state);
+        let limit = self.category_limits[category as usize];
+        (limit > 0).then(|| (limit, ffi::lua_totalbytes(state, category as c_int)))
+    }
+
+    // Returns the error describing the last allocation failure caused by the memory limit
+    #[inline]
+    pub(crate) unsafe fn take_oom_error(state: *mut ffi::lua_State) -> Option<Error> {
+        let mem_state = Self::get(state);
+        if mem_state.is_null() {
+            return None;
+        }
+        (*mem_state).oom_error.take()
+    }
+
     // This function is used primarily for calling `lua_pushcfunction` in lua5.1/jit/luau
     // to bypass the memory limit (if set).
     #[cfg(any(feature = "lua51", feature = "luajit", feature = "luau"))]
@@ -97,6 +273,19 @@ impl MemoryState {
     }
 }
 
+/// Asks the system allocator to return free memory to the OS.
+///
+/// Only glibc supports this (via `malloc_trim`), on other platforms this is a no-op.
+pub(crate) fn trim_system_allocator() {
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    unsafe {
+        extern "C" {
+            fn malloc_trim(pad: usize) -> std::os::raw::c_int;
+        }
+        malloc_trim(0);
+    }
+}
+
 unsafe extern "C" fn allocator(
     extra: *mut c_void,
     ptr: *mut c_void,
@@ -113,8 +302,13 @@ unsafe extern "C" fn allocator(
     if nsize == 0 {
         // Free memory
         if !ptr.is_null() {
-            let layout = Layout::from_size_align_unchecked(osize, ffi::SYS_MIN_ALIGN);
-            alloc::dealloc(ptr as *mut u8, layout);
+            match &mut mem_state.custom_allocator {
+                Some(allocator) => allocator.free(ptr as *mut u8, osize),
+                None => {
+                    let layout = Layout::from_size_align_unchecked(osize, ffi::SYS_MIN_ALIGN);
+                    alloc::dealloc(ptr as *mut u8, layout);
+                }
+            }
             mem_state.used_memory -= osize as isize;
         }
         return ptr::null_mut();
@@ -133,13 +327,43 @@ unsafe extern "C" fn allocator(
     let mem_limit = mem_state.memory_limit;
     let new_used_memory = mem_state.used_memory + mem_diff;
     if mem_limit > 0 && new_used_memory > mem_limit && !mem_state.ignore_limit {
-        #[cfg(feature = "luau")]
-        {
-            mem_state.limit_reached = true;
+        return limit_exceeded(
+            mem_state,
+            nsize,
+            mem_limit as usize,
+            mem_state.used_memory as usize,
+        );
+    }
+    #[cfg(feature = "luau")]
+    if mem_diff > 0 && !mem_state.ignore_limit {
+        if let Some((limit, used)) = mem_state.category_usage() {
+            if used + mem_diff as usize > limit {
+                return limit_exceeded(mem_state, nsize, limit, used);
+            }
         }
-        return ptr::null_mut();
+    }
+    if mem_diff > 0 && mem_state.oom_error.is_some() {
+        // The memory is available again (eg. after an emergency GC, or the failure was caught
+        // by `pcall`), so the last failure is stale.
+        // Shrinking the stack after an error does not grow the memory and keeps the error.
+        mem_state.oom_error = None;
     }
     mem_state.used_memory += mem_diff;
+    if mem_state.track_peak && mem_state.used_memory > mem_state.peak_memory {
+        mem_state.peak_memory = mem_state.used_memory;
+    }
+
+    if let Some(allocator) = &mut mem_state.custom_allocator {
+        let new_ptr = match ptr.is_null() {
+            true => allocator.alloc(nsize),
+            false => allocator.realloc(ptr as *mut u8, osize, nsize),
+        };
+        if new_ptr.is_null() {
+            // Lua raises a memory error, the original block (if any) is kept
+            mem_state.used_memory -= mem_diff;
+        }
+        return new_ptr as *mut c_void;
+    }
 
     if ptr.is_null() {
         // Allocate new memory
@@ -162,3 +386,22 @@ unsafe extern "C" fn allocator(
     }
     new_ptr
 }
+
+// Records the allocation failure caused by a memory limit
+#[cold]
+fn limit_exceeded(mem_state: &mut MemoryState, requested: usize, limit: usize, used: usize) -> *mut c_void {
+    #[cfg(feature = "luau")]
+    {
+        mem_state.limit_reached = true;
+    }
+    let err = Error::OutOfMemory {
+        requested,
+        limit,
+        used,
+    };
+    if let Some(callback) = &mem_state.oom_callback {
+        callback(&err);
+    }
+    mem_state.oom_error = Some(err);
+    ptr::null_mut()
+}
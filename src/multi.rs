@@ -7,7 +7,7 @@ use std::result::Result as StdResult;
 
 use crate::error::Result;
 use crate::state::{Lua, RawLua};
-use crate::traits::{FromLua, FromLuaMulti, IntoLua, IntoLuaMulti};
+use crate::traits::{FromLua, FromLuaMulti, FromLuaTuple, IntoLua, IntoLuaMulti};
 use crate::util::check_stack;
 use crate::value::{Nil, Value};
 
@@ -484,6 +484,34 @@ impl_tuple!(A B C D E F G H I J K L M N);
 impl_tuple!(A B C D E F G H I J K L M N O);
 impl_tuple!(A B C D E F G H I J K L M N O P);
 
+macro_rules! impl_from_lua_tuple {
+    ($($name:ident)*) => (
+        impl<$($name,)*> FromLuaTuple for ($($name,)*)
+            where $($name: FromLua,)*
+        {
+            const LEN: usize = 0 $(+ { _ = stringify!($name); 1 })*;
+        }
+    );
+}
+
+impl_from_lua_tuple!();
+impl_from_lua_tuple!(A);
+impl_from_lua_tuple!(A B);
+impl_from_lua_tuple!(A B C);
+impl_from_lua_tuple!(A B C D);
+impl_from_lua_tuple!(A B C D E);
+impl_from_lua_tuple!(A B C D E F);
+impl_from_lua_tuple!(A B C D E F G);
+impl_from_lua_tuple!(A B C D E F G H);
+impl_from_lua_tuple!(A B C D E F G H I);
+impl_from_lua_tuple!(A B C D E F G H I J);
+impl_from_lua_tuple!(A B C D E F G H I J K);
+impl_from_lua_tuple!(A B C D E F G H I J K L);
+impl_from_lua_tuple!(A B C D E F G H I J K L M);
+impl_from_lua_tuple!(A B C D E F G H I J K L M N);
+impl_from_lua_tuple!(A B C D E F G H I J K L M N O);
+impl_from_lua_tuple!(A B C D E F G H I J K L M N O P);
+
 #[cfg(test)]
 mod assertions {
     use super::*;
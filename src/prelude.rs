@@ -3,18 +3,26 @@
 #[doc(no_inline)]
 pub use crate::{
     AnyUserData as LuaAnyUserData, BorrowedBytes as LuaBorrowedBytes, BorrowedStr as LuaBorrowedStr,
-    Chunk as LuaChunk, ContinuationStatus as LuaContinuationStatus, Either as LuaEither, Error as LuaError,
+    CapabilityTable as LuaCapabilityTable, Chunk as LuaChunk, ClockKind as LuaClockKind,
+    ContinuationStatus as LuaContinuationStatus, Either as LuaEither, Error as LuaError,
     ErrorContext as LuaErrorContext, ExternalError as LuaExternalError, ExternalResult as LuaExternalResult,
-    FromLua, FromLuaMulti, Function as LuaFunction, FunctionInfo as LuaFunctionInfo, GCMode as LuaGCMode,
-    Integer as LuaInteger, IntoLua, IntoLuaMulti, LightUserData as LuaLightUserData, Lua, LuaNativeFn,
-    LuaNativeFnMut, LuaOptions, MetaMethod as LuaMetaMethod, MultiValue as LuaMultiValue, Nil as LuaNil,
-    Number as LuaNumber, ObjectLike as LuaObjectLike, RegistryKey as LuaRegistryKey, Result as LuaResult,
-    StdLib as LuaStdLib, String as LuaString, Table as LuaTable, TablePairs as LuaTablePairs,
-    TableSequence as LuaTableSequence, Thread as LuaThread, ThreadStatus as LuaThreadStatus,
+    ForLoopSemantics as LuaForLoopSemantics, FromLua, FromLuaMulti, FromLuaTuple, Function as LuaFunction,
+    FunctionInfo as LuaFunctionInfo, GCMode as LuaGCMode, GcGuard as LuaGcGuard, Integer as LuaInteger,
+    IntoLua, IntoLuaMulti, LightUserData as LuaLightUserData, Lua, LuaAllocator, LuaNativeFn, LuaNativeFnMut,
+    LuaOptions, MergeConflict as LuaMergeConflict, MergePolicy as LuaMergePolicy,
+    MetaMethod as LuaMetaMethod, MultiValue as LuaMultiValue, Nil as LuaNil, Number as LuaNumber,
+    ObjectLike as LuaObjectLike, OwnedValue as LuaOwnedValue, PinnedThreadId as LuaPinnedThreadId,
+    PooledThread as LuaPooledThread, RandomSource as LuaRandomSource, RegistryKey as LuaRegistryKey,
+    Result as LuaResult, ResumeOutcome as LuaResumeOutcome, RuntimeErrorInfo as LuaRuntimeErrorInfo,
+    SourceMap as LuaSourceMap, StdLib as LuaStdLib, String as LuaString, Table as LuaTable,
+    TableKeys as LuaTableKeys, TablePairs as LuaTablePairs, TablePairsRaw as LuaTablePairsRaw,
+    TableSequence as LuaTableSequence, TableValues as LuaTableValues, Thread as LuaThread,
+    ThreadId as LuaThreadId, ThreadPool as LuaThreadPool, ThreadResetOptions as LuaThreadResetOptions,
+    ThreadStatus as LuaThreadStatus, Traceback as LuaTraceback, TracebackFrame as LuaTracebackFrame,
     UserData as LuaUserData, UserDataFields as LuaUserDataFields, UserDataMetatable as LuaUserDataMetatable,
     UserDataMethods as LuaUserDataMethods, UserDataRef as LuaUserDataRef,
     UserDataRefMut as LuaUserDataRefMut, UserDataRegistry as LuaUserDataRegistry, Value as LuaValue,
-    Variadic as LuaVariadic, VmState as LuaVmState, WeakLua,
+    Variadic as LuaVariadic, Vfs as LuaVfs, VfsFile as LuaVfsFile, VmState as LuaVmState, WeakLua,
 };
 
 #[cfg(not(feature = "luau"))]
@@ -24,10 +32,24 @@ pub use crate::HookTriggers as LuaHookTriggers;
 #[cfg(feature = "luau")]
 #[doc(no_inline)]
 pub use crate::{
-    CompileConstant as LuaCompileConstant, CoverageInfo as LuaCoverageInfo,
-    NavigateError as LuaNavigateError, Require as LuaRequire, Vector as LuaVector,
+    AllocEvent as LuaAllocEvent, BreakpointId as LuaBreakpointId, CompileConstant as LuaCompileConstant,
+    CompiledChunk as LuaCompiledChunk, CoverageInfo as LuaCoverageInfo, CoverageReport as LuaCoverageReport,
+    DebugAction as LuaDebugAction, Declaration as LuaDeclaration, DeclarationKind as LuaDeclarationKind,
+    GcPhase as LuaGcPhase, HeapCensus as LuaHeapCensus, HeapCensusEntry as LuaHeapCensusEntry,
+    LineCoverage as LuaLineCoverage, MemoryCategoryGuard as LuaMemoryCategoryGuard,
+    NavigateError as LuaNavigateError, ProfileReport as LuaProfileReport,
+    ProfilerOptions as LuaProfilerOptions, Require as LuaRequire, ThreadEvent as LuaThreadEvent,
+    TypeInfo as LuaTypeInfo, Vector as LuaVector,
 };
 
+#[cfg(feature = "luau-assert")]
+#[doc(no_inline)]
+pub use crate::{AssertAction as LuaAssertAction, AssertionFailure as LuaAssertionFailure};
+
+#[cfg(feature = "checked-int")]
+#[doc(no_inline)]
+pub use crate::CheckedInt as LuaCheckedInt;
+
 #[cfg(feature = "serde")]
 #[doc(no_inline)]
 pub use crate::{
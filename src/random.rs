@@ -0,0 +1,111 @@
+//! Host-provided random number generator for the Lua `math` library.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{Error, Result};
+use crate::state::Lua;
+use crate::types::{Integer, MaybeSend};
+use crate::value::Value;
+
+/// A source of random numbers used by the Lua `math.random` function.
+///
+/// See [`Lua::set_random_source`] for details.
+///
+/// The trait is implemented for closures returning random `u64` values. Such closures cannot be
+/// reseeded, so `math.randomseed` has no effect on them.
+pub trait RandomSource: MaybeSend + 'static {
+    /// Returns the next random value with all bits uniformly distributed.
+    fn next_u64(&mut self) -> u64;
+
+    /// Reseeds the generator, called by `math.randomseed`.
+    ///
+    /// The default implementation does nothing.
+    fn set_seed(&mut self, seed: u64) {
+        let _ = seed;
+    }
+}
+
+impl<F: FnMut() -> u64 + MaybeSend + 'static> RandomSource for F {
+    fn next_u64(&mut self) -> u64 {
+        self()
+    }
+}
+
+/// Replaces `math.random` and `math.randomseed` with functions backed by `source`.
+pub(crate) fn install(lua: &Lua, source: impl RandomSource) -> Result<()> {
+    let math = match lua.globals().raw_get::<Value>("math")? {
+        Value::Table(math) => math,
+        _ => return Err(Error::runtime("`math` library is not loaded")),
+    };
+    unsafe { (*lua.lock().extra()).random_source = Some(Box::new(source)) };
+
+    let random = lua.create_function(|lua, (m, n): (Option<Integer>, Option<Integer>)| {
+        let (low, up) = match (m, n) {
+            // Float in `[0, 1)` using the upper 53 bits
+            (None, _) => return Ok(Value::Number((next_u64(lua)? >> 11) as f64 / (1u64 << 53) as f64)),
+            #[cfg(feature = "lua54")]
+            (Some(0), None) => return Ok(Value::Integer(next_u64(lua)? as Integer)),
+            (Some(up), None) => (1, up),
+            (Some(low), Some(up)) => (low, up),
+        };
+        if low > up {
+            let arg = if n.is_some() { 2 } else { 1 };
+            return Err(Error::runtime(format!(
+                "bad argument #{arg} to 'random' (interval is empty)"
+            )));
+        }
+        let offset = project(next_u64(lua)?, up.wrapping_sub(low) as u64, lua)?;
+        Ok(Value::Integer(low.wrapping_add(offset as Integer)))
+    })?;
+
+    let randomseed = lua.create_function(|lua, seed: Option<Value>| {
+        let seed = match seed {
+            Some(Value::Integer(i)) => i as u64,
+            Some(Value::Number(n)) if n.fract() == 0.0 && n.abs() < 2f64.powi(63) => n as i64 as u64,
+            Some(Value::Number(n)) => n.to_bits(),
+            Some(value) => {
+                return Err(Error::runtime(format!(
+                    "bad argument #1 to 'randomseed' (number expected, got {})",
+                    value.type_name()
+                )))
+            }
+            None => {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+                now.as_nanos() as u64
+            }
+        };
+        with_source(lua, |source| source.set_seed(seed))
+    })?;
+
+    Lua::raw_set_global(&math, "random", random)?;
+    Lua::raw_set_global(&math, "randomseed", randomseed)
+}
+
+fn with_source<R>(lua: &Lua, f: impl FnOnce(&mut dyn RandomSource) -> R) -> Result<R> {
+    let lua = lua.lock();
+    match unsafe { (*lua.extra()).random_source.as_deref_mut() } {
+        Some(source) => Ok(f(source)),
+        None => Err(Error::runtime("random source is not set")),
+    }
+}
+
+fn next_u64(lua: &Lua) -> Result<u64> {
+    with_source(lua, |source| source.next_u64())
+}
+
+// Projects a random value into the interval `[0, n]`, the same way as Lua 5.4 does.
+fn project(mut rv: u64, n: u64, lua: &Lua) -> Result<u64> {
+    if n & n.wrapping_add(1) == 0 {
+        // `n + 1` is a power of 2
+        return Ok(rv & n);
+    }
+    // Compute the smallest `2^b - 1` not smaller than `n`
+    let lim = u64::MAX >> n.leading_zeros();
+    loop {
+        rv &= lim;
+        if rv <= n {
+            return Ok(rv);
+        }
+        rv = next_u64(lua)?;
+    }
+}
@@ -531,7 +531,7 @@ impl ser::SerializeStruct for SerializeStruct<'_> {
             Some(table @ Value::Table(_)) => Ok(table),
             Some(value) if self.options.detect_serde_json_arbitrary_precision => {
                 let number_s = value.as_str().expect("not an arbitrary precision number");
-                if number_s.contains(['.', 'e', 'E']) {
+                if number_s.contains(['.', 'e', 'E']) || number_s == "-0" {
                     if let Ok(number) = number_s.parse().map(Value::Number) {
                         return Ok(number);
                     }
@@ -0,0 +1,253 @@
+use std::ffi::CStr;
+use std::marker::PhantomData;
+use std::os::raw::c_int;
+use std::string::String as StdString;
+
+use crate::error::{Error, Result};
+use crate::state::{Lua, RawLua};
+use crate::traits::{FromLua, IntoLuaMulti, ShortTypeName as _};
+use crate::types::{Callback, MaybeSend};
+use crate::value::Nil;
+
+/// A declarative description of a Rust function arguments.
+///
+/// Created by [`Lua::create_function_spec`]. Each call to [`FunctionSpec::arg`] declares the next
+/// positional argument; once all arguments are declared, [`FunctionSpec::build`] attaches the
+/// handler which receives the converted arguments as a tuple.
+///
+/// Conversion errors are reported using the standard Lua wording, eg.
+/// `bad argument #2 to 'ban' (number expected, got string)`.
+/// Missing trailing arguments are converted from `nil`, so [`Option`] arguments default to `None`.
+///
+/// If the first argument is named `self`, the function is treated as a method and errors for it
+/// are reported as `calling 'name' on bad self`, with the rest of arguments numbered from 1.
+pub struct FunctionSpec<A> {
+    name: StdString,
+    args: Vec<StdString>,
+    strict: bool,
+    _phantom: PhantomData<fn() -> A>,
+}
+
+/// A [`FunctionSpec`] with a handler attached, ready to be turned into a [`Function`].
+///
+/// [`Function`]: crate::Function
+pub struct BuiltFunctionSpec(pub(crate) Callback);
+
+impl FunctionSpec<()> {
+    pub(crate) fn new(name: &str) -> Self {
+        FunctionSpec {
+            name: name.to_string(),
+            args: Vec::new(),
+            strict: false,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<A: SpecArgs> FunctionSpec<A> {
+    /// Declares the next argument of type `T` with the given `name`.
+    pub fn arg<T: FromLua>(self, name: &str) -> FunctionSpec<A::Output>
+    where
+        A: SpecPush<T>,
+    {
+        let mut args = self.args;
+        args.push(name.to_string());
+        FunctionSpec {
+            name: self.name,
+            args,
+            strict: self.strict,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Rejects calls with more arguments than declared.
+    ///
+    /// Default: **false**
+    pub fn strict(mut self, enabled: bool) -> Self {
+        self.strict = enabled;
+        self
+    }
+
+    /// Attaches the handler that is called with the converted arguments.
+    pub fn build<F, R>(self, func: F) -> BuiltFunctionSpec
+    where
+        F: Fn(&Lua, A) -> Result<R> + MaybeSend + 'static,
+        R: IntoLuaMulti,
+    {
+        BuiltFunctionSpec(Box::new(move |rawlua, nargs| unsafe {
+            let state = rawlua.state();
+            if self.strict && nargs as usize > A::COUNT {
+                return Err(Error::runtime(format!(
+                    "wrong number of arguments to '{}'",
+                    self.name
+                )));
+            }
+            let args = A::from_spec_stack(nargs, &self.name, &self.args, rawlua, state)?;
+            func(rawlua.lua(), args)?.push_into_specified_stack_multi(rawlua, state)
+        }))
+    }
+}
+
+/// Tuple of arguments declared in a [`FunctionSpec`].
+#[doc(hidden)]
+pub trait SpecArgs: Sized {
+    const COUNT: usize;
+
+    #[doc(hidden)]
+    unsafe fn from_spec_stack(
+        nargs: c_int,
+        func: &str,
+        names: &[StdString],
+        lua: &RawLua,
+        state: *mut ffi::lua_State,
+    ) -> Result<Self>;
+}
+
+/// Tuple of arguments that can be extended with one more argument of type `T`.
+#[doc(hidden)]
+pub trait SpecPush<T>: SpecArgs {
+    type Output: SpecArgs;
+}
+
+/// Converts argument `pos` (1-based) from the Lua stack, producing a Lua-style error on failure.
+unsafe fn spec_arg<T: FromLua>(
+    nargs: c_int,
+    pos: c_int,
+    func: &str,
+    names: &[StdString],
+    lua: &RawLua,
+    state: *mut ffi::lua_State,
+) -> Result<T> {
+    let idx = pos - nargs - 1;
+    let result = if pos <= nargs {
+        T::from_specified_stack(idx, lua, state)
+    } else {
+        T::from_lua(Nil, lua.lua())
+    };
+    result.map_err(|err| {
+        let got = if pos <= nargs {
+            CStr::from_ptr(ffi::luaL_typename(state, idx)).to_string_lossy()
+        } else {
+            "no value".into()
+        };
+        let extramsg = match err {
+            Error::FromLuaConversionError { to, .. } if to == "userdata" => {
+                format!("{} expected, got {got}", userdata_type_name::<T>(lua))
+            }
+            Error::FromLuaConversionError { from, to, message } => {
+                let expected = lua_type_name(&to);
+                if pos <= nargs && lua_type_name(from) == expected {
+                    // The type is right, but the value is not acceptable
+                    message.unwrap_or_else(|| format!("invalid {expected}"))
+                } else {
+                    format!("{expected} expected, got {got}")
+                }
+            }
+            Error::UserDataTypeMismatch => format!("{} expected, got {got}", userdata_type_name::<T>(lua)),
+            err => err.to_string(),
+        };
+        match names.first() {
+            Some(name) if name == "self" && pos == 1 => {
+                Error::runtime(format!("calling '{func}' on bad self ({extramsg})"))
+            }
+            Some(name) if name == "self" => {
+                Error::runtime(format!("bad argument #{} to '{func}' ({extramsg})", pos - 1))
+            }
+            _ => Error::runtime(format!("bad argument #{pos} to '{func}' ({extramsg})")),
+        }
+    })
+}
+
+/// Maps a conversion target to the corresponding Lua type name where possible.
+fn lua_type_name(to: &str) -> &str {
+    match to {
+        "i8" | "u8" | "i16" | "u16" | "i32" | "u32" | "i64" | "u64" | "i128" | "u128" | "isize" | "usize"
+        | "f32" | "f64" | "integer" => "number",
+        "String" | "Box<str>" | "CString" | "BString" | "OsString" | "PathBuf" | "char" => "string",
+        "bool" => "boolean",
+        _ if to.starts_with("Vec<") || to.starts_with("HashMap<") || to.starts_with("BTreeMap<") => "table",
+        _ => to
+            .strip_prefix("Option<")
+            .and_then(|s| s.strip_suffix('>'))
+            .map_or(to, lua_type_name),
+    }
+}
+
+/// Returns the name of a userdata type without `UserDataRef`-like wrappers.
+///
+/// Custom names set by [`Lua::register_userdata_type_with_name`] take precedence.
+///
+/// [`Lua::register_userdata_type_with_name`]: crate::Lua::register_userdata_type_with_name
+fn userdata_type_name<T: FromLua>(lua: &RawLua) -> StdString {
+    let mut name = T::type_name();
+    for wrapper in ["Option<", "UserDataRef<", "UserDataRefMut<"] {
+        if let Some(inner) = name.strip_prefix(wrapper).and_then(|s| s.strip_suffix('>')) {
+            name = inner.to_string();
+        }
+    }
+    match name.as_str() {
+        "AnyUserData" => "userdata".to_string(),
+        _ => T::userdata_type_id()
+            .and_then(|type_id| lua.custom_userdata_type_name(type_id))
+            .unwrap_or(name),
+    }
+}
+
+macro_rules! impl_spec_args {
+    ($($name:ident)*) => (
+        impl<$($name: FromLua,)*> SpecArgs for ($($name,)*) {
+            const COUNT: usize = <[&str]>::len(&[$(stringify!($name)),*]);
+
+            #[allow(unused_mut, unused_variables, non_snake_case)]
+            unsafe fn from_spec_stack(
+                nargs: c_int,
+                func: &str,
+                names: &[StdString],
+                lua: &RawLua,
+                state: *mut ffi::lua_State,
+            ) -> Result<Self> {
+                let mut pos = 0;
+                $(
+                    pos += 1;
+                    let $name = spec_arg::<$name>(nargs, pos, func, names, lua, state)?;
+                )*
+                Ok(($($name,)*))
+            }
+        }
+    );
+}
+
+macro_rules! impl_spec_push {
+    ($($name:ident)* => $next:ident) => (
+        impl<$($name: FromLua,)* $next: FromLua> SpecPush<$next> for ($($name,)*) {
+            type Output = ($($name,)* $next,);
+        }
+    );
+}
+
+impl_spec_args!();
+impl_spec_args!(A);
+impl_spec_args!(A B);
+impl_spec_args!(A B C);
+impl_spec_args!(A B C D);
+impl_spec_args!(A B C D E);
+impl_spec_args!(A B C D E F);
+impl_spec_args!(A B C D E F G);
+impl_spec_args!(A B C D E F G H);
+impl_spec_args!(A B C D E F G H I);
+impl_spec_args!(A B C D E F G H I J);
+impl_spec_args!(A B C D E F G H I J K);
+impl_spec_args!(A B C D E F G H I J K L);
+
+impl_spec_push!(=> A);
+impl_spec_push!(A => B);
+impl_spec_push!(A B => C);
+impl_spec_push!(A B C => D);
+impl_spec_push!(A B C D => E);
+impl_spec_push!(A B C D E => F);
+impl_spec_push!(A B C D E F => G);
+impl_spec_push!(A B C D E F G => H);
+impl_spec_push!(A B C D E F G H => I);
+impl_spec_push!(A B C D E F G H I => J);
+impl_spec_push!(A B C D E F G H I J => K);
+impl_spec_push!(A B C D E F G H I J K => L);
@@ -1,46 +1,59 @@
 use std::any::TypeId;
 use std::cell::{BorrowError, BorrowMutError, RefCell};
+use std::ffi::CStr;
 use std::marker::PhantomData;
 use std::ops::Deref;
-use std::os::raw::{c_char, c_int};
+use std::os::raw::{c_char, c_int, c_void};
 use std::panic::Location;
 use std::result::Result as StdResult;
+use std::string::String as StdString;
 use std::{fmt, mem, ptr};
 
 use crate::chunk::{AsChunk, Chunk};
-use crate::error::{Error, Result};
+use crate::error::{Error, Result, Traceback};
 use crate::function::Function;
 use crate::hook::Debug;
-use crate::memory::MemoryState;
+use crate::memory::{LuaAllocator, MemoryState};
 use crate::multi::MultiValue;
+use crate::spec::{BuiltFunctionSpec, FunctionSpec};
 use crate::state::util::get_next_spot;
 use crate::stdlib::StdLib;
 use crate::string::String;
 use crate::table::Table;
-use crate::thread::Thread;
+use crate::thread::{PinnedThreadId, ResumeOutcome, Thread, ThreadStatus};
 
 #[cfg(all(not(feature = "lua51"), not(feature = "luajit")))]
 use crate::thread::ContinuationStatus;
 
 use crate::traits::{FromLua, FromLuaMulti, IntoLua, IntoLuaMulti};
 use crate::types::{
-    AppDataRef, AppDataRefMut, ArcReentrantMutexGuard, Integer, LuaType, MaybeSend, Number, ReentrantMutex,
-    ReentrantMutexGuard, RegistryKey, VmState, XRc, XWeak,
+    AppDataRef, AppDataRefMut, ArcReentrantMutexGuard, Callback, Integer, LuaType, MaybeSend, Number,
+    ReentrantMutex, ReentrantMutexGuard, RegistryEntry, RegistryKey, VmState, XRc, XWeak,
+};
+use crate::userdata::{AnyUserData, MetaMethod, UserData, UserDataProxy, UserDataRegistry, UserDataStorage};
+use crate::util::{
+    assert_stack, check_stack, protect_lua_closure, push_string, rawset_field, short_type_name,
+    with_callee_name, StackGuard,
 };
-use crate::userdata::{AnyUserData, UserData, UserDataProxy, UserDataRegistry, UserDataStorage};
-use crate::util::{assert_stack, check_stack, protect_lua_closure, push_string, rawset_field, StackGuard};
 use crate::value::{Nil, Value};
 
 #[cfg(not(feature = "luau"))]
 use crate::{hook::HookTriggers, types::HookKind};
 
 #[cfg(any(feature = "luau", doc))]
-use crate::{buffer::Buffer, chunk::Compiler};
+use std::time::Duration;
+
+#[cfg(any(feature = "luau", doc))]
+use crate::{
+    buffer::Buffer,
+    chunk::{CompiledChunk, Compiler},
+    thread::{ThreadEvent, ThreadId},
+};
 
 #[cfg(feature = "serde")]
 use serde::Serialize;
 
-pub(crate) use extra::ExtraData;
+pub(crate) use extra::{ExtraData, PendingFinalizer, UserDataTracker};
 pub use raw::RawLua;
 pub(crate) use util::callback_error_ext;
 
@@ -59,6 +72,10 @@ pub struct WeakLua(XWeak<ReentrantMutex<RawLua>>);
 
 pub(crate) struct LuaGuard(ArcReentrantMutexGuard<RawLua>);
 
+// Functions tracked by `Lua::coverage_report`
+#[cfg(feature = "luau")]
+struct TrackedCoverage(Vec<RegistryKey>);
+
 /// Mode of the Lua garbage collector (GC).
 ///
 /// In Lua 5.4 GC can work in two modes: incremental and generational.
@@ -75,6 +92,140 @@ pub enum GCMode {
     Generational,
 }
 
+/// Boundary of a garbage collection cycle reported to [`Lua::set_gc_callback`].
+#[cfg(any(feature = "luau", doc))]
+#[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GcPhase {
+    /// A new collection cycle has started.
+    Start,
+    /// A collection cycle has finished.
+    End {
+        /// Difference between the heap size at the start and at the end of the cycle (in bytes).
+        collected: usize,
+    },
+}
+
+/// A memory operation reported to [`Lua::set_allocation_callback`].
+#[cfg(any(feature = "luau", doc))]
+#[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct AllocEvent {
+    /// Previous size of the block (zero for new blocks).
+    pub osize: usize,
+    /// New size of the block (zero for freed blocks).
+    pub nsize: usize,
+    /// Memory category of the thread that made the operation.
+    pub memcat: u8,
+}
+
+/// Keeps the garbage collector stopped while alive.
+///
+/// Returned by [`Lua::gc_pause_guard`].
+#[must_use = "the garbage collector is resumed when the guard is dropped"]
+pub struct GcGuard {
+    lua: WeakLua,
+    step_kbytes: Option<c_int>,
+}
+
+impl GcGuard {
+    /// Performs a collector step of `kbytes` when the guard is dropped, to catch up with the
+    /// garbage allocated while the collector was stopped.
+    ///
+    /// See [`Lua::gc_step_kbytes`] for details. The step is skipped if the guard is dropped
+    /// during panic unwinding.
+    pub fn step_on_drop(mut self, kbytes: c_int) -> Self {
+        self.step_kbytes = Some(kbytes);
+        self
+    }
+}
+
+impl Drop for GcGuard {
+    fn drop(&mut self) {
+        let Some(lua) = self.lua.try_upgrade() else {
+            return;
+        };
+        let resume = unsafe {
+            let extra = lua.lock().extra.get();
+            (*extra).gc_pause_depth -= 1;
+            (*extra).gc_pause_depth == 0 && (*extra).gc_resume_on_unpause
+        };
+        if resume {
+            lua.gc_restart();
+        }
+        if let Some(kbytes) = self.step_kbytes {
+            if !std::thread::panicking() {
+                let _ = lua.gc_step_kbytes(kbytes);
+            }
+        }
+    }
+}
+
+/// Restores the previous memory category of the thread it was created on when dropped.
+///
+/// Returned by [`Lua::set_memory_category`].
+#[cfg(any(feature = "luau", doc))]
+#[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+#[must_use = "the previous memory category is restored when the guard is dropped"]
+pub struct MemoryCategoryGuard {
+    thread: Thread,
+    previous: u8,
+}
+
+#[cfg(feature = "luau")]
+impl Drop for MemoryCategoryGuard {
+    fn drop(&mut self) {
+        if let Some(_lua) = self.thread.0.lua.try_lock() {
+            unsafe { Lua::set_thread_memory_category(self.thread.1, self.previous) };
+        }
+    }
+}
+
+/// Semantics of numeric `for` loops in the active Lua backend.
+///
+/// Returned by [`Lua::for_loop_semantics`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ForLoopSemantics {
+    /// Loops with integer start and step use integers. The number of iterations is computed
+    /// before the loop starts, so the loop never overflows (Lua 5.4).
+    Integer,
+    /// Loops with integer start and step use integers. The loop variable wraps around on
+    /// overflow, so a loop ending at `math.maxinteger` never terminates (Lua 5.3).
+    IntegerWrapping,
+    /// All loops use floating point numbers (Lua 5.1, Lua 5.2, LuaJIT and Luau).
+    ///
+    /// Loops near 2^53 may skip values or never terminate because of precision loss.
+    Float,
+}
+
+impl ForLoopSemantics {
+    /// Returns `true` if loops with integer bounds use integer arithmetic.
+    pub const fn is_integer(self) -> bool {
+        matches!(
+            self,
+            ForLoopSemantics::Integer | ForLoopSemantics::IntegerWrapping
+        )
+    }
+}
+
+/// Clock read by the `os` library functions when a clock source is set.
+///
+/// See [`Lua::set_clock_source`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockKind {
+    /// Current time in seconds since the Unix epoch, used by `os.time` and `os.date`.
+    Time,
+    /// Processor time used by the program in seconds, used by `os.clock`.
+    Cpu,
+}
+
+// Key to store the original `print` function in the registry
+static PRINT_KEY: u8 = 0;
+
+// Key to store the original `os` library functions replaced by a clock source in the registry
+static OS_CLOCK_KEY: u8 = 0;
+
 /// Controls Lua interpreter behavior such as Rust panics handling.
 #[derive(Clone, Debug)]
 #[non_exhaustive]
@@ -92,6 +243,62 @@ pub struct LuaOptions {
     /// [`pcall`]: https://www.lua.org/manual/5.4/manual.html#pdf-pcall
     /// [`xpcall`]: https://www.lua.org/manual/5.4/manual.html#pdf-xpcall
     pub catch_rust_panics: bool,
+
+    /// Reject non-integral numbers when converting to Rust integer types.
+    ///
+    /// If enabled, converting a number with a fractional part (eg. `3.5`) to an integer type
+    /// returns [`Error::FromLuaConversionError`] instead of truncating it.
+    ///
+    /// Default: **false**
+    pub strict_number_coercion: bool,
+
+    /// Maximum size (in bytes) of a string produced by `string.rep` or `table.concat`.
+    ///
+    /// These functions are checked before building the result, so a single call cannot produce
+    /// an oversized string. This is not a bound on string sizes in general: strings built in other
+    /// ways (eg. with the `..` operator, `string.format` or `string.gsub`) are not checked. Use
+    /// [`Lua::set_memory_limit`] to bound the total memory usage.
+    ///
+    /// Default: **unlimited**
+    pub max_string_bytes: Option<usize>,
+
+    /// Maximum number of entries of a table preallocated by `table.create`.
+    ///
+    /// This is not a bound on table sizes in general: tables grown by assignments, constructors
+    /// or other library functions are not checked. Use [`Lua::set_memory_limit`] to bound the
+    /// total memory usage.
+    ///
+    /// Default: **unlimited**
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub max_table_entries: Option<usize>,
+
+    /// Capture a structured traceback when a Lua error is raised.
+    ///
+    /// If enabled, errors raised in Lua code or returned from Rust callbacks are wrapped in
+    /// [`Error::WithTraceback`] carrying the call stack at the point where the error was raised,
+    /// available through [`Error::traceback`]. This changes the shape of returned errors (eg.
+    /// [`Error::RuntimeError`] is no longer the outermost variant), and walking the stack has a
+    /// cost for every raised error.
+    ///
+    /// Default: **false**
+    pub capture_tracebacks: bool,
+
+    /// Always capture a backtrace when a Rust panic is caught at the boundary of a Rust callback.
+    ///
+    /// By default the backtrace is captured only if enabled by the `RUST_BACKTRACE` or
+    /// `RUST_LIB_BACKTRACE` environment variables. It's available through
+    /// [`Error::panic_backtrace`] when the panic is caught by Lua code.
+    ///
+    /// Default: **false**
+    pub capture_panic_backtraces: bool,
+
+    /// Track the peak memory usage reported by [`Lua::peak_memory`].
+    ///
+    /// Tracking adds a comparison to every allocation.
+    ///
+    /// Default: **true**
+    pub track_peak_memory: bool,
 }
 
 impl Default for LuaOptions {
@@ -105,6 +312,13 @@ impl LuaOptions {
     pub const fn new() -> Self {
         LuaOptions {
             catch_rust_panics: true,
+            strict_number_coercion: false,
+            max_string_bytes: None,
+            #[cfg(any(feature = "luau", doc))]
+            max_table_entries: None,
+            capture_tracebacks: false,
+            capture_panic_backtraces: false,
+            track_peak_memory: true,
         }
     }
 
@@ -116,6 +330,87 @@ impl LuaOptions {
         self.catch_rust_panics = enabled;
         self
     }
+
+    /// Sets [`strict_number_coercion`] option.
+    ///
+    /// [`strict_number_coercion`]: #structfield.strict_number_coercion
+    #[must_use]
+    pub const fn strict_number_coercion(mut self, enabled: bool) -> Self {
+        self.strict_number_coercion = enabled;
+        self
+    }
+
+    /// Sets [`max_string_bytes`] option.
+    ///
+    /// [`max_string_bytes`]: #structfield.max_string_bytes
+    #[must_use]
+    pub const fn max_string_bytes(mut self, limit: usize) -> Self {
+        self.max_string_bytes = Some(limit);
+        self
+    }
+
+    /// Sets [`max_table_entries`] option.
+    ///
+    /// [`max_table_entries`]: #structfield.max_table_entries
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    #[must_use]
+    pub const fn max_table_entries(mut self, limit: usize) -> Self {
+        self.max_table_entries = Some(limit);
+        self
+    }
+
+    /// Sets [`capture_tracebacks`] option.
+    ///
+    /// [`capture_tracebacks`]: #structfield.capture_tracebacks
+    #[must_use]
+    pub const fn capture_tracebacks(mut self, enabled: bool) -> Self {
+        self.capture_tracebacks = enabled;
+        self
+    }
+
+    /// Sets [`capture_panic_backtraces`] option.
+    ///
+    /// [`capture_panic_backtraces`]: #structfield.capture_panic_backtraces
+    #[must_use]
+    pub const fn capture_panic_backtraces(mut self, enabled: bool) -> Self {
+        self.capture_panic_backtraces = enabled;
+        self
+    }
+
+    /// Sets [`track_peak_memory`] option.
+    ///
+    /// [`track_peak_memory`]: #structfield.track_peak_memory
+    #[must_use]
+    pub const fn track_peak_memory(mut self, enabled: bool) -> Self {
+        self.track_peak_memory = enabled;
+        self
+    }
+}
+
+/// Returns caller location to record for registry values (only in debug builds).
+#[inline]
+#[track_caller]
+fn registry_location() -> Option<&'static Location<'static>> {
+    if cfg!(debug_assertions) {
+        Some(Location::caller())
+    } else {
+        None
+    }
+}
+
+/// Returns the name of a Rust function `F` created at the caller location, to show in tracebacks
+/// (only with `named-closures` feature).
+#[inline]
+#[track_caller]
+fn callback_name<F>() -> Option<StdString> {
+    if cfg!(all(feature = "named-closures", feature = "luau")) {
+        let location = Location::caller();
+        let type_name = std::any::type_name::<F>();
+        Some(format!("{type_name} ({}:{})", location.file(), location.line()))
+    } else {
+        None
+    }
 }
 
 impl Drop for Lua {
@@ -182,6 +477,33 @@ impl Lua {
     ///
     /// See [`StdLib`] documentation for a list of unsafe modules that cannot be loaded.
     pub fn new_with(libs: StdLib, options: LuaOptions) -> Result<Lua> {
+        Self::new_with_inner(libs, options, None)
+    }
+
+    /// Creates a new Lua state backed by a host provided memory `allocator`.
+    ///
+    /// Works like [`Lua::new_with`], but all memory of the Lua state is requested from
+    /// `allocator`, for example a per-request arena. The memory limit and usage metrics of mlua are
+    /// applied on top of it. The allocator is dropped after the Lua state is closed.
+    ///
+    /// Returns [`Error::MemoryError`] if the allocator cannot allocate the Lua state.
+    ///
+    /// Not available for LuaJIT, which does not support custom allocators on 64-bit platforms.
+    #[cfg(not(feature = "luajit"))]
+    #[cfg_attr(docsrs, doc(cfg(not(feature = "luajit"))))]
+    pub fn new_with_allocator(
+        libs: StdLib,
+        options: LuaOptions,
+        allocator: impl LuaAllocator,
+    ) -> Result<Lua> {
+        Self::new_with_inner(libs, options, Some(Box::new(allocator)))
+    }
+
+    fn new_with_inner(
+        libs: StdLib,
+        options: LuaOptions,
+        allocator: Option<Box<dyn LuaAllocator>>,
+    ) -> Result<Lua> {
         #[cfg(not(feature = "luau"))]
         if libs.contains(StdLib::DEBUG) {
             return Err(Error::SafetyError(
@@ -195,7 +517,7 @@ impl Lua {
             ));
         }
 
-        let lua = unsafe { Self::inner_new(libs, options) };
+        let lua = unsafe { Self::inner_new(libs, options, allocator)? };
 
         #[cfg(not(feature = "luau"))]
         if libs.contains(StdLib::PACKAGE) {
@@ -206,6 +528,78 @@ impl Lua {
         Ok(lua)
     }
 
+    /// Creates a new Lua state hardened for running untrusted scripts.
+    ///
+    /// This is the recommended entry point for security-sensitive embedders. It bundles the
+    /// following steps:
+    /// - loads only the libraries without access to the host: `table`, `string`, `math`, `utf8` and
+    ///   `coroutine` (where available). On Luau all the standard libraries ([`StdLib::ALL_SAFE`])
+    ///   are loaded, as none of them can reach the host.
+    /// - removes the `load`, `loadstring`, `loadfile` and `dofile` functions on non-Luau, and the
+    ///   default `require` function (which loads modules from the filesystem) on Luau
+    /// - makes globals strict: reading an undefined global variable raises an error instead of
+    ///   returning `nil`. The globals metatable is locked.
+    /// - enables the Luau sandbox (see [`Lua::sandbox`]), on Luau only
+    /// - limits the memory usage to 64 MiB (see [`Lua::set_memory_limit`])
+    ///
+    /// Each setting can be changed afterwards. Strict globals are implemented as a metatable of the
+    /// globals table, so reading missing globals from Rust with [`Table::get`] fails too, use
+    /// [`Table::raw_get`] instead. The call depth is bounded by the limits the Lua backend is
+    /// compiled with, which cannot be changed at runtime.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, Result};
+    /// # fn main() -> Result<()> {
+    /// let lua = Lua::new_sandboxed()?;
+    /// assert_eq!(lua.load("return 1 + 1").eval::<i32>()?, 2);
+    /// assert!(lua.load("return undefined_var").exec().is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_sandboxed() -> Result<Lua> {
+        #[cfg(feature = "luau")]
+        let libs = StdLib::ALL_SAFE;
+        #[cfg(not(feature = "luau"))]
+        let libs = StdLib::TABLE | StdLib::STRING | StdLib::MATH;
+        #[cfg(any(feature = "lua54", feature = "lua53"))]
+        let libs = libs | StdLib::UTF8;
+        #[cfg(any(feature = "lua54", feature = "lua53", feature = "lua52"))]
+        let libs = libs | StdLib::COROUTINE;
+        let lua = Self::new_with(libs, LuaOptions::default())?;
+
+        #[cfg(not(feature = "luau"))]
+        for name in ["load", "loadstring", "loadfile", "dofile"] {
+            lua.globals().raw_set(name, Nil)?;
+        }
+        // The default Luau `require` loads modules from the filesystem
+        #[cfg(feature = "luau")]
+        lua.globals().raw_set("require", Nil)?;
+
+        // Keep references to the functions in case the script replaces them
+        let strict_index = (lua.load(
+            r#"
+            local error, tostring = error, tostring
+            return function(_, name)
+                error("attempt to read undefined global '" .. tostring(name) .. "'", 2)
+            end
+            "#,
+        ))
+        .set_name("=strict")
+        .eval::<Function>()?;
+        let metatable = lua.create_table()?;
+        metatable.raw_set("__index", strict_index)?;
+        metatable.raw_set("__metatable", false)?;
+        lua.globals().set_metatable(Some(metatable));
+
+        #[cfg(feature = "luau")]
+        lua.sandbox(true)?;
+        lua.set_memory_limit(64 * 1024 * 1024)?;
+
+        Ok(lua)
+    }
+
     /// Creates a new Lua state and loads the specified subset of the standard libraries.
     ///
     /// Use the [`StdLib`] flags to specify the libraries you want to load.
@@ -231,20 +625,28 @@ impl Lua {
             _symbols.push(ffi::luaL_setfuncs as _);
         }
 
-        Self::inner_new(libs, options)
+        mlua_expect!(Self::inner_new(libs, options, None), "Error creating Lua state")
     }
 
-    /// Creates a new Lua state with required `libs` and `options`
-    unsafe fn inner_new(libs: StdLib, options: LuaOptions) -> Lua {
+    /// Creates a new Lua state with required `libs`, `options` and optional custom allocator
+    ///
+    /// Fails only if the custom allocator cannot allocate the state.
+    unsafe fn inner_new(
+        libs: StdLib,
+        options: LuaOptions,
+        allocator: Option<Box<dyn LuaAllocator>>,
+    ) -> Result<Lua> {
         let lua = Lua {
-            raw: RawLua::new(libs, &options),
+            raw: RawLua::new(libs, &options, allocator)?,
             collect_garbage: true,
         };
 
         #[cfg(feature = "luau")]
         mlua_expect!(lua.configure_luau(), "Error configuring Luau");
 
-        lua
+        mlua_expect!(lua.apply_size_limits(libs), "Error applying size limits");
+
+        Ok(lua)
     }
 
     /// Returns or constructs Lua instance from a raw state.
@@ -316,7 +718,13 @@ impl Lua {
     ///
     /// Use the [`StdLib`] flags to specify the libraries you want to load.
     pub fn load_std_libs(&self, libs: StdLib) -> Result<()> {
-        unsafe { self.lock().load_std_libs(libs) }
+        let new_libs = {
+            let lua = self.lock();
+            let curr_libs = unsafe { (*lua.extra.get()).libs };
+            unsafe { lua.load_std_libs(libs)? };
+            libs ^ (libs & curr_libs)
+        };
+        self.apply_size_limits(new_libs)
     }
 
     /// Registers module into an existing Lua state using the specified value.
@@ -429,7 +837,8 @@ impl Lua {
 
         callback_error_ext(state, ptr::null_mut(), true, move |extra, nargs| {
             let rawlua = (*extra).raw_lua();
-            let args = A::from_specified_stack_args(nargs, 1, None, rawlua, state)?;
+            let args = A::from_specified_stack_args(nargs, 1, None, rawlua, state)
+                .map_err(|err| with_callee_name(state, err))?;
             func(rawlua.lua(), args)?.push_into_specified_stack(rawlua, state)?;
             Ok(1)
         })
@@ -652,32 +1061,11 @@ impl Lua {
     where
         F: Fn(&Lua) -> Result<VmState> + MaybeSend + 'static,
     {
-        unsafe extern "C-unwind" fn interrupt_proc(state: *mut ffi::lua_State, gc: c_int) {
-            if gc >= 0 {
-                // We don't support GC interrupts since they cannot survive Lua exceptions
-                return;
-            }
-            let result = callback_error_ext(state, ptr::null_mut(), false, move |extra, _| {
-                let interrupt_cb = (*extra).interrupt_callback.clone();
-                let interrupt_cb = mlua_expect!(interrupt_cb, "no interrupt callback set in interrupt_proc");
-                if XRc::strong_count(&interrupt_cb) > 2 {
-                    return Ok(VmState::Continue); // Don't allow recursion
-                }
-                interrupt_cb((*extra).lua())
-            });
-            match result {
-                VmState::Continue => {}
-                VmState::Yield => {
-                    ffi::lua_yield(state, 0);
-                }
-            }
-        }
-
         // Set interrupt callback
         let lua = self.lock();
         unsafe {
             (*lua.extra.get()).interrupt_callback = Some(XRc::new(callback));
-            (*ffi::lua_callbacks(lua.main_state())).interrupt = Some(interrupt_proc);
+            Self::update_interrupt_proc(&lua);
         }
     }
 
@@ -690,112 +1078,806 @@ impl Lua {
         let lua = self.lock();
         unsafe {
             (*lua.extra.get()).interrupt_callback = None;
-            (*ffi::lua_callbacks(lua.main_state())).interrupt = None;
+            Self::update_interrupt_proc(&lua);
         }
     }
 
-    /// Sets a thread creation callback that will be called when a thread is created.
+    /// Sets how often the interrupt function set by [`Lua::set_interrupt`] is called.
+    ///
+    /// Luau does not count instructions: the VM checks for interrupts at function calls and loop
+    /// iterations only. With an interval of `n`, the interrupt function is called on every `n`-th
+    /// check and the other checks are skipped cheaply. Larger intervals reduce the overhead of
+    /// the interrupt function at the cost of reacting later (eg. to a deadline), while code
+    /// without calls or loops is never interrupted regardless of the interval.
+    ///
+    /// The default interval is `1`, calling the interrupt function on every check. Zero is treated
+    /// as `1`. The profiler and GC callbacks are not affected.
     #[cfg(any(feature = "luau", doc))]
     #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
-    pub fn set_thread_creation_callback<F>(&self, callback: F)
-    where
-        F: Fn(&Lua, Thread) -> Result<()> + MaybeSend + 'static,
-    {
+    pub fn set_interrupt_interval(&self, interval: u32) {
         let lua = self.lock();
         unsafe {
-            (*lua.extra.get()).thread_creation_callback = Some(XRc::new(callback));
-            (*ffi::lua_callbacks(lua.main_state())).userthread = Some(Self::userthread_proc);
+            let extra = lua.extra.get();
+            (*extra).interrupt_interval = interval.max(1);
+            (*extra).interrupt_ticks = 0;
         }
     }
 
-    /// Sets a thread collection callback that will be called when a thread is destroyed.
+    /// Starts the built-in sampling profiler.
     ///
-    /// Luau GC does not support exceptions during collection, so the callback must be
-    /// non-panicking. If the callback panics, the program will be aborted.
+    /// The profiler is driven by the Luau interrupt handler: every
+    /// [`interval_ticks`](crate::ProfilerOptions::interval_ticks) interrupts the call stack of the
+    /// running thread is recorded. Any interrupt function set with [`Lua::set_interrupt`] keeps
+    /// working while profiling. Starting the profiler again discards the samples collected so far.
+    ///
+    /// Collected samples can be retrieved with [`Lua::stop_profiling`]. When the profiler is not
+    /// running it adds no overhead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use mlua::{Lua, ProfilerOptions, Result};
+    /// # #[cfg(feature = "luau")]
+    /// # fn main() -> Result<()> {
+    /// let lua = Lua::new();
+    /// lua.start_profiling(ProfilerOptions::new().interval_ticks(10));
+    /// lua.load("for i = 1, 10000 do local _ = tostring(i) end").exec()?;
+    /// let report = lua.stop_profiling().unwrap();
+    /// assert!(report.total_samples() > 0);
+    /// println!("{}", report.to_collapsed());
+    /// # Ok(())
+    /// # }
+    ///
+    /// # #[cfg(not(feature = "luau"))]
+    /// # fn main() {}
+    /// ```
     #[cfg(any(feature = "luau", doc))]
     #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
-    pub fn set_thread_collection_callback<F>(&self, callback: F)
-    where
-        F: Fn(crate::LightUserData) + MaybeSend + 'static,
-    {
+    pub fn start_profiling(&self, options: crate::ProfilerOptions) {
         let lua = self.lock();
         unsafe {
-            (*lua.extra.get()).thread_collection_callback = Some(XRc::new(callback));
-            (*ffi::lua_callbacks(lua.main_state())).userthread = Some(Self::userthread_proc);
+            (*lua.extra.get()).profiler = Some(crate::luau::profiler::Profiler::new(options));
+            Self::update_interrupt_proc(&lua);
+        }
+    }
+
+    /// Stops the profiler started by [`Lua::start_profiling`] and returns the collected samples.
+    ///
+    /// Returns `None` if the profiler is not running.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn stop_profiling(&self) -> Option<crate::ProfileReport> {
+        let lua = self.lock();
+        unsafe {
+            let profiler = (*lua.extra.get()).profiler.take();
+            Self::update_interrupt_proc(&lua);
+            profiler.map(|profiler| profiler.into_report())
         }
     }
 
+    // Installs the interrupt handler required by the callbacks currently set (if any)
     #[cfg(feature = "luau")]
-    unsafe extern "C-unwind" fn userthread_proc(parent: *mut ffi::lua_State, child: *mut ffi::lua_State) {
-        let extra = ExtraData::get(child);
-        if !parent.is_null() {
-            // Thread is created
-            let callback = match (*extra).thread_creation_callback {
-                Some(ref cb) => cb.clone(),
-                None => return,
-            };
-            if XRc::strong_count(&callback) > 2 {
-                return; // Don't allow recursion
-            }
-            ffi::lua_pushthread(child);
-            let (aux_thread, index, replace) = get_next_spot(extra);
-            ffi::lua_xmove(child, (*extra).raw_lua().ref_thread(aux_thread), 1);
-            if replace {
-                ffi::lua_replace((*extra).raw_lua().ref_thread(aux_thread), index);
-            }
-            let value = Thread((*extra).raw_lua().new_value_ref(aux_thread, index), child);
-            callback_error_ext(parent, extra, false, move |extra, _| {
-                callback((*extra).lua(), value)
-            })
+    unsafe fn update_interrupt_proc(lua: &RawLua) {
+        let extra = lua.extra.get();
+        let callbacks = ffi::lua_callbacks(lua.main_state());
+        (*callbacks).interrupt = if (*extra).profiler.is_some() {
+            Some(Self::profiler_interrupt_proc)
+        } else if (*extra).interrupt_callback.is_some() || (*extra).gc_callback.is_some() {
+            Some(Self::interrupt_proc)
         } else {
-            // Thread is about to be collected
-            let callback = match (*extra).thread_collection_callback {
-                Some(ref cb) => cb.clone(),
-                None => return,
-            };
+            None
+        };
+    }
 
-            // We need to wrap the callback call in non-unwind function as it's not safe to unwind when
-            // Luau GC is running.
-            // This will trigger `abort()` if the callback panics.
-            unsafe extern "C" fn run_callback(
-                callback: *const crate::types::ThreadCollectionCallback,
-                value: *mut ffi::lua_State,
-            ) {
-                (*callback)(crate::LightUserData(value as _));
+    #[cfg(feature = "luau")]
+    unsafe extern "C-unwind" fn profiler_interrupt_proc(state: *mut ffi::lua_State, gc: c_int) {
+        if gc < 0 {
+            if let Some(profiler) = (*ExtraData::get(state)).profiler.as_mut() {
+                profiler.tick(state);
             }
+        }
+        Self::interrupt_proc(state, gc);
+    }
 
-            (*extra).running_gc = true;
-            run_callback(&callback, child);
-            (*extra).running_gc = false;
+    #[cfg(feature = "luau")]
+    unsafe extern "C-unwind" fn interrupt_proc(state: *mut ffi::lua_State, gc: c_int) {
+        if gc >= 0 {
+            // GC interrupts cannot survive Lua exceptions, so only the GC callback is supported
+            Self::gc_interrupt(state, gc);
+            return;
+        }
+        let extra = ExtraData::get(state);
+        if (*extra).interrupt_callback.is_none() {
+            return;
+        }
+        // Skip the checks until the interval is reached
+        (*extra).interrupt_ticks += 1;
+        if (*extra).interrupt_ticks < (*extra).interrupt_interval {
+            return;
+        }
+        (*extra).interrupt_ticks = 0;
+        let result = callback_error_ext(state, ptr::null_mut(), false, move |extra, _| {
+            let interrupt_cb = (*extra).interrupt_callback.clone();
+            let interrupt_cb = mlua_expect!(interrupt_cb, "no interrupt callback set in interrupt_proc");
+            if XRc::strong_count(&interrupt_cb) > 2 {
+                return Ok(VmState::Continue); // Don't allow recursion
+            }
+            interrupt_cb((*extra).lua())
+        });
+        match result {
+            VmState::Continue => {}
+            VmState::Yield => {
+                ffi::lua_yield(state, 0);
+            }
         }
     }
 
-    /// Removes any thread creation or collection callbacks previously set by
-    /// [`Lua::set_thread_creation_callback`] or [`Lua::set_thread_collection_callback`].
+    #[cfg(feature = "luau")]
+    unsafe fn gc_interrupt(state: *mut ffi::lua_State, gc: c_int) {
+        // We need to wrap the callback call in non-unwind function as it's not safe to unwind when
+        // Luau GC is running.
+        // This will trigger `abort()` if the callback panics.
+        unsafe extern "C" fn run_callback(extra: *mut ExtraData, gc: c_int, heap: usize) {
+            if let Some(callback) = (*extra).gc_callback.clone() {
+                (*extra).gc_cycle.on_interrupt(gc, heap, &callback);
+            }
+        }
+
+        let extra = ExtraData::get(state);
+        let heap = (*MemoryState::get(state)).used_memory();
+        run_callback(extra, gc, heap);
+    }
+
+    /// Sets a breakpoint in a Lua function at the given line.
     ///
-    /// This function has no effect if a thread callbacks were not previously set.
+    /// The breakpoint is set on the first line with code at or after `line`, which can also be in
+    /// a function defined inside `func`. When it's hit, the callback set with
+    /// [`Lua::set_debug_callback`] is called.
+    ///
+    /// Requires debug information (line info) in the function.
     #[cfg(any(feature = "luau", doc))]
     #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
-    pub fn remove_thread_callbacks(&self) {
+    pub fn set_breakpoint(&self, func: &Function, line: u32) -> Result<crate::hook::BreakpointId> {
         let lua = self.lock();
-        unsafe {
-            let extra = lua.extra.get();
-            (*extra).thread_creation_callback = None;
-            (*extra).thread_collection_callback = None;
-            (*ffi::lua_callbacks(lua.main_state())).userthread = None;
+        let state = lua.state();
+        let target = unsafe {
+            let _sg = StackGuard::new(state);
+            check_stack(state, 1)?;
+
+            lua.push_ref_at(&func.0, state);
+            if ffi::lua_iscfunction(state, -1) != 0 {
+                return Err(Error::runtime("cannot set a breakpoint in a Rust/C function"));
+            }
+            let line = c_int::try_from(line).map_err(|_| Error::runtime("line number is too large"))?;
+            ffi::lua_breakpoint(state, -1, line, 1)
+        };
+        if target < 0 {
+            return Err(Error::runtime(format!("no code at or after line {line}")));
         }
+        Ok(crate::hook::BreakpointId {
+            func: func.clone(),
+            line: target as u32,
+        })
     }
 
-    /// Sets the warning function to be used by Lua to emit warnings.
-    #[cfg(feature = "lua54")]
-    #[cfg_attr(docsrs, doc(cfg(feature = "lua54")))]
-    pub fn set_warning_function<F>(&self, callback: F)
-    where
+    /// Removes a breakpoint previously set by [`Lua::set_breakpoint`].
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn remove_breakpoint(&self, breakpoint: crate::hook::BreakpointId) {
+        let lua = self.lock();
+        let state = lua.state();
+        unsafe {
+            let _sg = StackGuard::new(state);
+            assert_stack(state, 1);
+
+            lua.push_ref_at(&breakpoint.func.0, state);
+            ffi::lua_breakpoint(state, -1, breakpoint.line as c_int, 0);
+        }
+    }
+
+    /// Sets a callback that will be called when a breakpoint is hit or a step is completed.
+    ///
+    /// The callback receives the [`Debug`] structure of the function being executed and returns
+    /// a [`DebugAction`] to take on resume.
+    ///
+    /// Execution is paused by yielding the coroutine, so only code running in a [`Thread`] can be
+    /// paused: [`Thread::resume`] returns (without values) after the callback and the next call
+    /// to it continues execution. Outside of a coroutine (or when yielding is not possible) the
+    /// callback is still called, but execution continues immediately. Stepping does not follow
+    /// other coroutines resumed from the paused one.
+    ///
+    /// The callback is not called recursively (eg. if it runs Lua code with breakpoints).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use mlua::{DebugAction, Lua, Result, ThreadStatus};
+    /// # #[cfg(feature = "luau")]
+    /// # fn main() -> Result<()> {
+    /// let lua = Lua::new();
+    /// let func = lua.load("local x = 1\nx += 1\nreturn x").into_function()?;
+    /// lua.set_breakpoint(&func, 2)?;
+    /// lua.set_debug_callback(|_, debug| {
+    ///     println!("paused at line {}", debug.curr_line());
+    ///     Ok(DebugAction::Continue)
+    /// });
+    ///
+    /// let co = lua.create_thread(func)?;
+    /// co.resume::<()>(())?; // paused on line 2
+    /// assert_eq!(co.resume::<i32>(())?, 2);
+    /// assert_eq!(co.status(), ThreadStatus::Finished);
+    /// # Ok(())
+    /// # }
+    ///
+    /// # #[cfg(not(feature = "luau"))]
+    /// # fn main() {}
+    /// ```
+    ///
+    /// [`DebugAction`]: crate::DebugAction
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn set_debug_callback<F>(&self, callback: F)
+    where
+        F: Fn(&Lua, Debug) -> Result<crate::hook::DebugAction> + MaybeSend + 'static,
+    {
+        let lua = self.lock();
+        unsafe {
+            (*lua.extra.get()).debug_callback = Some(XRc::new(callback));
+            (*lua.extra.get()).debug_paused.clear();
+            let callbacks = ffi::lua_callbacks(lua.main_state());
+            (*callbacks).debugbreak = Some(Self::debugbreak_proc);
+            (*callbacks).debugstep = Some(Self::debugstep_proc);
+            // Paused states of destroyed threads are removed in `userthread_proc`
+            (*callbacks).userthread = Some(Self::userthread_proc);
+        }
+    }
+
+    /// Removes any callback previously set by [`Lua::set_debug_callback`].
+    ///
+    /// Breakpoints are kept, but have no effect without the callback.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn remove_debug_callback(&self) {
+        let lua = self.lock();
+        unsafe {
+            (*lua.extra.get()).debug_callback = None;
+            (*lua.extra.get()).debug_paused.clear();
+            let callbacks = ffi::lua_callbacks(lua.main_state());
+            if (*lua.extra.get()).debug_break_callback.is_none() {
+                (*callbacks).debugbreak = None;
+            }
+            if (*lua.extra.get()).debug_step_callback.is_none() {
+                (*callbacks).debugstep = None;
+            }
+            let extra = lua.extra.get();
+            if (*extra).thread_creation_callback.is_none()
+                && (*extra).thread_collection_callback.is_none()
+                && (*extra).thread_event_callback.is_none()
+                && !(*MemoryState::get(lua.main_state())).has_memory_categories()
+            {
+                (*callbacks).userthread = None;
+            }
+        }
+    }
+
+    /// Sets a low-level callback that will be called when a breakpoint is hit.
+    ///
+    /// This is a building block for custom debuggers and takes precedence over
+    /// [`Lua::set_debug_callback`]. The callback receives the [`Debug`] structure of the function
+    /// being executed and can yield the current thread by returning [`VmState::Yield`]. Note that
+    /// the breakpoint is executed again when the thread is resumed, so the callback is called
+    /// again for the same location.
+    ///
+    /// The callback is not called recursively. If it returns an error (or panics), the error is
+    /// raised in the running Lua code.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn set_debug_break_callback<F>(&self, callback: F)
+    where
+        F: Fn(&Lua, Debug) -> Result<VmState> + MaybeSend + 'static,
+    {
+        let lua = self.lock();
+        unsafe {
+            (*lua.extra.get()).debug_break_callback = Some(XRc::new(callback));
+            (*ffi::lua_callbacks(lua.main_state())).debugbreak = Some(Self::debugbreak_proc);
+        }
+    }
+
+    /// Removes any callback previously set by [`Lua::set_debug_break_callback`].
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn remove_debug_break_callback(&self) {
+        let lua = self.lock();
+        unsafe {
+            (*lua.extra.get()).debug_break_callback = None;
+            if (*lua.extra.get()).debug_callback.is_none() {
+                (*ffi::lua_callbacks(lua.main_state())).debugbreak = None;
+            }
+        }
+    }
+
+    /// Sets a low-level callback that will be called before each instruction of threads in
+    /// single-step mode.
+    ///
+    /// Single-step mode is enabled with [`Thread::set_single_step`]. This is a building block for
+    /// custom debuggers and takes precedence over [`Lua::set_debug_callback`]. Returning
+    /// [`VmState::Yield`] yields the current thread, the instruction is executed again (and the
+    /// callback called again) when the thread is resumed.
+    ///
+    /// The callback is not called recursively. If it returns an error (or panics), the error is
+    /// raised in the running Lua code.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn set_debug_step_callback<F>(&self, callback: F)
+    where
+        F: Fn(&Lua, Debug) -> Result<VmState> + MaybeSend + 'static,
+    {
+        let lua = self.lock();
+        unsafe {
+            (*lua.extra.get()).debug_step_callback = Some(XRc::new(callback));
+            (*ffi::lua_callbacks(lua.main_state())).debugstep = Some(Self::debugstep_proc);
+        }
+    }
+
+    /// Removes any callback previously set by [`Lua::set_debug_step_callback`].
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn remove_debug_step_callback(&self) {
+        let lua = self.lock();
+        unsafe {
+            (*lua.extra.get()).debug_step_callback = None;
+            if (*lua.extra.get()).debug_callback.is_none() {
+                (*ffi::lua_callbacks(lua.main_state())).debugstep = None;
+            }
+        }
+    }
+
+    /// Sets a low-level callback that will be called when a thread is interrupted because a
+    /// coroutine it resumed (with `coroutine.resume`) was suspended by a break.
+    ///
+    /// The callback receives the [`Debug`] structure of the function that resumed the coroutine.
+    /// Breaks are issued by native code calling `lua_break`, mlua itself only yields threads.
+    ///
+    /// The callback is not called recursively. If it returns an error (or panics), the error is
+    /// raised in the running Lua code.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn set_debug_interrupt_callback<F>(&self, callback: F)
+    where
+        F: Fn(&Lua, Debug) -> Result<()> + MaybeSend + 'static,
+    {
+        let lua = self.lock();
+        unsafe {
+            (*lua.extra.get()).debug_interrupt_callback = Some(XRc::new(callback));
+            (*ffi::lua_callbacks(lua.main_state())).debuginterrupt = Some(Self::debuginterrupt_proc);
+        }
+    }
+
+    /// Removes any callback previously set by [`Lua::set_debug_interrupt_callback`].
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn remove_debug_interrupt_callback(&self) {
+        let lua = self.lock();
+        unsafe {
+            (*lua.extra.get()).debug_interrupt_callback = None;
+            (*ffi::lua_callbacks(lua.main_state())).debuginterrupt = None;
+        }
+    }
+
+    // Calls a low-level debug callback and applies the returned state
+    #[cfg(feature = "luau")]
+    unsafe fn debug_hook(
+        state: *mut ffi::lua_State,
+        get: fn(&ExtraData) -> Option<crate::types::DebugHookCallback>,
+    ) {
+        let result = callback_error_ext(state, ptr::null_mut(), false, move |extra, _| {
+            let debug_cb = mlua_expect!(get(&*extra), "no debug callback set in debug_hook");
+            if XRc::strong_count(&debug_cb) > 2 {
+                return Ok(VmState::Continue); // Don't allow recursion
+            }
+            let lua = (*extra).lua();
+            let debug = mlua_expect!(lua.inspect_stack(0), "no active function in debug_hook");
+            debug_cb(lua, debug)
+        });
+        match result {
+            VmState::Continue => {}
+            VmState::Yield => {
+                ffi::lua_yield(state, 0);
+            }
+        }
+    }
+
+    #[cfg(feature = "luau")]
+    unsafe extern "C-unwind" fn debuginterrupt_proc(state: *mut ffi::lua_State, _ar: *mut ffi::lua_Debug) {
+        if (*ExtraData::get(state)).debug_interrupt_callback.is_none() {
+            return;
+        }
+        callback_error_ext(state, ptr::null_mut(), false, move |extra, _| {
+            let debug_cb = (*extra).debug_interrupt_callback.clone();
+            let debug_cb = mlua_expect!(debug_cb, "no debug interrupt callback set in debuginterrupt_proc");
+            if XRc::strong_count(&debug_cb) > 2 {
+                return Ok(()); // Don't allow recursion
+            }
+            let lua = (*extra).lua();
+            let debug = mlua_expect!(lua.inspect_stack(0), "no active function in debuginterrupt_proc");
+            debug_cb(lua, debug)
+        })
+    }
+
+    #[cfg(feature = "luau")]
+    unsafe extern "C-unwind" fn debugbreak_proc(state: *mut ffi::lua_State, ar: *mut ffi::lua_Debug) {
+        let extra = ExtraData::get(state);
+        if (*extra).debug_break_callback.is_some() {
+            Self::debug_hook(state, |extra| extra.debug_break_callback.clone());
+            return;
+        }
+        if let Some(pause) = (*extra).debug_paused.get_mut(&state) {
+            if pause.skip {
+                // The breakpoint we paused on is executed again
+                pause.skip = false;
+                if pause.action == crate::hook::DebugAction::Continue {
+                    (*extra).debug_paused.remove(&state);
+                }
+                return;
+            }
+        }
+        Self::debug_event(state, ar, true);
+    }
+
+    #[cfg(feature = "luau")]
+    unsafe extern "C-unwind" fn debugstep_proc(state: *mut ffi::lua_State, ar: *mut ffi::lua_Debug) {
+        use crate::hook::DebugAction;
+
+        let extra = ExtraData::get(state);
+        if (*extra).debug_step_callback.is_some() {
+            Self::debug_hook(state, |extra| extra.debug_step_callback.clone());
+            return;
+        }
+        let Some(pause) = (*extra).debug_paused.get_mut(&state) else {
+            return;
+        };
+        if pause.skip {
+            pause.skip = false;
+            return;
+        }
+        let depth = ffi::lua_stackdepth(state);
+        let line = (*ar).currentline;
+        let triggered = match pause.action {
+            DebugAction::StepIn => depth != pause.depth || line != pause.line,
+            DebugAction::StepOver => depth < pause.depth || (depth == pause.depth && line != pause.line),
+            DebugAction::Continue | DebugAction::Abort => false,
+        };
+        if triggered {
+            Self::debug_event(state, ar, false);
+        }
+    }
+
+    #[cfg(feature = "luau")]
+    unsafe fn debug_event(state: *mut ffi::lua_State, ar: *mut ffi::lua_Debug, is_break: bool) {
+        use crate::hook::{DebugAction, DebugPause};
+
+        let extra = ExtraData::get(state);
+        if (*extra).debug_callback.is_none() {
+            return;
+        }
+        let action = callback_error_ext(state, extra, false, move |extra, _| {
+            let debug_cb = (*extra).debug_callback.clone();
+            let debug_cb = mlua_expect!(debug_cb, "no debug callback set in debug_event");
+            if XRc::strong_count(&debug_cb) > 2 {
+                return Ok(DebugAction::Continue); // Don't allow recursion
+            }
+            let lua = (*extra).lua();
+            let debug = mlua_expect!(lua.inspect_stack(0), "no active function in debug_event");
+            match debug_cb(lua, debug)? {
+                DebugAction::Abort => Err(Error::runtime("execution aborted by debugger")),
+                action => Ok(action),
+            }
+        });
+
+        let stepping = matches!(action, DebugAction::StepIn | DebugAction::StepOver);
+        ffi::lua_singlestep(state, stepping as c_int);
+
+        let pause = DebugPause {
+            action,
+            depth: ffi::lua_stackdepth(state),
+            line: (*ar).currentline,
+            skip: false,
+        };
+        if ffi::lua_isyieldable(state) != 0 {
+            // A breakpoint is always executed again, a step only if stepping continues
+            let skip = is_break || stepping;
+            if skip {
+                (*extra).debug_paused.insert(state, DebugPause { skip, ..pause });
+            } else {
+                (*extra).debug_paused.remove(&state);
+            }
+            ffi::lua_yield(state, 0);
+        } else if stepping {
+            (*extra).debug_paused.insert(state, pause);
+        } else {
+            (*extra).debug_paused.remove(&state);
+        }
+    }
+
+    /// Sets a callback that will be called on garbage collection cycle boundaries.
+    ///
+    /// The callback receives [`GcPhase::Start`] when a new cycle begins and [`GcPhase::End`]
+    /// (with the amount of collected memory) when it completes. For `End`, the duration is the
+    /// total time spent in the collector during the cycle, which excludes time spent running
+    /// Lua code between incremental steps. For `Start` the duration is zero.
+    ///
+    /// The callback is invoked while the collector is running, so it must not call into Lua
+    /// and must not panic. If the callback panics, the program will be aborted.
+    ///
+    /// The end of an incremental cycle can be observed only when the collector does more work, so
+    /// [`GcPhase::End`] is reported at the beginning of the next cycle. Cycles completed by
+    /// [`Lua::gc_collect`] or [`Lua::gc_step`] are reported immediately.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn set_gc_callback<F>(&self, callback: F)
+    where
+        F: Fn(GcPhase, Duration) + MaybeSend + 'static,
+    {
+        let lua = self.lock();
+        unsafe {
+            (*lua.extra.get()).gc_callback = Some(XRc::new(callback));
+            (*lua.extra.get()).gc_cycle = Default::default();
+            Self::update_interrupt_proc(&lua);
+        }
+    }
+
+    /// Removes any callback previously set by [`Lua::set_gc_callback`].
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn remove_gc_callback(&self) {
+        let lua = self.lock();
+        unsafe {
+            (*lua.extra.get()).gc_callback = None;
+            (*lua.extra.get()).gc_cycle = Default::default();
+            Self::update_interrupt_proc(&lua);
+        }
+    }
+
+    /// Sets a callback that will be called on every memory allocation, reallocation or release.
+    ///
+    /// This is a lightweight way to trace allocations (eg. to hunt leaks) without replacing the
+    /// allocator. The event carries the old and new sizes of the block and the memory category
+    /// of the allocating thread, as set by [`Lua::set_memory_category`] or
+    /// [`Thread::set_memory_category`].
+    ///
+    /// The callback runs inside the allocation, so it receives no [`Lua`] handle and must not
+    /// call into Lua in any other way. It must not panic: if the callback panics, the program
+    /// will be aborted. Keep it cheap, as it's called very often.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn set_allocation_callback<F>(&self, callback: F)
+    where
+        F: Fn(AllocEvent) + MaybeSend + 'static,
+    {
+        let lua = self.lock();
+        unsafe {
+            (*lua.extra.get()).allocation_callback = Some(XRc::new(callback));
+            (*ffi::lua_callbacks(lua.main_state())).onallocate = Some(Self::onallocate_proc);
+        }
+    }
+
+    /// Removes any callback previously set by [`Lua::set_allocation_callback`].
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn remove_allocation_callback(&self) {
+        let lua = self.lock();
+        unsafe {
+            (*ffi::lua_callbacks(lua.main_state())).onallocate = None;
+            (*lua.extra.get()).allocation_callback = None;
+        }
+    }
+
+    #[cfg(feature = "luau")]
+    unsafe extern "C-unwind" fn onallocate_proc(state: *mut ffi::lua_State, osize: usize, nsize: usize) {
+        // Unwinding out of the allocator is not safe, the non-unwind function will trigger
+        // `abort()` if the callback panics.
+        unsafe extern "C" fn run_callback(extra: *mut ExtraData, event: AllocEvent) {
+            if let Some(callback) = &(*extra).allocation_callback {
+                callback(event);
+            }
+        }
+
+        let memcat = (*MemoryState::get(state)).memory_category(state);
+        run_callback(ExtraData::get(state), AllocEvent { osize, nsize, memcat });
+    }
+
+    /// Sets a thread creation callback that will be called when a thread is created.
+    ///
+    /// This replaces only a callback previously set by this method, callbacks set by
+    /// [`Lua::set_thread_collection_callback`] or [`Lua::set_thread_event_callback`] are kept and
+    /// called after this one.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn set_thread_creation_callback<F>(&self, callback: F)
+    where
+        F: Fn(&Lua, Thread) -> Result<()> + MaybeSend + 'static,
+    {
+        let lua = self.lock();
+        unsafe {
+            (*lua.extra.get()).thread_creation_callback = Some(XRc::new(callback));
+            (*ffi::lua_callbacks(lua.main_state())).userthread = Some(Self::userthread_proc);
+        }
+    }
+
+    /// Sets a thread collection callback that will be called when a thread is destroyed.
+    ///
+    /// Luau GC does not support exceptions during collection, so the callback must be
+    /// non-panicking. If the callback panics, the program will be aborted.
+    ///
+    /// This replaces only a callback previously set by this method, callbacks set by
+    /// [`Lua::set_thread_creation_callback`] or [`Lua::set_thread_event_callback`] are kept and
+    /// called after this one.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn set_thread_collection_callback<F>(&self, callback: F)
+    where
+        F: Fn(crate::LightUserData) + MaybeSend + 'static,
+    {
+        let lua = self.lock();
+        unsafe {
+            (*lua.extra.get()).thread_collection_callback = Some(XRc::new(callback));
+            (*ffi::lua_callbacks(lua.main_state())).userthread = Some(Self::userthread_proc);
+        }
+    }
+
+    /// Sets a callback that will be called on thread lifecycle events.
+    ///
+    /// The callback receives [`ThreadEvent::Created`] when a thread is created and
+    /// [`ThreadEvent::Destroyed`] when a thread is about to be collected. Use [`Thread::id`] to
+    /// match the created thread with its destruction event.
+    ///
+    /// Returning an error from a creation event fails the thread creation. Errors returned from
+    /// destruction events are ignored, as Luau GC does not support exceptions during collection.
+    ///
+    /// # Destruction events
+    ///
+    /// Destruction events are delivered while the garbage collector is running and the VM is
+    /// suspended. The `&Lua` argument must not be used to call into Lua while handling
+    /// [`ThreadEvent::Destroyed`]: any such call panics, and as unwinding is not possible during
+    /// collection, the program will be aborted. The same applies to panics in the callback itself.
+    /// Record the [`ThreadId`] and handle it after the collection instead.
+    ///
+    /// This replaces only a callback previously set by this method, callbacks set by
+    /// [`Lua::set_thread_creation_callback`] or [`Lua::set_thread_collection_callback`] are kept
+    /// and called before this one.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn set_thread_event_callback<F>(&self, callback: F)
+    where
+        F: Fn(&Lua, ThreadEvent) -> Result<()> + MaybeSend + 'static,
+    {
+        let lua = self.lock();
+        unsafe {
+            (*lua.extra.get()).thread_event_callback = Some(XRc::new(callback));
+            (*ffi::lua_callbacks(lua.main_state())).userthread = Some(Self::userthread_proc);
+        }
+    }
+
+    /// Sets the active memory category of the thread `state`.
+    ///
+    /// The category is tracked on the Rust side (Luau has no API function to read it), which
+    /// requires `userthread_proc` to propagate it to new threads.
+    #[cfg(feature = "luau")]
+    pub(crate) unsafe fn set_thread_memory_category(state: *mut ffi::lua_State, category: u8) {
+        (*MemoryState::get(state)).set_memory_category(state, category);
+        if category != 0 {
+            (*ffi::lua_callbacks(state)).userthread = Some(Self::userthread_proc);
+        }
+    }
+
+    #[cfg(feature = "luau")]
+    unsafe extern "C-unwind" fn userthread_proc(parent: *mut ffi::lua_State, child: *mut ffi::lua_State) {
+        let mem_state = MemoryState::get(child);
+        match parent.is_null() {
+            true => (*mem_state).thread_destroyed(child),
+            false => (*mem_state).thread_created(parent, child),
+        }
+
+        let extra = ExtraData::get(child);
+        if !parent.is_null() {
+            // Thread is created
+            let creation_callback = (*extra).thread_creation_callback.clone();
+            let event_callback = (*extra).thread_event_callback.clone();
+            if creation_callback.is_none() && event_callback.is_none() {
+                return;
+            }
+            // Don't allow recursion
+            if creation_callback
+                .as_ref()
+                .is_some_and(|cb| XRc::strong_count(cb) > 2)
+                || event_callback
+                    .as_ref()
+                    .is_some_and(|cb| XRc::strong_count(cb) > 2)
+            {
+                return;
+            }
+            ffi::lua_pushthread(child);
+            let (aux_thread, index, replace) = get_next_spot(extra);
+            ffi::lua_xmove(child, (*extra).raw_lua().ref_thread(aux_thread), 1);
+            if replace {
+                ffi::lua_replace((*extra).raw_lua().ref_thread(aux_thread), index);
+            }
+            let value = Thread((*extra).raw_lua().new_value_ref(aux_thread, index), child);
+            callback_error_ext(parent, extra, false, move |extra, _| {
+                let lua = (*extra).lua();
+                if let Some(cb) = creation_callback {
+                    cb(lua, value.clone())?;
+                }
+                if let Some(cb) = event_callback {
+                    cb(lua, ThreadEvent::Created(value))?;
+                }
+                Ok(())
+            })
+        } else {
+            // Thread is about to be collected
+            // Its address can be reused by a new thread, which must not inherit the paused state
+            (*extra).debug_paused.remove(&child);
+
+            let collection_callback = (*extra).thread_collection_callback.clone();
+            let event_callback = (*extra).thread_event_callback.clone();
+            if collection_callback.is_none() && event_callback.is_none() {
+                return;
+            }
+
+            // We need to wrap the callback call in non-unwind function as it's not safe to unwind when
+            // Luau GC is running.
+            // This will trigger `abort()` if the callback panics.
+            unsafe extern "C" fn run_callback(
+                collection_callback: Option<crate::types::ThreadCollectionCallback>,
+                event_callback: Option<crate::types::ThreadEventCallback>,
+                lua: *const Lua,
+                value: *mut ffi::lua_State,
+            ) {
+                if let Some(cb) = collection_callback {
+                    cb(crate::LightUserData(value as _));
+                }
+                if let Some(cb) = event_callback {
+                    // Errors cannot be propagated from GC
+                    let _ = cb(&*lua, ThreadEvent::Destroyed(ThreadId::from_state(value)));
+                }
+            }
+
+            (*extra).running_gc = true;
+            run_callback(collection_callback, event_callback, (*extra).lua(), child);
+            (*extra).running_gc = false;
+        }
+    }
+
+    /// Removes any thread creation or collection callbacks previously set by
+    /// [`Lua::set_thread_creation_callback`], [`Lua::set_thread_collection_callback`] or
+    /// [`Lua::set_thread_event_callback`].
+    ///
+    /// This function has no effect if a thread callbacks were not previously set.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn remove_thread_callbacks(&self) {
+        let lua = self.lock();
+        unsafe {
+            let extra = lua.extra.get();
+            (*extra).thread_creation_callback = None;
+            (*extra).thread_collection_callback = None;
+            (*extra).thread_event_callback = None;
+            let mem_state = MemoryState::get(lua.main_state());
+            if (*extra).debug_callback.is_none() && !(*mem_state).has_memory_categories() {
+                (*ffi::lua_callbacks(lua.main_state())).userthread = None;
+            }
+        }
+    }
+
+    /// Sets the warning function to be used by Lua to emit warnings.
+    #[cfg(feature = "lua54")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "lua54")))]
+    pub fn set_warning_function<F>(&self, callback: F)
+    where
         F: Fn(&Lua, &str, bool) -> Result<()> + MaybeSend + 'static,
     {
         use std::ffi::CStr;
         use std::os::raw::{c_char, c_void};
-        use std::string::String as StdString;
 
         unsafe extern "C-unwind" fn warn_proc(ud: *mut c_void, msg: *const c_char, tocont: c_int) {
             let extra = ud as *mut ExtraData;
@@ -830,6 +1912,337 @@ impl Lua {
         }
     }
 
+    /// Sets a handler for the output of the Lua `print` function.
+    ///
+    /// The global `print` function is replaced by a function that formats its arguments exactly
+    /// as the native one (converting them using `__tostring`/`__name` metafields and separating
+    /// by tabs) and passes the resulting line (without trailing newline) to the handler.
+    /// Invalid UTF-8 sequences in the line are replaced with `U+FFFD`.
+    ///
+    /// Errors returned by the handler are raised as Lua errors from `print`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::sync::{Arc, Mutex};
+    /// # use mlua::{Lua, Result};
+    /// # fn main() -> Result<()> {
+    /// # let lua = Lua::new();
+    /// let output = Arc::new(Mutex::new(Vec::new()));
+    /// let output2 = output.clone();
+    /// lua.set_print_handler(move |_, line| {
+    ///     output2.lock().unwrap().push(line.to_string());
+    ///     Ok(())
+    /// })?;
+    /// lua.load("print('hello', 1, nil)").exec()?;
+    /// assert_eq!(*output.lock().unwrap(), ["hello\t1\tnil"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_print_handler<F>(&self, handler: F) -> Result<()>
+    where
+        F: Fn(&Lua, &str) -> Result<()> + MaybeSend + 'static,
+    {
+        let print = self.create_function(move |lua, args: MultiValue| {
+            let line = lua.format_print_line(&args)?;
+            handler(lua, &StdString::from_utf8_lossy(&line))
+        })?;
+
+        // Keep the original `print` function to restore it later
+        let globals = self.globals();
+        if self.internal_registry_value::<Value>(&PRINT_KEY)?.is_nil() {
+            let original = globals.raw_get::<Value>("print")?;
+            self.set_internal_registry_value(&PRINT_KEY, original)?;
+        }
+        Self::raw_set_global(&globals, "print", print)
+    }
+
+    /// Removes the print handler previously set by [`Lua::set_print_handler`], restoring the
+    /// original `print` function.
+    ///
+    /// This function has no effect if a print handler was not previously set.
+    pub fn remove_print_handler(&self) -> Result<()> {
+        let original = self.internal_registry_value::<Value>(&PRINT_KEY)?;
+        if original.is_nil() {
+            return Ok(());
+        }
+        Self::raw_set_global(&self.globals(), "print", original)?;
+        self.set_internal_registry_value(&PRINT_KEY, Nil)
+    }
+
+    /// Sets a source of the current time used by the Lua `os` library.
+    ///
+    /// The `os.time`, `os.date` and `os.clock` functions are replaced by functions that ask the
+    /// source for the current time (in seconds, see [`ClockKind`]) instead of the system clock.
+    /// `os.time` returns the time rounded down to an integer. Calls with an explicit time or date
+    /// (eg. `os.time({year = 2000, month = 1, day = 1})`) are passed to the original functions.
+    ///
+    /// This can be used to run time-dependent scripts deterministically, for example by freezing
+    /// the time in tests. Calling this function again replaces the previous source.
+    ///
+    /// Returns an error if the `os` library is not loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{ClockKind, Lua, Result};
+    /// # fn main() -> Result<()> {
+    /// # let lua = Lua::new();
+    /// lua.set_clock_source(|kind| match kind {
+    ///     ClockKind::Time => 1_700_000_000.0,
+    ///     ClockKind::Cpu => 0.5,
+    /// })?;
+    /// assert_eq!(lua.load("os.time()").eval::<i64>()?, 1_700_000_000);
+    /// assert_eq!(lua.load("os.clock()").eval::<f64>()?, 0.5);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_clock_source<F>(&self, source: F) -> Result<()>
+    where
+        F: Fn(ClockKind) -> f64 + MaybeSend + 'static,
+    {
+        let os = match self.globals().raw_get::<Value>("os")? {
+            Value::Table(os) => os,
+            _ => return Err(Error::runtime("`os` library is not loaded")),
+        };
+        unsafe { (*self.lock().extra.get()).clock_source = Some(XRc::new(source)) };
+
+        if !self.internal_registry_value::<Value>(&OS_CLOCK_KEY)?.is_nil() {
+            // Functions are already replaced and use the new source
+            return Ok(());
+        }
+
+        fn now(lua: &Lua, kind: ClockKind) -> Result<f64> {
+            let source = unsafe { (*lua.lock().extra.get()).clock_source.clone() };
+            let source = source.ok_or_else(|| Error::runtime("clock source is not set"))?;
+            Ok(source(kind))
+        }
+
+        let original = self.create_table()?;
+        for name in ["time", "date", "clock"] {
+            original.raw_set(name, os.raw_get::<Value>(name)?)?;
+        }
+
+        let time = original.raw_get::<Function>("time")?;
+        let os_time = self.create_function(move |lua, date: Value| match date {
+            Value::Nil => Ok(Value::Integer(now(lua, ClockKind::Time)?.floor() as Integer)),
+            date => time.call(date),
+        })?;
+        let date = original.raw_get::<Function>("date")?;
+        let os_date = self.create_function(move |lua, (format, time): (Option<StdString>, Value)| {
+            let format = format.unwrap_or_else(|| "%c".to_string());
+            match time {
+                Value::Nil => date.call::<Value>((format, now(lua, ClockKind::Time)?.floor() as Integer)),
+                time => date.call::<Value>((format, time)),
+            }
+        })?;
+        let os_clock = self.create_function(|lua, ()| now(lua, ClockKind::Cpu))?;
+
+        Self::raw_set_global(&os, "time", os_time)?;
+        Self::raw_set_global(&os, "date", os_date)?;
+        Self::raw_set_global(&os, "clock", os_clock)?;
+        self.set_internal_registry_value(&OS_CLOCK_KEY, original)
+    }
+
+    /// Sets a random number generator used by the Lua `math` library.
+    ///
+    /// The `math.random` function is replaced by a function that draws values from `source`
+    /// instead of the built-in generator, keeping the Lua 5.4 semantics of its arguments. Calls to
+    /// `math.randomseed` reseed the source (see [`RandomSource::set_seed`]).
+    ///
+    /// This can be used to make script randomness reproducible, for example by seeding a
+    /// deterministic generator per request. Calling this function again replaces the previous
+    /// source.
+    ///
+    /// The source is owned by this Lua instance and is only called while the instance is locked,
+    /// so it is never used concurrently. With the `send` feature enabled it must be [`Send`].
+    ///
+    /// Returns an error if the `math` library is not loaded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, Result};
+    /// # fn main() -> Result<()> {
+    /// # let lua = Lua::new();
+    /// let mut state = 0u64;
+    /// lua.set_random_source(move || {
+    ///     state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+    ///     state
+    /// })?;
+    /// let value = lua.load("math.random(1, 6)").eval::<i64>()?;
+    /// assert!((1..=6).contains(&value));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`RandomSource::set_seed`]: crate::RandomSource::set_seed
+    pub fn set_random_source(&self, source: impl crate::random::RandomSource) -> Result<()> {
+        crate::random::install(self, source)
+    }
+
+    /// Restricts the global environment to the given capabilities.
+    ///
+    /// All current globals (including the standard library) are removed, and only the values
+    /// granted by `caps` are set instead. Reading any other global raises the
+    /// `capability '<name>' not granted` error (returned to Rust as [`Error::CallbackError`] with
+    /// an [`Error::RuntimeError`] cause). Scripts can still define their own globals.
+    ///
+    /// The globals table is modified in place, so this applies to already loaded functions and
+    /// existing threads too. The same restriction applies to [`Lua::globals`] when used from
+    /// Rust. The globals metatable is locked.
+    ///
+    /// On Luau the `safeenv` flag of the globals is cleared, so builtin functions (such as
+    /// `setmetatable` or `math.abs`) are always looked up and cannot bypass the restriction. When
+    /// combined with [`Lua::sandbox`], enable the sandbox first: it creates a new `safeenv`
+    /// environment on top of the globals.
+    ///
+    /// See [`CapabilityTable`] for an example.
+    ///
+    /// [`CapabilityTable`]: crate::CapabilityTable
+    pub fn set_capabilities(&self, caps: crate::capabilities::CapabilityTable) -> Result<()> {
+        crate::capabilities::install(self, caps)
+    }
+
+    /// Sets a function to format Rust errors seen by Lua code.
+    ///
+    /// Errors raised from Rust callbacks (and [`Error`] values passed to Lua) are converted to
+    /// strings when Lua code calls `tostring` on them or prints them. By default they use the
+    /// [`Display`] implementation, the formatter allows to rewrite the messages in one place (eg.
+    /// to strip internal paths or limit the length).
+    ///
+    /// If the formatter returns an error or panics, the default formatting is used instead. It
+    /// does not change the [`Display`] implementation used by Rust code.
+    ///
+    /// [`Display`]: std::fmt::Display
+    pub fn set_error_formatter<F>(&self, formatter: F)
+    where
+        F: Fn(&Error, &mut dyn fmt::Write) -> fmt::Result + MaybeSend + 'static,
+    {
+        unsafe { (*self.lock().extra.get()).error_formatter = Some(XRc::new(formatter)) };
+    }
+
+    /// Removes the error formatter previously set by [`Lua::set_error_formatter`].
+    pub fn remove_error_formatter(&self) {
+        unsafe { (*self.lock().extra.get()).error_formatter = None };
+    }
+
+    /// Sets a function to rewrite error messages generated by the Lua VM and standard library.
+    ///
+    /// The translator is called with the message of an error raised by the VM (eg. "attempt to
+    /// modify a readonly table") when it's converted to [`Error::RuntimeError`], without the
+    /// position prefix. Returning `Some` replaces the message while the position and the
+    /// traceback are kept, returning `None` leaves it unchanged. This allows to rewrite or
+    /// localize messages that are confusing for end users.
+    ///
+    /// Errors raised by scripts using the `error` or `assert` functions are never translated, even
+    /// if they have the same text or the functions are called through another name. The functions
+    /// are recognized by identity, as found in the globals when the translator is set.
+    /// If the translator panics, the message is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, Result};
+    /// # fn main() -> Result<()> {
+    /// let lua = Lua::new();
+    /// lua.set_vm_message_translator(|msg| {
+    ///     msg.starts_with("attempt to call a nil value").then(|| "no such command".to_string())
+    /// });
+    ///
+    /// let err = lua.load("launch_rockets()").exec().unwrap_err();
+    /// assert!(err.to_string().contains("no such command"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_vm_message_translator<F>(&self, translator: F)
+    where
+        F: Fn(&str) -> Option<StdString> + MaybeSend + 'static,
+    {
+        // Remember the native functions that raise script errors to not translate their messages
+        let globals = self.globals();
+        let funcs = ["error", "assert"].map(|name| globals.raw_get::<Option<Function>>(name));
+        let lua = self.lock();
+        let state = lua.state();
+        unsafe {
+            let _sg = StackGuard::new(state);
+            assert_stack(state, 1);
+
+            let mut raisers = Vec::new();
+            for func in funcs.into_iter().flatten().flatten() {
+                lua.push_ref(&func.0);
+                if let Some(func) = ffi::lua_tocfunction(state, -1) {
+                    raisers.push(func as usize);
+                }
+                ffi::lua_pop(state, 1);
+            }
+
+            let extra = &mut *lua.extra.get();
+            extra.script_error_raisers = raisers;
+            extra.vm_message_translator = Some(XRc::new(translator));
+        }
+    }
+
+    /// Removes the translator previously set by [`Lua::set_vm_message_translator`].
+    pub fn remove_vm_message_translator(&self) {
+        unsafe { (*self.lock().extra.get()).vm_message_translator = None };
+    }
+
+    /// Replaces the Lua `io` library with functions backed by a virtual filesystem.
+    ///
+    /// The new global `io` table provides `io.open`, `io.lines`, `io.close` and `io.type`
+    /// functions. Files opened by them support `read` (with `"a"`, `"l"`, `"L"` and numeric
+    /// formats), `write`, `lines`, `flush` and `close` methods. Failures of the [`Vfs`] and
+    /// [`VfsFile`] methods are reported the same way as by the native `io` library (as
+    /// `nil, message, errno` results). Other functions of the native library (eg. the default
+    /// input and output files) are not available.
+    ///
+    /// This can be used to provide familiar file APIs to sandboxed scripts without access to the
+    /// real filesystem. Without a handler, the `io` library is not changed. The `io` global is
+    /// also set in Luau, which does not have the `io` library.
+    ///
+    /// [`Vfs`]: crate::Vfs
+    /// [`VfsFile`]: crate::VfsFile
+    pub fn set_io_handler(&self, vfs: impl crate::vfs::Vfs) -> Result<()> {
+        crate::vfs::install(self, vfs)
+    }
+
+    // Formats `args` the same way as the Lua `print` function does.
+    fn format_print_line(&self, args: &MultiValue) -> Result<Vec<u8>> {
+        let lua = self.lock();
+        let state = lua.state();
+        let mut line = Vec::new();
+        for (i, arg) in args.iter().enumerate() {
+            if i > 0 {
+                line.push(b'\t');
+            }
+            unsafe {
+                let _sg = StackGuard::new(state);
+                check_stack(state, 3)?;
+                lua.push_value_at(arg, state)?;
+                protect_lua!(state, 1, 1, fn(state) {
+                    ffi::luaL_tolstring(state, -1, ptr::null_mut());
+                })?;
+                let mut len = 0;
+                let s = ffi::lua_tolstring(state, -1, &mut len);
+                line.extend_from_slice(std::slice::from_raw_parts(s as *const u8, len));
+            }
+        }
+        Ok(line)
+    }
+
+    // Sets a global variable, even if the globals table is readonly (in Luau).
+    pub(crate) fn raw_set_global(globals: &Table, name: &str, value: impl IntoLua) -> Result<()> {
+        #[cfg(feature = "luau")]
+        let readonly = globals.is_readonly();
+        #[cfg(feature = "luau")]
+        globals.set_readonly(false);
+        let result = globals.raw_set(name, value);
+        #[cfg(feature = "luau")]
+        globals.set_readonly(readonly);
+        result
+    }
+
     /// Emits a warning with the given message.
     ///
     /// A message in a call with `incomplete` set to `true` should be continued in
@@ -869,30 +2282,204 @@ impl Lua {
             if ffi::lua_getinfo(lua.state(), level, cstr!(""), &mut ar) == 0 {
                 return None;
             }
-            Some(Debug::new_owned(lua, level, ar))
+            Some(Debug::new_owned(lua, level, ar))
+        }
+    }
+
+    /// Returns a traceback of the current call stack, like `debug.traceback` does.
+    ///
+    /// Level 0 is the currently running function (eg. the Rust callback calling this method), so
+    /// `level` allows to skip the innermost frames. If `message` is set, it's prepended to the
+    /// traceback. Outside of any call the traceback has no frames.
+    ///
+    /// The traceback is of the running thread, so inside a coroutine it ends at the function the
+    /// coroutine was created from. This does not require the `debug` library to be loaded.
+    pub fn traceback(&self, message: Option<&str>, level: usize) -> StdString {
+        let lua = self.lock();
+        let state = lua.state();
+        let traceback = unsafe {
+            let _sg = StackGuard::new(state);
+            check_stack(state, ffi::LUA_TRACEBACK_STACK + 3).and_then(|_| {
+                // Skip the frame of the protected call itself
+                protect_lua!(state, 0, 1, |state| {
+                    crate::util::traceback(state, state, ptr::null(), level as c_int + 1)
+                })?;
+                Ok(crate::util::to_string(state, -1))
+            })
+        };
+        let traceback = traceback.unwrap_or_else(|_| "<not enough stack space for traceback>".to_string());
+        match message {
+            Some(message) => format!("{message}\n{traceback}"),
+            None => traceback,
+        }
+    }
+
+    /// Returns the frames of the current call stack, starting from `level`.
+    ///
+    /// This is a structured version of [`Lua::traceback`], frames are ordered from the innermost
+    /// one. Outside of any call the list is empty.
+    pub fn traceback_frames(&self, level: usize) -> Traceback {
+        let lua = self.lock();
+        unsafe { crate::util::capture_traceback(lua.state(), level as c_int) }
+    }
+
+    /// Returns the contents of the current Lua stack, for debugging purposes.
+    ///
+    /// Each entry contains the (absolute) slot index, the type name as reported by Lua and the
+    /// value. When called from a Rust callback, this is the stack of the callback, which includes
+    /// its arguments (and may include internal values below them).
+    ///
+    /// This is intended for diagnosing unbalanced stacks in low-level code and leaves the stack
+    /// unchanged. If there is not enough stack space to read values, they are reported as `nil`.
+    pub fn dump_stack(&self) -> Vec<(i32, StdString, Value)> {
+        let lua = self.lock();
+        let state = lua.state();
+        unsafe {
+            let can_read = check_stack(state, 2).is_ok();
+            let top = ffi::lua_gettop(state);
+            (1..=top)
+                .map(|idx| {
+                    let ty = ffi::lua_type(state, idx);
+                    let type_name = CStr::from_ptr(ffi::lua_typename(state, ty));
+                    let value = match can_read {
+                        true => lua.stack_value_at(idx, Some(ty), state),
+                        false => Nil,
+                    };
+                    (idx, type_name.to_string_lossy().into_owned(), value)
+                })
+                .collect()
+        }
+    }
+
+    /// Returns semantics of numeric `for` loops in the active Lua backend.
+    ///
+    /// This can be used to handle differences between backends when running the same scripts.
+    pub const fn for_loop_semantics() -> ForLoopSemantics {
+        if cfg!(feature = "lua54") {
+            ForLoopSemantics::Integer
+        } else if cfg!(feature = "lua53") {
+            ForLoopSemantics::IntegerWrapping
+        } else {
+            ForLoopSemantics::Float
+        }
+    }
+
+    /// Returns the amount of memory (in bytes) currently used inside this Lua state.
+    pub fn used_memory(&self) -> usize {
+        let lua = self.lock();
+        let state = lua.main_state();
+        unsafe {
+            match MemoryState::get(state) {
+                mem_state if !mem_state.is_null() => (*mem_state).used_memory(),
+                _ => {
+                    // Get data from the Lua GC
+                    let used_kbytes = ffi::lua_gc(state, ffi::LUA_GCCOUNT, 0);
+                    let used_kbytes_rem = ffi::lua_gc(state, ffi::LUA_GCCOUNTB, 0);
+                    (used_kbytes as usize) * 1024 + (used_kbytes_rem as usize)
+                }
+            }
+        }
+    }
+
+    /// Returns the maximum amount of memory (in bytes) used inside this Lua state since it was
+    /// created or since the last call to [`Lua::reset_peak_memory`].
+    ///
+    /// If peak tracking is disabled by [`LuaOptions::track_peak_memory`] or not available (in
+    /// module mode), returns the current memory usage.
+    pub fn peak_memory(&self) -> usize {
+        let lua = self.lock();
+        unsafe {
+            match MemoryState::get(lua.main_state()) {
+                mem_state if !mem_state.is_null() => (*mem_state).peak_memory(),
+                _ => self.used_memory(),
+            }
+        }
+    }
+
+    /// Resets the peak memory usage to the current memory usage.
+    ///
+    /// This allows to measure the peak of a single operation (eg. handling of a request).
+    pub fn reset_peak_memory(&self) {
+        let lua = self.lock();
+        unsafe {
+            let mem_state = MemoryState::get(lua.main_state());
+            if !mem_state.is_null() {
+                (*mem_state).reset_peak_memory();
+            }
         }
     }
 
-    /// Returns the amount of memory (in bytes) currently used inside this Lua state.
-    pub fn used_memory(&self) -> usize {
+    /// Sets the memory category of the current thread until the returned guard is dropped.
+    ///
+    /// Luau attributes every allocation to a category (an id in the `0..=255` range). Category `0`
+    /// is the default one and is used for all allocations until another category is selected.
+    /// Switching the category affects subsequent allocations made by the thread until it's changed
+    /// back, and new threads inherit the category of the thread that created them. The meaning of
+    /// other ids is up to the application, for example each script subsystem can use its own id.
+    ///
+    /// Dropping the guard restores the category that was set before on the same thread, even if
+    /// it's dropped while another thread is running. Guards should be dropped in the reverse order
+    /// of creation.
+    ///
+    /// Memory is accounted to the category that was active when it was allocated, even if it's
+    /// freed later under another category. Use [`Lua::memory_stats`] to get the totals.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn set_memory_category(&self, category: u8) -> MemoryCategoryGuard {
+        let thread = self.current_thread();
+        let _lua = self.lock();
+        let previous = unsafe {
+            let previous = (*MemoryState::get(thread.1)).memory_category(thread.1);
+            Self::set_thread_memory_category(thread.1, category);
+            previous
+        };
+        MemoryCategoryGuard { thread, previous }
+    }
+
+    /// Returns the amount of memory (in bytes) currently used by each memory category.
+    ///
+    /// Only categories with non-zero usage are returned, sorted by category id.
+    /// See [`Lua::set_memory_category`] for details.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn memory_stats(&self) -> Vec<(u8, usize)> {
         let lua = self.lock();
         let state = lua.main_state();
+        (0..=u8::MAX)
+            .map(|category| (category, unsafe { ffi::lua_totalbytes(state, category as c_int) }))
+            .filter(|&(_, bytes)| bytes > 0)
+            .collect()
+    }
+
+    /// Sets a memory limit (in bytes) on the given memory category.
+    ///
+    /// Allocations made by a thread with the category active (see [`Lua::set_memory_category`]
+    /// and [`Thread::set_memory_category`]) fail with [`Error::OutOfMemory`] once the memory used
+    /// by the category would exceed the limit, while code running under other categories continues
+    /// to work. Memory is returned to the category when the objects allocated under it are
+    /// collected. Returns previous limit (zero means no limit).
+    ///
+    /// The limit is checked against the category of the thread known to be running: the main
+    /// thread, a thread resumed from Rust or a thread that called a Rust function. A coroutine
+    /// resumed from Lua (eg. using `coroutine.resume`) is checked against the category of the
+    /// thread that resumed it until it calls into Rust.
+    ///
+    /// Does not work in module mode where Lua state is managed externally.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn set_category_limit(&self, category: u8, limit: usize) -> Result<usize> {
+        let lua = self.lock();
         unsafe {
-            match MemoryState::get(state) {
-                mem_state if !mem_state.is_null() => (*mem_state).used_memory(),
-                _ => {
-                    // Get data from the Lua GC
-                    let used_kbytes = ffi::lua_gc(state, ffi::LUA_GCCOUNT, 0);
-                    let used_kbytes_rem = ffi::lua_gc(state, ffi::LUA_GCCOUNTB, 0);
-                    (used_kbytes as usize) * 1024 + (used_kbytes_rem as usize)
-                }
+            match MemoryState::get(lua.state()) {
+                mem_state if !mem_state.is_null() => Ok((*mem_state).set_category_limit(category, limit)),
+                _ => Err(Error::MemoryControlNotAvailable),
             }
         }
     }
 
     /// Sets a memory limit (in bytes) on this Lua state.
     ///
-    /// Once an allocation occurs that would pass this memory limit, a `Error::MemoryError` is
+    /// Once an allocation occurs that would pass this memory limit, an [`Error::OutOfMemory`] is
     /// generated instead.
     /// Returns previous limit (zero means no limit).
     ///
@@ -907,6 +2494,44 @@ impl Lua {
         }
     }
 
+    /// Sets a callback that will be called when an allocation fails because of the memory limit.
+    ///
+    /// The callback receives the [`Error::OutOfMemory`] describing the failed allocation before
+    /// the error is raised, which allows the host to log it or collect metrics. It's invoked from
+    /// inside the allocator, so it must not call into Lua and must not panic. If the callback
+    /// panics, the program will be aborted.
+    ///
+    /// Lua 5.2+ run an emergency garbage collection and retry a failed allocation, so the callback
+    /// can be called even if the retry succeeds.
+    ///
+    /// Does not work in module mode where Lua state is managed externally.
+    pub fn set_oom_callback<F>(&self, callback: F) -> Result<()>
+    where
+        F: Fn(&Error) + MaybeSend + 'static,
+    {
+        let lua = self.lock();
+        unsafe {
+            match MemoryState::get(lua.state()) {
+                mem_state if !mem_state.is_null() => {
+                    (*mem_state).set_oom_callback(Some(XRc::new(callback)));
+                    Ok(())
+                }
+                _ => Err(Error::MemoryControlNotAvailable),
+            }
+        }
+    }
+
+    /// Removes any callback previously set by [`Lua::set_oom_callback`].
+    pub fn remove_oom_callback(&self) {
+        let lua = self.lock();
+        unsafe {
+            let mem_state = MemoryState::get(lua.state());
+            if !mem_state.is_null() {
+                (*mem_state).set_oom_callback(None);
+            }
+        }
+    }
+
     /// Returns `true` if the garbage collector is currently running automatically.
     #[cfg(any(feature = "lua54", feature = "lua53", feature = "lua52", feature = "luau"))]
     pub fn gc_is_running(&self) -> bool {
@@ -935,10 +2560,48 @@ impl Lua {
         let state = lua.main_state();
         unsafe {
             check_stack(state, 2)?;
-            protect_lua!(state, 0, 0, fn(state) ffi::lua_gc(state, ffi::LUA_GCCOLLECT, 0))
+
+            #[cfg(feature = "luau")]
+            if let Some(callback) = (*lua.extra.get()).gc_callback.clone() {
+                // Full collection does not trigger GC interrupts
+                let heap_before = (*MemoryState::get(state)).used_memory();
+                let start = std::time::Instant::now();
+                protect_lua!(state, 0, 0, fn(state) ffi::lua_gc(state, ffi::LUA_GCCOLLECT, 0))?;
+                let heap = (heap_before, (*MemoryState::get(state)).used_memory());
+                (*lua.extra.get())
+                    .gc_cycle
+                    .full_collection(start.elapsed(), heap, &callback);
+                ExtraData::run_pending_finalizers(lua.extra.get());
+                return Ok(());
+            }
+
+            protect_lua!(state, 0, 0, fn(state) ffi::lua_gc(state, ffi::LUA_GCCOLLECT, 0))?;
+            ExtraData::run_pending_finalizers(lua.extra.get());
+            Ok(())
         }
     }
 
+    /// Performs a full garbage-collection cycle and returns freed memory to the OS.
+    ///
+    /// Runs the collector twice (to finish the current cycle and to run a complete new one), which
+    /// also shrinks internal Lua structures such as thread stacks and the string table. Then asks
+    /// the system allocator to release free pages back to the OS.
+    ///
+    /// # Platform limitations
+    ///
+    /// Releasing memory to the OS is supported only on Linux with glibc (using `malloc_trim`), and
+    /// only when Lua memory is allocated by the system allocator (eg. no custom
+    /// `#[global_allocator]` is installed). On other platforms this function is equivalent to
+    /// calling [`Lua::gc_collect`] twice and the process memory usage may not drop.
+    ///
+    /// `malloc_trim` affects the whole process, not just this Lua instance.
+    pub fn collect_and_shrink(&self) -> Result<()> {
+        self.gc_collect()?;
+        self.gc_collect()?;
+        crate::memory::trim_system_allocator();
+        Ok(())
+    }
+
     /// Steps the garbage collector one indivisible step.
     ///
     /// Returns `true` if this has finished a collection cycle.
@@ -950,14 +2613,67 @@ impl Lua {
     ///
     /// if `kbytes` is 0, then this is the same as calling `gc_step`. Returns true if this step has
     /// finished a collection cycle.
+    ///
+    /// Larger values of `kbytes` perform more collection work per call, which allows to spread a
+    /// collection cycle over several calls with a bounded amount of work each (eg. one call per
+    /// frame). Steps are performed even if the collector is stopped with [`Lua::gc_stop`].
     pub fn gc_step_kbytes(&self, kbytes: c_int) -> Result<bool> {
         let lua = self.lock();
         let state = lua.main_state();
         unsafe {
             check_stack(state, 3)?;
-            protect_lua!(state, 0, 0, |state| {
+            let finished = protect_lua!(state, 0, 0, |state| {
                 ffi::lua_gc(state, ffi::LUA_GCSTEP, kbytes) != 0
-            })
+            })?;
+
+            #[cfg(feature = "luau")]
+            if finished {
+                if let Some(callback) = (*lua.extra.get()).gc_callback.clone() {
+                    (*lua.extra.get()).gc_cycle.finish(&callback);
+                }
+            }
+
+            ExtraData::run_pending_finalizers(lua.extra.get());
+            Ok(finished)
+        }
+    }
+
+    /// Stops the garbage collector until the returned guard is dropped.
+    ///
+    /// Guards can be nested. When the last guard is dropped (including during panic unwinding),
+    /// the collector is restarted if it was running when the first guard was created. Explicit
+    /// collections (eg. [`Lua::gc_collect`]) still work while the collector is stopped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, Result};
+    /// # fn main() -> Result<()> {
+    /// let lua = Lua::new();
+    /// {
+    ///     let _guard = lua.gc_pause_guard().step_on_drop(64);
+    ///     lua.load("for i = 1, 100 do local t = {} end").exec()?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn gc_pause_guard(&self) -> GcGuard {
+        let lua = self.lock();
+        unsafe {
+            let extra = lua.extra.get();
+            if (*extra).gc_pause_depth == 0 {
+                #[cfg(any(feature = "lua54", feature = "lua53", feature = "lua52", feature = "luau"))]
+                let running = ffi::lua_gc(lua.main_state(), ffi::LUA_GCISRUNNING, 0) != 0;
+                #[cfg(any(feature = "lua51", feature = "luajit"))]
+                let running = true;
+                (*extra).gc_resume_on_unpause = running;
+                ffi::lua_gc(lua.main_state(), ffi::LUA_GCSTOP, 0);
+            }
+            (*extra).gc_pause_depth += 1;
+        }
+        GcGuard {
+            lua: self.weak(),
+            step_kbytes: None,
         }
     }
 
@@ -966,7 +2682,11 @@ impl Lua {
     /// Returns the previous value of `pause`. More information can be found in the Lua
     /// [documentation].
     ///
-    /// For Luau this parameter sets GC goal
+    /// The value is a percentage of the heap size after a collection (default `200`):
+    /// - Lua 5.4 stores it with a precision of 4, so values are rounded down to a multiple of 4 and
+    ///   must be below `1024`.
+    /// - For Luau this parameter sets GC goal (the heap size at the end of a cycle, relative to the
+    ///   live data), which should be at least `100`.
     ///
     /// [documentation]: https://www.lua.org/manual/5.4/manual.html#2.5
     pub fn gc_set_pause(&self, pause: c_int) -> c_int {
@@ -985,12 +2705,96 @@ impl Lua {
     /// Returns the previous value of the `step multiplier`. More information can be found in the
     /// Lua [documentation].
     ///
+    /// The value is a percentage of the collector speed relative to memory allocation:
+    /// - Lua 5.4 defaults to `100`, stores it with a precision of 4 and requires a value below
+    ///   `1024`.
+    /// - Lua 5.3 raises values below `40` to `40`.
+    /// - Other versions (and Luau) default to `200`.
+    ///
     /// [documentation]: https://www.lua.org/manual/5.4/manual.html#2.5
     pub fn gc_set_step_multiplier(&self, step_multiplier: c_int) -> c_int {
         let lua = self.lock();
         unsafe { ffi::lua_gc(lua.main_state(), ffi::LUA_GCSETSTEPMUL, step_multiplier) }
     }
 
+    /// Sets the GC goal of the Luau collector.
+    ///
+    /// The goal is the heap size at the end of a collection cycle, as a percentage of the live
+    /// data (default `200`, which means that a new cycle starts when the heap size doubles).
+    /// Lower values collect more often and use less memory, higher values reduce the time spent
+    /// in the collector at the cost of a larger heap. Values at or below `100` make the collector
+    /// run continuously.
+    ///
+    /// Returns the previous value. This is the same as [`Lua::gc_set_pause`] for Luau.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn gc_set_goal(&self, goal: c_int) -> c_int {
+        let lua = self.lock();
+        unsafe { ffi::lua_gc(lua.main_state(), ffi::LUA_GCSETGOAL, goal) }
+    }
+
+    /// Returns the GC goal of the Luau collector.
+    ///
+    /// See [`Lua::gc_set_goal`] for details.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn gc_goal(&self) -> c_int {
+        let lua = self.lock();
+        let state = lua.main_state();
+        unsafe {
+            let goal = ffi::lua_gc(state, ffi::LUA_GCSETGOAL, 0);
+            ffi::lua_gc(state, ffi::LUA_GCSETGOAL, goal);
+            goal
+        }
+    }
+
+    /// Returns the `step multiplier` value of the Luau collector.
+    ///
+    /// See [`Lua::gc_set_step_multiplier`] for details.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn gc_step_multiplier(&self) -> c_int {
+        let lua = self.lock();
+        let state = lua.main_state();
+        unsafe {
+            let step_multiplier = ffi::lua_gc(state, ffi::LUA_GCSETSTEPMUL, 0);
+            ffi::lua_gc(state, ffi::LUA_GCSETSTEPMUL, step_multiplier);
+            step_multiplier
+        }
+    }
+
+    /// Sets the step size (in kilobytes) of the Luau collector.
+    ///
+    /// The collector does an incremental step each time this amount of memory is allocated
+    /// (default `1`). The amount of work done by each step is proportional to the step size and
+    /// the [step multiplier], so larger steps mean fewer but longer pauses. Very large values
+    /// make each step close to a full collection, which defeats the incremental collector.
+    ///
+    /// Returns the previous value.
+    ///
+    /// [step multiplier]: Lua::gc_set_step_multiplier
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn gc_set_step_size(&self, kbytes: c_int) -> c_int {
+        let lua = self.lock();
+        unsafe { ffi::lua_gc(lua.main_state(), ffi::LUA_GCSETSTEPSIZE, kbytes) }
+    }
+
+    /// Returns the step size (in kilobytes) of the Luau collector.
+    ///
+    /// See [`Lua::gc_set_step_size`] for details.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn gc_step_size(&self) -> c_int {
+        let lua = self.lock();
+        let state = lua.main_state();
+        unsafe {
+            let step_size = ffi::lua_gc(state, ffi::LUA_GCSETSTEPSIZE, 0);
+            ffi::lua_gc(state, ffi::LUA_GCSETSTEPSIZE, step_size);
+            step_size
+        }
+    }
+
     /// Changes the collector to incremental mode with the given parameters.
     ///
     /// Returns the previous mode (always `GCMode::Incremental` in Lua < 5.4).
@@ -1059,55 +2863,349 @@ impl Lua {
         }
     }
 
-    /// Sets a default Luau compiler (with custom options).
+    /// Sets a default Luau compiler (with custom options).
+    ///
+    /// This compiler will be used by default to load all Lua chunks
+    /// including via `require` function.
+    ///
+    /// See [`Compiler`] for details and possible options.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn set_compiler(&self, compiler: Compiler) {
+        let lua = self.lock();
+        unsafe { (*lua.extra.get()).compiler = Some(compiler) };
+    }
+
+    /// Toggles JIT compilation mode for new chunks of code.
+    ///
+    /// By default JIT is enabled. Changing this option does not have any effect on
+    /// already loaded functions.
+    #[cfg(any(feature = "luau-jit", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau-jit")))]
+    pub fn enable_jit(&self, enable: bool) {
+        let lua = self.lock();
+        unsafe { (*lua.extra.get()).enable_jit = enable };
+    }
+
+    /// Sets Luau feature flag (global setting).
+    ///
+    /// See https://github.com/luau-lang/luau/blob/master/CONTRIBUTING.md#feature-flags for details.
+    #[cfg(feature = "luau")]
+    #[doc(hidden)]
+    #[allow(clippy::result_unit_err)]
+    pub fn set_fflag(name: &str, enabled: bool) -> StdResult<(), ()> {
+        let _guard = fflags::lock();
+        fflags::snapshot_defaults();
+        if let Ok(name) = std::ffi::CString::new(name) {
+            if unsafe { ffi::luau_setfflag(name.as_ptr(), enabled as c_int) != 0 } {
+                return Ok(());
+            }
+        }
+        Err(())
+    }
+
+    /// Sets Luau integer feature flag (global setting).
+    ///
+    /// Returns an error if the flag is unknown or is not an integer flag.
+    #[cfg(feature = "luau")]
+    #[doc(hidden)]
+    #[allow(clippy::result_unit_err)]
+    pub fn set_fflag_int(name: &str, value: i32) -> StdResult<(), ()> {
+        let _guard = fflags::lock();
+        fflags::snapshot_defaults();
+        let name = std::ffi::CString::new(name).map_err(|_| ())?;
+        match unsafe { ffi::luau_setfintflag(name.as_ptr(), value) } {
+            0 => Err(()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the current value of Luau integer feature flag.
+    ///
+    /// Returns an error if the flag is unknown or is not an integer flag.
+    #[cfg(feature = "luau")]
+    #[doc(hidden)]
+    #[allow(clippy::result_unit_err)]
+    pub fn fflag_int(name: &str) -> StdResult<i32, ()> {
+        let _guard = fflags::lock();
+        let name = std::ffi::CString::new(name).map_err(|_| ())?;
+        let mut value = 0;
+        match unsafe { ffi::luau_getfintflag(name.as_ptr(), &mut value) } {
+            0 => Err(()),
+            _ => Ok(value),
+        }
+    }
+
+    /// Returns all known Luau boolean feature flags (global setting) and their current values.
+    ///
+    /// The list is sorted by flag name.
+    #[cfg(feature = "luau")]
+    #[doc(hidden)]
+    pub fn fflags() -> Vec<(std::string::String, bool)> {
+        let _guard = fflags::lock();
+        fflags::snapshot_defaults();
+        let mut flags = (fflags::bool_flags().into_iter())
+            .map(|(name, value)| (name.to_string_lossy().into_owned(), value))
+            .collect::<Vec<_>>();
+        flags.sort();
+        flags
+    }
+
+    /// Restores default values of all Luau feature flags (global setting).
+    ///
+    /// Default values are the values flags had before they were first changed using
+    /// [`Lua::set_fflag`] or [`Lua::set_fflag_int`], or listed by [`Lua::fflags`].
+    #[cfg(feature = "luau")]
+    #[doc(hidden)]
+    pub fn reset_fflags() {
+        let _guard = fflags::lock();
+        let defaults = fflags::snapshot_defaults();
+        for (name, value) in &defaults.bools {
+            unsafe { ffi::luau_setfflag(name.as_ptr(), *value as c_int) };
+        }
+        for (name, value) in &defaults.ints {
+            unsafe { ffi::luau_setfintflag(name.as_ptr(), *value) };
+        }
+    }
+
+    /// Returns Lua source code as a `Chunk` builder type.
+    ///
+    /// In order to actually compile or run the resulting code, you must call [`Chunk::exec`] or
+    /// similar on the returned builder. Code is not even parsed until one of these methods is
+    /// called.
+    ///
+    /// [`Chunk::exec`]: crate::Chunk::exec
+    #[track_caller]
+    pub fn load<'a>(&self, chunk: impl AsChunk + 'a) -> Chunk<'a> {
+        self.load_with_location(chunk, Location::caller())
+    }
+
+    /// Removes the source map registered for chunks named `name`.
+    ///
+    /// `name` is the chunk name as passed to [`Chunk::set_name`]. Returns `true` if a source map
+    /// was registered for the name.
+    ///
+    /// See [`Chunk::set_source_map`] for details.
+    ///
+    /// [`Chunk::set_name`]: crate::Chunk::set_name
+    /// [`Chunk::set_source_map`]: crate::Chunk::set_source_map
+    pub fn remove_source_map(&self, name: &str) -> bool {
+        let Ok(name) = std::ffi::CString::new(name) else {
+            return false;
+        };
+        let lua = self.lock();
+        match Chunk::printable_name(&lua, &name) {
+            Some(src) => unsafe { (*lua.extra()).source_maps.remove(&src).is_some() },
+            None => false,
+        }
+    }
+
+    /// Calls a function found by the dotted `path` (eg. `module.on_event`) in the globals.
+    ///
+    /// Each segment of the path is looked up as normal indexing does (invoking `__index`
+    /// metamethods) in a table or userdata. The target can be a function, or a table or userdata
+    /// with the `__call` metamethod.
+    ///
+    /// Returns an error naming the first missing segment if the path cannot be resolved, or if
+    /// the target is not callable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, Result};
+    /// # fn main() -> Result<()> {
+    /// let lua = Lua::new();
+    /// lua.load("plugin = { events = { on_join = function(name) return 'hi ' .. name end } }").exec()?;
+    /// let greeting: String = lua.call_path("plugin.events.on_join", "alice")?;
+    /// assert_eq!(greeting, "hi alice");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn call_path<R: FromLuaMulti>(&self, path: &str, args: impl IntoLuaMulti) -> Result<R> {
+        use crate::traits::ObjectLike;
+
+        let mut value = Value::Table(self.globals());
+        let mut resolved = 0;
+        for segment in path.split('.') {
+            if segment.is_empty() {
+                return Err(Error::runtime(format!("invalid path '{path}'")));
+            }
+            let prefix = &path[..resolved];
+            value = match value {
+                Value::Table(t) => t.get(segment)?,
+                Value::UserData(ud) => ud.get(segment)?,
+                value => {
+                    let type_name = value.type_name();
+                    return Err(Error::runtime(format!(
+                        "cannot resolve '{path}': '{prefix}' is not indexable (a {type_name} value)"
+                    )));
+                }
+            };
+            resolved += segment.len() + (resolved > 0) as usize;
+            if value.is_nil() {
+                let prefix = &path[..resolved];
+                return Err(Error::runtime(format!(
+                    "cannot resolve '{path}': '{prefix}' is nil"
+                )));
+            }
+        }
+
+        let has_call =
+            |mt: Option<Table>| mt.is_some_and(|mt| mt.raw_get::<Value>("__call").is_ok_and(|v| !v.is_nil()));
+        match value {
+            Value::Function(func) => func.call(args),
+            Value::Table(t) if has_call(t.metatable()) => t.call(args),
+            Value::UserData(ud) if has_call(ud.metatable().ok().map(|mt| mt.0)) => ud.call(args),
+            value => {
+                let type_name = value.type_name();
+                Err(Error::runtime(format!(
+                    "cannot call '{path}': not a function (a {type_name} value)"
+                )))
+            }
+        }
+    }
+
+    /// Evaluates a single Lua expression (eg. `a + b > 10`) in the given environment.
+    ///
+    /// The expression is compiled as `return <expr>` in a chunk named `expression`, so syntax
+    /// errors refer to the expression text only. Anything other than an expression (eg. a
+    /// statement) is rejected as a syntax error. Global variables used by the expression are
+    /// looked up in `env`.
+    ///
+    /// Compiled expressions are cached by their text (see [`Chunk::try_cache`]), so evaluating
+    /// the same expression again does not compile it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, Result};
+    /// # fn main() -> Result<()> {
+    /// let lua = Lua::new();
+    /// let env = lua.create_table_from([("a", 4), ("b", 7)])?;
+    /// assert!(lua.eval_expr::<bool>("a + b > 10", env)?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn eval_expr<T: FromLua>(&self, expr: &str, env: Table) -> Result<T> {
+        self.load(format!("return {expr}"))
+            .set_name("=expression")
+            .set_environment(env)
+            .try_cache()
+            .call(())
+    }
+
+    /// Loads a precompiled [`CompiledChunk`] into a [`Function`].
     ///
-    /// This compiler will be used by default to load all Lua chunks
-    /// including via `require` function.
-    ///
-    /// See [`Compiler`] for details and possible options.
+    /// The same chunk can be loaded into many `Lua` instances, so the source is compiled only
+    /// once. Returns an error if the bytecode was produced by an incompatible Luau version.
     #[cfg(any(feature = "luau", doc))]
     #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
-    pub fn set_compiler(&self, compiler: Compiler) {
-        let lua = self.lock();
-        unsafe { (*lua.extra.get()).compiler = Some(compiler) };
+    #[track_caller]
+    pub fn load_compiled(&self, chunk: &CompiledChunk) -> Result<Function> {
+        chunk.validate()?;
+        self.load(chunk).into_function()
     }
 
-    /// Toggles JIT compilation mode for new chunks of code.
+    /// Returns line coverage aggregated across all chunks loaded with coverage enabled.
     ///
-    /// By default JIT is enabled. Changing this option does not have any effect on
-    /// already loaded functions.
-    #[cfg(any(feature = "luau-jit", doc))]
-    #[cfg_attr(docsrs, doc(cfg(feature = "luau-jit")))]
-    pub fn enable_jit(&self, enable: bool) {
-        let lua = self.lock();
-        unsafe { (*lua.extra.get()).enable_jit = enable };
+    /// Chunks loaded with a [`Compiler`] having nonzero [coverage level] are tracked from the
+    /// moment they are loaded, including chunks loaded after a previous report was made. Tracked
+    /// functions are kept alive by this `Lua` instance, so their counts are not lost when they
+    /// would otherwise be garbage collected. Use [`Lua::clear_coverage`] to release them.
+    ///
+    /// [coverage level]: Compiler::set_coverage_level
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn coverage_report(&self) -> crate::function::CoverageReport {
+        let functions = {
+            let lua = self.lock();
+            let tracked = lua.priv_app_data_ref::<TrackedCoverage>();
+            (tracked.iter().flat_map(|tracked| &tracked.0))
+                .filter_map(|key| self.registry_value::<Function>(key).ok())
+                .collect::<Vec<_>>()
+        };
+
+        let mut report = crate::function::CoverageReport::default();
+        for func in functions {
+            let source = func.info().source.unwrap_or_default();
+            func.coverage(|cov| report.add(&source, &cov.hits));
+        }
+        report
     }
 
-    /// Sets Luau feature flag (global setting).
+    /// Stops tracking coverage of the chunks loaded so far and releases them.
     ///
-    /// See https://github.com/luau-lang/luau/blob/master/CONTRIBUTING.md#feature-flags for details.
+    /// Subsequent reports made by [`Lua::coverage_report`] include only chunks loaded after this
+    /// call. Released functions can be garbage collected once they are no longer referenced.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn clear_coverage(&self) {
+        let tracked = self.lock().set_priv_app_data(TrackedCoverage(Vec::new()));
+        drop(tracked);
+    }
+
+    // Starts tracking coverage of a function for `Lua::coverage_report`
     #[cfg(feature = "luau")]
-    #[doc(hidden)]
-    #[allow(clippy::result_unit_err)]
-    pub fn set_fflag(name: &str, enabled: bool) -> StdResult<(), ()> {
-        if let Ok(name) = std::ffi::CString::new(name) {
-            if unsafe { ffi::luau_setfflag(name.as_ptr(), enabled as c_int) != 0 } {
-                return Ok(());
+    pub(crate) fn track_coverage(&self, func: &Function) -> Result<()> {
+        let key = self.create_registry_value(func)?;
+        let lua = self.lock();
+        match lua.priv_app_data_mut::<TrackedCoverage>() {
+            Some(mut tracked) => tracked.0.push(key),
+            None => {
+                lua.set_priv_app_data(TrackedCoverage(vec![key]));
             }
         }
-        Err(())
+        Ok(())
     }
 
-    /// Returns Lua source code as a `Chunk` builder type.
+    /// Compiles a batch of named chunks, each independently of the others.
     ///
-    /// In order to actually compile or run the resulting code, you must call [`Chunk::exec`] or
-    /// similar on the returned builder. Code is not even parsed until one of these methods is
-    /// called.
+    /// Unlike loading chunks one by one, this does not stop at the first invalid chunk and returns
+    /// a result per chunk in the same order, which is convenient for validating a bundle of
+    /// scripts. The compiler set with [`Lua::set_compiler`] is used (or the default one).
     ///
-    /// [`Chunk::exec`]: crate::Chunk::exec
-    #[track_caller]
-    pub fn load<'a>(&self, chunk: impl AsChunk + 'a) -> Chunk<'a> {
-        self.load_with_location(chunk, Location::caller())
+    /// Syntax errors are prefixed with the chunk name and line, the same way as errors returned
+    /// when loading a chunk. See [`Chunk::set_name`] for possible name prefixes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, Result};
+    /// # #[cfg(feature = "luau")]
+    /// # fn main() -> Result<()> {
+    /// let lua = Lua::new();
+    /// let results = lua.compile_all(&[("=good", "return 1"), ("=bad", "return +")]);
+    /// assert!(results[0].is_ok());
+    /// assert!(results[1].as_ref().unwrap_err().to_string().contains("bad:1:"));
+    /// # Ok(())
+    /// # }
+    ///
+    /// # #[cfg(not(feature = "luau"))]
+    /// # fn main() {}
+    /// ```
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn compile_all<N, S>(&self, chunks: &[(N, S)]) -> Vec<Result<CompiledChunk>>
+    where
+        N: AsRef<str>,
+        S: AsRef<[u8]>,
+    {
+        let compiler = unsafe { (*self.lock().extra.get()).compiler.clone() }.unwrap_or_default();
+        (chunks.iter())
+            .map(|(name, source)| {
+                let name = name.as_ref();
+                compiler.compile_chunk(name, source).map_err(|err| match err {
+                    Error::SyntaxError {
+                        message,
+                        incomplete_input,
+                    } => Error::SyntaxError {
+                        message: format!("{}:{message}", crate::chunk::printable_chunk_name(name)),
+                        incomplete_input,
+                    },
+                    err => err,
+                })
+            })
+            .collect()
     }
 
     pub(crate) fn load_with_location<'a>(
@@ -1125,6 +3223,9 @@ impl Lua {
             source: chunk.source(),
             #[cfg(feature = "luau")]
             compiler: unsafe { (*self.lock().extra.get()).compiler.clone() },
+            #[cfg(feature = "luau")]
+            sandboxed: false,
+            source_map: None,
         }
     }
 
@@ -1191,6 +3292,50 @@ impl Lua {
         unsafe { self.lock().create_sequence_from(iter) }
     }
 
+    /// Creates a table from a JSON document.
+    ///
+    /// The document is parsed using [`serde_json`] and converted with [`LuaSerdeExt::to_value`], so
+    /// JSON arrays become sequences with the [array metatable] attached and `null` values are
+    /// encoded as [`LuaSerdeExt::null`].
+    ///
+    /// Returns an error (with the line and column) if the document cannot be parsed, or if it is
+    /// not a JSON object or array.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, Result, Table};
+    /// # fn main() -> Result<()> {
+    /// # let lua = Lua::new();
+    /// let table = lua.create_table_from_json(r#"{"name": "fixture", "items": [1, 2, 3]}"#)?;
+    /// assert_eq!(table.get::<String>("name")?, "fixture");
+    /// assert_eq!(table.get::<Table>("items")?.raw_len(), 3);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [array metatable]: crate::LuaSerdeExt::array_metatable
+    /// [`LuaSerdeExt::null`]: crate::LuaSerdeExt::null
+    /// [`LuaSerdeExt::to_value`]: crate::LuaSerdeExt::to_value
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn create_table_from_json(&self, json: &str) -> Result<Table> {
+        use crate::serde::{ser, LuaSerdeExt};
+
+        let value = serde_json::from_str::<serde_json::Value>(json)
+            .map_err(|err| Error::DeserializeError(err.to_string()))?;
+        // Numbers are serialized as structs if `serde_json/arbitrary_precision` is enabled
+        let options = ser::Options::new().detect_serde_json_arbitrary_precision(true);
+        match self.to_value_with(&value, options)? {
+            Value::Table(table) => Ok(table),
+            value => Err(Error::FromLuaConversionError {
+                from: value.type_name(),
+                to: "table".to_string(),
+                message: Some("JSON document is not an object or array".to_string()),
+            }),
+        }
+    }
+
     /// Wraps a Rust function or closure, creating a callable Lua function handle to it.
     ///
     /// The function's return value is always a `Result`: If the function returns `Err`, the error
@@ -1202,6 +3347,9 @@ impl Lua {
     /// values. For details on Rust-to-Lua conversions, refer to the [`IntoLua`] and
     /// [`IntoLuaMulti`] traits.
     ///
+    /// With the `named-closures` feature, Luau tracebacks show the function under the type name of
+    /// `func` and the location of this call.
+    ///
     /// # Examples
     ///
     /// Create a function which prints its argument:
@@ -1233,17 +3381,29 @@ impl Lua {
     /// # Ok(())
     /// # }
     /// ```
+    #[cfg_attr(feature = "named-closures", track_caller)]
     pub fn create_function<F, A, R>(&self, func: F) -> Result<Function>
     where
         F: Fn(&Lua, A) -> Result<R> + MaybeSend + 'static,
         A: FromLuaMulti,
         R: IntoLuaMulti,
     {
-        (self.lock()).create_callback(Box::new(move |rawlua, nargs| unsafe {
+        self.create_function_with_name(func, callback_name::<F>())
+    }
+
+    fn create_function_with_name<F, A, R>(&self, func: F, name: Option<StdString>) -> Result<Function>
+    where
+        F: Fn(&Lua, A) -> Result<R> + MaybeSend + 'static,
+        A: FromLuaMulti,
+        R: IntoLuaMulti,
+    {
+        let callback: Callback = Box::new(move |rawlua, nargs| unsafe {
             let state = rawlua.state();
-            let args = A::from_specified_stack_args(nargs, 1, None, rawlua, state)?;
+            let args = A::from_specified_stack_args(nargs, 1, None, rawlua, state)
+                .map_err(|err| with_callee_name(state, err))?;
             func(rawlua.lua(), args)?.push_into_specified_stack_multi(rawlua, state)
-        }))
+        });
+        (self.lock()).create_named_callback(callback, name.as_deref())
     }
 
     /// Same as ``create_function`` but with an added continuation function.
@@ -1274,12 +3434,14 @@ impl Lua {
         (self.lock()).create_callback_with_continuation(
             Box::new(move |rawlua, nargs| unsafe {
                 let state = rawlua.state();
-                let args = A::from_specified_stack_args(nargs, 1, None, rawlua, state)?;
+                let args = A::from_specified_stack_args(nargs, 1, None, rawlua, state)
+                    .map_err(|err| with_callee_name(state, err))?;
                 func(rawlua.lua(), args)?.push_into_specified_stack_multi(rawlua, state)
             }),
             Box::new(move |rawlua, nargs, status| unsafe {
                 let state = rawlua.state();
-                let args = AC::from_specified_stack_args(nargs, 1, None, rawlua, state)?;
+                let args = AC::from_specified_stack_args(nargs, 1, None, rawlua, state)
+                    .map_err(|err| with_callee_name(state, err))?;
                 let status = ContinuationStatus::from_status(status);
                 cont(rawlua.lua(), status, args)?.push_into_specified_stack_multi(rawlua, state)
             }),
@@ -1289,16 +3451,81 @@ impl Lua {
     /// Wraps a Rust mutable closure, creating a callable Lua function handle to it.
     ///
     /// This is a version of [`Lua::create_function`] that accepts a `FnMut` argument.
+    ///
+    /// The closure is borrowed mutably for the duration of each call, so there is no need to wrap
+    /// its state into a `RefCell` manually.
+    ///
+    /// # Reentrancy
+    ///
+    /// A mutable function cannot be reentered: if the closure (directly or through Lua code it
+    /// invokes) calls the same function again, the inner call fails with
+    /// [`Error::RecursiveMutCallback`] ("mutable callback called recursively") instead of
+    /// panicking. The error is raised as a normal Lua error and can be caught with `pcall`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, Result};
+    /// # fn main() -> Result<()> {
+    /// # let lua = Lua::new();
+    /// let mut counter = 0;
+    /// let next = lua.create_function_mut(move |_, ()| {
+    ///     counter += 1;
+    ///     Ok(counter)
+    /// })?;
+    /// assert_eq!(next.call::<i32>(())?, 1);
+    /// assert_eq!(next.call::<i32>(())?, 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "named-closures", track_caller)]
     pub fn create_function_mut<F, A, R>(&self, func: F) -> Result<Function>
     where
         F: FnMut(&Lua, A) -> Result<R> + MaybeSend + 'static,
         A: FromLuaMulti,
         R: IntoLuaMulti,
     {
+        let name = callback_name::<F>();
         let func = RefCell::new(func);
-        self.create_function(move |lua, args| {
+        let func = move |lua: &Lua, args: A| {
             (*func.try_borrow_mut().map_err(|_| Error::RecursiveMutCallback)?)(lua, args)
-        })
+        };
+        self.create_function_with_name(func, name)
+    }
+
+    /// Wraps a Rust function with declared arguments, creating a callable Lua function handle to
+    /// it.
+    ///
+    /// Unlike [`Lua::create_function`], arguments are described using [`FunctionSpec`] and
+    /// conversion errors are reported using the standard Lua wording, so they look the same as
+    /// errors raised by builtin functions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, Result};
+    /// # fn main() -> Result<()> {
+    /// # let lua = Lua::new();
+    /// let ban = lua.create_function_spec("ban", |spec| {
+    ///     spec.arg::<String>("user")
+    ///         .arg::<Option<u32>>("days")
+    ///         .strict(true)
+    ///         .build(|_, (user, days)| Ok(format!("{user} banned for {} days", days.unwrap_or(1))))
+    /// })?;
+    /// lua.globals().set("ban", ban)?;
+    ///
+    /// let err = lua.load("ban('alice', 'ten')").exec().unwrap_err();
+    /// assert!(err.to_string().contains("bad argument #2 to 'ban' (number expected, got string)"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn create_function_spec(
+        &self,
+        name: &str,
+        spec: impl FnOnce(FunctionSpec<()>) -> BuiltFunctionSpec,
+    ) -> Result<Function> {
+        let BuiltFunctionSpec(callback) = spec(FunctionSpec::new(name));
+        (self.lock()).create_callback(callback)
     }
 
     /// Wraps a C function, creating a callable Lua function handle to it.
@@ -1323,6 +3550,106 @@ impl Lua {
         unsafe { self.lock().create_thread(&func) }
     }
 
+    /// Wraps a Lua function into a new function that resumes a coroutine on each call.
+    ///
+    /// Equivalent to `coroutine.wrap`. Arguments of each call are passed to the coroutine, and the
+    /// values it yields or returns are returned. Errors raised in the coroutine are propagated to
+    /// the caller. Calling the function after the coroutine has finished raises a
+    /// "cannot resume dead coroutine" error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, Result};
+    /// # fn main() -> Result<()> {
+    /// # let lua = Lua::new();
+    /// let counter = lua.load("function() for i = 1, 3 do coroutine.yield(i) end end").eval()?;
+    /// lua.globals().set("counter", lua.create_coroutine_function(counter)?)?;
+    ///
+    /// let sum: i64 = lua.load(r#"
+    ///     local sum = 0
+    ///     for i in counter do
+    ///         sum = sum + i
+    ///     end
+    ///     return sum
+    /// "#).eval()?;
+    /// assert_eq!(sum, 6);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn create_coroutine_function(&self, func: Function) -> Result<Function> {
+        let thread = self.create_thread(func)?;
+        self.create_function(move |_, args: MultiValue| match thread.status() {
+            ThreadStatus::Resumable => thread.resume::<MultiValue>(args),
+            ThreadStatus::Running => Err(Error::runtime("cannot resume non-suspended coroutine")),
+            ThreadStatus::Finished | ThreadStatus::Error => {
+                Err(Error::runtime("cannot resume dead coroutine"))
+            }
+        })
+    }
+
+    /// Resumes each of the `threads` with its arguments, collecting the outcomes in order.
+    ///
+    /// Threads are resumed sequentially (Lua is not parallel), but the bookkeeping shared by all
+    /// resumes, such as locking the Lua state, is done once for the whole batch. This makes the
+    /// method suitable for the core loop of a scheduler that resumes many threads per tick.
+    ///
+    /// Failures are isolated: an error in one thread is reported in its slot and does not stop
+    /// the remaining threads from being resumed. Errors are reported the same way as in
+    /// [`Thread::resume_raw`]; threads belonging to another Lua instance fail with a runtime
+    /// error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, MultiValue, ResumeOutcome, Result, Thread};
+    /// # fn main() -> Result<()> {
+    /// # let lua = Lua::new();
+    /// let worker: Thread = lua.load("coroutine.create(function(n) coroutine.yield(n) end)").eval()?;
+    /// let failing: Thread = lua.load("coroutine.create(function() error('boom') end)").eval()?;
+    ///
+    /// let batch = [
+    ///     (worker.clone(), MultiValue::from_vec(vec![21.into()])),
+    ///     (failing, MultiValue::new()),
+    /// ];
+    /// let outcomes = lua.resume_many(&batch);
+    /// assert!(matches!(outcomes[0], Ok(ResumeOutcome::Yielded(_))));
+    /// assert!(outcomes[1].is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resume_many(&self, threads: &[(Thread, MultiValue)]) -> Vec<Result<ResumeOutcome>> {
+        let lua = self.lock();
+        let mut outcomes = Vec::with_capacity(threads.len());
+        for (thread, args) in threads {
+            if thread.0.lua != *lua.weak() {
+                outcomes.push(Err(Error::runtime("thread belongs to a different Lua instance")));
+                continue;
+            }
+            outcomes.push(thread.resume_outcome(&lua, args.clone()));
+        }
+        outcomes
+    }
+
+    /// Resumes a thread pinned with [`Thread::pin`], passing `args` as arguments.
+    ///
+    /// This is equivalent to [`Thread::resume`] called on the pinned thread. Returns an error if
+    /// no thread is pinned under `id`.
+    pub fn resume_pinned<R>(&self, id: PinnedThreadId, args: impl IntoLuaMulti) -> Result<R>
+    where
+        R: FromLuaMulti,
+    {
+        crate::thread::pin::get(self, id)?.resume(args)
+    }
+
+    /// Unpins a thread pinned with [`Thread::pin`], allowing it to be garbage collected.
+    ///
+    /// Does nothing if no thread is pinned under `id`, so it's safe to call this method more than
+    /// once, or for threads that have already finished.
+    pub fn unpin(&self, id: PinnedThreadId) -> Result<()> {
+        crate::thread::pin::unpin(self, id)
+    }
+
     /// Creates a Lua userdata object from a custom userdata type.
     ///
     /// All userdata instances of the same type `T` shares the same metatable.
@@ -1394,6 +3721,71 @@ impl Lua {
         Ok(())
     }
 
+    /// Sets a custom name for userdata type `T` to use instead of the Rust type name.
+    ///
+    /// The name is stored in the `__name` (`__type` in Luau) field of the userdata metatable
+    /// (unless [`MetaMethod::Type`] is provided by the type), so it's reported by `tostring` in
+    /// Lua 5.3+ and Luau, and by `typeof` in Luau. It's also used in error messages when a value
+    /// of a wrong type is passed where `T` is expected.
+    ///
+    /// The name can be set before or after the type is registered (or its first instance is
+    /// created). By default, the name is derived from the Rust type name.
+    ///
+    /// [`MetaMethod::Type`]: crate::MetaMethod::Type
+    pub fn register_userdata_type_with_name<T: 'static>(&self, name: &str) -> Result<()> {
+        let type_id = TypeId::of::<T>();
+        let lua = self.lock();
+        unsafe {
+            let extra = lua.extra.get();
+            let prev_name = (*extra).userdata_type_names.insert(type_id, name.to_string());
+
+            // Update the metatable if it has already been created
+            if let Some(&table_id) = (*extra).registered_userdata_t.get(&type_id) {
+                let state = lua.state();
+                let _sg = StackGuard::new(state);
+                check_stack(state, 1)?;
+                ffi::lua_rawgeti(state, ffi::LUA_REGISTRYINDEX, table_id as _);
+                let metatable = Table(lua.pop_ref());
+                let current_name = metatable.raw_get::<Option<StdString>>(MetaMethod::Type.name())?;
+                let prev_name = prev_name.unwrap_or_else(short_type_name::<T>);
+                // Keep the name provided by the type itself
+                if current_name.as_deref() == Some(prev_name.as_str()) {
+                    metatable.raw_set(MetaMethod::Type.name(), name)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the maximum number of live instances of userdata type `T`.
+    ///
+    /// When the limit is reached, creating a new instance (eg. with [`Lua::create_userdata`] or
+    /// when converting `T` to a Lua value) fails with a runtime error "userdata limit exceeded
+    /// for type '...'". This can be used to bound the number of objects backed by external
+    /// resources (eg. file descriptors) that scripts can create.
+    ///
+    /// An instance stops being counted when it's garbage collected (not when it's taken or
+    /// destroyed), so unreachable instances keep taking a slot until they are collected. In Lua
+    /// 5.1 and LuaJIT this can take up to three full collection cycles.
+    ///
+    /// Only instances created after the limit is first set are counted. Setting the limit again
+    /// keeps the current count.
+    pub fn set_userdata_limit<T: 'static>(&self, max: usize) {
+        let lua = self.lock();
+        let limits = unsafe { &mut (*lua.extra.get()).userdata_limits };
+        limits.entry(TypeId::of::<T>()).or_default().max = max;
+    }
+
+    /// Returns the number of live instances of userdata type `T` counted against the limit set
+    /// by [`Lua::set_userdata_limit`].
+    ///
+    /// Returns `0` if no limit is set for the type.
+    pub fn userdata_count<T: 'static>(&self) -> usize {
+        let lua = self.lock();
+        let limits = unsafe { &(*lua.extra.get()).userdata_limits };
+        limits.get(&TypeId::of::<T>()).map_or(0, |limit| limit.live)
+    }
+
     /// Create a Lua userdata "proxy" object from a custom userdata type.
     ///
     /// Proxy object is an empty userdata object that has `T` metatable attached.
@@ -1591,6 +3983,29 @@ impl Lua {
         })
     }
 
+    /// Applies [`LuaOptions::max_string_bytes`] and [`LuaOptions::max_table_entries`] limits to
+    /// the given standard libraries.
+    fn apply_size_limits(&self, libs: StdLib) -> Result<()> {
+        let (max_string_bytes, max_table_entries) = unsafe {
+            let extra = &*self.lock().extra.get();
+            #[cfg(feature = "luau")]
+            let max_table_entries = extra.max_table_entries;
+            #[cfg(not(feature = "luau"))]
+            let max_table_entries = None;
+            (extra.max_string_bytes, max_table_entries)
+        };
+        if max_string_bytes.is_none() && max_table_entries.is_none() {
+            return Ok(());
+        }
+        limits::apply_size_limits(self, libs, max_string_bytes, max_table_entries)
+    }
+
+    /// Returns `true` if [`LuaOptions::strict_number_coercion`] option is enabled.
+    #[inline]
+    pub(crate) fn strict_number_coercion(&self) -> bool {
+        unsafe { (*self.lock().extra.get()).strict_number_coercion }
+    }
+
     /// Attempts to coerce a Lua value into a Number in a manner consistent with Lua's internal
     /// behavior.
     ///
@@ -1651,7 +4066,9 @@ impl Lua {
     ///
     /// This value will be available to Rust from all Lua instances which share the same main
     /// state.
+    #[track_caller]
     pub fn set_named_registry_value(&self, key: &str, t: impl IntoLua) -> Result<()> {
+        let location = registry_location();
         let lua = self.lock();
         let state = lua.state();
         unsafe {
@@ -1659,7 +4076,16 @@ impl Lua {
             check_stack(state, 5)?;
 
             lua.push_at(state, t)?;
-            rawset_field(state, ffi::LUA_REGISTRYINDEX, key)
+            let is_nil = ffi::lua_isnil(state, -1) != 0;
+            rawset_field(state, ffi::LUA_REGISTRYINDEX, key)?;
+
+            let named_values = &mut (*lua.extra.get()).named_registry_values;
+            if is_nil {
+                named_values.remove(key);
+            } else {
+                named_values.insert(key.to_string(), location);
+            }
+            Ok(())
         }
     }
 
@@ -1689,10 +4115,43 @@ impl Lua {
     ///
     /// Equivalent to calling [`Lua::set_named_registry_value`] with a value of [`Nil`].
     #[inline]
+    #[track_caller]
     pub fn unset_named_registry_value(&self, key: &str) -> Result<()> {
         self.set_named_registry_value(key, Nil)
     }
 
+    /// Gets a value stored by mlua in the registry under the address of `key`.
+    ///
+    /// Unlike named registry values, internal values cannot be reached or replaced using a string
+    /// key and are not reported by [`Lua::registry_size`] or [`Lua::registry_dump`].
+    pub(crate) fn internal_registry_value<T: FromLua>(&self, key: &'static u8) -> Result<T> {
+        let lua = self.lock();
+        let state = lua.state();
+        unsafe {
+            let _sg = StackGuard::new(state);
+            check_stack(state, 1)?;
+
+            ffi::lua_rawgetp(state, ffi::LUA_REGISTRYINDEX, key as *const u8 as *const c_void);
+            T::from_specified_stack(-1, &lua, state)
+        }
+    }
+
+    /// Sets a value stored by mlua in the registry under the address of `key`.
+    pub(crate) fn set_internal_registry_value(&self, key: &'static u8, t: impl IntoLua) -> Result<()> {
+        let lua = self.lock();
+        let state = lua.state();
+        unsafe {
+            let _sg = StackGuard::new(state);
+            check_stack(state, 3)?;
+
+            lua.push_at(state, t)?;
+            let key = key as *const u8 as *const c_void;
+            protect_lua!(state, 1, 0, |state| {
+                ffi::lua_rawsetp(state, ffi::LUA_REGISTRYINDEX, key)
+            })
+        }
+    }
+
     /// Place a value in the Lua registry with an auto-generated key.
     ///
     /// This value will be available to Rust from all Lua instances which share the same main
@@ -1701,7 +4160,9 @@ impl Lua {
     /// Be warned, garbage collection of values held inside the registry is not automatic, see
     /// [`RegistryKey`] for more details.
     /// However, dropped [`RegistryKey`]s automatically reused to store new values.
+    #[track_caller]
     pub fn create_registry_value(&self, t: impl IntoLua) -> Result<RegistryKey> {
+        let location = registry_location();
         let lua = self.lock();
         let state = lua.state();
         unsafe {
@@ -1722,6 +4183,7 @@ impl Lua {
             if let Some(registry_id) = free_registry_id {
                 // It must be safe to replace the value without triggering memory error
                 ffi::lua_rawseti(state, ffi::LUA_REGISTRYINDEX, registry_id as Integer);
+                (*lua.extra.get()).registry_slots.insert(registry_id, location);
                 return Ok(RegistryKey::new(registry_id, unref_list));
             }
 
@@ -1733,6 +4195,7 @@ impl Lua {
                     ffi::luaL_ref(state, ffi::LUA_REGISTRYINDEX)
                 })?
             };
+            (*lua.extra.get()).registry_slots.insert(registry_id, location);
             Ok(RegistryKey::new(registry_id, unref_list))
         }
     }
@@ -1772,7 +4235,11 @@ impl Lua {
             return Err(Error::MismatchedRegistryKey);
         }
 
-        unsafe { ffi::luaL_unref(lua.state(), ffi::LUA_REGISTRYINDEX, key.take()) };
+        unsafe {
+            let registry_id = key.take();
+            ffi::luaL_unref(lua.state(), ffi::LUA_REGISTRYINDEX, registry_id);
+            (*lua.extra.get()).registry_slots.remove(&registry_id);
+        }
         Ok(())
     }
 
@@ -1781,6 +4248,7 @@ impl Lua {
     /// An identifier used in [`RegistryKey`] may possibly be changed to a new value.
     ///
     /// See [`Lua::create_registry_value`] for more details.
+    #[track_caller]
     pub fn replace_registry_value(&self, key: &mut RegistryKey, t: impl IntoLua) -> Result<()> {
         let lua = self.lock();
         if !lua.owns_registry_value(key) {
@@ -1801,6 +4269,7 @@ impl Lua {
                 (Value::Nil, registry_id) => {
                     // Remove the value
                     ffi::luaL_unref(state, ffi::LUA_REGISTRYINDEX, registry_id);
+                    (*lua.extra.get()).registry_slots.remove(&registry_id);
                     key.set_id(ffi::LUA_REFNIL);
                 }
                 (value, ffi::LUA_REFNIL) => {
@@ -1842,7 +4311,83 @@ impl Lua {
             let unref_list = unref_list.replace(Vec::new());
             for id in mlua_expect!(unref_list, "unref list is not set") {
                 ffi::luaL_unref(state, ffi::LUA_REGISTRYINDEX, id);
+                (*lua.extra.get()).registry_slots.remove(&id);
+            }
+        }
+    }
+
+    /// Returns the number of live values placed in the Lua registry.
+    ///
+    /// This counts values created by [`Lua::create_registry_value`] whose [`RegistryKey`]s have
+    /// not been dropped, and values set by [`Lua::set_named_registry_value`].
+    /// Values stored internally by mlua are not included.
+    ///
+    /// This is cheap to call and can be used to monitor registry growth.
+    pub fn registry_size(&self) -> usize {
+        let lua = self.lock();
+        unsafe {
+            let extra = lua.extra.get();
+            let unref_list = (*extra).registry_unref_list.lock();
+            let expired = unref_list.as_ref().map(|list| list.len()).unwrap_or_default();
+            (*extra).registry_slots.len().saturating_sub(expired) + (*extra).named_registry_values.len()
+        }
+    }
+
+    /// Returns a list of live values placed in the Lua registry.
+    ///
+    /// The list contains the same values that are counted by [`Lua::registry_size`], along with
+    /// their type names and, in debug builds, locations where they were created.
+    ///
+    /// This is intended for debugging (eg. finding forgotten [`RegistryKey`]s) and is relatively
+    /// expensive, as it has to walk through all the values.
+    pub fn registry_dump(&self) -> Vec<RegistryEntry> {
+        let lua = self.lock();
+        let state = lua.state();
+        unsafe {
+            let _sg = StackGuard::new(state);
+            assert_stack(state, 1);
+
+            let extra = lua.extra.get();
+            let type_name = |state| {
+                let name = CStr::from_ptr(ffi::luaL_typename(state, -1))
+                    .to_str()
+                    .unwrap_or("?");
+                ffi::lua_pop(state, 1);
+                name
+            };
+
+            let unref_list = (*extra).registry_unref_list.lock();
+            let expired = unref_list.as_deref().unwrap_or_default();
+            let mut entries = Vec::new();
+            for (&id, &location) in &(*extra).registry_slots {
+                if expired.contains(&id) {
+                    continue;
+                }
+                ffi::lua_rawgeti(state, ffi::LUA_REGISTRYINDEX, id as Integer);
+                let type_name = type_name(state);
+                entries.push(RegistryEntry {
+                    name: None,
+                    id: Some(id),
+                    type_name,
+                    location,
+                });
+            }
+            for (name, &location) in &(*extra).named_registry_values {
+                if push_string(state, name.as_bytes(), !lua.unlikely_memory_error()).is_err() {
+                    continue;
+                }
+                ffi::lua_rawget(state, ffi::LUA_REGISTRYINDEX);
+                let type_name = type_name(state);
+                let name = Some(name.clone());
+                entries.push(RegistryEntry {
+                    name,
+                    id: None,
+                    type_name,
+                    location,
+                });
             }
+            entries.sort_by(|a, b| (&a.name, a.id).cmp(&(&b.name, b.id)));
+            entries
         }
     }
 
@@ -2114,6 +4659,9 @@ impl Deref for LuaGuard {
 }
 
 pub(crate) mod extra;
+#[cfg(feature = "luau")]
+mod fflags;
+mod limits;
 mod raw;
 pub(crate) mod util;
 
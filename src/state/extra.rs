@@ -2,9 +2,13 @@ use std::any::TypeId;
 use std::cell::UnsafeCell;
 use std::mem::MaybeUninit;
 use std::os::raw::{c_int, c_void};
+use std::panic::Location;
 use std::ptr;
 use std::rc::Rc;
+use std::string::String as StdString;
 use std::sync::Arc;
+#[cfg(feature = "luau")]
+use std::time::{Duration, Instant};
 
 use parking_lot::Mutex;
 use rustc_hash::FxHashMap;
@@ -20,6 +24,8 @@ use crate::util::{get_internal_metatable, push_internal_userdata, TypeKey, Wrapp
 #[cfg(any(feature = "luau", doc))]
 use crate::chunk::Compiler;
 use crate::MultiValue;
+#[cfg(feature = "luau")]
+use crate::{state::GcPhase, types::GcCallback};
 
 use super::{Lua, WeakLua};
 
@@ -67,6 +73,116 @@ impl RefThread {
     }
 }
 
+/// Tracks progress of the current garbage collection cycle for [`Lua::set_gc_callback`].
+#[cfg(feature = "luau")]
+#[derive(Default)]
+pub(super) struct GcCycleState {
+    // Start time and heap size of the collector step in progress
+    step_start: Option<(Instant, usize)>,
+    in_cycle: bool,
+    // Time spent in the collector during the current cycle
+    duration: Duration,
+    start_heap: usize,
+    // Heap size at the end of the last collector step
+    end_heap: usize,
+}
+
+#[cfg(feature = "luau")]
+impl GcCycleState {
+    /// Handles a GC interrupt, which is triggered before and after each collector step.
+    ///
+    /// After the step `gc` is the collector state the step started from.
+    pub(super) fn on_interrupt(&mut self, gc: c_int, heap: usize, callback: &GcCallback) {
+        let Some((start, start_heap)) = self.step_start.take() else {
+            self.step_start = Some((Instant::now(), heap));
+            return;
+        };
+        // The step started from the `GCSpause` state, so a new cycle has begun.
+        // The end of the previous one cannot be observed earlier.
+        if gc == 0 {
+            self.finish(callback);
+            self.start(start_heap, callback);
+        }
+        self.duration += start.elapsed();
+        self.end_heap = heap;
+    }
+
+    /// Accounts a full collection (which does not trigger GC interrupts).
+    pub(super) fn full_collection(&mut self, elapsed: Duration, heap: (usize, usize), callback: &GcCallback) {
+        if !self.in_cycle {
+            self.start(heap.0, callback);
+        }
+        self.duration += elapsed;
+        self.end_heap = heap.1;
+        self.finish(callback);
+    }
+
+    pub(super) fn start(&mut self, heap: usize, callback: &GcCallback) {
+        self.in_cycle = true;
+        self.duration = Duration::ZERO;
+        self.start_heap = heap;
+        callback(GcPhase::Start, Duration::ZERO);
+    }
+
+    pub(super) fn finish(&mut self, callback: &GcCallback) {
+        if std::mem::take(&mut self.in_cycle) {
+            let collected = self.start_heap.saturating_sub(self.end_heap);
+            callback(GcPhase::End { collected }, self.duration);
+        }
+    }
+}
+
+/// Number of live instances of a userdata type limited by `Lua::set_userdata_limit`.
+#[derive(Default)]
+pub(crate) struct UserDataLimit {
+    pub(crate) max: usize,
+    pub(crate) live: usize,
+}
+
+// Stored in a weak table next to a limited userdata instance, so it's collected with the instance
+// and decrements the live count on drop.
+pub(crate) struct UserDataTracker {
+    pub(super) extra: *mut ExtraData,
+    pub(super) type_id: TypeId,
+}
+
+impl Drop for UserDataTracker {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(limit) = (*self.extra).userdata_limits.get_mut(&self.type_id) {
+                limit.live = limit.live.saturating_sub(1);
+            }
+        }
+    }
+}
+
+// Userdata value collected by GC whose drop is deferred to run in the finalization priority order
+pub(crate) struct PendingFinalizer {
+    priority: i32,
+    value: *mut c_void,
+    drop: unsafe fn(*mut c_void),
+}
+
+impl PendingFinalizer {
+    pub(crate) fn new<T>(priority: i32, value: T) -> Self {
+        unsafe fn drop_value<T>(value: *mut c_void) {
+            drop(Box::from_raw(value as *mut T));
+        }
+
+        PendingFinalizer {
+            priority,
+            value: Box::into_raw(Box::new(value)) as *mut c_void,
+            drop: drop_value::<T>,
+        }
+    }
+}
+
+impl Drop for PendingFinalizer {
+    fn drop(&mut self) {
+        unsafe { (self.drop)(self.value) }
+    }
+}
+
 /// Data associated with the Lua state.
 pub(crate) struct ExtraData {
     pub(super) lua: MaybeUninit<Lua>,
@@ -78,9 +194,18 @@ pub(crate) struct ExtraData {
     pub(super) registered_userdata_t: FxHashMap<TypeId, c_int>,
     pub(super) registered_userdata_mt: FxHashMap<*const c_void, Option<TypeId>>,
     pub(super) last_checked_userdata_mt: (*const c_void, Option<TypeId>),
+    // Custom userdata type names
+    pub(super) userdata_type_names: FxHashMap<TypeId, StdString>,
+    pub(super) userdata_limits: FxHashMap<TypeId, UserDataLimit>,
+    // Finalization priorities of userdata instances (by address) and their deferred finalizers
+    pub(crate) finalization_priorities: FxHashMap<*const c_void, i32>,
+    pub(crate) pending_finalizers: Vec<PendingFinalizer>,
 
     // When Lua instance dropped, setting `None` would prevent collecting `RegistryKey`s
     pub(super) registry_unref_list: Arc<Mutex<Option<Vec<c_int>>>>,
+    // Registry slots allocated for `RegistryKey`s and named registry values (with creation location)
+    pub(super) registry_slots: FxHashMap<c_int, Option<&'static Location<'static>>>,
+    pub(super) named_registry_values: FxHashMap<StdString, Option<&'static Location<'static>>>,
 
     // Containers to store arbitrary data (extensions)
     pub(super) app_data: AppData,
@@ -90,6 +215,12 @@ pub(crate) struct ExtraData {
     pub(super) libs: StdLib,
     // Used in module mode
     pub(super) skip_memory_check: bool,
+    pub(super) strict_number_coercion: bool,
+    pub(super) max_string_bytes: Option<usize>,
+    #[cfg(feature = "luau")]
+    pub(super) max_table_entries: Option<usize>,
+    pub(crate) capture_tracebacks: bool,
+    pub(crate) capture_panic_backtraces: bool,
 
     // Auxiliary threads to store references
     pub(super) ref_thread: Vec<RefThread>,
@@ -101,7 +232,7 @@ pub(crate) struct ExtraData {
     pub(super) wrapped_failure_top: usize,
 
     // Address of `WrappedFailure` metatable
-    pub(super) wrapped_failure_mt_ptr: *const c_void,
+    pub(crate) wrapped_failure_mt_ptr: *const c_void,
 
     #[cfg(not(feature = "luau"))]
     pub(super) hook_callback: Option<crate::types::HookCallback>,
@@ -111,10 +242,48 @@ pub(crate) struct ExtraData {
     pub(super) warn_callback: Option<crate::types::WarnCallback>,
     #[cfg(feature = "luau")]
     pub(super) interrupt_callback: Option<crate::types::InterruptCallback>,
+    // Number of interrupt checks between calls of the interrupt callback and checks counted so far
+    #[cfg(feature = "luau")]
+    pub(super) interrupt_interval: u32,
+    #[cfg(feature = "luau")]
+    pub(super) interrupt_ticks: u32,
+    #[cfg(feature = "luau")]
+    pub(super) debug_callback: Option<crate::types::DebugCallback>,
+    #[cfg(feature = "luau")]
+    pub(super) debug_paused: FxHashMap<*mut ffi::lua_State, crate::hook::DebugPause>,
+    #[cfg(feature = "luau")]
+    pub(super) debug_break_callback: Option<crate::types::DebugHookCallback>,
+    #[cfg(feature = "luau")]
+    pub(super) debug_step_callback: Option<crate::types::DebugHookCallback>,
+    #[cfg(feature = "luau")]
+    pub(super) debug_interrupt_callback: Option<crate::types::DebugInterruptCallback>,
+    pub(super) clock_source: Option<crate::types::ClockSourceCallback>,
+    pub(crate) error_formatter: Option<crate::types::ErrorFormatterCallback>,
+    pub(crate) vm_message_translator: Option<crate::types::VmMessageTranslator>,
+    // Addresses of the native functions that raise script errors (`error` and `assert`)
+    pub(crate) script_error_raisers: Vec<usize>,
+    // Source maps set by `Chunk::set_source_map` (by printable chunk name)
+    pub(crate) source_maps: FxHashMap<StdString, crate::chunk::SourceMap>,
+    // Names of native functions, Luau keeps pointers to them until the state is closed
+    #[cfg(feature = "luau")]
+    pub(super) function_names: FxHashMap<StdString, std::ffi::CString>,
+    // Number of live `GcGuard`s and whether to restart the collector when the last one is dropped
+    pub(super) gc_pause_depth: usize,
+    pub(super) gc_resume_on_unpause: bool,
+    #[cfg(feature = "luau")]
+    pub(super) gc_callback: Option<crate::types::GcCallback>,
+    #[cfg(feature = "luau")]
+    pub(super) allocation_callback: Option<crate::types::AllocationCallback>,
+    #[cfg(feature = "luau")]
+    pub(super) gc_cycle: GcCycleState,
     #[cfg(feature = "luau")]
     pub(super) thread_creation_callback: Option<crate::types::ThreadCreationCallback>,
     #[cfg(feature = "luau")]
     pub(super) thread_collection_callback: Option<crate::types::ThreadCollectionCallback>,
+    #[cfg(feature = "luau")]
+    pub(super) thread_event_callback: Option<crate::types::ThreadEventCallback>,
+    #[cfg(feature = "luau")]
+    pub(super) profiler: Option<crate::luau::profiler::Profiler>,
 
     #[cfg(feature = "luau")]
     pub(crate) running_gc: bool,
@@ -127,6 +296,15 @@ pub(crate) struct ExtraData {
 
     // Values currently being yielded from Lua.yield()
     pub(super) yielded_values: Option<MultiValue>,
+
+    // Set when any thread has a resume limit
+    pub(crate) resume_limits: bool,
+
+    // Last id assigned to a pinned thread
+    pub(crate) next_pinned_thread_id: u64,
+
+    // Generator used by `math.random` set by `Lua::set_random_source`
+    pub(crate) random_source: Option<Box<dyn crate::RandomSource>>,
 }
 
 impl Drop for ExtraData {
@@ -173,13 +351,25 @@ impl ExtraData {
             registered_userdata_dtors: FxHashMap::default(),
             registered_userdata_t: FxHashMap::default(),
             registered_userdata_mt: FxHashMap::default(),
+            userdata_type_names: FxHashMap::default(),
+            userdata_limits: FxHashMap::default(),
+            finalization_priorities: FxHashMap::default(),
+            pending_finalizers: Vec::new(),
             last_checked_userdata_mt: (ptr::null(), None),
             registry_unref_list: Arc::new(Mutex::new(Some(Vec::new()))),
+            registry_slots: FxHashMap::default(),
+            named_registry_values: FxHashMap::default(),
             app_data: AppData::default(),
             app_data_priv: AppData::default(),
             safe: false,
             libs: StdLib::NONE,
             skip_memory_check: false,
+            strict_number_coercion: false,
+            max_string_bytes: None,
+            #[cfg(feature = "luau")]
+            max_table_entries: None,
+            capture_tracebacks: false,
+            capture_panic_backtraces: false,
             ref_thread: vec![RefThread::new(state)],
             ref_thread_internal: RefThread::new(state),
             wrapped_failure_pool: Vec::with_capacity(WRAPPED_FAILURE_POOL_DEFAULT_CAPACITY),
@@ -194,10 +384,43 @@ impl ExtraData {
             #[cfg(feature = "luau")]
             interrupt_callback: None,
             #[cfg(feature = "luau")]
+            interrupt_interval: 1,
+            #[cfg(feature = "luau")]
+            interrupt_ticks: 0,
+            #[cfg(feature = "luau")]
+            debug_callback: None,
+            #[cfg(feature = "luau")]
+            debug_paused: FxHashMap::default(),
+            #[cfg(feature = "luau")]
+            debug_break_callback: None,
+            #[cfg(feature = "luau")]
+            debug_step_callback: None,
+            #[cfg(feature = "luau")]
+            debug_interrupt_callback: None,
+            clock_source: None,
+            error_formatter: None,
+            vm_message_translator: None,
+            script_error_raisers: Vec::new(),
+            source_maps: FxHashMap::default(),
+            #[cfg(feature = "luau")]
+            function_names: FxHashMap::default(),
+            gc_pause_depth: 0,
+            gc_resume_on_unpause: false,
+            #[cfg(feature = "luau")]
+            gc_callback: None,
+            #[cfg(feature = "luau")]
+            allocation_callback: None,
+            #[cfg(feature = "luau")]
+            gc_cycle: GcCycleState::default(),
+            #[cfg(feature = "luau")]
             thread_creation_callback: None,
             #[cfg(feature = "luau")]
             thread_collection_callback: None,
             #[cfg(feature = "luau")]
+            thread_event_callback: None,
+            #[cfg(feature = "luau")]
+            profiler: None,
+            #[cfg(feature = "luau")]
             sandboxed: false,
             #[cfg(feature = "luau")]
             compiler: None,
@@ -206,6 +429,9 @@ impl ExtraData {
             #[cfg(feature = "luau")]
             running_gc: false,
             yielded_values: None,
+            resume_limits: false,
+            next_pinned_thread_id: 0,
+            random_source: None,
         }));
 
         // Store it in the registry
@@ -222,7 +448,7 @@ impl ExtraData {
         self.weak.write(WeakLua(XRc::downgrade(raw)));
     }
 
-    pub(super) unsafe fn get(state: *mut ffi::lua_State) -> *mut Self {
+    pub(crate) unsafe fn get(state: *mut ffi::lua_State) -> *mut Self {
         #[cfg(feature = "luau")]
         if cfg!(not(feature = "module")) {
             // In the main app we can use `lua_callbacks` to access ExtraData
@@ -274,4 +500,41 @@ impl ExtraData {
     pub(crate) unsafe fn get_userdata_dtor(&self, type_id: TypeId) -> Option<ffi::lua_CFunction> {
         self.registered_userdata_dtors.get(&type_id).copied()
     }
+
+    /// Returns the debug name of a native function, which must stay valid while the state is open.
+    #[cfg(feature = "luau")]
+    pub(super) fn function_debug_name(&mut self, name: &str) -> *const std::os::raw::c_char {
+        if let Some(name) = self.function_names.get(name) {
+            return name.as_ptr();
+        }
+        let cname = std::ffi::CString::new(name).unwrap_or_default();
+        let ptr = cname.as_ptr();
+        self.function_names.insert(name.to_string(), cname);
+        ptr
+    }
+
+    /// Takes the finalization priority of the userdata at the given address (if set).
+    pub(crate) fn take_finalization_priority(&mut self, ud: *const c_void) -> Option<i32> {
+        if self.finalization_priorities.is_empty() {
+            return None;
+        }
+        self.finalization_priorities.remove(&ud)
+    }
+
+    /// Drops userdata values deferred by GC, lower priorities first.
+    ///
+    /// Finalizers may run Lua code, so `extra` must not be borrowed during the call.
+    pub(crate) unsafe fn run_pending_finalizers(extra: *mut ExtraData) {
+        if (*extra).pending_finalizers.is_empty() {
+            return;
+        }
+        #[cfg(feature = "luau")]
+        if (*extra).running_gc {
+            return;
+        }
+        let mut pending = std::mem::take(&mut (*extra).pending_finalizers);
+        // Stable sort keeps the collection order for values with the same priority
+        pending.sort_by_key(|finalizer| finalizer.priority);
+        drop(pending);
+    }
 }
@@ -0,0 +1,48 @@
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+
+/// Locks flags for reading or writing.
+///
+/// Flags are global for the process and Luau does not synchronize access to them.
+pub(super) fn lock() -> MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Returns all Luau boolean feature flags and their current values.
+pub(super) fn bool_flags() -> Vec<(CString, bool)> {
+    let mut flags = Vec::new();
+    unsafe { ffi::luau_visitfflags(visit_flag, &mut flags as *mut Vec<_> as *mut c_void) };
+    flags
+        .into_iter()
+        .map(|(name, value)| (name, value != 0))
+        .collect()
+}
+
+/// Returns all Luau integer feature flags and their current values.
+pub(super) fn int_flags() -> Vec<(CString, c_int)> {
+    let mut flags = Vec::new();
+    unsafe { ffi::luau_visitfintflags(visit_flag, &mut flags as *mut Vec<_> as *mut c_void) };
+    flags
+}
+
+unsafe extern "C" fn visit_flag(ud: *mut c_void, name: *const c_char, value: c_int) {
+    let flags = &mut *(ud as *mut Vec<(CString, c_int)>);
+    flags.push((CStr::from_ptr(name).to_owned(), value));
+}
+
+/// Default values of flags.
+pub(super) struct Defaults {
+    pub(super) bools: Vec<(CString, bool)>,
+    pub(super) ints: Vec<(CString, c_int)>,
+}
+
+/// Remembers the current values of all flags as defaults (once), and returns them.
+pub(super) fn snapshot_defaults() -> &'static Defaults {
+    static DEFAULTS: OnceLock<Defaults> = OnceLock::new();
+    DEFAULTS.get_or_init(|| Defaults {
+        bools: bool_flags(),
+        ints: int_flags(),
+    })
+}
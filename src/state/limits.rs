@@ -0,0 +1,155 @@
+use crate::error::{Error, Result};
+use crate::function::Function;
+use crate::multi::MultiValue;
+use crate::state::Lua;
+use crate::stdlib::StdLib;
+use crate::table::Table;
+use crate::types::MaybeSend;
+use crate::value::Value;
+
+/// Wraps standard library functions that can produce large strings or tables in a single call
+/// to enforce [`LuaOptions::max_string_bytes`] and [`LuaOptions::max_table_entries`].
+///
+/// Only functions that are not Luau builtins are wrapped: calls to builtins skip the library
+/// table lookup in `safeenv` environments, so a wrapper would not be called.
+///
+/// [`LuaOptions::max_string_bytes`]: crate::LuaOptions::max_string_bytes
+/// [`LuaOptions::max_table_entries`]: crate::LuaOptions::max_table_entries
+pub(super) fn apply_size_limits(
+    lua: &Lua,
+    libs: StdLib,
+    max_string_bytes: Option<usize>,
+    max_table_entries: Option<usize>,
+) -> Result<()> {
+    let globals = lua.globals();
+
+    if let Some(max_bytes) = max_string_bytes.filter(|_| libs.contains(StdLib::STRING)) {
+        if let Some(string) = globals.raw_get::<Option<Table>>("string")? {
+            wrap(lua, &string, "rep", move |lua, args| {
+                let len = string_len(lua, args.front())?;
+                let n = integer(lua, args.get(1))?;
+                // Separator is supported since Lua 5.2
+                let sep_len = match args.get(2) {
+                    Some(Value::Nil) | None => Some(0),
+                    _ if cfg!(any(feature = "lua51", feature = "luajit", feature = "luau")) => Some(0),
+                    sep => string_len(lua, sep)?,
+                };
+                if let (Some(len), Some(n), Some(sep_len)) = (len, n, sep_len) {
+                    let n = n.max(0) as u64;
+                    let total = (len as u64)
+                        .saturating_mul(n)
+                        .saturating_add((sep_len as u64).saturating_mul(n.saturating_sub(1)));
+                    if total > max_bytes as u64 {
+                        return Err(string_too_large(max_bytes));
+                    }
+                }
+                Ok(())
+            })?;
+        }
+    }
+
+    if !libs.contains(StdLib::TABLE) {
+        return Ok(());
+    }
+    let Some(table) = globals.raw_get::<Option<Table>>("table")? else {
+        return Ok(());
+    };
+
+    if let Some(max_bytes) = max_string_bytes {
+        wrap(lua, &table, "concat", move |lua, args| {
+            let Some(Value::Table(t)) = args.front() else {
+                return Ok(());
+            };
+            let sep_len = match args.get(1) {
+                Some(Value::Nil) | None => Some(0),
+                sep => string_len(lua, sep)?,
+            };
+            let i = match args.get(2) {
+                Some(Value::Nil) | None => Some(1),
+                i => integer(lua, i)?,
+            };
+            let j = match args.get(3) {
+                Some(Value::Nil) | None => Some(t.raw_len() as i64),
+                j => integer(lua, j)?,
+            };
+            let (Some(sep_len), Some(i), Some(j)) = (sep_len, i, j) else {
+                return Ok(());
+            };
+            let mut total = 0usize;
+            for idx in i..=j {
+                // Invalid values are reported by the original function
+                let Some(len) = string_len(lua, Some(&t.raw_get::<Value>(idx)?))? else {
+                    return Ok(());
+                };
+                total = total.saturating_add(len);
+                if idx < j {
+                    total = total.saturating_add(sep_len);
+                }
+                if total > max_bytes {
+                    return Err(string_too_large(max_bytes));
+                }
+            }
+            Ok(())
+        })?;
+    }
+
+    #[cfg(feature = "luau")]
+    if let Some(max_entries) = max_table_entries {
+        wrap(lua, &table, "create", move |lua, args| {
+            if let Some(n) = integer(lua, args.front())? {
+                if n > max_entries as i64 {
+                    return Err(table_too_large(max_entries));
+                }
+            }
+            Ok(())
+        })?;
+    }
+    #[cfg(not(feature = "luau"))]
+    let _ = max_table_entries;
+
+    Ok(())
+}
+
+/// Replaces `lib[name]` with a function that runs `check` before calling the original one.
+fn wrap<F>(lua: &Lua, lib: &Table, name: &str, check: F) -> Result<()>
+where
+    F: Fn(&Lua, &MultiValue) -> Result<()> + MaybeSend + 'static,
+{
+    let Some(original) = lib.raw_get::<Option<Function>>(name)? else {
+        return Ok(());
+    };
+    let func = lua.create_function(move |lua, args: MultiValue| {
+        check(lua, &args)?;
+        original.call::<MultiValue>(args)
+    })?;
+    lib.raw_set(name, func)
+}
+
+fn string_len(lua: &Lua, value: Option<&Value>) -> Result<Option<usize>> {
+    match value {
+        Some(Value::String(s)) => Ok(Some(s.as_bytes().len())),
+        Some(v @ (Value::Integer(_) | Value::Number(_))) => {
+            Ok(lua.coerce_string(v.clone())?.map(|s| s.as_bytes().len()))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[allow(clippy::useless_conversion)]
+fn integer(lua: &Lua, value: Option<&Value>) -> Result<Option<i64>> {
+    match value {
+        Some(v) => Ok(lua.coerce_integer(v.clone())?.map(i64::from)),
+        None => Ok(None),
+    }
+}
+
+fn string_too_large(max_bytes: usize) -> Error {
+    Error::runtime(format!(
+        "resulting string is too large (limit is {max_bytes} bytes)"
+    ))
+}
+
+#[cfg(feature = "luau")]
+fn table_too_large(max_entries: usize) -> Error {
+    Error::runtime(format!("table is too large (limit is {max_entries} entries)"))
+}
@@ -5,12 +5,13 @@ use std::mem;
 use std::os::raw::{c_char, c_int, c_void};
 use std::panic::resume_unwind;
 use std::ptr::{self, NonNull};
+use std::string::String as StdString;
 use std::sync::Arc;
 
 use crate::chunk::ChunkMode;
 use crate::error::{Error, Result};
 use crate::function::Function;
-use crate::memory::{MemoryState, ALLOCATOR};
+use crate::memory::{LuaAllocator, MemoryState, ALLOCATOR};
 #[allow(unused_imports)]
 use crate::state::util::callback_error_ext;
 use crate::state::util::{callback_error_ext_yieldable, get_next_spot};
@@ -41,7 +42,7 @@ use crate::util::{
 };
 use crate::value::{Nil, Value};
 
-use super::extra::ExtraData;
+use super::extra::{ExtraData, UserDataTracker};
 use super::{Lua, LuaOptions, WeakLua};
 
 #[cfg(not(feature = "luau"))]
@@ -50,6 +51,9 @@ use crate::{
     types::{HookCallback, HookKind, VmState},
 };
 
+// Unique key to store the trackers of limited userdata instances in the registry
+static USERDATA_TRACKERS_KEY: u8 = 0;
+
 /// An inner Lua struct which holds a raw Lua state.
 #[doc(hidden)]
 pub struct RawLua {
@@ -74,9 +78,12 @@ impl Drop for RawLua {
                 // Reset any callbacks
                 (*ffi::lua_callbacks(self.main_state())).interrupt = None;
                 (*ffi::lua_callbacks(self.main_state())).userthread = None;
+                (*ffi::lua_callbacks(self.main_state())).onallocate = None;
             }
 
             ffi::lua_close(self.main_state());
+            // Drop userdata values deferred during closing, in their priority order
+            ExtraData::run_pending_finalizers(self.extra.get());
 
             // Deallocate `MemoryState`
             if !mem_state.is_null() {
@@ -136,12 +143,24 @@ impl RawLua {
         self.extra.get()
     }
 
-    pub(super) unsafe fn new(libs: StdLib, options: &LuaOptions) -> XRc<ReentrantMutex<Self>> {
+    pub(super) unsafe fn new(
+        libs: StdLib,
+        options: &LuaOptions,
+        allocator: Option<Box<dyn LuaAllocator>>,
+    ) -> Result<XRc<ReentrantMutex<Self>>> {
         let mem_state: *mut MemoryState = Box::into_raw(Box::default());
+        (*mem_state).set_track_peak(options.track_peak_memory);
+        (*mem_state).set_custom_allocator(allocator);
         let mut state = ffi::lua_newstate(ALLOCATOR, mem_state as *mut c_void);
-        // If state is null then switch to Lua internal allocator
+        // If state is null then switch to Lua internal allocator (unless the host provided one)
         if state.is_null() {
+            let custom_allocator = (*mem_state).has_custom_allocator();
             drop(Box::from_raw(mem_state));
+            if custom_allocator {
+                return Err(Error::MemoryError(
+                    "cannot allocate a Lua state using the provided allocator".to_string(),
+                ));
+            }
             state = ffi::luaL_newstate();
         }
         assert!(!state.is_null(), "Failed to create a Lua VM");
@@ -157,12 +176,25 @@ impl RawLua {
 
         let rawlua = Self::init_from_ptr(state, true);
         let extra = rawlua.lock().extra.get();
+        // Category limits are checked against the thread running code
+        #[cfg(feature = "luau")]
+        if let Some(mem_state) = MemoryState::get(state).as_mut() {
+            mem_state.set_current_state(&rawlua.lock().state);
+        }
 
         mlua_expect!(
             load_std_libs(state, libs),
             "Error during loading standard libraries"
         );
         (*extra).libs |= libs;
+        (*extra).strict_number_coercion = options.strict_number_coercion;
+        (*extra).max_string_bytes = options.max_string_bytes;
+        #[cfg(feature = "luau")]
+        {
+            (*extra).max_table_entries = options.max_table_entries;
+        }
+        (*extra).capture_tracebacks = options.capture_tracebacks;
+        (*extra).capture_panic_backtraces = options.capture_panic_backtraces;
 
         if !options.catch_rust_panics {
             mlua_expect!(
@@ -186,7 +218,7 @@ impl RawLua {
             )
         }
 
-        rawlua
+        Ok(rawlua)
     }
 
     pub(super) unsafe fn init_from_ptr(state: *mut ffi::lua_State, owned: bool) -> XRc<ReentrantMutex<Self>> {
@@ -208,6 +240,7 @@ impl RawLua {
                 init_internal_metatable::<XRc<UnsafeCell<ExtraData>>>(state, None)?;
                 init_internal_metatable::<Callback>(state, None)?;
                 init_internal_metatable::<CallbackUpvalue>(state, None)?;
+                init_internal_metatable::<UserDataTracker>(state, None)?;
                 #[cfg(all(not(feature = "lua51"), not(feature = "luajit")))]
                 init_internal_metatable::<ContinuationUpvalue>(state, None)?;
                 #[cfg(not(feature = "luau"))]
@@ -609,7 +642,9 @@ impl RawLua {
 
         let protect = !self.unlikely_memory_error();
         #[cfg(feature = "luau")]
-        let protect = protect || (*self.extra.get()).thread_creation_callback.is_some();
+        let protect = protect
+            || (*self.extra.get()).thread_creation_callback.is_some()
+            || (*self.extra.get()).thread_event_callback.is_some();
 
         let thread_state = if !protect {
             ffi::lua_newthread(state)
@@ -754,7 +789,7 @@ impl RawLua {
                 let failure_mt_ptr = (*self.extra.get()).wrapped_failure_mt_ptr;
                 match get_internal_userdata::<WrappedFailure>(state, idx, failure_mt_ptr).as_mut() {
                     Some(WrappedFailure::Error(err)) => Value::Error(Box::new(err.clone())),
-                    Some(WrappedFailure::Panic(panic)) => {
+                    Some(WrappedFailure::Panic(panic, _)) => {
                         if let Some(panic) = panic.take() {
                             resume_unwind(panic);
                         }
@@ -934,7 +969,7 @@ impl RawLua {
         })
     }
 
-    unsafe fn make_userdata_with_metatable<T>(
+    unsafe fn make_userdata_with_metatable<T: 'static>(
         &self,
         data: UserDataStorage<T>,
         get_metatable_id: impl FnOnce() -> Result<Integer>,
@@ -943,10 +978,23 @@ impl RawLua {
         let _sg = StackGuard::new(state);
         check_stack(state, 3)?;
 
+        let type_id = TypeId::of::<T>();
+        let limit = (*self.extra.get()).userdata_limits.get(&type_id);
+        if let Some(limit) = limit {
+            if limit.live >= limit.max {
+                let name = self.userdata_type_name::<T>(type_id);
+                return Err(Error::runtime(format!(
+                    "userdata limit exceeded for type '{name}'"
+                )));
+            }
+        }
+
         // We generate metatable first to make sure it *always* available when userdata pushed
         let mt_id = get_metatable_id()?;
         let protect = !self.unlikely_memory_error();
-        crate::util::push_userdata(state, data, protect)?;
+        let ud = crate::util::push_userdata(state, data, protect)?;
+        // Drop a stale priority of a collected userdata that used the same address
+        (*self.extra.get()).take_finalization_priority(ud as *const c_void);
         ffi::lua_rawgeti(state, ffi::LUA_REGISTRYINDEX, mt_id);
         ffi::lua_setmetatable(state, -2);
 
@@ -962,9 +1010,51 @@ impl RawLua {
             ffi::lua_setuservalue(state, -2);
         }
 
+        if limit.is_some() {
+            self.track_userdata(state, type_id)?;
+        }
+
         Ok(AnyUserData(self.pop_ref()))
     }
 
+    // Counts the userdata at the top of the stack as a live instance of a limited type until
+    // it's garbage collected.
+    unsafe fn track_userdata(&self, state: *mut ffi::lua_State, type_id: TypeId) -> Result<()> {
+        let _sg = StackGuard::new(state);
+        check_stack(state, 5)?;
+
+        let protect = !self.unlikely_memory_error();
+        let trackers_key = &USERDATA_TRACKERS_KEY as *const u8 as *const c_void;
+        ffi::lua_rawgetp(state, ffi::LUA_REGISTRYINDEX, trackers_key);
+        if ffi::lua_isnil(state, -1) != 0 {
+            ffi::lua_pop(state, 1);
+            // Create a table with weak keys mapping userdata to their trackers
+            protect_lua!(state, 0, 1, fn(state) {
+                ffi::lua_createtable(state, 0, 0);
+                ffi::lua_createtable(state, 0, 1);
+                ffi::lua_pushstring(state, cstr!("k"));
+                ffi::lua_setfield(state, -2, cstr!("__mode"));
+                ffi::lua_setmetatable(state, -2);
+                ffi::lua_pushvalue(state, -1);
+                ffi::lua_rawsetp(state, ffi::LUA_REGISTRYINDEX, &USERDATA_TRACKERS_KEY as *const u8 as *const c_void);
+            })?;
+        }
+
+        ffi::lua_pushvalue(state, -2);
+        let extra = self.extra.get();
+        // The tracker decrements the count when dropped, even if it fails to be pushed
+        if let Some(limit) = (*extra).userdata_limits.get_mut(&type_id) {
+            limit.live += 1;
+        }
+        push_internal_userdata(state, UserDataTracker { extra, type_id }, protect)?;
+        if protect {
+            protect_lua!(state, 3, 0, fn(state) ffi::lua_rawset(state, -3))?;
+        } else {
+            ffi::lua_rawset(state, -3);
+        }
+        Ok(())
+    }
+
     pub(crate) unsafe fn create_userdata_metatable_at(
         &self,
         registry: RawUserDataRegistry,
@@ -1005,7 +1095,8 @@ impl RawLua {
         let metatable_nrec = registry.meta_methods.len() + registry.meta_fields.len();
         push_table(state, 0, metatable_nrec, true)?;
         for (k, m) in registry.meta_methods {
-            self.push_at(state, self.create_callback(m)?)?;
+            let name = method_name(&registry.type_name, &k);
+            self.push_at(state, self.create_named_callback(m, name.as_deref())?)?;
             rawset_field(state, -2, MetaMethod::validate(&k)?)?;
         }
         let mut has_name = false;
@@ -1016,7 +1107,10 @@ impl RawLua {
         }
         // Set `__name/__type` if not provided
         if !has_name {
-            let type_name = registry.type_name;
+            let type_name = registry
+                .type_id
+                .and_then(|type_id| (*self.extra.get()).userdata_type_names.get(&type_id));
+            let type_name = type_name.unwrap_or(&registry.type_name);
             push_string(state, type_name.as_bytes(), !self.unlikely_memory_error())?;
             rawset_field(state, -2, MetaMethod::Type.name())?;
         }
@@ -1093,7 +1187,8 @@ impl RawLua {
                 }
             }
             for (k, m) in registry.methods {
-                self.push_at(state, self.create_callback(m)?)?;
+                let name = method_name(&registry.type_name, &k);
+                self.push_at(state, self.create_named_callback(m, name.as_deref())?)?;
                 rawset_field(state, -2, &k)?;
             }
             match index_type {
@@ -1143,11 +1238,26 @@ impl RawLua {
         unsafe { self.get_userdata_type_id_inner(self.ref_thread(vref.aux_thread), vref.index) }
     }
 
+    /// Returns the name of userdata type `T` with the given `TypeId`, as set by
+    /// `Lua::register_userdata_type_with_name` or derived from the Rust type name.
+    pub(crate) fn userdata_type_name<T>(&self, type_id: TypeId) -> StdString {
+        self.custom_userdata_type_name(type_id)
+            .unwrap_or_else(short_type_name::<T>)
+    }
+
+    /// Returns the custom name of a userdata type set by `Lua::register_userdata_type_with_name`.
+    pub(crate) fn custom_userdata_type_name(&self, type_id: TypeId) -> Option<StdString> {
+        unsafe { (*self.extra.get()).userdata_type_names.get(&type_id).cloned() }
+    }
+
     // Same as `get_userdata_ref_type_id` but assumes the userdata is already on the stack.
+    //
+    // `expected` is the `TypeId` of `T`, used to report its name in errors.
     pub(crate) unsafe fn get_userdata_type_id<T>(
         &self,
         state: *mut ffi::lua_State,
         idx: c_int,
+        expected: TypeId,
     ) -> Result<Option<TypeId>> {
         match self.get_userdata_type_id_inner(state, idx) {
             Ok(type_id) => Ok(type_id),
@@ -1155,7 +1265,10 @@ impl RawLua {
                 // Report `FromLuaConversionError` instead
                 let idx_type_name = CStr::from_ptr(ffi::luaL_typename(state, idx));
                 let idx_type_name = idx_type_name.to_str().unwrap();
-                let message = format!("expected userdata of type '{}'", short_type_name::<T>());
+                let message = format!(
+                    "expected userdata of type '{}'",
+                    self.userdata_type_name::<T>(expected)
+                );
                 Err(Error::from_lua_conversion(idx_type_name, "userdata", message))
             }
             Err(err) => Err(err),
@@ -1203,7 +1316,13 @@ impl RawLua {
     }
 
     // Creates a Function out of a Callback containing a 'static Fn.
+    #[inline]
     pub(crate) fn create_callback(&self, func: Callback) -> Result<Function> {
+        self.create_named_callback(func, None)
+    }
+
+    // Creates a Function out of a Callback, with the name shown in Luau tracebacks (if provided).
+    pub(crate) fn create_named_callback(&self, func: Callback, name: Option<&str>) -> Result<Function> {
         unsafe extern "C-unwind" fn call_callback(state: *mut ffi::lua_State) -> c_int {
             let upvalue = get_userdata::<CallbackUpvalue>(state, ffi::lua_upvalueindex(1));
             callback_error_ext_yieldable(
@@ -1228,10 +1347,27 @@ impl RawLua {
             let _sg = StackGuard::new(state);
             check_stack(state, 4)?;
 
+            #[cfg(feature = "luau")]
+            let debugname = match name {
+                Some(name) => (*self.extra.get()).function_debug_name(name),
+                None => ptr::null(),
+            };
+            #[cfg(not(feature = "luau"))]
+            let _ = name;
+
             let func = Some(func);
             let extra = XRc::clone(&self.extra);
             let protect = !self.unlikely_memory_error();
             push_internal_userdata(state, CallbackUpvalue { data: func, extra }, protect)?;
+            #[cfg(feature = "luau")]
+            if protect {
+                protect_lua!(state, 1, 1, |state| {
+                    ffi::lua_pushcclosured(state, call_callback, debugname, 1);
+                })?;
+            } else {
+                ffi::lua_pushcclosured(state, call_callback, debugname, 1);
+            }
+            #[cfg(not(feature = "luau"))]
             if protect {
                 protect_lua!(state, 1, 1, fn(state) {
                     ffi::lua_pushcclosure(state, call_callback, 1);
@@ -1369,6 +1505,16 @@ impl RawLua {
     }
 }
 
+// Returns the name of a userdata method to show in tracebacks (only with `named-closures` feature)
+#[inline]
+fn method_name(type_name: &str, method: &str) -> Option<StdString> {
+    if cfg!(all(feature = "named-closures", feature = "luau")) {
+        Some(format!("{type_name}:{method}"))
+    } else {
+        None
+    }
+}
+
 // Uses 3 stack spaces
 unsafe fn load_std_libs(state: *mut ffi::lua_State, libs: StdLib) -> Result<()> {
     unsafe fn requiref(
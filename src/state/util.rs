@@ -13,10 +13,11 @@ use crate::util::{self, check_stack, get_internal_metatable, WrappedFailure};
 #[cfg(all(not(feature = "lua51"), not(feature = "luajit"), not(feature = "luau")))]
 use crate::{types::ContinuationUpvalue, util::get_userdata};
 
-struct StateGuard<'a>(&'a RawLua, *mut ffi::lua_State);
+// Makes `state` the current state of `RawLua` until dropped
+pub(crate) struct StateGuard<'a>(&'a RawLua, *mut ffi::lua_State);
 
 impl<'a> StateGuard<'a> {
-    fn new(inner: &'a RawLua, mut state: *mut ffi::lua_State) -> Self {
+    pub(crate) fn new(inner: &'a RawLua, mut state: *mut ffi::lua_State) -> Self {
         state = inner.state.replace(state);
         Self(inner, state)
     }
@@ -130,7 +131,7 @@ where
 
             // Build `CallbackError` with traceback
             let traceback = if ffi::lua_checkstack(state, ffi::LUA_TRACEBACK_STACK) != 0 {
-                ffi::luaL_traceback(state, state, ptr::null(), 0);
+                util::traceback(state, state, ptr::null(), 0);
                 let traceback = util::to_string(state, -1);
                 ffi::lua_pop(state, 1);
                 traceback
@@ -149,7 +150,7 @@ where
         }
         Err(p) => {
             let wrapped_panic = prealloc_failure.r#use(state, extra);
-            ptr::write(wrapped_panic, WrappedFailure::Panic(Some(p)));
+            ptr::write(wrapped_panic, WrappedFailure::panic(state, p));
             get_internal_metatable::<WrappedFailure>(state);
             ffi::lua_setmetatable(state, -2);
             ffi::lua_error(state)
@@ -184,7 +185,10 @@ where
     match catch_unwind(AssertUnwindSafe(|| {
         let rawlua = (*extra).raw_lua();
         let _guard = StateGuard::new(rawlua, state);
-        f(extra, nargs)
+        let result = f(extra, nargs);
+        // Returning from a Rust function is a safe point to drop values deferred by GC
+        ExtraData::run_pending_finalizers(extra);
+        result
     })) {
         Ok(Ok(r)) => {
             // Return unused `WrappedFailure` to the pool
@@ -302,7 +306,7 @@ where
 
             // Build `CallbackError` with traceback
             let traceback = if ffi::lua_checkstack(state, ffi::LUA_TRACEBACK_STACK) != 0 {
-                ffi::luaL_traceback(state, state, ptr::null(), 0);
+                util::traceback(state, state, ptr::null(), 0);
                 let traceback = util::to_string(state, -1);
                 ffi::lua_pop(state, 1);
                 traceback
@@ -321,7 +325,7 @@ where
         }
         Err(p) => {
             let wrapped_panic = prealloc_failure.r#use(state, extra);
-            ptr::write(wrapped_panic, WrappedFailure::Panic(Some(p)));
+            ptr::write(wrapped_panic, WrappedFailure::panic(state, p));
             get_internal_metatable::<WrappedFailure>(state);
             ffi::lua_setmetatable(state, -2);
             ffi::lua_error(state)
@@ -1,6 +1,9 @@
-use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
 
 /// Flags describing the set of lua standard libraries to load.
+///
+/// Flags can be combined using the bitwise operators, eg. `StdLib::ALL_SAFE & !StdLib::DEBUG`
+/// loads every safe library except `debug`.
 #[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub struct StdLib(u32);
 
@@ -76,6 +79,11 @@ impl StdLib {
     /// (**unsafe**) All standard libraries
     pub const ALL: StdLib = StdLib(u32::MAX);
     /// The safe subset of the standard libraries
+    ///
+    /// It includes everything except `debug` and `ffi`, apart from Luau where all libraries are
+    /// safe (its `debug` library only provides `debug.info` and `debug.traceback`). Use
+    /// `StdLib::ALL_SAFE & !StdLib::DEBUG` to exclude the `debug` library on all backends, for
+    /// example to prevent scripts from introspecting the call stack in sandboxes.
     #[cfg(not(feature = "luau"))]
     pub const ALL_SAFE: StdLib = StdLib((1 << 30) - 1);
     #[cfg(feature = "luau")]
@@ -99,6 +107,13 @@ impl BitAndAssign for StdLib {
     }
 }
 
+impl Not for StdLib {
+    type Output = Self;
+    fn not(self) -> Self::Output {
+        StdLib(!self.0)
+    }
+}
+
 impl BitOr for StdLib {
     type Output = Self;
     fn bitor(self, rhs: Self) -> Self::Output {
@@ -1,4 +1,5 @@
-use std::collections::HashSet;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::marker::PhantomData;
 use std::os::raw::{c_int, c_void};
@@ -393,6 +394,71 @@ impl Table {
         }
     }
 
+    /// Removes the element at position `idx` from the sequence part of the table and returns it,
+    /// without invoking metamethods.
+    ///
+    /// The removed element is replaced by the last element of the sequence.
+    /// This does not preserve ordering, but is `O(1)`, similar to [`Vec::swap_remove`].
+    /// If you need to preserve the element order, use [`Table::raw_remove`] instead.
+    pub fn swap_remove<V: FromLua>(&self, idx: Integer) -> Result<V> {
+        let lua = self.0.lua.lock();
+        let state = lua.state();
+        unsafe {
+            #[cfg(feature = "luau")]
+            self.check_readonly_write(&lua)?;
+
+            let _sg = StackGuard::new(state);
+            check_stack(state, 4)?;
+
+            lua.push_ref_at(&self.0, state);
+            let len = ffi::lua_rawlen(state, -1) as Integer;
+            if idx < 1 || idx > len {
+                return Err(Error::runtime("index out of bounds"));
+            }
+            ffi::lua_rawgeti(state, -1, idx);
+            // Replacing existing slots must be safe to do (no reallocation)
+            if idx != len {
+                ffi::lua_rawgeti(state, -2, len);
+                ffi::lua_rawseti(state, -3, idx);
+            }
+            ffi::lua_pushnil(state);
+            ffi::lua_rawseti(state, -3, len);
+
+            V::from_specified_stack(-1, &lua, state)
+        }
+    }
+
+    /// Inserts a value into the sequence part of the table, keeping it sorted, without invoking
+    /// metamethods.
+    ///
+    /// The position is found by binary search, calling `compare` with the inserted value and an
+    /// existing element. The value is inserted after any elements that compare equal to it, so
+    /// elements with the same priority keep their insertion order. Returns the index of the
+    /// inserted value.
+    ///
+    /// The sequence must already be sorted according to `compare`, otherwise the value is inserted
+    /// at an unspecified position. The search takes `O(log n)` comparisons, but the insertion
+    /// itself is `O(n)` like [`Table::raw_insert`].
+    pub fn insert_sorted<F>(&self, value: Value, mut compare: F) -> Result<Integer>
+    where
+        F: FnMut(&Value, &Value) -> Ordering,
+    {
+        #[cfg(feature = "luau")]
+        self.check_readonly_write(&self.0.lua.lock())?;
+
+        let (mut lo, mut hi) = (1, self.raw_len() as Integer + 1);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let elem: Value = self.raw_get(mid)?;
+            match compare(&value, &elem) {
+                Ordering::Less => hi = mid,
+                Ordering::Equal | Ordering::Greater => lo = mid + 1,
+            }
+        }
+        self.raw_insert(lo, value)?;
+        Ok(lo)
+    }
+
     /// Clears the table, removing all keys and values from array and hash parts,
     /// without invoking metamethods.
     ///
@@ -434,6 +500,52 @@ impl Table {
         Ok(())
     }
 
+    /// Merges the key-value pairs of `other` into this table, without invoking metamethods.
+    ///
+    /// Keys missing in this table are copied from `other`. Keys present in both tables are
+    /// resolved according to [`MergePolicy::on_conflict`]. Nested tables are not merged (see
+    /// [`Table::deep_merge`]) but assigned by reference. If [`MergePolicy::concat_arrays`] is
+    /// enabled and both tables (or both values of a key) are arrays, the values of `other` are
+    /// appended instead.
+    ///
+    /// The table is not modified if an error is returned (eg. on a conflict with
+    /// [`MergeConflict::Error`] or when the table is readonly in Luau).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, MergePolicy, Result, Table};
+    /// # fn main() -> Result<()> {
+    /// # let lua = Lua::new();
+    /// let config: Table = lua.load("{ host = 'localhost', port = 80 }").eval()?;
+    /// let overrides: Table = lua.load("{ port = 8080 }").eval()?;
+    /// config.merge(&overrides, MergePolicy::new())?;
+    /// assert_eq!(config.get::<u16>("port")?, 8080);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn merge(&self, other: &Table, policy: MergePolicy) -> Result<()> {
+        TableMerge::run(self, other, policy, false)
+    }
+
+    /// Recursively merges `other` into this table, without invoking metamethods.
+    ///
+    /// This method is similar to [`Table::merge`], but when both tables have a table value for
+    /// the same key, the nested tables are merged instead of being treated as conflicting
+    /// values. Arrays are not merged element-wise: they are either concatenated or resolved as
+    /// conflicting values, depending on [`MergePolicy::concat_arrays`].
+    ///
+    /// Tables assigned from `other` are copied (recursively, keeping their metatables and table
+    /// keys), so the result does not share tables with `other` and can be merged into later
+    /// without modifying it.
+    ///
+    /// Returns an error on conflicts with [`MergeConflict::Error`] (naming the path to the key)
+    /// or if `other` contains itself at a merged position. The tables are not modified in this
+    /// case.
+    pub fn deep_merge(&self, other: &Table, policy: MergePolicy) -> Result<()> {
+        TableMerge::run(self, other, policy, true)
+    }
+
     /// Returns the result of the Lua `#` operator.
     ///
     /// This might invoke the `__len` metamethod. Use the [`Table::raw_len`] method if that is not
@@ -619,6 +731,106 @@ impl Table {
         }
     }
 
+    /// Returns an iterator over the pairs of the table that tolerates table mutation.
+    ///
+    /// Unlike [`Table::pairs`], the keys are collected upfront and values are fetched (without
+    /// invoking metamethods) when the iterator advances. This makes it safe to modify the table
+    /// while iterating: removed keys are skipped, and keys inserted after the iterator was
+    /// created are not visited. Values updated during iteration are returned with their current
+    /// value.
+    ///
+    /// The pairs are wrapped in a [`Result`], since they are lazily converted to `K` and `V` types.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, Result};
+    /// # fn main() -> Result<()> {
+    /// # let lua = Lua::new();
+    /// let table = lua.create_table_from([("a", 1), ("b", 2), ("c", 3)])?;
+    /// for pair in table.pairs_raw::<String, i64>() {
+    ///     let (key, _) = pair?;
+    ///     // Remove all keys including the current one
+    ///     table.clear()?;
+    ///     table.set(key, 0)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn pairs_raw<K: FromLua, V: FromLua>(&self) -> TablePairsRaw<'_, K, V> {
+        let lua = self.0.lua.lock();
+        let state = lua.state();
+        let keys = unsafe {
+            (|| {
+                let _sg = StackGuard::new(state);
+                check_stack(state, 5)?;
+
+                let mut keys = Vec::new();
+                lua.push_ref_at(&self.0, state);
+                ffi::lua_pushnil(state);
+                while ffi::lua_next(state, -2) != 0 {
+                    keys.push(lua.stack_value_at(-2, None, state));
+                    // Keep key for next iteration
+                    ffi::lua_pop(state, 1);
+                }
+                Ok(keys)
+            })()
+        };
+        let (keys, error) = match keys {
+            Ok(keys) => (keys, None),
+            Err(err) => (Vec::new(), Some(err)),
+        };
+        TablePairsRaw {
+            table: self,
+            keys: keys.into_iter(),
+            error,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the keys of the table.
+    ///
+    /// Keys of the array part are visited first (in order), followed by the keys of the hash part
+    /// in an unspecified order. Like [`Table::pairs`], it does not invoke the `__pairs` metamethod.
+    ///
+    /// Keys are lazily converted to `K`. Conversion errors are returned as `Err` items without
+    /// stopping the iteration, so they can be skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, Result};
+    /// # fn main() -> Result<()> {
+    /// # let lua = Lua::new();
+    /// let table = lua.create_table_from([("a", 1), ("b", 2)])?;
+    /// let mut keys = table.keys::<String>().collect::<Result<Vec<_>>>()?;
+    /// keys.sort();
+    /// assert_eq!(keys, ["a", "b"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn keys<K: FromLua>(&self) -> TableKeys<'_, K> {
+        TableKeys {
+            pairs: self.pairs(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the values of the table.
+    ///
+    /// Values of the array part are visited first (in order), followed by the values of the hash
+    /// part in an unspecified order. Like [`Table::pairs`], it does not invoke the `__pairs`
+    /// metamethod.
+    ///
+    /// Values are lazily converted to `V`. Conversion errors are returned as `Err` items without
+    /// stopping the iteration, so they can be skipped.
+    pub fn values<V: FromLua>(&self) -> TableValues<'_, V> {
+        TableValues {
+            pairs: self.pairs(),
+            _phantom: PhantomData,
+        }
+    }
+
     /// Iterates over the pairs of the table, invoking the given closure on each pair.
     ///
     /// This method is similar to [`Table::pairs`], but optimized for performance.
@@ -1092,6 +1304,89 @@ where
     }
 }
 
+/// An iterator over the pairs of a Lua table that tolerates table mutation.
+///
+/// This struct is created by the [`Table::pairs_raw`] method.
+///
+/// [`Table::pairs_raw`]: crate::Table::pairs_raw
+pub struct TablePairsRaw<'a, K, V> {
+    table: &'a Table,
+    keys: std::vec::IntoIter<Value>,
+    error: Option<Error>,
+    _phantom: PhantomData<(K, V)>,
+}
+
+impl<K, V> Iterator for TablePairsRaw<'_, K, V>
+where
+    K: FromLua,
+    V: FromLua,
+{
+    type Item = Result<(K, V)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.error.take() {
+            return Some(Err(err));
+        }
+        for key in self.keys.by_ref() {
+            let value = match self.table.raw_get::<Value>(&key) {
+                // The key has been removed
+                Ok(Value::Nil) => continue,
+                Ok(value) => value,
+                Err(err) => return Some(Err(err)),
+            };
+            let lua = self.table.0.lua.lock();
+            return Some((|| {
+                Ok((K::from_lua(key, lua.lua())?, V::from_lua(value, lua.lua())?))
+            })());
+        }
+        None
+    }
+}
+
+/// An iterator over the keys of a Lua table.
+///
+/// This struct is created by the [`Table::keys`] method.
+///
+/// [`Table::keys`]: crate::Table::keys
+pub struct TableKeys<'a, K> {
+    pairs: TablePairs<'a, Value, Value>,
+    _phantom: PhantomData<K>,
+}
+
+impl<K: FromLua> Iterator for TableKeys<'_, K> {
+    type Item = Result<K>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, _) = match self.pairs.next()? {
+            Ok(pair) => pair,
+            Err(err) => return Some(Err(err)),
+        };
+        Some(K::from_lua(key, self.pairs.guard.lua()))
+    }
+}
+
+/// An iterator over the values of a Lua table.
+///
+/// This struct is created by the [`Table::values`] method.
+///
+/// [`Table::values`]: crate::Table::values
+pub struct TableValues<'a, V> {
+    pairs: TablePairs<'a, Value, Value>,
+    _phantom: PhantomData<V>,
+}
+
+impl<V: FromLua> Iterator for TableValues<'_, V> {
+    type Item = Result<V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_, value) = match self.pairs.next()? {
+            Ok(pair) => pair,
+            Err(err) => return Some(Err(err)),
+        };
+        Some(V::from_lua(value, self.pairs.guard.lua()))
+    }
+}
+
 /// An iterator over the sequence part of a Lua table.
 ///
 /// This struct is created by the [`Table::sequence_values`] method.
@@ -1131,6 +1426,207 @@ where
     }
 }
 
+/// Resolution of keys present in both tables in [`Table::merge`] and [`Table::deep_merge`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeConflict {
+    /// Replace the existing value with the value from the other table.
+    Overwrite,
+    /// Keep the existing value.
+    KeepExisting,
+    /// Return an error.
+    Error,
+}
+
+/// Options for [`Table::merge`] and [`Table::deep_merge`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MergePolicy {
+    /// How to resolve keys present in both tables.
+    ///
+    /// Default: [`MergeConflict::Overwrite`]
+    pub on_conflict: MergeConflict,
+
+    /// Concatenate arrays instead of treating them as conflicting values.
+    ///
+    /// A table is considered an array if it is non-empty and has only the keys `1..=n`.
+    ///
+    /// Default: **false**
+    pub concat_arrays: bool,
+}
+
+impl Default for MergePolicy {
+    fn default() -> Self {
+        const { MergePolicy::new() }
+    }
+}
+
+impl MergePolicy {
+    /// Returns a new instance of `MergePolicy` with default parameters.
+    pub const fn new() -> Self {
+        MergePolicy {
+            on_conflict: MergeConflict::Overwrite,
+            concat_arrays: false,
+        }
+    }
+
+    /// Sets [`on_conflict`] option.
+    ///
+    /// [`on_conflict`]: #structfield.on_conflict
+    #[must_use]
+    pub const fn on_conflict(mut self, on_conflict: MergeConflict) -> Self {
+        self.on_conflict = on_conflict;
+        self
+    }
+
+    /// Sets [`concat_arrays`] option.
+    ///
+    /// [`concat_arrays`]: #structfield.concat_arrays
+    #[must_use]
+    pub const fn concat_arrays(mut self, enabled: bool) -> Self {
+        self.concat_arrays = enabled;
+        self
+    }
+}
+
+struct TableMerge {
+    policy: MergePolicy,
+    deep: bool,
+    // Validate the merge without modifying tables
+    dry_run: bool,
+    // Keys leading to the table being merged
+    path: Vec<Value>,
+    // Source tables being merged, to detect cycles
+    sources: Vec<*const c_void>,
+    // Copies of source tables assigned by a deep merge
+    copies: HashMap<*const c_void, Table>,
+}
+
+impl TableMerge {
+    fn run(target: &Table, source: &Table, policy: MergePolicy, deep: bool) -> Result<()> {
+        let mut merge = TableMerge {
+            policy,
+            deep,
+            dry_run: true,
+            path: Vec::new(),
+            sources: Vec::new(),
+            copies: HashMap::new(),
+        };
+        // Check for errors first to not leave the table partially merged
+        merge.merge(target, source)?;
+        merge.dry_run = false;
+        merge.merge(target, source)
+    }
+
+    fn merge(&mut self, target: &Table, source: &Table) -> Result<()> {
+        if self.policy.concat_arrays && is_sequence(target)? && is_sequence(source)? {
+            let values = source.sequence_values().collect::<Result<Vec<Value>>>()?;
+            if self.dry_run {
+                return check_writable(target);
+            }
+            return (values.into_iter()).try_for_each(|value| target.raw_push(self.assigned(value)?));
+        }
+
+        let ptr = source.to_pointer();
+        if self.sources.contains(&ptr) {
+            return Err(self.error("cannot merge a table containing itself"));
+        }
+        self.sources.push(ptr);
+
+        let pairs = source.pairs::<Value, Value>().collect::<Result<Vec<_>>>()?;
+        for (key, value) in pairs {
+            let value = match (target.raw_get::<Value>(&key)?, value) {
+                (Value::Nil, value) => Some(value),
+                (Value::Table(t), Value::Table(s)) if self.merges_nested(&t, &s)? => {
+                    self.path.push(key.clone());
+                    self.merge(&t, &s)?;
+                    self.path.pop();
+                    None
+                }
+                (_, value) => match self.policy.on_conflict {
+                    MergeConflict::Overwrite => Some(value),
+                    MergeConflict::KeepExisting => None,
+                    MergeConflict::Error => {
+                        self.path.push(key);
+                        return Err(self.error("conflicting values"));
+                    }
+                },
+            };
+            match value {
+                Some(_) if self.dry_run => check_writable(target)?,
+                Some(value) => target.raw_set(key, self.assigned(value)?)?,
+                None => {}
+            }
+        }
+
+        self.sources.pop();
+        Ok(())
+    }
+
+    // Returns the value to assign to the target: a copy of nested tables in deep mode, so later
+    // merges into the result do not modify the source.
+    fn assigned(&mut self, value: Value) -> Result<Value> {
+        match value {
+            Value::Table(table) if self.deep => self.deep_copy(&table).map(Value::Table),
+            value => Ok(value),
+        }
+    }
+
+    fn deep_copy(&mut self, table: &Table) -> Result<Table> {
+        let ptr = table.to_pointer();
+        if let Some(copy) = self.copies.get(&ptr) {
+            return Ok(copy.clone());
+        }
+        let copy = unsafe { table.0.lua.lock().create_table_with_capacity(0, 0)? };
+        self.copies.insert(ptr, copy.clone());
+        let pairs = table.pairs::<Value, Value>().collect::<Result<Vec<_>>>()?;
+        for (key, value) in pairs {
+            copy.raw_set(key, self.assigned(value)?)?;
+        }
+        copy.set_metatable(table.metatable());
+        Ok(copy)
+    }
+
+    // Returns `true` if both tables under the same key should be merged rather than conflict.
+    fn merges_nested(&self, target: &Table, source: &Table) -> Result<bool> {
+        let arrays = is_sequence(target)? && is_sequence(source)?;
+        Ok(if arrays {
+            self.policy.concat_arrays
+        } else {
+            self.deep
+        })
+    }
+
+    fn error(&self, msg: &str) -> Error {
+        if self.path.is_empty() {
+            return Error::runtime(msg);
+        }
+        let path = (self.path.iter())
+            .map(|key| key.to_string().unwrap_or_else(|_| key.type_name().to_string()))
+            .collect::<Vec<_>>()
+            .join(".");
+        Error::runtime(format!("{msg} at key '{path}'"))
+    }
+}
+
+// Returns `true` if the table is non-empty and has only the keys `1..=n`.
+fn is_sequence(table: &Table) -> Result<bool> {
+    let len = table.raw_len();
+    let mut count = 0;
+    table.for_each(|_: Value, _: Value| {
+        count += 1;
+        Ok(())
+    })?;
+    Ok(len > 0 && count == len)
+}
+
+fn check_writable(_table: &Table) -> Result<()> {
+    #[cfg(feature = "luau")]
+    if _table.is_readonly() {
+        return Err(Error::runtime("attempt to modify a readonly table"));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod assertions {
     use super::*;
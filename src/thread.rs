@@ -1,12 +1,17 @@
+use std::ffi::CStr;
 use std::fmt;
-use std::os::raw::{c_int, c_void};
+use std::os::raw::{c_char, c_int, c_void};
 
 use crate::error::{Error, Result};
 use crate::function::Function;
-use crate::state::RawLua;
+use crate::multi::MultiValue;
+use crate::state::{ExtraData, RawLua};
 use crate::traits::{FromLuaMulti, IntoLuaMulti};
 use crate::types::{LuaType, ValueRef};
-use crate::util::{check_stack, error_traceback_thread, pop_error, StackGuard};
+use crate::util::{
+    check_stack, error_traceback_thread, pop_error, push_thread_name, to_string, StackGuard, THREAD_NAMES_KEY,
+};
+use crate::value::Value;
 
 #[cfg(not(feature = "luau"))]
 use crate::{
@@ -14,6 +19,14 @@ use crate::{
     types::HookKind,
 };
 
+#[cfg(any(feature = "luau", doc))]
+use crate::{table::Table, util::assert_stack};
+
+#[cfg(feature = "luau")]
+use crate::state::util::StateGuard;
+
+pub use pool::{PooledThread, ThreadPool};
+
 /// Continuation thread status. Can either be Ok, Yielded (rare, but can happen) or Error
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum ContinuationStatus {
@@ -48,6 +61,100 @@ pub enum ThreadStatus {
     Error,
 }
 
+/// Outcome of resuming a thread with [`Lua::resume_many`].
+///
+/// [`Lua::resume_many`]: crate::Lua::resume_many
+#[derive(Debug, Clone)]
+pub enum ResumeOutcome {
+    /// The thread has yielded these values and can be resumed again.
+    Yielded(MultiValue),
+    /// The thread has returned these values from its main function.
+    Finished(MultiValue),
+}
+
+impl ResumeOutcome {
+    /// Returns the values yielded or returned by the thread.
+    pub fn into_values(self) -> MultiValue {
+        match self {
+            ResumeOutcome::Yielded(values) | ResumeOutcome::Finished(values) => values,
+        }
+    }
+}
+
+/// An opaque identifier of a Lua thread.
+///
+/// The identifier stays the same for the whole thread lifetime and can be used to match thread
+/// creation and destruction events. It can be reused after the thread is collected.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ThreadId(usize);
+
+/// Options for [`Thread::reset_with`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ThreadResetOptions {
+    /// Keep the globals table of the thread.
+    ///
+    /// If enabled, a sandboxed thread (or a thread with custom globals) stays sandboxed after
+    /// reset, and values written to its globals are kept. If disabled, the thread shares the
+    /// globals table with the main thread again.
+    ///
+    /// Has no effect on Lua versions other than Luau, as their threads always share globals.
+    ///
+    /// Default: **true**
+    pub preserve_globals: bool,
+}
+
+impl Default for ThreadResetOptions {
+    fn default() -> Self {
+        const { ThreadResetOptions::new() }
+    }
+}
+
+impl ThreadResetOptions {
+    /// Returns a new instance of `ThreadResetOptions` with default parameters.
+    pub const fn new() -> Self {
+        ThreadResetOptions {
+            preserve_globals: true,
+        }
+    }
+
+    /// Sets [`preserve_globals`] option.
+    ///
+    /// [`preserve_globals`]: #structfield.preserve_globals
+    #[must_use]
+    pub const fn preserve_globals(mut self, enabled: bool) -> Self {
+        self.preserve_globals = enabled;
+        self
+    }
+}
+
+/// An identifier of a thread pinned with [`Thread::pin`].
+///
+/// Ids are never reused within the same Lua instance.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct PinnedThreadId(u64);
+
+/// A thread lifecycle event passed to the callback set by [`Lua::set_thread_event_callback`].
+///
+/// [`Lua::set_thread_event_callback`]: crate::Lua::set_thread_event_callback
+#[cfg(any(feature = "luau", doc))]
+#[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+#[derive(Debug)]
+pub enum ThreadEvent {
+    /// A new thread has been created.
+    Created(Thread),
+    /// A thread is about to be collected.
+    Destroyed(ThreadId),
+}
+
+#[cfg(feature = "luau")]
+impl ThreadId {
+    #[inline]
+    pub(crate) fn from_state(state: *mut ffi::lua_State) -> Self {
+        ThreadId(state as usize)
+    }
+}
+
 /// Internal representation of a Lua thread status.
 ///
 /// The number in `New` and `Yielded` variants is the number of arguments pushed
@@ -129,6 +236,9 @@ impl Thread {
             ThreadStatusInner::New(nargs) | ThreadStatusInner::Yielded(nargs) => nargs,
             _ => return Err(Error::CoroutineUnresumable),
         };
+        if unsafe { (*lua.extra()).resume_limits } {
+            limits::check_resume(lua.lua(), self)?;
+        }
 
         let state = lua.state();
         let thread_state = self.state();
@@ -139,15 +249,162 @@ impl Thread {
             let nargs = args.push_into_specified_stack_multi(&lua, thread_state)?;
             pushed_nargs += nargs;
 
-            let (_, nresults) = self.resume_inner(&lua, pushed_nargs)?;
+            // Errors from the thread itself must reach `store_error` below
+            self.resume_inner(&lua, pushed_nargs)
+                .and_then(|(_, nresults)| R::from_specified_stack_multi(nresults, &lua, thread_state))
+        }
+        .inspect_err(|err| self.store_error(&lua, err))
+    }
+
+    /// Resumes execution of this thread, passing and returning values as they are.
+    ///
+    /// This is a specialized version of [`Thread::resume`] for passing values between threads
+    /// (for example, in a scheduler). Values are moved by reference without any conversion, and
+    /// the `args` buffer is reused to return the results.
+    ///
+    /// Errors are reported the same way as in [`Thread::resume`].
+    pub fn resume_raw(&self, args: MultiValue) -> Result<MultiValue> {
+        let lua = self.0.lua.lock();
+        self.resume_outcome(&lua, args).map(ResumeOutcome::into_values)
+    }
+
+    /// Resumes this thread under an already held lock, reporting whether it yielded or finished.
+    pub(crate) fn resume_outcome(&self, lua: &RawLua, mut args: MultiValue) -> Result<ResumeOutcome> {
+        let mut pushed_nargs = match self.status_inner(lua) {
+            ThreadStatusInner::New(nargs) | ThreadStatusInner::Yielded(nargs) => nargs,
+            _ => return Err(Error::CoroutineUnresumable),
+        };
+        if unsafe { (*lua.extra()).resume_limits } {
+            limits::check_resume(lua.lua(), self)?;
+        }
+
+        let state = lua.state();
+        let thread_state = self.state();
+        unsafe {
+            let _sg = StackGuard::new(state);
+            let _thread_sg = StackGuard::with_top(thread_state, 0);
 
-            R::from_specified_stack_multi(nresults, &lua, thread_state)
+            let nargs: c_int = args.len().try_into().map_err(|_| Error::StackError)?;
+            check_stack(thread_state, nargs + 1)?;
+            for value in args.drain(..) {
+                lua.push_value_at(&value, thread_state)?;
+            }
+            pushed_nargs += nargs;
+
+            // Errors from the thread itself must reach `store_error` below
+            self.resume_inner(lua, pushed_nargs).map(|(status, nresults)| {
+                args.reserve(nresults as usize);
+                for idx in 0..nresults {
+                    args.push_back(lua.stack_value_at(-nresults + idx, None, thread_state));
+                }
+                match status {
+                    ThreadStatusInner::Finished => ResumeOutcome::Finished(args),
+                    _ => ResumeOutcome::Yielded(args),
+                }
+            })
         }
+        .inspect_err(|err| self.store_error(lua, err))
+    }
+
+    /// Resumes this thread repeatedly until it finishes, collecting all yielded values.
+    ///
+    /// The thread is first resumed with `args` and then without arguments. Returns the values
+    /// passed to each `coroutine.yield` and the final return value. Stops at the first error.
+    ///
+    /// If `max_yields` is set and the thread yields more times than that, returns
+    /// [`Error::TooManyYields`], leaving the thread suspended. Values of the yield over the limit
+    /// are discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, Result, Thread};
+    /// # fn main() -> Result<()> {
+    /// # let lua = Lua::new();
+    /// let thread: Thread = lua.load(r#"
+    ///     coroutine.create(function(n)
+    ///         for i = 1, n do
+    ///             coroutine.yield(i)
+    ///         end
+    ///         return 0
+    ///     end)
+    /// "#).eval()?;
+    ///
+    /// let (yields, last) = thread.resume_until_done::<i32>(3, None)?;
+    /// assert_eq!(yields, vec![1, 2, 3]);
+    /// assert_eq!(last, 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resume_until_done<T>(
+        &self,
+        args: impl IntoLuaMulti,
+        max_yields: Option<usize>,
+    ) -> Result<(Vec<T>, T)>
+    where
+        T: FromLuaMulti,
+    {
+        self.resume_until_done_with(args, max_yields, |_| Ok(()))
+    }
+
+    /// Resumes this thread repeatedly until it finishes, calling `budget` between resumes.
+    ///
+    /// Works like [`Thread::resume_until_done`], but calls `budget` with the number of values
+    /// yielded so far before every subsequent resume. Returning an error from `budget` stops
+    /// the loop and returns that error, leaving the thread suspended. This can be used to limit
+    /// the time spent on draining a generator.
+    pub fn resume_until_done_with<T, F>(
+        &self,
+        args: impl IntoLuaMulti,
+        max_yields: Option<usize>,
+        mut budget: F,
+    ) -> Result<(Vec<T>, T)>
+    where
+        T: FromLuaMulti,
+        F: FnMut(usize) -> Result<()>,
+    {
+        let mut yields = Vec::new();
+        let mut value = self.resume::<T>(args)?;
+        while self.status() == ThreadStatus::Resumable {
+            if max_yields.is_some_and(|max| yields.len() >= max) {
+                return Err(Error::TooManyYields(yields.len()));
+            }
+            yields.push(value);
+            budget(yields.len())?;
+            value = self.resume::<T>(())?;
+        }
+        Ok((yields, value))
     }
 
     /// Resumes execution of this thread, immediately raising an error.
     ///
+    /// The `error` is raised at the point where the thread is suspended (eg. from the
+    /// `coroutine.yield` call), so the thread's own error handling (`pcall`, cleanup code) applies
+    /// normally. This is useful to cancel a script waiting on `coroutine.yield()`.
+    ///
+    /// The final outcome is returned the same way as [`Thread::resume`] does: the values yielded
+    /// or returned by the thread if the error was handled, or the error itself otherwise.
+    ///
     /// This is a Luau specific extension.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, Result, Thread};
+    /// # fn main() -> Result<()> {
+    /// # let lua = Lua::new();
+    /// let thread: Thread = lua.load(r#"
+    ///     coroutine.create(function()
+    ///         local ok, err = pcall(coroutine.yield)
+    ///         return if ok then "resumed" else "cancelled: " .. err
+    ///     end)
+    /// "#).eval()?;
+    ///
+    /// thread.resume::<()>(())?;
+    /// assert_eq!(thread.resume_error::<String>("timeout")?, "cancelled: timeout");
+    /// # Ok(())
+    /// # }
+    /// ```
     #[cfg(feature = "luau")]
     #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
     pub fn resume_error<R>(&self, error: impl crate::IntoLua) -> Result<R>
@@ -159,6 +416,9 @@ impl Thread {
             ThreadStatusInner::New(_) | ThreadStatusInner::Yielded(_) => {}
             _ => return Err(Error::CoroutineUnresumable),
         };
+        if unsafe { (*lua.extra()).resume_limits } {
+            limits::check_resume(lua.lua(), self)?;
+        }
 
         let state = lua.state();
         let thread_state = self.state();
@@ -169,10 +429,11 @@ impl Thread {
             check_stack(thread_state, 1)?;
             error.push_into_specified_stack(&lua, thread_state)?;
 
-            let (_, nresults) = self.resume_inner(&lua, ffi::LUA_RESUMEERROR)?;
-
-            R::from_specified_stack_multi(nresults, &lua, thread_state)
+            // Errors from the thread itself must reach `store_error` below
+            self.resume_inner(&lua, ffi::LUA_RESUMEERROR)
+                .and_then(|(_, nresults)| R::from_specified_stack_multi(nresults, &lua, thread_state))
         }
+        .inspect_err(|err| self.store_error(&lua, err))
     }
 
     /// Resumes execution of this thread.
@@ -185,7 +446,12 @@ impl Thread {
         #[cfg(not(feature = "luau"))]
         let ret = ffi::lua_resume(thread_state, state, nargs, &mut nresults as *mut c_int);
         #[cfg(feature = "luau")]
-        let ret = ffi::lua_resumex(thread_state, state, nargs, &mut nresults as *mut c_int);
+        let ret = {
+            // Allocations are checked against the memory category of the resumed thread
+            let _state_guard = StateGuard::new(lua, thread_state);
+            ffi::lua_resumex(thread_state, state, nargs, &mut nresults as *mut c_int)
+        };
+        ExtraData::run_pending_finalizers(lua.extra());
 
         match ret {
             ffi::LUA_OK => Ok((ThreadStatusInner::Finished, nresults)),
@@ -202,6 +468,105 @@ impl Thread {
         }
     }
 
+    /// Returns the error that terminated this thread, if any.
+    ///
+    /// The error is kept after [`Thread::resume`] fails, so code supervising several threads
+    /// can find out why a thread died without owning the original result. It is cleared when
+    /// the thread is reset.
+    ///
+    /// Returns `None` if the thread status is not [`ThreadStatus::Error`] or the thread was
+    /// resumed from Lua (eg. using `coroutine.resume`).
+    pub fn last_error(&self) -> Option<Error> {
+        let lua = self.0.lua.lock();
+        if !matches!(self.status_inner(&lua), ThreadStatusInner::Error) {
+            return None;
+        }
+        let state = lua.state();
+        let thread_state = self.state();
+        unsafe {
+            if ffi::lua_gettop(thread_state) == 0 {
+                return None;
+            }
+            let _sg = StackGuard::new(state);
+            check_stack(state, 3).ok()?;
+            ffi::lua_xpush(thread_state, state, -1);
+            match lua.pop_value_at(state) {
+                Value::Error(err) => Some(*err),
+                _ => None,
+            }
+        }
+    }
+
+    /// Keeps `err` on the stack of the failed thread to be returned by [`Thread::last_error`].
+    fn store_error(&self, lua: &RawLua, err: &Error) {
+        if !matches!(self.status_inner(lua), ThreadStatusInner::Error) {
+            return;
+        }
+        let state = lua.state();
+        let thread_state = self.state();
+        unsafe {
+            let _sg = StackGuard::new(state);
+            if check_stack(state, 3).is_err() || check_stack(thread_state, 1).is_err() {
+                return;
+            }
+            // Values cannot be created directly on the errored thread, so move it from the main one
+            if lua
+                .push_value_at(&Value::Error(Box::new(err.clone())), state)
+                .is_ok()
+            {
+                ffi::lua_xmove(state, thread_state, 1);
+            }
+        }
+    }
+
+    /// Sets a name for this thread to identify it in diagnostics.
+    ///
+    /// The name is included in tracebacks of errors raised in this thread and in the [`Debug`]
+    /// output. It is kept when the thread is [reset] and released when the thread is garbage
+    /// collected.
+    ///
+    /// [reset]: Thread::reset
+    pub fn set_name(&self, name: &str) -> Result<()> {
+        let lua = self.0.lua.lock();
+        let state = lua.state();
+        unsafe {
+            let _sg = StackGuard::new(state);
+            check_stack(state, 5)?;
+
+            lua.push_ref_at(&self.0, state);
+            protect_lua!(state, 1, 0, |state| {
+                let key = &THREAD_NAMES_KEY as *const u8 as *const c_void;
+                if ffi::lua_rawgetp(state, ffi::LUA_REGISTRYINDEX, key) != ffi::LUA_TTABLE {
+                    ffi::lua_pop(state, 1);
+                    ffi::lua_createtable(state, 0, 1);
+                    ffi::lua_pushliteral(state, c"k");
+                    ffi::lua_setfield(state, -2, cstr!("__mode")); // names.__mode = "k"
+                    ffi::lua_pushvalue(state, -1);
+                    ffi::lua_setmetatable(state, -2); // metatable(names) = names
+                    ffi::lua_pushvalue(state, -1);
+                    ffi::lua_rawsetp(state, ffi::LUA_REGISTRYINDEX, key);
+                }
+                ffi::lua_pushvalue(state, -2);
+                ffi::lua_pushlstring(state, name.as_ptr() as *const c_char, name.len());
+                ffi::lua_rawset(state, -3); // names[thread] = name
+            })
+        }
+    }
+
+    /// Returns the name of this thread set by [`Thread::set_name`].
+    pub fn name(&self) -> Option<String> {
+        self.name_inner(&self.0.lua.lock())
+    }
+
+    fn name_inner(&self, lua: &RawLua) -> Option<String> {
+        let state = lua.state();
+        unsafe {
+            let _sg = StackGuard::new(state);
+            check_stack(state, 3).ok()?;
+            push_thread_name(state, self.state()).then(|| to_string(state, -1))
+        }
+    }
+
     /// Gets the status of the thread.
     pub fn status(&self) -> ThreadStatus {
         match self.status_inner(&self.0.lua.lock()) {
@@ -212,6 +577,135 @@ impl Thread {
         }
     }
 
+    /// Pins the thread, keeping it alive without holding the `Thread` handle.
+    ///
+    /// The thread is stored in the registry until [`Lua::unpin`] is called, and can be resumed
+    /// using the returned id with [`Lua::resume_pinned`]. Pinning the same thread again returns
+    /// a new id.
+    ///
+    /// [`Lua::unpin`]: crate::Lua::unpin
+    /// [`Lua::resume_pinned`]: crate::Lua::resume_pinned
+    pub fn pin(&self) -> Result<PinnedThreadId> {
+        let lua = self.0.lua.upgrade();
+        pin::pin(&lua, self)
+    }
+
+    /// Limits the number of times this thread can be resumed.
+    ///
+    /// Once the thread has been resumed `max` times from Rust (using [`Thread::resume`] and
+    /// related methods), further resumes fail with [`Error::ResumeLimitExceeded`]. This prevents
+    /// scripts driven by a host scheduler from keeping a coroutine alive forever by re-yielding.
+    ///
+    /// Resumes from Lua (eg. using `coroutine.resume`) are neither counted nor limited. To bound
+    /// the work done by Lua code itself, use an interrupt or a hook.
+    ///
+    /// Calling this method again sets a new limit and resets the counter. The limit is kept when
+    /// the thread is [reset].
+    ///
+    /// [reset]: Thread::reset
+    pub fn set_max_resumes(&self, max: u32) -> Result<()> {
+        let lua = self.0.lua.upgrade();
+        limits::set_max_resumes(&lua, self, max)
+    }
+
+    /// Returns the number of times this thread has been resumed since [`Thread::set_max_resumes`]
+    /// was called.
+    ///
+    /// Returns `0` for threads without a resume limit.
+    pub fn resume_count(&self) -> u32 {
+        let lua = self.0.lua.upgrade();
+        limits::resume_count(&lua, self).unwrap_or(0)
+    }
+
+    /// Returns the values of live local variables in the innermost Lua function of a suspended
+    /// thread, without resuming it.
+    ///
+    /// This is intended for debugging: for a thread waiting on `coroutine.yield`, it returns the
+    /// locals (including parameters) of the function that called `coroutine.yield`, in order of
+    /// declaration. Values passed to `coroutine.yield` are returned by [`Thread::resume`] and are
+    /// not kept on the thread stack.
+    ///
+    /// New and finished threads have no active frames, so the list is empty.
+    ///
+    /// In Luau, local variables are available only for code compiled with debug level 2
+    /// (see [`Compiler::set_debug_level`]).
+    ///
+    /// Returns an error if the thread is currently running.
+    ///
+    /// [`Compiler::set_debug_level`]: crate::Compiler::set_debug_level
+    pub fn stack_values(&self) -> Result<Vec<Value>> {
+        let lua = self.0.lua.lock();
+        if let ThreadStatusInner::Running = self.status_inner(&lua) {
+            return Err(Error::runtime("cannot inspect stack of a running thread"));
+        }
+        let state = lua.state();
+        let thread_state = self.state();
+        unsafe {
+            let _sg = StackGuard::new(state);
+            let _thread_sg = StackGuard::new(thread_state);
+            check_stack(state, 3)?;
+            check_stack(thread_state, 1)?;
+
+            let mut ar: ffi::lua_Debug = std::mem::zeroed();
+            let mut level = 0;
+            loop {
+                #[cfg(not(feature = "luau"))]
+                let found = ffi::lua_getstack(thread_state, level, &mut ar) != 0
+                    && ffi::lua_getinfo(thread_state, cstr!("S"), &mut ar) != 0;
+                #[cfg(feature = "luau")]
+                let found = ffi::lua_getinfo(thread_state, level, cstr!("s"), &mut ar) != 0;
+                if !found {
+                    return Ok(Vec::new());
+                }
+                if CStr::from_ptr(ar.what).to_bytes() != b"C" {
+                    break;
+                }
+                level += 1;
+            }
+
+            let mut values = Vec::new();
+            for n in 1.. {
+                #[cfg(not(feature = "luau"))]
+                let name = ffi::lua_getlocal(thread_state, &ar, n);
+                #[cfg(feature = "luau")]
+                let name = ffi::lua_getlocal(thread_state, level, n);
+                if name.is_null() {
+                    break;
+                }
+                // Skip temporaries and internal variables (eg. "(for state)")
+                if *name == b'(' as c_char {
+                    ffi::lua_pop(thread_state, 1);
+                    continue;
+                }
+                ffi::lua_xmove(thread_state, state, 1);
+                values.push(lua.pop_value_at(state));
+            }
+            Ok(values)
+        }
+    }
+
+    /// Returns the call depth of the thread (the number of active function frames).
+    ///
+    /// New and finished threads have zero depth.
+    pub fn depth(&self) -> usize {
+        let _lua = self.0.lua.lock();
+        let thread_state = self.state();
+        unsafe {
+            #[cfg(feature = "luau")]
+            return ffi::lua_stackdepth(thread_state) as usize;
+
+            #[cfg(not(feature = "luau"))]
+            {
+                let mut ar: ffi::lua_Debug = std::mem::zeroed();
+                let mut depth = 0;
+                while ffi::lua_getstack(thread_state, depth, &mut ar) != 0 {
+                    depth += 1;
+                }
+                depth as usize
+            }
+        }
+    }
+
     /// Gets the status of the thread (internal implementation).
     fn status_inner(&self, lua: &RawLua) -> ThreadStatusInner {
         let thread_state = self.state();
@@ -270,6 +764,19 @@ impl Thread {
         }
     }
 
+    /// Enables or disables single-step mode for this thread.
+    ///
+    /// In single-step mode the callback set with [`Lua::set_debug_step_callback`] is called before
+    /// each instruction. Threads created by this thread inherit the mode.
+    ///
+    /// [`Lua::set_debug_step_callback`]: crate::Lua::set_debug_step_callback
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn set_single_step(&self, enabled: bool) {
+        let _lua = self.0.lua.lock();
+        unsafe { ffi::lua_singlestep(self.state(), enabled as c_int) };
+    }
+
     /// Resets a thread
     ///
     /// In [Lua 5.4]: cleans its call stack and closes all pending to-be-closed variables.
@@ -283,8 +790,20 @@ impl Thread {
     ///
     /// Sets a Lua function for the thread afterwards.
     ///
+    /// The globals table of the thread is kept, so a [sandboxed] thread stays sandboxed. Use
+    /// [`Thread::reset_with`] to control this behavior.
+    ///
     /// [Lua 5.4]: https://www.lua.org/manual/5.4/manual.html#lua_closethread
+    /// [sandboxed]: Thread::sandbox
     pub fn reset(&self, func: Function) -> Result<()> {
+        self.reset_with(func, ThreadResetOptions::new())
+    }
+
+    /// Resets a thread using the provided options.
+    ///
+    /// This is the same as [`Thread::reset`], but allows e.g. to drop the sandbox (custom
+    /// globals) of the thread by disabling [`ThreadResetOptions::preserve_globals`].
+    pub fn reset_with(&self, func: Function, options: ThreadResetOptions) -> Result<()> {
         let lua = self.0.lua.lock();
         let thread_state = self.state();
         unsafe {
@@ -295,16 +814,73 @@ impl Thread {
             ffi::lua_xpush(lua.ref_thread(func.0.aux_thread), thread_state, func.0.index);
 
             #[cfg(feature = "luau")]
-            {
+            if !options.preserve_globals {
                 // Inherit `LUA_GLOBALSINDEX` from the main thread
                 ffi::lua_xpush(lua.main_state(), thread_state, ffi::LUA_GLOBALSINDEX);
                 ffi::lua_replace(thread_state, ffi::LUA_GLOBALSINDEX);
             }
+            #[cfg(not(feature = "luau"))]
+            let _ = options;
 
             Ok(())
         }
     }
 
+    /// Closes a thread, releasing its stack immediately.
+    ///
+    /// The thread transitions to the [`ThreadStatus::Finished`] state, and any subsequent attempt
+    /// to resume it will return [`Error::CoroutineUnresumable`]. All values held by the thread
+    /// stack become eligible for garbage collection without waiting for the thread itself to be
+    /// collected.
+    ///
+    /// In [Lua 5.4] pending to-be-closed variables are closed, and errors raised by their closing
+    /// methods are returned. This includes threads stopped by an error: the original error (already
+    /// returned by [`Thread::resume`]) is not returned again, but errors raised while closing are.
+    ///
+    /// Closing a finished thread is a no-op. Closing a running thread returns an error.
+    ///
+    /// Lua versions other than Lua 5.4 and Luau can close only new or finished threads.
+    ///
+    /// [Lua 5.4]: https://www.lua.org/manual/5.4/manual.html#lua_closethread
+    pub fn close(&self) -> Result<()> {
+        let lua = self.0.lua.lock();
+        unsafe {
+            match self.status_inner(&lua) {
+                ThreadStatusInner::Running => Err(Error::runtime("cannot close a running thread")),
+                ThreadStatusInner::Finished => Ok(()),
+                #[cfg(not(any(feature = "lua54", feature = "luau")))]
+                ThreadStatusInner::Yielded(_) | ThreadStatusInner::Error => {
+                    Err(Error::runtime("cannot close non-finished thread"))
+                }
+                #[cfg(feature = "lua54")]
+                ThreadStatusInner::Error => {
+                    let state = lua.state();
+                    let thread_state = self.state();
+                    let _sg = StackGuard::new(state);
+                    check_stack(state, 3)?;
+
+                    // The error that stopped the thread has already been returned by `resume`,
+                    // so only errors raised by closing methods are reported
+                    let has_error = ffi::lua_gettop(thread_state) > 0;
+                    if has_error {
+                        ffi::lua_xpush(thread_state, state, -1);
+                    }
+                    let status = self.close_thread();
+                    if status == ffi::LUA_OK {
+                        return Ok(());
+                    }
+                    ffi::lua_xpush(thread_state, state, -1);
+                    ffi::lua_settop(thread_state, 0);
+                    if has_error && ffi::lua_rawequal(state, -1, -2) != 0 {
+                        return Ok(());
+                    }
+                    Err(pop_error(state, status))
+                }
+                status => self.reset_inner(status),
+            }
+        }
+    }
+
     unsafe fn reset_inner(&self, status: ThreadStatusInner) -> Result<()> {
         match status {
             ThreadStatusInner::New(_) => {
@@ -322,16 +898,12 @@ impl Thread {
             ThreadStatusInner::Yielded(_) | ThreadStatusInner::Error => {
                 let thread_state = self.state();
 
-                #[cfg(all(feature = "lua54", not(feature = "vendored")))]
-                let status = ffi::lua_resetthread(thread_state);
-                #[cfg(all(feature = "lua54", feature = "vendored"))]
-                let status = {
-                    let lua = self.0.lua.lock();
-                    ffi::lua_closethread(thread_state, lua.state())
-                };
                 #[cfg(feature = "lua54")]
-                if status != ffi::LUA_OK {
-                    return Err(pop_error(thread_state, status));
+                {
+                    let status = self.close_thread();
+                    if status != ffi::LUA_OK {
+                        return Err(pop_error(thread_state, status));
+                    }
                 }
                 #[cfg(feature = "luau")]
                 ffi::lua_resetthread(thread_state);
@@ -341,6 +913,21 @@ impl Thread {
         }
     }
 
+    /// Closes the thread call stack and pending to-be-closed variables.
+    ///
+    /// Returns the status of the thread, leaving the error object (if any) on its stack.
+    #[cfg(feature = "lua54")]
+    unsafe fn close_thread(&self) -> c_int {
+        #[cfg(not(feature = "vendored"))]
+        {
+            ffi::lua_resetthread(self.state())
+        }
+        #[cfg(feature = "vendored")]
+        {
+            ffi::lua_closethread(self.state(), self.0.lua.lock().state())
+        }
+    }
+
     /// Enables sandbox mode on this thread.
     ///
     /// Under the hood replaces the global environment table with a new table,
@@ -387,6 +974,68 @@ impl Thread {
         }
     }
 
+    /// Returns the globals table of this thread.
+    ///
+    /// By default threads share the globals table with the main thread, but sandboxed threads
+    /// (see [`Thread::sandbox`]) have their own one.
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn globals(&self) -> Table {
+        let lua = self.0.lua.lock();
+        let state = lua.state();
+        unsafe {
+            let _sg = StackGuard::new(state);
+            assert_stack(state, 1);
+            ffi::lua_xpush(self.state(), state, ffi::LUA_GLOBALSINDEX);
+            Table(lua.pop_ref())
+        }
+    }
+
+    /// Sets the globals table of this thread.
+    ///
+    /// Code running in the thread (including Rust functions calling [`Lua::globals`]) sees the
+    /// new table. Functions loaded before keep their own environment.
+    ///
+    /// Returns an error if the thread is running.
+    ///
+    /// [`Lua::globals`]: crate::Lua::globals
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn set_globals(&self, globals: Table) -> Result<()> {
+        let lua = self.0.lua.lock();
+        let thread_state = self.state();
+        unsafe {
+            if let ThreadStatusInner::Running = self.status_inner(&lua) {
+                return Err(Error::runtime("cannot change globals of a running thread"));
+            }
+            check_stack(thread_state, 1)?;
+            lua.push_ref_at(&globals.0, thread_state);
+            ffi::lua_replace(thread_state, ffi::LUA_GLOBALSINDEX);
+        }
+        Ok(())
+    }
+
+    /// Sets the memory category of this thread.
+    ///
+    /// Allocations made by the thread are attributed to the category (see
+    /// [`Lua::set_memory_category`]). When the thread is resumed from Rust, the category is also
+    /// checked against the limit set by [`Lua::set_category_limit`], so a script exceeding its
+    /// quota fails with [`Error::OutOfMemory`] while other threads keep working.
+    ///
+    /// Threads resumed from Lua (eg. using `coroutine.resume`) are checked against the category
+    /// of the resuming thread until they call into Rust, but their allocations are still
+    /// attributed to their own category.
+    ///
+    /// [`Lua::set_memory_category`]: crate::Lua::set_memory_category
+    /// [`Lua::set_category_limit`]: crate::Lua::set_category_limit
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    pub fn set_memory_category(&self, category: u8) -> Result<()> {
+        let _lua = self.0.lua.lock();
+        unsafe { crate::state::Lua::set_thread_memory_category(self.state(), category) };
+        Ok(())
+    }
+
     /// Converts this thread to a generic C pointer.
     ///
     /// There is no way to convert the pointer back to its original value.
@@ -396,11 +1045,24 @@ impl Thread {
     pub fn to_pointer(&self) -> *const c_void {
         self.0.to_pointer()
     }
+
+    /// Returns an opaque identifier of this thread.
+    ///
+    /// Two handles to the same thread have the same identifier.
+    #[inline]
+    pub fn id(&self) -> ThreadId {
+        ThreadId(self.to_pointer() as usize)
+    }
 }
 
 impl fmt::Debug for Thread {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt.debug_tuple("Thread").field(&self.0).finish()
+        let mut tuple = fmt.debug_tuple("Thread");
+        tuple.field(&self.0);
+        if let Some(name) = self.0.lua.try_lock().and_then(|lua| self.name_inner(&lua)) {
+            tuple.field(&name);
+        }
+        tuple.finish()
     }
 }
 
@@ -414,6 +1076,10 @@ impl LuaType for Thread {
     const TYPE_ID: c_int = ffi::LUA_TTHREAD;
 }
 
+mod limits;
+pub(crate) mod pin;
+mod pool;
+
 #[cfg(test)]
 mod assertions {
     use super::*;
@@ -0,0 +1,50 @@
+use crate::error::{Error, Result};
+use crate::state::Lua;
+use crate::table::Table;
+use crate::thread::Thread;
+
+// Key to store resume limits in the registry (in a weak table)
+static RESUME_LIMITS_KEY: u8 = 0;
+
+/// Sets the maximum number of resumes of `thread` and resets its counter.
+pub(super) fn set_max_resumes(lua: &Lua, thread: &Thread, max: u32) -> Result<()> {
+    let limits = match lua.internal_registry_value::<Option<Table>>(&RESUME_LIMITS_KEY)? {
+        Some(limits) => limits,
+        None => {
+            let limits = lua.create_table()?;
+            let meta = lua.create_table_from([("__mode", "k")])?;
+            limits.set_metatable(Some(meta));
+            lua.set_internal_registry_value(&RESUME_LIMITS_KEY, &limits)?;
+            unsafe { (*lua.lock().extra()).resume_limits = true };
+            limits
+        }
+    };
+    limits.raw_set(thread, lua.create_sequence_from([0, max])?)
+}
+
+/// Returns the number of resumes of `thread` counted since its limit was set.
+pub(super) fn resume_count(lua: &Lua, thread: &Thread) -> Result<u32> {
+    match entry(lua, thread)? {
+        Some(entry) => entry.raw_get(1),
+        None => Ok(0),
+    }
+}
+
+/// Counts a resume of `thread`, failing if the thread has reached its limit.
+pub(super) fn check_resume(lua: &Lua, thread: &Thread) -> Result<()> {
+    let Some(entry) = entry(lua, thread)? else {
+        return Ok(());
+    };
+    let (count, max): (u32, u32) = (entry.raw_get(1)?, entry.raw_get(2)?);
+    if count >= max {
+        return Err(Error::ResumeLimitExceeded(max));
+    }
+    entry.raw_set(1, count + 1)
+}
+
+fn entry(lua: &Lua, thread: &Thread) -> Result<Option<Table>> {
+    match lua.internal_registry_value::<Option<Table>>(&RESUME_LIMITS_KEY)? {
+        Some(limits) => limits.raw_get(thread),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,45 @@
+use crate::error::{Error, Result};
+use crate::state::Lua;
+use crate::table::Table;
+use crate::thread::Thread;
+
+use super::PinnedThreadId;
+
+// Key to store pinned threads in the registry
+static PINNED_THREADS_KEY: u8 = 0;
+
+/// Stores `thread` in the registry under a new id.
+pub(super) fn pin(lua: &Lua, thread: &Thread) -> Result<PinnedThreadId> {
+    let pinned = match lua.internal_registry_value::<Option<Table>>(&PINNED_THREADS_KEY)? {
+        Some(pinned) => pinned,
+        None => {
+            let pinned = lua.create_table()?;
+            lua.set_internal_registry_value(&PINNED_THREADS_KEY, &pinned)?;
+            pinned
+        }
+    };
+    let id = unsafe {
+        let extra = lua.lock().extra();
+        (*extra).next_pinned_thread_id += 1;
+        (*extra).next_pinned_thread_id
+    };
+    pinned.raw_set(id, thread)?;
+    Ok(PinnedThreadId(id))
+}
+
+/// Returns the thread pinned under `id`.
+pub(crate) fn get(lua: &Lua, id: PinnedThreadId) -> Result<Thread> {
+    let thread = match lua.internal_registry_value::<Option<Table>>(&PINNED_THREADS_KEY)? {
+        Some(pinned) => pinned.raw_get::<Option<Thread>>(id.0)?,
+        None => None,
+    };
+    thread.ok_or_else(|| Error::runtime(format!("thread {id:?} is not pinned")))
+}
+
+/// Removes the thread pinned under `id` from the registry.
+pub(crate) fn unpin(lua: &Lua, id: PinnedThreadId) -> Result<()> {
+    match lua.internal_registry_value::<Option<Table>>(&PINNED_THREADS_KEY)? {
+        Some(pinned) => pinned.raw_set(id.0, crate::Nil),
+        None => Ok(()),
+    }
+}
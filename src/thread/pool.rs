@@ -0,0 +1,199 @@
+use std::mem;
+use std::ops::Deref;
+
+use parking_lot::Mutex;
+
+use crate::error::Result;
+use crate::function::Function;
+use crate::state::{Lua, WeakLua};
+use crate::traits::IntoLuaMulti;
+use crate::types::XRc;
+use crate::util::check_stack;
+
+use super::{Thread, ThreadResetOptions, ThreadStatusInner};
+
+/// A pool of Lua threads (coroutines) reused to run short jobs.
+///
+/// Creating a new thread for every job has a measurable overhead. The pool keeps threads that
+/// have finished their jobs and resets them (see [`Thread::reset`]) to run new ones.
+///
+/// Threads are handed out as [`PooledThread`] handles and are returned to the pool when the handle
+/// is dropped. Only finished (or not started) threads are returned; threads that are suspended or
+/// running are discarded. Threads that errored are discarded unless
+/// [`ThreadPool::reuse_errored`] is enabled.
+///
+/// # Examples
+///
+/// ```
+/// # use mlua::{Function, Lua, Result, ThreadPool};
+/// # fn main() -> Result<()> {
+/// let lua = Lua::new();
+/// let pool = ThreadPool::new(&lua, 8);
+/// let double: Function = lua.load("function(x) return x * 2 end").eval()?;
+///
+/// for i in 0..100 {
+///     let thread = pool.spawn(&double, i)?;
+///     assert_eq!(thread.resume::<i64>(())?, i * 2);
+/// }
+/// assert_eq!(pool.idle_count(), 1);
+/// # Ok(())
+/// # }
+/// ```
+pub struct ThreadPool {
+    lua: WeakLua,
+    inner: XRc<Mutex<PoolInner>>,
+    #[cfg(feature = "luau")]
+    sandbox: bool,
+}
+
+struct PoolInner {
+    idle: Vec<Thread>,
+    max_size: usize,
+    reuse_errored: bool,
+}
+
+/// A thread taken from a [`ThreadPool`].
+///
+/// Dereferences to [`Thread`]. The thread is returned to the pool when this handle is dropped.
+pub struct PooledThread {
+    thread: Option<Thread>,
+    pool: XRc<Mutex<PoolInner>>,
+}
+
+impl ThreadPool {
+    /// Creates a new thread pool that keeps up to `max_size` idle threads.
+    pub fn new(lua: &Lua, max_size: usize) -> Self {
+        ThreadPool {
+            lua: lua.weak(),
+            inner: XRc::new(Mutex::new(PoolInner {
+                idle: Vec::new(),
+                max_size,
+                reuse_errored: false,
+            })),
+            #[cfg(feature = "luau")]
+            sandbox: false,
+        }
+    }
+
+    /// Sets whether threads that raised an error should be reset and reused.
+    ///
+    /// Only Lua 5.4 and Luau can reset errored threads, other Lua versions always discard them.
+    ///
+    /// Default: **false**
+    #[must_use]
+    pub fn reuse_errored(self, enabled: bool) -> Self {
+        self.inner.lock().reuse_errored = enabled;
+        self
+    }
+
+    /// Sets whether every spawned thread should be sandboxed.
+    ///
+    /// Reused threads are sandboxed again with a fresh environment, so globals set by a previous
+    /// job are not visible to the next one. See [`Thread::sandbox`] for details.
+    ///
+    /// Default: **false**
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    #[must_use]
+    pub fn sandbox(mut self, enabled: bool) -> Self {
+        self.sandbox = enabled;
+        self
+    }
+
+    /// Returns the maximum number of idle threads kept by the pool.
+    pub fn max_size(&self) -> usize {
+        self.inner.lock().max_size
+    }
+
+    /// Returns the number of idle threads in the pool.
+    pub fn idle_count(&self) -> usize {
+        self.inner.lock().idle.len()
+    }
+
+    /// Takes a thread from the pool (or creates a new one) to run `func`.
+    ///
+    /// The `args` are passed to `func` when the thread is resumed for the first time, in addition
+    /// to the arguments passed to [`Thread::resume`].
+    pub fn spawn(&self, func: &Function, args: impl IntoLuaMulti) -> Result<PooledThread> {
+        let lua = self.lua.upgrade();
+        let idle = self.inner.lock().idle.pop();
+        let thread = match idle {
+            Some(thread) => {
+                // Pooled threads must not inherit globals from the previous run
+                thread.reset_with(func.clone(), ThreadResetOptions::new().preserve_globals(false))?;
+                thread
+            }
+            None => lua.create_thread(func.clone())?,
+        };
+
+        #[cfg(feature = "luau")]
+        if self.sandbox {
+            thread.sandbox()?;
+        }
+
+        let args = args.into_lua_multi(&lua)?;
+        if !args.is_empty() {
+            let lua = lua.lock();
+            let thread_state = thread.state();
+            unsafe {
+                check_stack(thread_state, args.len() as _)?;
+                for arg in args.iter() {
+                    lua.push_value_at(arg, thread_state)?;
+                }
+            }
+        }
+
+        Ok(PooledThread {
+            thread: Some(thread),
+            pool: self.inner.clone(),
+        })
+    }
+
+    /// Drops all idle threads, releasing their memory.
+    pub fn trim(&self) {
+        let idle = mem::take(&mut self.inner.lock().idle);
+        drop(idle);
+    }
+}
+
+impl Deref for PooledThread {
+    type Target = Thread;
+
+    #[inline]
+    fn deref(&self) -> &Thread {
+        self.thread.as_ref().unwrap()
+    }
+}
+
+impl Drop for PooledThread {
+    fn drop(&mut self) {
+        let Some(thread) = self.thread.take() else {
+            return;
+        };
+        let reuse_errored = self.pool.lock().reuse_errored;
+        let reusable = match thread.0.lua.try_lock() {
+            Some(lua) => unsafe {
+                match thread.status_inner(&lua) {
+                    ThreadStatusInner::Finished => true,
+                    status @ ThreadStatusInner::New(_) => thread.reset_inner(status).is_ok(),
+                    status @ ThreadStatusInner::Error if reuse_errored => {
+                        // Lua 5.4 returns the original error after resetting the thread
+                        let _ = thread.reset_inner(status);
+                        matches!(thread.status_inner(&lua), ThreadStatusInner::Finished)
+                    }
+                    _ => false,
+                }
+            },
+            None => false,
+        };
+        if reusable {
+            let mut pool = self.pool.lock();
+            if pool.idle.len() < pool.max_size {
+                pool.idle.push(thread);
+                return;
+            }
+        }
+        // The thread must be dropped outside of the pool lock
+        drop(thread);
+    }
+}
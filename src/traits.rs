@@ -1,3 +1,4 @@
+use std::any::TypeId;
 use std::os::raw::c_int;
 use std::string::String as StdString;
 use std::sync::Arc;
@@ -70,6 +71,15 @@ pub trait FromLua: Sized {
             cause: Arc::new(err),
         })
     }
+
+    /// Returns the `TypeId` of the userdata type this type is converted from, if any.
+    ///
+    /// Used to report custom userdata type names in errors.
+    #[doc(hidden)]
+    #[inline]
+    fn userdata_type_id() -> Option<TypeId> {
+        None
+    }
 }
 
 /// Trait for types convertible to any number of Lua values.
@@ -162,6 +172,16 @@ pub trait FromLuaMulti: Sized {
     }
 }
 
+/// Trait for tuples of [`FromLua`] values, which have a fixed number of elements.
+///
+/// Used by [`Chunk::eval_tuple`] to check the number of returned values.
+///
+/// [`Chunk::eval_tuple`]: crate::chunk::Chunk::eval_tuple
+pub trait FromLuaTuple: FromLuaMulti {
+    /// Number of elements in the tuple.
+    const LEN: usize;
+}
+
 /// A trait for types that can be used as Lua objects (usually table and userdata).
 pub trait ObjectLike: Sealed {
     /// Gets the value associated to `key` from the object, assuming it has `__index` metamethod.
@@ -1,7 +1,7 @@
 use std::cell::UnsafeCell;
 use std::os::raw::{c_int, c_void};
 
-use crate::error::Result;
+use crate::error::{Error, Result};
 #[cfg(not(feature = "luau"))]
 use crate::hook::{Debug, HookTriggers};
 use crate::state::{ExtraData, Lua, RawLua};
@@ -11,7 +11,7 @@ pub(crate) use sync::{ArcReentrantMutexGuard, ReentrantMutex, ReentrantMutexGuar
 
 pub use app_data::{AppData, AppDataRef, AppDataRefMut};
 pub use either::Either;
-pub use registry_key::RegistryKey;
+pub use registry_key::{RegistryEntry, RegistryKey};
 pub(crate) use value_ref::ValueRef;
 
 /// Type of Lua integer numbers.
@@ -75,6 +75,37 @@ pub(crate) type InterruptCallback = XRc<dyn Fn(&Lua) -> Result<VmState> + Send>;
 #[cfg(all(not(feature = "send"), feature = "luau"))]
 pub(crate) type InterruptCallback = XRc<dyn Fn(&Lua) -> Result<VmState>>;
 
+#[cfg(all(feature = "send", feature = "luau"))]
+pub(crate) type DebugCallback =
+    XRc<dyn Fn(&Lua, crate::hook::Debug) -> Result<crate::hook::DebugAction> + Send>;
+
+#[cfg(all(not(feature = "send"), feature = "luau"))]
+pub(crate) type DebugCallback = XRc<dyn Fn(&Lua, crate::hook::Debug) -> Result<crate::hook::DebugAction>>;
+
+#[cfg(all(feature = "send", feature = "luau"))]
+pub(crate) type DebugHookCallback = XRc<dyn Fn(&Lua, crate::hook::Debug) -> Result<VmState> + Send>;
+
+#[cfg(all(not(feature = "send"), feature = "luau"))]
+pub(crate) type DebugHookCallback = XRc<dyn Fn(&Lua, crate::hook::Debug) -> Result<VmState>>;
+
+#[cfg(all(feature = "send", feature = "luau"))]
+pub(crate) type DebugInterruptCallback = XRc<dyn Fn(&Lua, crate::hook::Debug) -> Result<()> + Send>;
+
+#[cfg(all(not(feature = "send"), feature = "luau"))]
+pub(crate) type DebugInterruptCallback = XRc<dyn Fn(&Lua, crate::hook::Debug) -> Result<()>>;
+
+#[cfg(all(feature = "send", feature = "luau"))]
+pub(crate) type GcCallback = XRc<dyn Fn(crate::GcPhase, std::time::Duration) + Send>;
+
+#[cfg(all(not(feature = "send"), feature = "luau"))]
+pub(crate) type GcCallback = XRc<dyn Fn(crate::GcPhase, std::time::Duration)>;
+
+#[cfg(all(feature = "send", feature = "luau"))]
+pub(crate) type AllocationCallback = XRc<dyn Fn(crate::AllocEvent) + Send>;
+
+#[cfg(all(not(feature = "send"), feature = "luau"))]
+pub(crate) type AllocationCallback = XRc<dyn Fn(crate::AllocEvent)>;
+
 #[cfg(all(feature = "send", feature = "luau"))]
 pub(crate) type ThreadCreationCallback = XRc<dyn Fn(&Lua, crate::Thread) -> Result<()> + Send>;
 
@@ -87,12 +118,43 @@ pub(crate) type ThreadCollectionCallback = XRc<dyn Fn(crate::LightUserData) + Se
 #[cfg(all(not(feature = "send"), feature = "luau"))]
 pub(crate) type ThreadCollectionCallback = XRc<dyn Fn(crate::LightUserData)>;
 
+#[cfg(all(feature = "send", feature = "luau"))]
+pub(crate) type ThreadEventCallback = XRc<dyn Fn(&Lua, crate::ThreadEvent) -> Result<()> + Send>;
+
+#[cfg(all(not(feature = "send"), feature = "luau"))]
+pub(crate) type ThreadEventCallback = XRc<dyn Fn(&Lua, crate::ThreadEvent) -> Result<()>>;
+
 #[cfg(all(feature = "send", feature = "lua54"))]
 pub(crate) type WarnCallback = XRc<dyn Fn(&Lua, &str, bool) -> Result<()> + Send>;
 
 #[cfg(all(not(feature = "send"), feature = "lua54"))]
 pub(crate) type WarnCallback = XRc<dyn Fn(&Lua, &str, bool) -> Result<()>>;
 
+#[cfg(feature = "send")]
+pub(crate) type OomCallback = XRc<dyn Fn(&Error) + Send>;
+
+#[cfg(not(feature = "send"))]
+pub(crate) type OomCallback = XRc<dyn Fn(&Error)>;
+
+#[cfg(feature = "send")]
+pub(crate) type ClockSourceCallback = XRc<dyn Fn(crate::ClockKind) -> f64 + Send>;
+
+#[cfg(not(feature = "send"))]
+pub(crate) type ClockSourceCallback = XRc<dyn Fn(crate::ClockKind) -> f64>;
+
+#[cfg(feature = "send")]
+pub(crate) type ErrorFormatterCallback =
+    XRc<dyn Fn(&Error, &mut dyn std::fmt::Write) -> std::fmt::Result + Send>;
+
+#[cfg(not(feature = "send"))]
+pub(crate) type ErrorFormatterCallback = XRc<dyn Fn(&Error, &mut dyn std::fmt::Write) -> std::fmt::Result>;
+
+#[cfg(feature = "send")]
+pub(crate) type VmMessageTranslator = XRc<dyn Fn(&str) -> Option<String> + Send>;
+
+#[cfg(not(feature = "send"))]
+pub(crate) type VmMessageTranslator = XRc<dyn Fn(&str) -> Option<String>>;
+
 /// A trait that adds `Send` requirement if `send` feature is enabled.
 #[cfg(feature = "send")]
 pub trait MaybeSend: Send {}
@@ -1,5 +1,7 @@
 use std::hash::{Hash, Hasher};
 use std::os::raw::c_int;
+use std::panic::Location;
+use std::string::String as StdString;
 use std::sync::Arc;
 use std::{fmt, mem, ptr};
 
@@ -92,6 +94,32 @@ impl RegistryKey {
     }
 }
 
+/// Information about a value stored in the Lua registry.
+///
+/// Returned by [`Lua::registry_dump`].
+///
+/// [`Lua::registry_dump`]: crate::Lua::registry_dump
+#[derive(Clone, Debug)]
+pub struct RegistryEntry {
+    /// Name of the value if it was set by [`Lua::set_named_registry_value`].
+    ///
+    /// `None` for values created by [`Lua::create_registry_value`].
+    ///
+    /// [`Lua::set_named_registry_value`]: crate::Lua::set_named_registry_value
+    /// [`Lua::create_registry_value`]: crate::Lua::create_registry_value
+    pub name: Option<StdString>,
+    /// Underlying Lua reference for values created by [`Lua::create_registry_value`].
+    ///
+    /// [`Lua::create_registry_value`]: crate::Lua::create_registry_value
+    pub id: Option<c_int>,
+    /// Lua type name of the value.
+    pub type_name: &'static str,
+    /// Location in Rust code where the value was placed into the registry.
+    ///
+    /// Recorded only in debug builds.
+    pub location: Option<&'static Location<'static>>,
+}
+
 #[cfg(test)]
 mod assertions {
     use super::*;
@@ -121,6 +121,42 @@ pub enum MetaMethod {
     ///
     /// Executed before the iteration begins, and should return an iterator function like `next`
     /// (or a custom one).
+    ///
+    /// This enables Luau generalized iteration over userdata (`for k, v in obj do ... end`). The
+    /// metamethod can return up to three values: the iterator function, its state and the initial
+    /// control value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use mlua::{Lua, MetaMethod, Result, UserData, UserDataMethods, UserDataRef};
+    /// # #[cfg(feature = "luau")]
+    /// # fn main() -> Result<()> {
+    /// struct Numbers(Vec<i64>);
+    ///
+    /// impl UserData for Numbers {
+    ///     fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+    ///         methods.add_meta_function(MetaMethod::Iter, |lua, this: mlua::AnyUserData| {
+    ///             let next = lua.create_function(|_, (this, i): (UserDataRef<Numbers>, usize)| {
+    ///                 // Returning `nil` as the control value ends the loop
+    ///                 let n = this.0.get(i).copied();
+    ///                 Ok((n.map(|_| i + 1), n))
+    ///             })?;
+    ///             Ok((next, this, 0))
+    ///         });
+    ///     }
+    /// }
+    ///
+    /// let lua = Lua::new();
+    /// lua.globals().set("numbers", Numbers(vec![1, 2, 3]))?;
+    /// let sum: i64 = lua.load("local s = 0 for _, n in numbers do s += n end return s").eval()?;
+    /// assert_eq!(sum, 6);
+    /// # Ok(())
+    /// # }
+    ///
+    /// # #[cfg(not(feature = "luau"))]
+    /// # fn main() {}
+    /// ```
     #[cfg(any(feature = "luau", doc))]
     #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
     Iter,
@@ -481,6 +517,48 @@ pub trait UserDataFields<T> {
 /// # Ok(())
 /// # }
 /// ```
+///
+/// # Trait objects
+///
+/// Userdata types are identified by their [`TypeId`], and a trait object does not have a single
+/// concrete type. A boxed trait object does, so types that only share a trait (eg. plugins) can
+/// be exposed uniformly as `Box<dyn Trait>`, with methods dispatching through the trait.
+/// `UserData` can be implemented for `Box<dyn Trait>` in the crate defining `Trait`:
+///
+/// ```
+/// # use mlua::{Lua, Result, UserData, UserDataMethods};
+/// # fn main() -> Result<()> {
+/// # let lua = Lua::new();
+/// trait Plugin: Send {
+///     fn name(&self) -> String;
+/// }
+///
+/// struct Logger;
+///
+/// impl Plugin for Logger {
+///     fn name(&self) -> String {
+///         "logger".to_string()
+///     }
+/// }
+///
+/// impl UserData for Box<dyn Plugin> {
+///     fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+///         methods.add_method("name", |_, this, ()| Ok(this.name()));
+///     }
+/// }
+///
+/// let plugin: Box<dyn Plugin> = Box::new(Logger);
+/// lua.globals().set("plugin", lua.create_userdata(plugin)?)?;
+/// lua.load("assert(plugin:name() == 'logger')").exec()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// If the trait is defined in another crate, register methods for `Box<dyn Trait>` using
+/// [`Lua::register_userdata_type`] and create instances with [`Lua::create_any_userdata`].
+/// Either way, the value is borrowed back as `Box<dyn Trait>` (eg. with [`UserDataRef`]).
+///
+/// [`TypeId`]: std::any::TypeId
 pub trait UserData: Sized {
     /// Adds custom fields specific to this userdata.
     #[allow(unused_variables)]
@@ -606,6 +684,7 @@ impl AnyUserData {
             Some(type_id) if type_id == TypeId::of::<T>() => unsafe {
                 let ref_thread = lua.ref_thread(self.0.aux_thread);
                 if (*get_userdata::<UserDataStorage<T>>(ref_thread, self.0.index)).has_exclusive_access() {
+                    (*lua.extra()).finalization_priorities.remove(&self.to_pointer());
                     take_userdata::<UserDataStorage<T>>(ref_thread, self.0.index).into_inner()
                 } else {
                     Err(Error::UserDataBorrowMutError)
@@ -628,6 +707,16 @@ impl AnyUserData {
             let _sg = StackGuard::new(state);
             check_stack(state, 3)?;
 
+            // Explicit destruction is never deferred by the finalization priority
+            let priority = (*lua.extra()).finalization_priorities.remove(&self.to_pointer());
+            let restore_priority = || {
+                if let Some(priority) = priority {
+                    (*lua.extra())
+                        .finalization_priorities
+                        .insert(self.to_pointer(), priority);
+                }
+            };
+
             // Luau does not have __gc
             #[cfg(feature = "luau")]
             {
@@ -649,6 +738,7 @@ impl AnyUserData {
                         if ffi::lua_isboolean(state, -1) != 0 && ffi::lua_toboolean(state, -1) != 0 {
                             return Ok(());
                         }
+                        restore_priority();
                         return Err(Error::UserDataBorrowMutError);
                     }
                     None => return Err(Error::UserDataTypeMismatch),
@@ -666,11 +756,38 @@ impl AnyUserData {
                 if ffi::lua_isboolean(state, -1) != 0 && ffi::lua_toboolean(state, -1) != 0 {
                     return Ok(());
                 }
+                restore_priority();
                 Err(Error::UserDataBorrowMutError)
             }
         }
     }
 
+    /// Sets the finalization priority of this userdata.
+    ///
+    /// When userdata with a priority is collected by Lua's GC, its value is not dropped right away
+    /// but is put into a queue. The queue is drained at safe points, where no Lua code is running:
+    /// when a Rust function called from Lua returns, when [`Function::call`] or a thread resume
+    /// returns, at the end of [`Lua::gc_collect`] and [`Lua::gc_step`] calls, and when the Lua
+    /// state is dropped. This includes collections triggered automatically by allocations. Values
+    /// collected together are dropped in ascending priority order, so higher-priority objects are
+    /// finalized later (e.g. a connection with a higher priority than its statements).
+    ///
+    /// This is best-effort: the order is only guaranteed between values collected before the same
+    /// queue drain. Userdata without a priority is finalized immediately as usual. Explicitly
+    /// destroyed or taken userdata is dropped immediately.
+    ///
+    /// [`Function::call`]: crate::Function::call
+    /// [`Lua::gc_collect`]: crate::Lua::gc_collect
+    /// [`Lua::gc_step`]: crate::Lua::gc_step
+    pub fn set_finalization_priority(&self, priority: i32) {
+        let lua = self.0.lua.lock();
+        unsafe {
+            (*lua.extra())
+                .finalization_priorities
+                .insert(self.to_pointer(), priority)
+        };
+    }
+
     /// Sets an associated value to this [`AnyUserData`].
     ///
     /// The value may be any Lua value whatsoever, and can be retrieved with [`user_value`].
@@ -83,6 +83,11 @@ impl<T: 'static> FromLua for UserDataRef<T> {
     unsafe fn from_specified_stack(idx: c_int, lua: &RawLua, state: *mut ffi::lua_State) -> Result<Self> {
         Self::borrow_from_stack(lua, state, idx)
     }
+
+    #[inline]
+    fn userdata_type_id() -> Option<TypeId> {
+        Some(TypeId::of::<T>())
+    }
 }
 
 impl<T: 'static> UserDataRef<T> {
@@ -110,7 +115,7 @@ impl<T: 'static> UserDataRef<T> {
         state: *mut ffi::lua_State,
         idx: c_int,
     ) -> Result<Self> {
-        let type_id = lua.get_userdata_type_id::<T>(state, idx)?;
+        let type_id = lua.get_userdata_type_id::<T>(state, idx, TypeId::of::<T>())?;
         match type_id {
             Some(type_id) if type_id == TypeId::of::<T>() => {
                 let ud = get_userdata::<UserDataStorage<T>>(state, idx);
@@ -298,6 +303,11 @@ impl<T: 'static> FromLua for UserDataRefMut<T> {
     unsafe fn from_specified_stack(idx: c_int, lua: &RawLua, state: *mut ffi::lua_State) -> Result<Self> {
         Self::borrow_from_stack(lua, state, idx)
     }
+
+    #[inline]
+    fn userdata_type_id() -> Option<TypeId> {
+        Some(TypeId::of::<T>())
+    }
 }
 
 impl<T: 'static> UserDataRefMut<T> {
@@ -325,7 +335,7 @@ impl<T: 'static> UserDataRefMut<T> {
         state: *mut ffi::lua_State,
         idx: c_int,
     ) -> Result<Self> {
-        let type_id = lua.get_userdata_type_id::<T>(state, idx)?;
+        let type_id = lua.get_userdata_type_id::<T>(state, idx, TypeId::of::<T>())?;
         match type_id {
             Some(type_id) if type_id == TypeId::of::<T>() => {
                 let ud = get_userdata::<UserDataStorage<T>>(state, idx);
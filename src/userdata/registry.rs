@@ -115,7 +115,7 @@ impl<T> UserDataRegistry<T> {
             match target_type {
                 #[rustfmt::skip]
                 UserDataType::Shared(type_hints) => {
-                    let type_id = try_self_arg!(rawlua.get_userdata_type_id::<T>(state, self_index));
+                    let type_id = try_self_arg!(rawlua.get_userdata_type_id::<T>(state, self_index, type_hints.type_id()));
                     try_self_arg!(borrow_userdata_scoped(state, self_index, type_id, type_hints, |ud| {
                         method(rawlua.lua(), ud, args?)?.push_into_specified_stack_multi(rawlua, state)
                     }))
@@ -154,7 +154,7 @@ impl<T> UserDataRegistry<T> {
             match target_type {
                 #[rustfmt::skip]
                 UserDataType::Shared(type_hints) => {
-                    let type_id = try_self_arg!(rawlua.get_userdata_type_id::<T>(state, self_index));
+                    let type_id = try_self_arg!(rawlua.get_userdata_type_id::<T>(state, self_index, type_hints.type_id()));
                     try_self_arg!(borrow_userdata_scoped_mut(state, self_index, type_id, type_hints, |ud| {
                         method(rawlua.lua(), ud, args?)?.push_into_specified_stack_multi(rawlua, state)
                     }))
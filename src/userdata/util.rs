@@ -6,6 +6,7 @@ use std::ptr;
 
 use super::UserDataStorage;
 use crate::error::{Error, Result};
+use crate::state::{ExtraData, PendingFinalizer};
 use crate::util::{get_userdata, rawget_field, rawset_field, take_userdata};
 
 // This is a trick to check if a type is `Sync` or not.
@@ -447,7 +448,15 @@ pub(crate) unsafe extern "C" fn collect_userdata<T>(
     (*extra).running_gc = true;
     // Luau does not support _any_ panics in destructors (they are declared as "C", NOT as "C-unwind"),
     // so any panics will trigger `abort()`.
-    ptr::drop_in_place(ud as *mut T);
+    match (*extra).take_finalization_priority(ud) {
+        Some(priority) => {
+            let value = ptr::read(ud as *mut T);
+            (*extra)
+                .pending_finalizers
+                .push(PendingFinalizer::new(priority, value));
+        }
+        None => ptr::drop_in_place(ud as *mut T),
+    }
     (*extra).running_gc = false;
 }
 
@@ -457,7 +466,18 @@ pub(crate) unsafe extern "C" fn collect_userdata<T>(
 pub(super) unsafe extern "C-unwind" fn destroy_userdata_storage<T>(state: *mut ffi::lua_State) -> c_int {
     let ud = get_userdata::<UserDataStorage<T>>(state, 1);
     if (*ud).is_safe_to_destroy() {
-        take_userdata::<UserDataStorage<T>>(state, 1);
+        let extra = ExtraData::get(state);
+        let value = take_userdata::<UserDataStorage<T>>(state, 1);
+        // Defer the drop if the userdata has a finalization priority
+        match extra
+            .as_mut()
+            .and_then(|extra| extra.take_finalization_priority(ud as *const _))
+        {
+            Some(priority) => (*extra)
+                .pending_finalizers
+                .push(PendingFinalizer::new(priority, value)),
+            None => drop(value),
+        }
         ffi::lua_pushboolean(state, 1);
     } else {
         ffi::lua_pushboolean(state, 0);
@@ -1,16 +1,19 @@
 use std::any::Any;
+use std::backtrace::{Backtrace, BacktraceStatus};
 use std::fmt::Write as _;
 use std::mem::MaybeUninit;
-use std::os::raw::{c_int, c_void};
+use std::os::raw::{c_char, c_int, c_void};
 use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
 use std::ptr;
 use std::sync::Arc;
 
-use crate::error::{Error, Result};
+use crate::error::{Error, Result, RuntimeErrorInfo, Traceback, TracebackFrame};
 use crate::memory::MemoryState;
+use crate::state::ExtraData;
 use crate::util::{
-    check_stack, get_internal_userdata, init_internal_metatable, push_internal_userdata, push_string,
-    push_table, rawset_field, to_string, TypeKey, DESTRUCTED_USERDATA_METATABLE,
+    check_stack, get_internal_userdata, init_internal_metatable, linenumber_to_usize, ptr_to_lossy_str,
+    ptr_to_str, push_internal_userdata, push_string, push_table, rawset_field, to_string, StackGuard,
+    TypeKey, DESTRUCTED_USERDATA_METATABLE,
 };
 
 static WRAPPED_FAILURE_TYPE_KEY: u8 = 0;
@@ -18,7 +21,22 @@ static WRAPPED_FAILURE_TYPE_KEY: u8 = 0;
 pub(crate) enum WrappedFailure {
     None,
     Error(Error),
-    Panic(Option<Box<dyn Any + Send + 'static>>),
+    Panic(Option<Box<dyn Any + Send + 'static>>, PanicDetails),
+}
+
+// Message and backtrace of a caught panic, kept after the panic is resumed
+pub(crate) struct PanicDetails {
+    message: String,
+    backtrace: Option<Arc<Backtrace>>,
+}
+
+impl PanicDetails {
+    pub(crate) fn to_error(&self) -> Error {
+        Error::RustPanic {
+            message: self.message.clone(),
+            backtrace: self.backtrace.clone(),
+        }
+    }
 }
 
 impl TypeKey for WrappedFailure {
@@ -33,6 +51,37 @@ impl WrappedFailure {
         // Unprotected calls always return `Ok`
         push_internal_userdata(state, WrappedFailure::None, false).unwrap()
     }
+
+    // Wraps a panic caught at the boundary of a Rust callback
+    pub(crate) unsafe fn panic(state: *mut ffi::lua_State, payload: Box<dyn Any + Send + 'static>) -> Self {
+        let extra = ExtraData::get(state);
+        let backtrace = match !extra.is_null() && (*extra).capture_panic_backtraces {
+            true => Backtrace::force_capture(),
+            false => Backtrace::capture(),
+        };
+        let details = PanicDetails {
+            message: panic_message(&*payload),
+            backtrace: (backtrace.status() == BacktraceStatus::Captured).then(|| Arc::new(backtrace)),
+        };
+        WrappedFailure::Panic(Some(payload), details)
+    }
+}
+
+// Formats a panic payload, falling back to `<panic>` for payloads of unknown types
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else if let Some(err) = payload.downcast_ref::<Error>() {
+        err.to_string()
+    } else if let Some(err) = payload.downcast_ref::<Box<dyn std::error::Error + Send + Sync>>() {
+        err.to_string()
+    } else if let Some(value) = payload.downcast_ref::<Box<dyn std::fmt::Debug + Send + Sync>>() {
+        format!("{value:?}")
+    } else {
+        "<panic>".to_string()
+    }
 }
 
 // In the context of a lua callback, this will call the given function and if the given function
@@ -75,21 +124,31 @@ where
 
             // Build `CallbackError` with traceback
             let traceback = if ffi::lua_checkstack(state, ffi::LUA_TRACEBACK_STACK) != 0 {
-                ffi::luaL_traceback(state, state, ptr::null(), 0);
+                traceback(state, state, ptr::null(), 0);
                 let traceback = to_string(state, -1);
                 ffi::lua_pop(state, 1);
                 traceback
             } else {
                 "<not enough stack space for traceback>".to_string()
             };
-            let cause = Arc::new(err);
+            // Errors from nested Lua calls already carry the traceback of the place they were raised
+            let capture = err.traceback().is_none()
+                && ffi::lua_checkstack(state, 1) != 0
+                && capture_tracebacks_enabled(state);
+            let cause = match capture {
+                true => Arc::new(Error::WithTraceback {
+                    traceback: capture_traceback(state, 0),
+                    cause: Arc::new(err),
+                }),
+                false => Arc::new(err),
+            };
             let wrapped_error = WrappedFailure::Error(Error::CallbackError { traceback, cause });
             ptr::write(ud, wrapped_error);
             ffi::lua_error(state)
         }
         Err(p) => {
             ffi::lua_settop(state, 1);
-            ptr::write(ud, WrappedFailure::Panic(Some(p)));
+            ptr::write(ud, WrappedFailure::panic(state, p));
             ffi::lua_error(state)
         }
     }
@@ -112,7 +171,7 @@ pub(crate) unsafe fn pop_error(state: *mut ffi::lua_State, err_code: c_int) -> E
             ffi::lua_pop(state, 1);
             err.clone()
         }
-        Some(WrappedFailure::Panic(panic)) => {
+        Some(WrappedFailure::Panic(panic, _)) => {
             if let Some(p) = panic.take() {
                 resume_unwind(p);
             } else {
@@ -122,6 +181,7 @@ pub(crate) unsafe fn pop_error(state: *mut ffi::lua_State, err_code: c_int) -> E
         _ => {
             let err_string = to_string(state, -1);
             ffi::lua_pop(state, 1);
+            let err_string = apply_source_maps(state, err_string);
 
             match err_code {
                 ffi::LUA_ERRRUN => Error::RuntimeError(err_string),
@@ -140,7 +200,10 @@ pub(crate) unsafe fn pop_error(state: *mut ffi::lua_State, err_code: c_int) -> E
                     // runtime errors, so we handle them the same way.
                     Error::RuntimeError(err_string)
                 }
-                ffi::LUA_ERRMEM => Error::MemoryError(err_string),
+                ffi::LUA_ERRMEM => match MemoryState::take_oom_error(state) {
+                    Some(err) => err,
+                    None => Error::MemoryError(err_string),
+                },
                 #[cfg(any(feature = "lua53", feature = "lua52"))]
                 ffi::LUA_ERRGCMM => Error::GarbageCollectorError(err_string),
                 _ => mlua_panic!("unrecognized lua error code"),
@@ -265,11 +328,15 @@ pub(crate) unsafe extern "C-unwind" fn error_traceback(state: *mut ffi::lua_Stat
     }
 
     if get_internal_userdata::<WrappedFailure>(state, -1, ptr::null()).is_null() {
+        translate_vm_message(state, state, 1);
         let s = ffi::luaL_tolstring(state, -1, ptr::null_mut());
+        let info = capture_error_info(state, state, 1);
         if ffi::lua_checkstack(state, ffi::LUA_TRACEBACK_STACK) != 0 {
-            ffi::luaL_traceback(state, state, s, 0);
+            traceback(state, state, s, 0);
             ffi::lua_remove(state, -2);
         }
+        // Skip the error handler frame itself
+        wrap_with_traceback(state, state, 1, info);
     }
 
     1
@@ -281,12 +348,335 @@ pub(crate) unsafe fn error_traceback_thread(state: *mut ffi::lua_State, thread:
     ffi::lua_xmove(thread, state, 1);
 
     if get_internal_userdata::<WrappedFailure>(state, -1, ptr::null()).is_null() {
+        translate_vm_message(state, thread, 0);
         let s = ffi::luaL_tolstring(state, -1, ptr::null_mut());
+        let info = capture_error_info(state, thread, 0);
         if ffi::lua_checkstack(state, ffi::LUA_TRACEBACK_STACK) != 0 {
-            ffi::luaL_traceback(state, thread, s, 0);
+            traceback(state, thread, s, 0);
             ffi::lua_remove(state, -2);
         }
+        wrap_with_traceback(state, thread, 0, info);
+    }
+}
+
+// Rewrites the error message on top of the stack using the translator set by
+// `Lua::set_vm_message_translator`, unless it was raised by a script (the `error` or `assert`
+// functions, recognized by their address).
+// `level` is the level of the function that raised the error in `thread`.
+// Uses 1 stack space, does not call checkstack.
+unsafe fn translate_vm_message(state: *mut ffi::lua_State, thread: *mut ffi::lua_State, level: c_int) {
+    let extra = ExtraData::get(state);
+    let Some(extra) = extra.as_ref() else {
+        return;
+    };
+    let Some(translator) = extra.vm_message_translator.clone() else {
+        return;
+    };
+    if ffi::lua_type(state, -1) != ffi::LUA_TSTRING || ffi::lua_checkstack(thread, 1) == 0 {
+        return;
+    }
+
+    let (raiser, position) = error_origin(thread, level);
+    if raiser.is_some_and(|func| extra.script_error_raisers.contains(&func)) {
+        // Raised by a script
+        return;
+    }
+    let position = position.map(|(src, line)| format!("{src}:{line}: "));
+
+    let message = to_string(state, -1);
+    let (prefix, text) = match position {
+        Some(position) if message.starts_with(&position) => message.split_at(position.len()),
+        _ => ("", message.as_str()),
+    };
+    if let Ok(Some(text)) = catch_unwind(AssertUnwindSafe(|| translator(text))) {
+        let message = format!("{prefix}{text}");
+        ffi::lua_pushlstring(state, message.as_ptr() as *const c_char, message.len());
+        ffi::lua_replace(state, -2);
+    }
+}
+
+// Finds the function that raised an error at `level` of `thread` and the first Lua function from
+// there, which position is added to the error message.
+// Returns the address of the raising native function (if any) and the position (source and line).
+// Uses 1 stack space of `thread`, does not call checkstack.
+unsafe fn error_origin(
+    thread: *mut ffi::lua_State,
+    level: c_int,
+) -> (Option<usize>, Option<(String, usize)>) {
+    let mut ar: ffi::lua_Debug = std::mem::zeroed();
+    let mut raiser = None;
+    for level in level..level + 2 {
+        #[cfg(not(feature = "luau"))]
+        let found = ffi::lua_getstack(thread, level, &mut ar) != 0
+            && ffi::lua_getinfo(thread, cstr!("Slf"), &mut ar) != 0;
+        #[cfg(feature = "luau")]
+        let found = ffi::lua_getinfo(thread, level, cstr!("slf"), &mut ar) != 0;
+        if !found {
+            break;
+        }
+        // Check the function pushed by `lua_getinfo`
+        let func = ffi::lua_tocfunction(thread, -1);
+        ffi::lua_pop(thread, 1);
+        if ptr_to_str(ar.what) == Some("C") {
+            raiser = raiser.or(func.map(|func| func as usize));
+            continue;
+        }
+        #[cfg(not(feature = "luau"))]
+        let short_src = ar.short_src.as_ptr();
+        #[cfg(feature = "luau")]
+        let short_src = ar.short_src;
+        if let (Some(src), Some(line)) = (ptr_to_lossy_str(short_src), linenumber_to_usize(ar.currentline)) {
+            return (raiser, Some((src.into_owned(), line)));
+        }
+        break;
+    }
+    (raiser, None)
+}
+
+// Captures the position and message of the error (converted to string) on top of the stack,
+// raised at `level` of `thread`, if `LuaOptions::capture_tracebacks` is enabled.
+// The position is known only if the message is prefixed with it (by the VM or `error` function).
+// Uses 1 stack space, does not call checkstack.
+unsafe fn capture_error_info(
+    state: *mut ffi::lua_State,
+    thread: *mut ffi::lua_State,
+    level: c_int,
+) -> Option<RuntimeErrorInfo> {
+    if !capture_tracebacks_enabled(state) || ffi::lua_checkstack(thread, 1) == 0 {
+        return None;
+    }
+    let message = to_string(state, -1);
+    if let (_, Some((source, line))) = error_origin(thread, level) {
+        if let Some(text) = message.strip_prefix(&format!("{source}:{line}: ")) {
+            let (source, line) = map_location(state, source, line);
+            return Some(RuntimeErrorInfo {
+                source: Some(source),
+                line: u32::try_from(line).ok(),
+                message: apply_source_maps(state, text.to_string()),
+            });
+        }
+    }
+    Some(RuntimeErrorInfo {
+        source: None,
+        line: None,
+        message: apply_source_maps(state, message),
+    })
+}
+
+// Formats `error` using the formatter set by `Lua::set_error_formatter`.
+// Falls back to the `Display` implementation if the formatter fails or panics.
+// Uses 1 stack space, does not call checkstack.
+unsafe fn format_error(state: *mut ffi::lua_State, error: &Error, buf: &mut String) {
+    let extra = ExtraData::get(state);
+    if let Some(formatter) = extra.as_ref().and_then(|extra| extra.error_formatter.clone()) {
+        if let Ok(Ok(())) = catch_unwind(AssertUnwindSafe(|| formatter(error, buf))) {
+            return;
+        }
+        buf.clear();
+    }
+    let _ = write!(buf, "{error}");
+}
+
+// Translates chunk locations in `text` using the source maps set by `Chunk::set_source_map`.
+// Uses 1 stack space, does not call checkstack.
+unsafe fn apply_source_maps(state: *mut ffi::lua_State, mut text: String) -> String {
+    if let Some(extra) = ExtraData::get(state).as_ref() {
+        for (src, map) in &extra.source_maps {
+            if let Some(translated) = map.translate(src, &text) {
+                text = translated;
+            }
+        }
+    }
+    text
+}
+
+// Translates a chunk location (printable source and line) using the source map set by
+// `Chunk::set_source_map`, returns the location unchanged if the chunk has no source map.
+// Uses 1 stack space, does not call checkstack.
+unsafe fn map_location(state: *mut ffi::lua_State, source: String, line: usize) -> (String, usize) {
+    let extra = ExtraData::get(state);
+    let map = extra.as_ref().and_then(|extra| extra.source_maps.get(&source));
+    match map.and_then(|map| map.lookup(line)) {
+        Some((file, line)) => (file.map_or(source, str::to_string), line),
+        None => (source, line),
+    }
+}
+
+// Returns `true` if `LuaOptions::capture_tracebacks` is enabled.
+// Uses 1 stack space, does not call checkstack.
+unsafe fn capture_tracebacks_enabled(state: *mut ffi::lua_State) -> bool {
+    let extra = ExtraData::get(state);
+    !extra.is_null() && (*extra).capture_tracebacks
+}
+
+// Replaces the error message on top of the stack with a `WrappedFailure` holding
+// `Error::WithTraceback` with the stack of `thread` (starting from `level`), if enabled.
+// `info` is the position and message captured by `capture_error_info`.
+unsafe fn wrap_with_traceback(
+    state: *mut ffi::lua_State,
+    thread: *mut ffi::lua_State,
+    level: c_int,
+    info: Option<RuntimeErrorInfo>,
+) {
+    if ffi::lua_checkstack(state, 1) == 0 || !capture_tracebacks_enabled(state) {
+        return;
+    }
+    let err = Error::WithTraceback {
+        traceback: capture_traceback(thread, level).with_error_info(info),
+        cause: Arc::new(Error::RuntimeError(apply_source_maps(
+            state,
+            to_string(state, -1),
+        ))),
+    };
+    let mut ud = ptr::null_mut();
+    MemoryState::relax_limit_with(state, || ud = WrappedFailure::new_userdata(state));
+    ptr::write(ud, WrappedFailure::Error(err));
+    ffi::lua_replace(state, -2);
+}
+
+// Fills in the name of the running function in argument conversion errors that do not have it
+pub(crate) unsafe fn with_callee_name(state: *mut ffi::lua_State, err: Error) -> Error {
+    match err {
+        Error::BadArgument {
+            to: None,
+            pos,
+            name,
+            cause,
+        } => Error::BadArgument {
+            to: callee_name(state),
+            pos,
+            name,
+            cause,
+        },
+        err => err,
+    }
+}
+
+// Returns the name the running function was called by, or the name of a global variable holding it
+unsafe fn callee_name(state: *mut ffi::lua_State) -> Option<String> {
+    if ffi::lua_checkstack(state, 4) == 0 {
+        return None;
+    }
+    let _sg = StackGuard::new(state);
+
+    let mut ar: ffi::lua_Debug = std::mem::zeroed();
+    #[cfg(not(feature = "luau"))]
+    let found =
+        ffi::lua_getstack(state, 0, &mut ar) != 0 && ffi::lua_getinfo(state, cstr!("nf"), &mut ar) != 0;
+    #[cfg(feature = "luau")]
+    let found = ffi::lua_getinfo(state, 0, cstr!("nf"), &mut ar) != 0;
+    if !found {
+        return None;
+    }
+    if let Some(name) = ptr_to_lossy_str(ar.name) {
+        return Some(name.into_owned());
+    }
+
+    ffi::lua_pushglobaltable(state);
+    ffi::lua_pushnil(state);
+    while ffi::lua_next(state, -2) != 0 {
+        if ffi::lua_type(state, -2) == ffi::LUA_TSTRING && ffi::lua_rawequal(state, -1, -4) != 0 {
+            return ptr_to_lossy_str(ffi::lua_tostring(state, -2)).map(|s| s.into_owned());
+        }
+        ffi::lua_pop(state, 1);
+    }
+    None
+}
+
+// Collects the call stack of `thread`, starting from `level`
+pub(crate) unsafe fn capture_traceback(thread: *mut ffi::lua_State, mut level: c_int) -> Traceback {
+    let mut frames = Vec::new();
+    let mut ar: ffi::lua_Debug = std::mem::zeroed();
+    loop {
+        #[cfg(not(feature = "luau"))]
+        let found = ffi::lua_getstack(thread, level, &mut ar) != 0
+            && ffi::lua_getinfo(thread, cstr!("Sln"), &mut ar) != 0;
+        #[cfg(feature = "luau")]
+        let found = ffi::lua_getinfo(thread, level, cstr!("sln"), &mut ar) != 0;
+        if !found {
+            break;
+        }
+
+        #[cfg(not(feature = "luau"))]
+        let short_src = ar.short_src.as_ptr();
+        #[cfg(feature = "luau")]
+        let short_src = ar.short_src;
+        let is_native = ptr_to_str(ar.what) == Some("C");
+        frames.push(TracebackFrame {
+            source: (!is_native)
+                .then(|| ptr_to_lossy_str(short_src).map(|s| s.into_owned()))
+                .flatten(),
+            line: (!is_native)
+                .then(|| linenumber_to_usize(ar.currentline))
+                .flatten(),
+            name: ptr_to_lossy_str(ar.name).map(|s| s.into_owned()),
+            is_native,
+        });
+        level += 1;
+    }
+
+    // Translate locations of chunks with source maps
+    if ffi::lua_checkstack(thread, 1) != 0 {
+        for frame in &mut frames {
+            if let (Some(source), Some(line)) = (frame.source.take(), frame.line) {
+                let (source, line) = map_location(thread, source, line);
+                (frame.source, frame.line) = (Some(source), Some(line));
+            }
+        }
+    }
+    Traceback::new(frames)
+}
+
+// Key to store thread names in the registry (in a weak table)
+pub(crate) static THREAD_NAMES_KEY: u8 = 0;
+
+// Pushes a traceback of `thread` (like `luaL_traceback`) starting from `level`, mentioning the
+// thread name if it was set
+pub(crate) unsafe fn traceback(
+    state: *mut ffi::lua_State,
+    thread: *mut ffi::lua_State,
+    msg: *const c_char,
+    level: c_int,
+) {
+    if ffi::lua_checkstack(state, 3) == 0 || !push_thread_name(state, thread) {
+        ffi::luaL_traceback(state, thread, msg, level);
+        return;
+    }
+    let name = ffi::lua_tostring(state, -1);
+    if msg.is_null() {
+        ffi::lua_pushfstring(state, cstr!("in thread '%s'"), name);
+    } else {
+        ffi::lua_pushfstring(state, cstr!("%s\nin thread '%s'"), msg, name);
+    }
+    ffi::luaL_traceback(state, thread, ffi::lua_tostring(state, -1), level);
+    ffi::lua_replace(state, -3);
+    ffi::lua_pop(state, 1);
+}
+
+// Pushes the name of `thread` set by `Thread::set_name` and returns `true`, or pushes nothing and
+// returns `false` if the thread has no name.
+// Uses 3 stack spaces, does not call checkstack.
+pub(crate) unsafe fn push_thread_name(state: *mut ffi::lua_State, thread: *mut ffi::lua_State) -> bool {
+    let key = &THREAD_NAMES_KEY as *const u8 as *const c_void;
+    if ffi::lua_rawgetp(state, ffi::LUA_REGISTRYINDEX, key) != ffi::LUA_TTABLE {
+        ffi::lua_pop(state, 1);
+        return false;
+    }
+    if thread == state {
+        ffi::lua_pushthread(state);
+    } else if ffi::lua_checkstack(thread, 1) != 0 {
+        ffi::lua_pushthread(thread);
+        ffi::lua_xmove(thread, state, 1);
+    } else {
+        ffi::lua_pop(state, 1);
+        return false;
+    }
+    if ffi::lua_rawget(state, -2) != ffi::LUA_TSTRING {
+        ffi::lua_pop(state, 2);
+        return false;
     }
+    ffi::lua_remove(state, -2);
+    true
 }
 
 // Initialize the error, panic, and destructed userdata metatables.
@@ -312,26 +702,20 @@ pub(crate) unsafe fn init_error_registry(state: *mut ffi::lua_State) -> Result<(
                     // Depending on how the API is used and what error types scripts are given, it may
                     // be possible to make this consume arbitrary amounts of memory (for example, some
                     // kind of recursive error structure?)
-                    let _ = write!(&mut (*err_buf), "{error}");
+                    format_error(state, error, &mut *err_buf);
                     Ok(err_buf)
                 }
-                Some(WrappedFailure::Panic(Some(panic))) => {
+                Some(WrappedFailure::Panic(Some(_), details)) => {
                     let err_buf_key = &ERROR_PRINT_BUFFER_KEY as *const u8 as *const c_void;
                     ffi::lua_rawgetp(state, ffi::LUA_REGISTRYINDEX, err_buf_key);
                     let err_buf = ffi::lua_touserdata(state, -1) as *mut String;
                     (*err_buf).clear();
                     ffi::lua_pop(state, 2);
 
-                    if let Some(msg) = panic.downcast_ref::<&str>() {
-                        let _ = write!(&mut (*err_buf), "{msg}");
-                    } else if let Some(msg) = panic.downcast_ref::<String>() {
-                        let _ = write!(&mut (*err_buf), "{msg}");
-                    } else {
-                        let _ = write!(&mut (*err_buf), "<panic>");
-                    };
+                    let _ = write!(&mut (*err_buf), "{}", details.message);
                     Ok(err_buf)
                 }
-                Some(WrappedFailure::Panic(None)) => Err(Error::PreviouslyResumedPanic),
+                Some(WrappedFailure::Panic(None, _)) => Err(Error::PreviouslyResumedPanic),
                 _ => {
                     // I'm not sure whether this is possible to trigger without bugs in mlua?
                     Err(Error::UserDataTypeMismatch)
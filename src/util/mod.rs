@@ -6,8 +6,9 @@ use std::{ptr, slice, str};
 use crate::error::{Error, Result};
 
 pub(crate) use error::{
-    error_traceback, error_traceback_thread, init_error_registry, pop_error, protect_lua_call,
-    protect_lua_closure, WrappedFailure,
+    capture_traceback, error_traceback, error_traceback_thread, init_error_registry, pop_error,
+    protect_lua_call, protect_lua_closure, push_thread_name, traceback, with_callee_name, WrappedFailure,
+    THREAD_NAMES_KEY,
 };
 pub(crate) use short_names::short_type_name;
 pub(crate) use types::TypeKey;
@@ -165,7 +166,7 @@ pub(crate) unsafe extern "C-unwind" fn safe_pcall(state: *mut ffi::lua_State) ->
         ffi::lua_gettop(state)
     } else {
         let wf_ud = get_internal_userdata::<WrappedFailure>(state, -1, ptr::null());
-        if let Some(WrappedFailure::Panic(_)) = wf_ud.as_ref() {
+        if let Some(WrappedFailure::Panic(..)) = wf_ud.as_ref() {
             ffi::lua_error(state);
         }
         ffi::lua_pushboolean(state, 0);
@@ -180,7 +181,7 @@ pub(crate) unsafe extern "C-unwind" fn safe_xpcall(state: *mut ffi::lua_State) -
         ffi::luaL_checkstack(state, 2, ptr::null());
 
         let wf_ud = get_internal_userdata::<WrappedFailure>(state, -1, ptr::null());
-        if let Some(WrappedFailure::Panic(_)) = wf_ud.as_ref() {
+        if let Some(WrappedFailure::Panic(..)) = wf_ud.as_ref() {
             1
         } else {
             ffi::lua_pushvalue(state, ffi::lua_upvalueindex(1));
@@ -209,7 +210,7 @@ pub(crate) unsafe extern "C-unwind" fn safe_xpcall(state: *mut ffi::lua_State) -
         ffi::lua_gettop(state) - 1
     } else {
         let wf_ud = get_internal_userdata::<WrappedFailure>(state, -1, ptr::null());
-        if let Some(WrappedFailure::Panic(_)) = wf_ud.as_ref() {
+        if let Some(WrappedFailure::Panic(..)) = wf_ud.as_ref() {
             ffi::lua_error(state);
         }
         ffi::lua_pushboolean(state, 0);
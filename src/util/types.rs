@@ -43,6 +43,14 @@ impl TypeKey for ContinuationUpvalue {
     }
 }
 
+impl TypeKey for crate::state::UserDataTracker {
+    #[inline(always)]
+    fn type_key() -> *const c_void {
+        static USERDATA_TRACKER_TYPE_KEY: u8 = 0;
+        &USERDATA_TRACKER_TYPE_KEY as *const u8 as *const c_void
+    }
+}
+
 #[cfg(not(feature = "luau"))]
 impl TypeKey for crate::types::HookCallback {
     #[inline(always)]
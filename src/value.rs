@@ -8,10 +8,11 @@ use num_traits::FromPrimitive;
 
 use crate::error::{Error, Result};
 use crate::function::Function;
+use crate::state::Lua;
 use crate::string::{BorrowedStr, String};
 use crate::table::Table;
 use crate::thread::Thread;
-use crate::types::{Integer, LightUserData, Number, ValueRef};
+use crate::types::{Integer, LightUserData, Number, RegistryKey, ValueRef};
 use crate::userdata::AnyUserData;
 use crate::util::{check_stack, StackGuard};
 
@@ -28,6 +29,10 @@ use {
 /// The non-primitive variants (eg. string/table/function/thread/userdata) contain handle types
 /// into the internal Lua state. It is a logic error to mix handle types between separate
 /// `Lua` instances, and doing so will result in a panic.
+///
+/// Handles keep the referenced object alive until they are dropped. Cloning a value clones the
+/// handle, not the object: both values refer to the same table, function, etc. Use
+/// [`Value::detach`] to store a value outside of the Lua state as a [`OwnedValue`].
 #[derive(Clone)]
 pub enum Value {
     /// The Lua value `nil`.
@@ -462,6 +467,31 @@ impl Value {
         self.as_buffer().is_some()
     }
 
+    /// Returns `true` if the value is a [`Vector`].
+    ///
+    /// [`Vector`]: crate::Vector
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    #[inline]
+    pub fn is_vector(&self) -> bool {
+        self.as_vector().is_some()
+    }
+
+    /// Cast the value to a [`Vector`].
+    ///
+    /// If the value is a [`Vector`], returns it or `None` otherwise.
+    ///
+    /// [`Vector`]: crate::Vector
+    #[cfg(any(feature = "luau", doc))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
+    #[inline]
+    pub fn as_vector(&self) -> Option<crate::Vector> {
+        match *self {
+            Value::Vector(v) => Some(v),
+            _ => None,
+        }
+    }
+
     /// Returns `true` if the value is an [`Error`].
     #[inline]
     pub fn is_error(&self) -> bool {
@@ -478,6 +508,38 @@ impl Value {
         }
     }
 
+    /// Detaches this value from the handles of the Lua state, storing it in the Lua registry.
+    ///
+    /// The returned [`OwnedValue`] is `Send` and `Sync` and can be kept in Rust structures
+    /// independently of the lifetime of any handle. Use [`OwnedValue::attach`] to get the value
+    /// back.
+    ///
+    /// The object is pinned (cannot be collected) until the [`OwnedValue`] is dropped. Values
+    /// referencing each other through Rust (eg. a Lua function holding a Rust closure which owns
+    /// the detached function) are never collected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use mlua::{Lua, OwnedValue, Result, Value};
+    /// # fn main() -> Result<()> {
+    /// let lua = Lua::new();
+    /// struct Handler {
+    ///     callback: OwnedValue,
+    /// }
+    ///
+    /// let func: Value = lua.load("function(x) return x * 2 end").eval()?;
+    /// let handler = Handler { callback: func.detach(&lua)? };
+    ///
+    /// let callback = handler.callback.attach(&lua)?;
+    /// assert_eq!(callback.as_function().unwrap().call::<i32>(21)?, 42);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn detach(&self, lua: &Lua) -> Result<OwnedValue> {
+        Ok(OwnedValue(lua.create_registry_value(self)?))
+    }
+
     /// Wrap reference to this Value into [`SerializableValue`].
     ///
     /// This allows customizing serialization behavior using serde.
@@ -629,6 +691,27 @@ impl PartialEq for Value {
     }
 }
 
+/// A Lua value detached from the Lua state handles, created by [`Value::detach`].
+///
+/// It's stored in the Lua registry, see [`RegistryKey`] for details about the registry.
+/// The referenced object is not collected until this value is dropped.
+#[derive(Debug)]
+pub struct OwnedValue(RegistryKey);
+
+impl OwnedValue {
+    /// Attaches the value back to the given Lua state.
+    ///
+    /// Returns [`Error::MismatchedRegistryKey`] if the value was detached from another Lua state.
+    pub fn attach(&self, lua: &Lua) -> Result<Value> {
+        lua.registry_value(&self.0)
+    }
+
+    /// Consumes the value and returns the underlying registry key.
+    pub fn into_registry_key(self) -> RegistryKey {
+        self.0
+    }
+}
+
 /// A wrapped [`Value`] with customized serialization behavior.
 #[cfg(feature = "serde")]
 #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
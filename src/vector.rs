@@ -7,6 +7,12 @@ use serde::ser::{Serialize, SerializeTupleStruct, Serializer};
 ///
 /// By default vectors are 3-dimensional, but can be 4-dimensional
 /// if the `luau-vector4` feature is enabled.
+///
+/// Conversions to and from [glam] and [nalgebra] vector types are available with the `glam` and
+/// `nalgebra` features respectively.
+///
+/// [glam]: https://docs.rs/glam
+/// [nalgebra]: https://docs.rs/nalgebra
 #[cfg_attr(docsrs, doc(cfg(feature = "luau")))]
 #[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd)]
 pub struct Vector(pub(crate) [f32; Self::SIZE]);
@@ -78,6 +84,78 @@ impl Serialize for Vector {
     }
 }
 
+#[cfg(all(feature = "glam", not(feature = "luau-vector4")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "glam")))]
+impl From<glam::Vec3> for Vector {
+    #[inline]
+    fn from(v: glam::Vec3) -> Self {
+        Self(v.to_array())
+    }
+}
+
+#[cfg(all(feature = "glam", not(feature = "luau-vector4")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "glam")))]
+impl From<Vector> for glam::Vec3 {
+    #[inline]
+    fn from(v: Vector) -> Self {
+        glam::Vec3::from_array(v.0)
+    }
+}
+
+#[cfg(all(feature = "glam", feature = "luau-vector4"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "glam", feature = "luau-vector4"))))]
+impl From<glam::Vec4> for Vector {
+    #[inline]
+    fn from(v: glam::Vec4) -> Self {
+        Self(v.to_array())
+    }
+}
+
+#[cfg(all(feature = "glam", feature = "luau-vector4"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "glam", feature = "luau-vector4"))))]
+impl From<Vector> for glam::Vec4 {
+    #[inline]
+    fn from(v: Vector) -> Self {
+        glam::Vec4::from_array(v.0)
+    }
+}
+
+#[cfg(all(feature = "nalgebra", not(feature = "luau-vector4")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "nalgebra")))]
+impl From<nalgebra::Vector3<f32>> for Vector {
+    #[inline]
+    fn from(v: nalgebra::Vector3<f32>) -> Self {
+        Self(v.into())
+    }
+}
+
+#[cfg(all(feature = "nalgebra", not(feature = "luau-vector4")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "nalgebra")))]
+impl From<Vector> for nalgebra::Vector3<f32> {
+    #[inline]
+    fn from(v: Vector) -> Self {
+        nalgebra::Vector3::from(v.0)
+    }
+}
+
+#[cfg(all(feature = "nalgebra", feature = "luau-vector4"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "nalgebra", feature = "luau-vector4"))))]
+impl From<nalgebra::Vector4<f32>> for Vector {
+    #[inline]
+    fn from(v: nalgebra::Vector4<f32>) -> Self {
+        Self(v.into())
+    }
+}
+
+#[cfg(all(feature = "nalgebra", feature = "luau-vector4"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "nalgebra", feature = "luau-vector4"))))]
+impl From<Vector> for nalgebra::Vector4<f32> {
+    #[inline]
+    fn from(v: Vector) -> Self {
+        nalgebra::Vector4::from(v.0)
+    }
+}
+
 impl PartialEq<[f32; Self::SIZE]> for Vector {
     #[inline]
     fn eq(&self, other: &[f32; Self::SIZE]) -> bool {
@@ -0,0 +1,313 @@
+//! Virtual filesystem support for the Lua `io` library.
+
+use std::io::{Error as IoError, Result as IoResult};
+use std::string::String as StdString;
+
+use crate::error::{Error, Result};
+use crate::multi::{MultiValue, Variadic};
+use crate::state::Lua;
+use crate::string::String as LuaString;
+use crate::traits::IntoLuaMulti;
+use crate::types::MaybeSend;
+use crate::userdata::{AnyUserData, MetaMethod, UserData, UserDataMethods, UserDataRefMut};
+use crate::value::{Nil, Value};
+
+// Size of the read buffer
+const READ_CHUNK_SIZE: usize = 4096;
+
+/// A virtual filesystem backing the Lua `io` library.
+///
+/// See [`Lua::set_io_handler`] for details.
+pub trait Vfs: MaybeSend + 'static {
+    /// Opens a file at `path` using the C `fopen`-style `mode` (eg. `"r"`, `"w"`, `"a+"`).
+    ///
+    /// The mode is validated before this method is called.
+    fn open(&self, path: &str, mode: &str) -> IoResult<Box<dyn VfsFile>>;
+}
+
+/// A file opened by a [`Vfs`].
+pub trait VfsFile: MaybeSend + 'static {
+    /// Reads some bytes into `buf`, returning the number of bytes read (`0` at end of file).
+    fn read(&mut self, buf: &mut [u8]) -> IoResult<usize>;
+
+    /// Writes all bytes from `buf` to the file.
+    fn write(&mut self, buf: &[u8]) -> IoResult<()>;
+
+    /// Flushes buffered data.
+    fn flush(&mut self) -> IoResult<()> {
+        Ok(())
+    }
+
+    /// Closes the file.
+    ///
+    /// The file is dropped afterwards (also when this method is not called explicitly).
+    fn close(&mut self) -> IoResult<()> {
+        self.flush()
+    }
+}
+
+/// Replaces the global `io` table with functions backed by `vfs`.
+pub(crate) fn install(lua: &Lua, vfs: impl Vfs) -> Result<()> {
+    let io = lua.create_table()?;
+
+    let open = lua.create_function(move |lua, (path, mode): (LuaString, Option<LuaString>)| {
+        let path = path.to_str()?;
+        let mode = match &mode {
+            Some(mode) => mode.to_str()?.to_string(),
+            None => "r".to_string(),
+        };
+        if !is_valid_mode(&mode) {
+            return Err(Error::runtime(format!(
+                "bad argument #2 to 'open' (invalid mode '{mode}')"
+            )));
+        }
+        match vfs.open(&path, &mode) {
+            Ok(file) => lua.create_userdata(FileHandle::new(file))?.into_lua_multi(lua),
+            Err(err) => io_error(lua, Some(&path), err),
+        }
+    })?;
+    io.raw_set("open", &open)?;
+
+    io.raw_set(
+        "lines",
+        lua.create_function(move |lua, (path, formats): (LuaString, Variadic<Value>)| {
+            match open.call::<(Value, Option<StdString>)>((path, "r"))? {
+                (Value::UserData(file), _) => lines_iterator(lua, file, formats, true),
+                (_, message) => Err(Error::runtime(message.unwrap_or_default())),
+            }
+        })?,
+    )?;
+
+    io.raw_set(
+        "close",
+        lua.create_function(|lua, mut file: UserDataRefMut<FileHandle>| file.close(lua))?,
+    )?;
+
+    io.raw_set(
+        "type",
+        lua.create_function(|_, value: Value| match value {
+            Value::UserData(ud) => match ud.borrow::<FileHandle>() {
+                Ok(file) if file.file.is_some() => Ok(Some("file")),
+                Ok(_) => Ok(Some("closed file")),
+                Err(_) => Ok(None),
+            },
+            _ => Ok(None),
+        })?,
+    )?;
+
+    Lua::raw_set_global(&lua.globals(), "io", io)
+}
+
+fn is_valid_mode(mode: &str) -> bool {
+    let mode = mode.strip_suffix('b').unwrap_or(mode);
+    matches!(mode, "r" | "w" | "a" | "r+" | "w+" | "a+")
+}
+
+// Returns `nil, message, errno` like the Lua `io` functions do on failure.
+fn io_error(lua: &Lua, path: Option<&str>, err: IoError) -> Result<MultiValue> {
+    let message = match path {
+        Some(path) => format!("{path}: {err}"),
+        None => err.to_string(),
+    };
+    (Nil, message, err.raw_os_error().unwrap_or(0)).into_lua_multi(lua)
+}
+
+// Creates an iterator function reading from `file` using `formats`.
+fn lines_iterator(lua: &Lua, file: AnyUserData, formats: Variadic<Value>, close: bool) -> Result<MultiValue> {
+    let formats = formats.into_iter().collect::<Vec<_>>();
+    let iter = lua.create_function(move |lua, ()| {
+        let mut handle = file.borrow_mut::<FileHandle>()?;
+        let results = handle.read(lua, &formats)?;
+        if let Some(Value::Nil) | None = results.front() {
+            if close {
+                handle.close(lua)?;
+            }
+        }
+        Ok(results)
+    })?;
+    iter.into_lua_multi(lua)
+}
+
+enum ReadFormat {
+    All,
+    Line { keep_newline: bool },
+    Count(usize),
+}
+
+impl ReadFormat {
+    fn parse(value: &Value) -> Result<Self> {
+        match value {
+            Value::Integer(n) => Ok(ReadFormat::Count((*n).max(0) as usize)),
+            Value::Number(n) => Ok(ReadFormat::Count(n.max(0.0) as usize)),
+            Value::String(s) => {
+                let s = s.as_bytes();
+                let s: &[u8] = &s;
+                match s.strip_prefix(b"*").unwrap_or(s).first() {
+                    Some(b'a') => Ok(ReadFormat::All),
+                    Some(b'l') => Ok(ReadFormat::Line { keep_newline: false }),
+                    Some(b'L') => Ok(ReadFormat::Line { keep_newline: true }),
+                    _ => Err(Error::runtime("bad argument to 'read' (invalid format)")),
+                }
+            }
+            _ => Err(Error::runtime("bad argument to 'read' (invalid format)")),
+        }
+    }
+}
+
+struct FileHandle {
+    file: Option<Box<dyn VfsFile>>,
+    buffer: Vec<u8>,
+    pos: usize,
+}
+
+impl FileHandle {
+    fn new(file: Box<dyn VfsFile>) -> Self {
+        FileHandle {
+            file: Some(file),
+            buffer: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    fn file(&mut self) -> Result<&mut Box<dyn VfsFile>> {
+        self.file
+            .as_mut()
+            .ok_or_else(|| Error::runtime("attempt to use a closed file"))
+    }
+
+    // Makes sure the buffer has unread data, returns `false` at end of file.
+    fn fill_buffer(&mut self) -> IoResult<bool> {
+        if self.pos < self.buffer.len() {
+            return Ok(true);
+        }
+        let Some(file) = self.file.as_mut() else {
+            return Ok(false);
+        };
+        self.buffer.resize(READ_CHUNK_SIZE, 0);
+        self.pos = 0;
+        let result = file.read(&mut self.buffer);
+        self.buffer.truncate(*result.as_ref().unwrap_or(&0));
+        result.map(|n| n > 0)
+    }
+
+    fn read_format(&mut self, format: &ReadFormat) -> IoResult<Option<Vec<u8>>> {
+        let mut data = Vec::new();
+        match *format {
+            ReadFormat::All => {
+                while self.fill_buffer()? {
+                    data.extend_from_slice(&self.buffer[self.pos..]);
+                    self.pos = self.buffer.len();
+                }
+                Ok(Some(data))
+            }
+            ReadFormat::Line { keep_newline } => {
+                while self.fill_buffer()? {
+                    let unread = &self.buffer[self.pos..];
+                    if let Some(i) = unread.iter().position(|&b| b == b'\n') {
+                        data.extend_from_slice(&unread[..i + keep_newline as usize]);
+                        self.pos += i + 1;
+                        return Ok(Some(data));
+                    }
+                    data.extend_from_slice(unread);
+                    self.pos = self.buffer.len();
+                }
+                Ok((!data.is_empty()).then_some(data))
+            }
+            // Test for end of file
+            ReadFormat::Count(0) => Ok(self.fill_buffer()?.then_some(data)),
+            ReadFormat::Count(count) => {
+                while data.len() < count && self.fill_buffer()? {
+                    let unread = &self.buffer[self.pos..];
+                    let n = unread.len().min(count - data.len());
+                    data.extend_from_slice(&unread[..n]);
+                    self.pos += n;
+                }
+                Ok((!data.is_empty()).then_some(data))
+            }
+        }
+    }
+
+    fn read(&mut self, lua: &Lua, formats: &[Value]) -> Result<MultiValue> {
+        self.file()?;
+        let formats = match formats {
+            [] => vec![ReadFormat::Line { keep_newline: false }],
+            formats => formats.iter().map(ReadFormat::parse).collect::<Result<_>>()?,
+        };
+        let mut results = MultiValue::with_capacity(formats.len());
+        for format in &formats {
+            match self.read_format(format) {
+                Ok(Some(data)) => results.push_back(Value::String(lua.create_string(data)?)),
+                Ok(None) => {
+                    // Stop reading at end of file
+                    results.push_back(Nil);
+                    break;
+                }
+                Err(err) => return io_error(lua, None, err),
+            }
+        }
+        Ok(results)
+    }
+
+    fn write(&mut self, lua: &Lua, args: Variadic<Value>) -> Result<Option<MultiValue>> {
+        let file = self.file()?;
+        for arg in args {
+            let result = match lua.coerce_string(arg)? {
+                Some(s) => file.write(&s.as_bytes()),
+                None => return Err(Error::runtime("bad argument to 'write' (string expected)")),
+            };
+            if let Err(err) = result {
+                return io_error(lua, None, err).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    fn close(&mut self, lua: &Lua) -> Result<MultiValue> {
+        let mut file = self
+            .file
+            .take()
+            .ok_or_else(|| Error::runtime("attempt to use a closed file"))?;
+        match file.close() {
+            Ok(()) => true.into_lua_multi(lua),
+            Err(err) => io_error(lua, None, err),
+        }
+    }
+}
+
+impl UserData for FileHandle {
+    fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method_mut("read", |lua, this, formats: Variadic<Value>| {
+            this.read(lua, &formats)
+        });
+
+        methods.add_function(
+            "write",
+            |lua, (ud, args): (AnyUserData, Variadic<Value>)| match ud
+                .borrow_mut::<Self>()?
+                .write(lua, args)?
+            {
+                Some(err) => Ok(err),
+                None => ud.clone().into_lua_multi(lua),
+            },
+        );
+
+        methods.add_function("lines", |lua, (ud, formats): (AnyUserData, Variadic<Value>)| {
+            ud.borrow_mut::<Self>()?.file()?;
+            lines_iterator(lua, ud, formats, false)
+        });
+
+        methods.add_method_mut("flush", |lua, this, ()| match this.file()?.flush() {
+            Ok(()) => true.into_lua_multi(lua),
+            Err(err) => io_error(lua, None, err),
+        });
+
+        methods.add_method_mut("close", |lua, this, ()| this.close(lua));
+
+        methods.add_meta_method(MetaMethod::ToString, |_, this, ()| {
+            Ok(match this.file {
+                Some(ref file) => format!("file ({:p})", &**file as *const dyn VfsFile as *const ()),
+                None => "file (closed)".to_string(),
+            })
+        });
+    }
+}
@@ -1,6 +1,6 @@
 use std::{fs, io};
 
-use mlua::{Chunk, ChunkMode, Lua, Result};
+use mlua::{Chunk, ChunkMode, Error, Lua, LuaOptions, Result, SourceMap, StdLib};
 
 #[test]
 fn test_chunk_methods() -> Result<()> {
@@ -165,6 +165,112 @@ fn test_compiler_library_constants() {
     assert_eq!(const_str.unwrap(), "value1");
 }
 
+#[cfg(feature = "luau")]
+#[test]
+fn test_load_compiled() -> Result<()> {
+    use mlua::{CompiledChunk, Compiler};
+
+    let chunk = Compiler::new().compile_chunk("=rules", "local x = ... return x * 2, debug.info(1, 's')")?;
+    assert_eq!(chunk.name(), "=rules");
+    assert_eq!(chunk.mode(), ChunkMode::Binary);
+
+    // The same chunk is loaded into two independent instances
+    for lua in [Lua::new(), Lua::new()] {
+        let (n, source) = lua.load_compiled(&chunk)?.call::<(i64, String)>(21)?;
+        assert_eq!(n, 42);
+        assert_eq!(source, "rules");
+    }
+
+    // Incompatible bytecode version
+    let mut bytecode = chunk.bytecode().to_vec();
+    bytecode[0] = u8::MAX;
+    let lua = Lua::new();
+    let err = lua
+        .load_compiled(&CompiledChunk::from_bytecode("=bad", bytecode))
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("compiled chunk '=bad' has bytecode version 255"),
+        "{err}"
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "luau")]
+#[test]
+fn test_compile_all() -> Result<()> {
+    let lua = Lua::new();
+
+    let results = lua.compile_all(&[
+        ("=first", "return 1"),
+        ("=second", "local x = 1\nreturn x +"),
+        ("@scripts/third.luau", "return 3"),
+        ("return (", "return ("),
+    ]);
+    assert_eq!(results.len(), 4);
+
+    // Valid chunks are compiled regardless of errors in other chunks
+    assert_eq!(
+        lua.load_compiled(results[0].as_ref().unwrap())?.call::<i32>(())?,
+        1
+    );
+    assert_eq!(
+        lua.load_compiled(results[2].as_ref().unwrap())?.call::<i32>(())?,
+        3
+    );
+    assert_eq!(results[2].as_ref().unwrap().name(), "@scripts/third.luau");
+
+    match &results[1] {
+        Err(Error::SyntaxError {
+            message,
+            incomplete_input,
+        }) => {
+            assert!(message.starts_with("second:2: "), "{message}");
+            assert!(*incomplete_input);
+        }
+        r => panic!("expected syntax error, got {r:?}"),
+    }
+    match &results[3] {
+        Err(Error::SyntaxError { message, .. }) => {
+            assert!(message.starts_with("[string \"return (\"]:1: "), "{message}")
+        }
+        r => panic!("expected syntax error, got {r:?}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_eval_expr() -> Result<()> {
+    let lua = Lua::new();
+
+    let env = lua.create_table_from([("a", 4), ("b", 7)])?;
+    assert!(lua.eval_expr::<bool>("a + b > 10", env.clone())?);
+    assert_eq!(lua.eval_expr::<i64>("a * b -- comment", env.clone())?, 28);
+    // Globals are not visible
+    assert_eq!(lua.eval_expr::<Option<String>>("_VERSION", env.clone())?, None);
+
+    // Syntax errors refer to the original expression
+    match lua.eval_expr::<bool>("a +", env.clone()) {
+        Err(Error::SyntaxError { message, .. }) => {
+            assert!(message.starts_with("expression:1:"), "{message}");
+            assert!(!message.contains("return"), "{message}");
+        }
+        res => panic!("expected syntax error, got {res:?}"),
+    }
+    // Statements are rejected
+    let res = lua.eval_expr::<i64>("1; x = 2", env.clone());
+    assert!(matches!(res, Err(Error::SyntaxError { .. })));
+    assert_eq!(env.get::<Option<i64>>("x")?, None);
+
+    // Runtime errors
+    let err = lua.eval_expr::<i64>("a + c", env).unwrap_err();
+    assert!(err.to_string().contains("expression:1:"), "{err}");
+
+    Ok(())
+}
+
 #[test]
 fn test_chunk_wrap() -> Result<()> {
     let lua = Lua::new();
@@ -181,3 +287,99 @@ fn test_chunk_wrap() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_chunk_eval_tuple() -> Result<()> {
+    let lua = Lua::new();
+
+    let (a, b, c) = lua
+        .load("return 1, 'x', true")
+        .eval_tuple::<(i64, String, bool)>()?;
+    assert_eq!((a, b.as_str(), c), (1, "x", true));
+    lua.load("local x = 1").eval_tuple::<()>()?;
+
+    // Wrong number of values
+    match lua.load("return 1, 2").eval_tuple::<(i64, i64, i64)>() {
+        Err(Error::FromLuaConversionError { message, .. }) => {
+            assert_eq!(message.unwrap(), "expected 3 values, got 2");
+        }
+        r => panic!("expected FromLuaConversionError, got {r:?}"),
+    }
+
+    // Wrong type
+    match lua.load("return 1, {}, 'x'").eval_tuple::<(i64, i64, String)>() {
+        Err(err @ Error::WithContext { .. }) => {
+            assert!(
+                err.to_string().starts_with("bad value #2 returned from chunk"),
+                "{err}"
+            );
+        }
+        r => panic!("expected WithContext error, got {r:?}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_chunk_source_map() -> Result<()> {
+    let lua = Lua::new();
+
+    let map = SourceMap::new().add_line(1, 10).add_file_line(3, "main.src", 20);
+    assert_eq!(map.lookup(2), Some((None, 10)));
+    assert_eq!(map.lookup(4), Some((Some("main.src"), 20)));
+
+    // Runtime errors
+    let err = (lua.load("local x = 1\nlocal y = 2\nerror('boom')"))
+        .set_name("=generated")
+        .set_source_map(map.clone())
+        .exec()
+        .unwrap_err();
+    assert!(err.to_string().contains("main.src:20: boom"), "{err}");
+
+    // Functions defined in the chunk
+    let func: mlua::Function = (lua.load("return function()\n  error('inner')\nend"))
+        .set_name("=generated2")
+        .set_source_map(SourceMap::new().add_line(2, 7))
+        .eval()?;
+    let err = func.call::<()>(()).unwrap_err();
+    assert!(err.to_string().contains("generated2:7: inner"), "{err}");
+
+    // Syntax errors
+    let err = (lua.load("local x = 1\nlocal = 2"))
+        .set_name("=generated3")
+        .set_source_map(SourceMap::new().add_line(2, 5))
+        .exec()
+        .unwrap_err();
+    match err {
+        Error::SyntaxError { ref message, .. } => assert!(message.contains("generated3:5:"), "{message}"),
+        _ => panic!("expected SyntaxError, got {err:?}"),
+    }
+
+    // Structured traceback frames
+    let lua = Lua::new_with(StdLib::ALL_SAFE, LuaOptions::new().capture_tracebacks(true))?;
+    let chunk = "local x = 1\nlocal y = 2\nerror('boom')";
+    let err = (lua.load(chunk).set_name("=generated"))
+        .set_source_map(map.clone())
+        .exec()
+        .unwrap_err();
+    let traceback = err.traceback().expect("traceback is not captured");
+    let frame = traceback.frames().iter().find(|f| !f.is_native).unwrap();
+    assert_eq!(
+        (frame.source.as_deref(), frame.line),
+        (Some("main.src"), Some(20))
+    );
+
+    // Removing the source map
+    assert!(lua.remove_source_map("=generated"));
+    assert!(!lua.remove_source_map("=generated"));
+    let err = lua.load(chunk).set_name("=generated").exec().unwrap_err();
+    assert!(err.to_string().contains("generated:3: boom"), "{err}");
+    let traceback = err.traceback().expect("traceback is not captured");
+    let frame = traceback.frames().iter().find(|f| !f.is_native).unwrap();
+    assert_eq!(
+        (frame.source.as_deref(), frame.line),
+        (Some("generated"), Some(3))
+    );
+
+    Ok(())
+}
@@ -6,8 +6,8 @@ use std::path::PathBuf;
 use bstr::BString;
 use maplit::{btreemap, btreeset, hashmap, hashset};
 use mlua::{
-    AnyUserData, BorrowedBytes, BorrowedStr, Either, Error, Function, IntoLua, Lua, RegistryKey, Result,
-    Table, Thread, UserDataRef, Value,
+    AnyUserData, BorrowedBytes, BorrowedStr, Either, Error, Function, IntoLua, Lua, LuaOptions, RegistryKey,
+    Result, StdLib, Table, Thread, UserDataRef, Value,
 };
 
 #[test]
@@ -357,6 +357,47 @@ fn test_integer_from_lua() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_strict_number_coercion() -> Result<()> {
+    // Lax mode (default) truncates fractional numbers
+    let lua = Lua::new();
+    assert_eq!(lua.unpack::<i64>(Value::Number(3.5))?, 3);
+    assert_eq!(lua.load("3.5").eval::<i64>()?, 3);
+    let f = lua.create_function(|_, i: i64| Ok(i))?;
+    assert_eq!(f.call::<i64>(-3.5)?, -3);
+
+    let lua = Lua::new_with(StdLib::ALL_SAFE, LuaOptions::new().strict_number_coercion(true))?;
+
+    // Fractional numbers are rejected
+    match lua.unpack::<i64>(Value::Number(3.5)) {
+        Err(Error::FromLuaConversionError { from, to, message }) => {
+            assert_eq!(from, "number");
+            assert_eq!(to, "i64");
+            assert_eq!(message.unwrap(), "number has no integer representation");
+        }
+        r => panic!("expected Error::FromLuaConversionError, got {r:?}"),
+    }
+    assert!(lua.load("3.5").eval::<u32>().is_err());
+    assert!(lua.load("'3.5'").eval::<i32>().is_err());
+    assert!(lua.load("0/0").eval::<i64>().is_err());
+    let f = lua.create_function(|_, i: i64| Ok(i))?;
+    assert!(f.call::<i64>(-3.5).is_err());
+    assert!(f.call::<i64>(2f64.powi(51) + 0.5).is_err());
+
+    // Integral numbers are still accepted, including floats at the 2^53 boundary
+    assert_eq!(lua.unpack::<i64>(Value::Number(3.0))?, 3);
+    assert_eq!(lua.load("2^53").eval::<i64>()?, 1 << 53);
+    assert_eq!(lua.load("-2^53").eval::<i64>()?, -(1 << 53));
+    assert_eq!(f.call::<i64>(2f64.powi(53))?, 1 << 53);
+    assert_eq!(f.call::<i64>(2f64.powi(53) + 1.0)?, 1 << 53);
+    assert_eq!(lua.load("'42'").eval::<i32>()?, 42);
+
+    // Floats are not affected
+    assert_eq!(lua.load("3.5").eval::<f64>()?, 3.5);
+
+    Ok(())
+}
+
 #[test]
 fn test_float_from_lua() -> Result<()> {
     let lua = Lua::new();
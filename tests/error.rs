@@ -1,7 +1,7 @@
 use std::error::Error as _;
 use std::{fmt, io};
 
-use mlua::{Error, ErrorContext, Lua, Result};
+use mlua::{Error, ErrorContext, Lua, LuaOptions, Result, StdLib};
 
 #[test]
 fn test_error_context() -> Result<()> {
@@ -46,6 +46,292 @@ fn test_error_context() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_error_traceback() -> Result<()> {
+    let lua = Lua::new_with(StdLib::ALL_SAFE, LuaOptions::new().capture_tracebacks(true))?;
+
+    let call_back =
+        lua.create_function(|lua, ()| lua.globals().get::<mlua::Function>("c")?.call::<()>(()))?;
+    lua.globals().set("call_back", call_back)?;
+    lua.load(
+        r#"
+        function c()
+            error("boom")
+        end
+        function b()
+            call_back()
+        end
+        function a()
+            b()
+        end
+    "#,
+    )
+    .set_name("=chunk")
+    .exec()?;
+
+    let err = lua
+        .globals()
+        .get::<mlua::Function>("a")?
+        .call::<()>(())
+        .unwrap_err();
+    assert!(err.to_string().contains("boom"));
+    let traceback = err.traceback().expect("traceback is not captured");
+    let frames = traceback
+        .frames()
+        .iter()
+        .map(|f| (f.source.as_deref(), f.line, f.is_native))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        frames,
+        vec![
+            (None, None, true), // `error`
+            (Some("chunk"), Some(3), false),
+            (None, None, true), // `call_back`
+            (Some("chunk"), Some(6), false),
+            (Some("chunk"), Some(9), false),
+        ]
+    );
+    assert_eq!(traceback.frames()[0].name.as_deref(), Some("error"));
+    assert_eq!(traceback.frames()[3].name.as_deref(), Some("b"));
+
+    // Capturing is disabled by default
+    let lua = Lua::new();
+    let err = lua.load("error('boom')").exec().unwrap_err();
+    assert!(matches!(err, Error::RuntimeError(_)));
+    assert!(err.traceback().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_error_runtime_info() -> Result<()> {
+    let lua = Lua::new_with(StdLib::ALL_SAFE, LuaOptions::new().capture_tracebacks(true))?;
+
+    // Script error with position
+    let err = lua
+        .load("local x = 1\nerror('bad thing: x=' .. x)")
+        .set_name("chunk")
+        .exec()
+        .unwrap_err();
+    let info = err.runtime_info().unwrap();
+    assert_eq!(info.source.as_deref(), Some("[string \"chunk\"]"));
+    assert_eq!(info.line, Some(2));
+    assert_eq!(info.message, "bad thing: x=1");
+    // Display is unchanged
+    assert!(err
+        .to_string()
+        .starts_with("runtime error: [string \"chunk\"]:2: bad thing"));
+
+    // Message that looks like a position is not parsed
+    let err = lua.load("error('x:1: not a position', 0)").exec().unwrap_err();
+    let info = err.runtime_info().unwrap();
+    assert_eq!((info.source, info.line), (None, None));
+    assert_eq!(info.message, "x:1: not a position");
+
+    // Error raised with a table value has no position
+    let err = lua.load("error({})").exec().unwrap_err();
+    let info = err.runtime_info().unwrap();
+    assert_eq!((info.source, info.line), (None, None));
+    assert!(info.message.starts_with("table: "), "{}", info.message);
+
+    // Rust-raised error through a callback
+    let func = lua.create_function(|_, ()| Err::<(), _>(Error::runtime("disk is full")))?;
+    let err = func.call::<()>(()).unwrap_err();
+    let info = err.runtime_info().unwrap();
+    assert_eq!((info.source, info.line), (None, None));
+    assert_eq!(info.message, "disk is full");
+
+    // Not a runtime error
+    assert!(Error::UserDataTypeMismatch.runtime_info().is_none());
+
+    // Position is not captured without tracebacks
+    let lua = Lua::new();
+    let err = lua.load("error('bad thing')").exec().unwrap_err();
+    let info = err.runtime_info().unwrap();
+    assert_eq!((info.source, info.line), (None, None));
+    assert!(info.message.contains("bad thing"), "{}", info.message);
+
+    Ok(())
+}
+
+#[test]
+fn test_error_root_cause() -> Result<()> {
+    #[derive(Debug)]
+    struct MyError(u32);
+
+    impl fmt::Display for MyError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "my error {}", self.0)
+        }
+    }
+
+    impl std::error::Error for MyError {}
+
+    let lua = Lua::new();
+    let fail = lua.create_function(|_, ()| Err::<(), _>(Error::external(MyError(42))))?;
+    // Observes the error caught by Lua and raises it again with context
+    let observe = lua.create_function(|_, err: Error| Err::<(), _>(err.context("observed")))?;
+    lua.globals().set("fail", fail)?;
+    lua.globals().set("observe", observe)?;
+
+    let err = lua
+        .load(
+            r#"
+            local ok, err = pcall(fail)
+            assert(not ok)
+            local ok, err = pcall(observe, err)
+            assert(not ok)
+            error(err)
+        "#,
+        )
+        .exec()
+        .unwrap_err();
+
+    assert!(matches!(err, Error::CallbackError { .. }));
+    // Plain `downcast_ref` does not look through callback errors
+    assert!(err.downcast_ref::<MyError>().is_none());
+    assert_eq!(err.downcast_ref_chain::<MyError>().map(|e| e.0), Some(42));
+    assert_eq!(err.root_cause().to_string(), "my error 42");
+    assert!(err.root_cause().downcast_ref::<MyError>().is_some());
+
+    // Errors without a cause are their own root
+    let err = Error::runtime("oops");
+    assert_eq!(err.root_cause().to_string(), "runtime error: oops");
+    assert!(err.downcast_ref_chain::<MyError>().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_error_formatter() -> Result<()> {
+    let lua = Lua::new();
+
+    let fail = lua.create_function(|_, msg: String| Err::<(), _>(Error::runtime(msg)))?;
+    lua.globals().set("fail", fail)?;
+    lua.set_error_formatter(|err, w| {
+        let root = err.root_cause().to_string();
+        match root.strip_prefix("runtime error: ") {
+            Some("panic") => panic!("formatter panic"),
+            Some("fmt") => Err(fmt::Error),
+            Some(msg) => write!(w, "[script error] {msg}"),
+            None => write!(w, "[script error] {root}"),
+        }
+    });
+
+    let check = lua.load(
+        r#"
+        local msg = ...
+        local ok, err = pcall(fail, msg)
+        assert(not ok)
+        return tostring(err)
+    "#,
+    );
+    let check = check.into_function()?;
+    assert_eq!(
+        check.call::<String>("access denied")?,
+        "[script error] access denied"
+    );
+
+    // Failing formatter falls back to the default formatting
+    for msg in ["panic", "fmt"] {
+        let formatted = check.call::<String>(msg)?;
+        assert!(
+            formatted.starts_with(&format!("runtime error: {msg}\nstack traceback:")),
+            "{formatted}"
+        );
+    }
+    // Rust-side Display is not affected
+    let err = lua.load("fail('oops')").exec().unwrap_err();
+    assert!(!err.to_string().contains("[script error]"));
+
+    lua.remove_error_formatter();
+    assert!(check.call::<String>("again")?.starts_with("runtime error: again"));
+
+    Ok(())
+}
+
+#[test]
+fn test_vm_message_translator() -> Result<()> {
+    let lua = Lua::new();
+
+    lua.set_vm_message_translator(|msg| match msg {
+        "attempt to modify a readonly table" => Some("this setting cannot be changed".to_string()),
+        msg if msg.starts_with("attempt to perform arithmetic") => Some("numbers expected".to_string()),
+        msg if msg.starts_with("attempt to index") => panic!("translator panic"),
+        _ => None,
+    });
+
+    #[cfg(feature = "luau")]
+    {
+        let config = lua.create_table()?;
+        config.set_readonly(true);
+        lua.globals().set("config", config)?;
+        let err = lua
+            .load("config.debug = true")
+            .set_name("=config")
+            .exec()
+            .unwrap_err();
+        let err = err.to_string();
+        assert!(err.contains("config:1: this setting cannot be changed"), "{err}");
+        assert!(!err.contains("readonly"), "{err}");
+    }
+
+    let err = lua
+        .load("local x = {}\nreturn x + 1")
+        .set_name("=calc")
+        .exec()
+        .unwrap_err();
+    let err = err.to_string();
+    assert!(err.contains("calc:2: numbers expected"), "{err}");
+    // Traceback is kept
+    assert!(err.contains("stack traceback:"), "{err}");
+
+    // Messages raised by scripts are not translated
+    let err = lua
+        .load(r#"error("attempt to modify a readonly table")"#)
+        .set_name("=script")
+        .exec()
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("script:1: attempt to modify a readonly table"),
+        "{err}"
+    );
+
+    // ...even if the `error` function is called through another name
+    let err = lua
+        .load(r#"local raise = error; raise("attempt to modify a readonly table")"#)
+        .set_name("=script")
+        .exec()
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("script:1: attempt to modify a readonly table"),
+        "{err}"
+    );
+
+    // ...or raised by `assert`
+    let err = lua
+        .load(r#"assert(false, "attempt to perform arithmetic on a nil value")"#)
+        .exec()
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("attempt to perform arithmetic on a nil value"),
+        "{err}"
+    );
+
+    // Panicking translator leaves the message unchanged
+    let err = lua.load("local t = nil\nreturn t.x").exec().unwrap_err();
+    assert!(err.to_string().contains("attempt to index"), "{err}");
+
+    lua.remove_vm_message_translator();
+    let err = lua.load("return {} + 1").exec().unwrap_err();
+    assert!(err.to_string().contains("attempt to perform arithmetic"), "{err}");
+
+    Ok(())
+}
+
 #[test]
 fn test_error_chain() -> Result<()> {
     let lua = Lua::new();
@@ -99,3 +385,31 @@ fn test_error_anyhow() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(feature = "anyhow")]
+#[test]
+fn test_error_into_anyhow() -> Result<()> {
+    let lua = Lua::new();
+
+    let func = lua.create_function(|_, ()| -> Result<()> {
+        Err(Error::external(io::Error::other("disk is full")).context("saving file"))
+    })?;
+    let err = func.call::<()>(()).unwrap_err();
+    assert!(matches!(err, Error::CallbackError { .. }));
+
+    let err = err.into_anyhow();
+    let chain = err.chain().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert!(chain[0].starts_with("stack traceback:"), "{chain:?}");
+    assert_eq!(chain[1], "saving file");
+    assert_eq!(chain[2], "disk is full");
+    assert!(format!("{err:#}").contains("stack traceback:"));
+    // The root cause is kept
+    let root = err.root_cause().downcast_ref::<Error>().unwrap();
+    assert!(root.downcast_ref::<io::Error>().is_some());
+    assert!(matches!(
+        err.downcast_ref::<Error>(),
+        Some(Error::ExternalError(_))
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+#![cfg(feature = "luau")]
+
+// Feature flags are global for the process, so they are tested in a separate test binary
+// to not affect other tests running in parallel.
+
+use mlua::Lua;
+
+#[test]
+fn test_fflags() {
+    // We cannot really on any particular feature flag to be present
+    assert!(Lua::set_fflag("UnknownFlag", true).is_err());
+
+    let flags = Lua::fflags();
+    assert!(!flags.is_empty());
+    assert!(flags.windows(2).all(|w| w[0].0 < w[1].0));
+
+    // Use a flag that does not affect the VM
+    let flag = "LuauAutocompleteAttributes";
+    let get_flag = || {
+        Lua::fflags()
+            .into_iter()
+            .find(|(name, _)| name == flag)
+            .map(|(_, v)| v)
+    };
+    let default = get_flag().expect("flag not found");
+    Lua::set_fflag(flag, !default).unwrap();
+    assert_eq!(get_flag(), Some(!default));
+    Lua::reset_fflags();
+    assert_eq!(get_flag(), Some(default));
+
+    // Integer flags
+    assert!(Lua::set_fflag_int("UnknownFlag", 1).is_err());
+    assert!(Lua::fflag_int("UnknownFlag").is_err());
+    assert!(Lua::set_fflag_int(flag, 1).is_err());
+    let int_flag = "LuauParseErrorLimit";
+    let default = Lua::fflag_int(int_flag).unwrap();
+    Lua::set_fflag_int(int_flag, default + 1).unwrap();
+    assert_eq!(Lua::fflag_int(int_flag), Ok(default + 1));
+    Lua::reset_fflags();
+    assert_eq!(Lua::fflag_int(int_flag), Ok(default));
+
+    // Concurrent access
+    let threads = (0..4)
+        .map(|i| {
+            std::thread::spawn(move || {
+                for _ in 0..100 {
+                    Lua::set_fflag_int(int_flag, default + i).unwrap();
+                    let value = Lua::fflag_int(int_flag).unwrap();
+                    assert!((default..default + 4).contains(&value));
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    Lua::reset_fflags();
+    assert_eq!(Lua::fflag_int(int_flag), Ok(default));
+}
@@ -1,4 +1,6 @@
-use mlua::{Error, Function, Lua, Result, String, Table, Variadic};
+use std::string::String as StdString;
+
+use mlua::{Error, Function, Lua, Result, String, Table, UserData, UserDataRef, Value, Variadic};
 
 #[test]
 fn test_function_call() -> Result<()> {
@@ -12,6 +14,27 @@ fn test_function_call() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_function_call_multi() -> Result<()> {
+    let lua = Lua::new();
+
+    let unpack = lua.load(r#"function(...) return ... end"#).eval::<Function>()?;
+    let results = unpack.call_multi((1, "two", true))?;
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_i64(), Some(1));
+    assert_eq!(results[1].to_string()?, "two");
+    assert_eq!(results[2], Value::Boolean(true));
+    assert!(unpack.call_multi(())?.is_empty());
+
+    // Results can be forwarded to another function as is
+    let count = lua
+        .load("function(...) return select('#', ...) end")
+        .eval::<Function>()?;
+    assert_eq!(count.call::<usize>(unpack.call_multi((1, Value::Nil, 3))?)?, 3);
+
+    Ok(())
+}
+
 #[test]
 fn test_function_call_error() -> Result<()> {
     let lua = Lua::new();
@@ -27,6 +50,61 @@ fn test_function_call_error() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_call_path() -> Result<()> {
+    let lua = Lua::new();
+
+    lua.load(
+        r#"
+        module = {
+            events = setmetatable({}, {
+                __index = function(_, name)
+                    if name == "on_event" then
+                        return function(a, b) return a + b end
+                    end
+                end,
+            }),
+            handler = setmetatable({}, { __call = function(_, x) return x * 10 end }),
+            value = "text",
+        }
+        function top(x) return x end
+    "#,
+    )
+    .exec()?;
+
+    // `__index` metamethods are used
+    assert_eq!(lua.call_path::<i64>("module.events.on_event", (1, 2))?, 3);
+    assert_eq!(lua.call_path::<i64>("module.handler", 4)?, 40);
+    assert_eq!(lua.call_path::<i64>("top", 7)?, 7);
+
+    let err_msg = |path| match lua.call_path::<()>(path, ()) {
+        Err(Error::RuntimeError(msg)) => msg,
+        res => panic!("expected runtime error, got {res:?}"),
+    };
+    assert_eq!(
+        err_msg("module.missing.on_event"),
+        "cannot resolve 'module.missing.on_event': 'module.missing' is nil"
+    );
+    assert_eq!(
+        err_msg("module.value.x"),
+        "cannot resolve 'module.value.x': 'module.value' is not indexable (a string value)"
+    );
+    assert_eq!(
+        err_msg("module.events"),
+        "cannot call 'module.events': not a function (a table value)"
+    );
+    assert_eq!(err_msg("module..x"), "invalid path 'module..x'");
+
+    // Errors raised by the function are returned as is
+    lua.load("function fail() error('boom', 0) end").exec()?;
+    match lua.call_path::<()>("fail", ()) {
+        Err(Error::RuntimeError(msg)) => assert!(msg.starts_with("boom"), "{msg}"),
+        res => panic!("expected runtime error, got {res:?}"),
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_function_bind() -> Result<()> {
     let lua = Lua::new();
@@ -286,6 +364,133 @@ fn test_function_coverage() -> Result<()> {
     Ok(())
 }
 
+#[cfg(feature = "luau")]
+#[test]
+fn test_function_line_coverage() -> Result<()> {
+    use mlua::LineCoverage;
+
+    let lua = Lua::new();
+
+    let code = r#"
+        local function check(i)
+            if i < 5 then
+                return "small"
+            end
+            return "large"
+        end
+        return check
+    "#;
+
+    // Coverage is not enabled by default
+    let check = lua.load(code).eval::<Function>()?;
+    check.call::<()>(1)?;
+    assert!(check.line_coverage()?.is_empty());
+
+    lua.set_compiler(mlua::Compiler::new().set_coverage_level(1));
+    let check = lua.load(code).eval::<Function>()?;
+    check.call::<()>(1)?;
+    check.call::<()>(2)?;
+    check.call::<()>(10)?;
+    assert_eq!(
+        check.line_coverage()?,
+        vec![
+            LineCoverage { line: 3, hits: 3 },
+            LineCoverage { line: 4, hits: 2 },
+            LineCoverage { line: 6, hits: 1 },
+        ]
+    );
+
+    // C functions have no coverage
+    let print = lua.globals().get::<Function>("print")?;
+    assert!(print.line_coverage().is_err());
+
+    Ok(())
+}
+
+#[cfg(feature = "luau")]
+#[test]
+fn test_coverage_report() -> Result<()> {
+    use mlua::LineCoverage;
+
+    let lua = Lua::new();
+
+    // Chunks loaded without coverage are not tracked
+    lua.load("return 1").exec()?;
+    assert_eq!(lua.coverage_report().chunks().count(), 0);
+
+    lua.set_compiler(mlua::Compiler::new().set_coverage_level(1));
+    lua.load(
+        r#"
+        function is_even(n)
+            if n % 2 == 0 then
+                return true
+            end
+            return false
+        end
+    "#,
+    )
+    .set_name("@src/a.luau")
+    .exec()?;
+
+    // The report includes chunks loaded after it was made
+    let report = lua.coverage_report();
+    assert_eq!(report.chunks().collect::<Vec<_>>(), ["src/a.luau"]);
+
+    lua.load(
+        r#"
+        local function unused()
+            return 0
+        end
+        return is_even(2)
+    "#,
+    )
+    .set_name("=b")
+    .exec()?;
+    let report = lua.coverage_report();
+    assert_eq!(report.chunks().collect::<Vec<_>>(), ["b", "src/a.luau"]);
+    assert_eq!(
+        report.lines("src/a.luau").unwrap(),
+        vec![
+            LineCoverage { line: 2, hits: 1 },
+            LineCoverage { line: 3, hits: 1 },
+            LineCoverage { line: 4, hits: 1 },
+            LineCoverage { line: 6, hits: 0 },
+        ]
+    );
+
+    let lcov = report.to_lcov();
+    let records = lcov.split("end_of_record\n").collect::<Vec<_>>();
+    assert_eq!(records.len(), 3);
+    assert!(records[0].starts_with("TN:\nSF:b\n"));
+    assert!(records[0].contains("DA:2,1\n"));
+    assert!(records[0].contains("DA:3,0\n"));
+    assert!(records[0].contains("DA:5,1\n"));
+    assert_eq!(
+        records[1],
+        "TN:\nSF:src/a.luau\nDA:2,1\nDA:3,1\nDA:4,1\nDA:6,0\nLF:4\nLH:3\n"
+    );
+
+    // Counts are kept after the functions are no longer referenced
+    lua.globals().set("is_even", mlua::Nil)?;
+    lua.gc_collect()?;
+    assert_eq!(lua.coverage_report(), report);
+
+    // Clearing releases the tracked functions
+    let weak = lua.create_table()?;
+    weak.set_metatable(Some(lua.create_table_from([("__mode", "v")])?));
+    weak.raw_set(1, lua.load("return 1").set_name("=tmp").into_function()?)?;
+    lua.clear_coverage();
+    assert_eq!(lua.coverage_report().chunks().count(), 0);
+    lua.expire_registry_values();
+    lua.gc_collect()?;
+    assert!(weak.raw_get::<Option<Function>>(1)?.is_none());
+    lua.load("return 1").set_name("=c").exec()?;
+    let report = lua.coverage_report();
+    assert_eq!(report.chunks().collect::<Vec<_>>(), ["c"]);
+
+    Ok(())
+}
+
 #[test]
 fn test_function_pointer() -> Result<()> {
     let lua = Lua::new();
@@ -420,3 +625,111 @@ fn test_function_wrap_raw() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_function_bad_argument_name() -> Result<()> {
+    let lua = Lua::new();
+
+    let set_port = lua.create_function(|_, (_host, _port): (StdString, u16)| Ok(()))?;
+    lua.globals().set("set_port", set_port)?;
+    let err = lua.load(r#"set_port("localhost", "http")"#).exec().unwrap_err();
+    let err = err.to_string();
+    assert!(err.contains("bad argument #2 to `set_port`"), "{err}");
+
+    // Functions called by another name use that name (not available in Luau)
+    #[cfg(not(feature = "luau"))]
+    {
+        let net = lua.create_table()?;
+        net.set("listen", Function::wrap(|port: u16| Ok(port)))?;
+        lua.globals().set("net", net)?;
+        let err = lua.load(r#"net.listen(-1)"#).exec().unwrap_err();
+        let err = err.to_string();
+        assert!(err.contains("bad argument #1 to `listen`"), "{err}");
+    }
+
+    // Anonymous functions called from Rust have no name
+    let f = lua.create_function(|_, (_host, _port): (StdString, u16)| Ok(()))?;
+    let err = f.call::<()>(("localhost", "http")).unwrap_err();
+    assert!(err.to_string().contains("bad argument #2:"), "{err}");
+
+    Ok(())
+}
+
+#[test]
+fn test_function_spec() -> Result<()> {
+    let lua = Lua::new();
+
+    struct User(StdString);
+    impl UserData for User {}
+
+    let ban = lua.create_function_spec("ban", |spec| {
+        spec.arg::<UserDataRef<User>>("user")
+            .arg::<Option<u32>>("days")
+            .strict(true)
+            .build(|_, (user, days)| Ok(format!("{} banned for {:?} days", user.0, days)))
+    })?;
+    lua.globals().set("ban", ban)?;
+    lua.globals().set("alice", User("alice".into()))?;
+
+    // Success path, with trailing optional argument filled
+    let res = lua.load("ban(alice, 3)").eval::<StdString>()?;
+    assert_eq!(res, "alice banned for Some(3) days");
+    let res = lua.load("ban(alice)").eval::<StdString>()?;
+    assert_eq!(res, "alice banned for None days");
+
+    // Wrong type
+    let err = lua.load("ban(alice, 'ten')").exec().unwrap_err().to_string();
+    assert!(
+        err.contains("bad argument #2 to 'ban' (number expected, got string)"),
+        "{err}"
+    );
+    let err = lua.load("ban(1)").exec().unwrap_err().to_string();
+    assert!(
+        err.contains("bad argument #1 to 'ban' (User expected, got number)"),
+        "{err}"
+    );
+
+    // Missing required argument
+    let err = lua.load("ban()").exec().unwrap_err().to_string();
+    assert!(
+        err.contains("bad argument #1 to 'ban' (User expected, got no value)"),
+        "{err}"
+    );
+
+    // Extra arguments in strict mode
+    let err = lua.load("ban(alice, 1, 2)").exec().unwrap_err().to_string();
+    assert!(err.contains("wrong number of arguments to 'ban'"), "{err}");
+
+    // Non-strict mode ignores extra arguments
+    let add = lua.create_function_spec("add", |spec| {
+        spec.arg::<i64>("a").arg::<i64>("b").build(|_, (a, b)| Ok(a + b))
+    })?;
+    assert_eq!(add.call::<i64>((1, 2, 3))?, 3);
+    let err = add.call::<i64>(1).unwrap_err().to_string();
+    assert!(
+        err.contains("bad argument #2 to 'add' (number expected, got no value)"),
+        "{err}"
+    );
+
+    // Methods report errors for `self`
+    let method = lua.create_function_spec("greet", |spec| {
+        spec.arg::<Table>("self")
+            .arg::<String>("name")
+            .build(|_, (_, _)| Ok(()))
+    })?;
+    let err = method.call::<()>((1, "a")).unwrap_err().to_string();
+    assert!(
+        err.contains("calling 'greet' on bad self (table expected, got number)"),
+        "{err}"
+    );
+    let err = method
+        .call::<()>((lua.create_table()?, false))
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("bad argument #1 to 'greet' (string expected, got boolean)"),
+        "{err}"
+    );
+
+    Ok(())
+}
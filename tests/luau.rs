@@ -2,13 +2,15 @@
 
 use std::cell::Cell;
 use std::fmt::Debug;
-use std::os::raw::c_void;
 use std::panic::{catch_unwind, AssertUnwindSafe};
-use std::sync::atomic::{AtomicBool, AtomicPtr, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use mlua::{
-    Compiler, Error, Function, Lua, LuaOptions, Result, StdLib, Table, ThreadStatus, Value, Vector, VmState,
+    AllocEvent, CapabilityTable, Compiler, DebugAction, Error, Function, GcPhase, Lua, LuaOptions,
+    MultiValue, ProfilerOptions, Result, StdLib, Table, Thread, ThreadEvent, ThreadId, ThreadResetOptions,
+    ThreadStatus, Value, Vector, VmState,
 };
 
 #[test]
@@ -100,6 +102,59 @@ fn test_vectors() -> Result<()> {
     Ok(())
 }
 
+#[cfg(feature = "glam")]
+#[test]
+fn test_vector_glam() -> Result<()> {
+    let lua = Lua::new();
+
+    #[cfg(not(feature = "luau-vector4"))]
+    {
+        let v: Vector = lua.load("vector.create(1, 2, 3)").eval()?;
+        assert_eq!(glam::Vec3::from(v), glam::Vec3::new(1.0, 2.0, 3.0));
+        let v = Vector::from(glam::Vec3::new(4.0, 5.0, 6.0));
+        lua.load("assert(... == vector.create(4, 5, 6))").call::<()>(v)?;
+    }
+
+    #[cfg(feature = "luau-vector4")]
+    {
+        let v: Vector = lua.load("vector.create(1, 2, 3, 4)").eval()?;
+        assert_eq!(glam::Vec4::from(v), glam::Vec4::new(1.0, 2.0, 3.0, 4.0));
+        let v = Vector::from(glam::Vec4::new(4.0, 5.0, 6.0, 7.0));
+        lua.load("assert(... == vector.create(4, 5, 6, 7))")
+            .call::<()>(v)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "nalgebra")]
+#[test]
+fn test_vector_nalgebra() -> Result<()> {
+    let lua = Lua::new();
+
+    #[cfg(not(feature = "luau-vector4"))]
+    {
+        let v: Vector = lua.load("vector.create(1, 2, 3)").eval()?;
+        assert_eq!(nalgebra::Vector3::from(v), nalgebra::Vector3::new(1.0, 2.0, 3.0));
+        let v = Vector::from(nalgebra::Vector3::new(4.0, 5.0, 6.0));
+        lua.load("assert(... == vector.create(4, 5, 6))").call::<()>(v)?;
+    }
+
+    #[cfg(feature = "luau-vector4")]
+    {
+        let v: Vector = lua.load("vector.create(1, 2, 3, 4)").eval()?;
+        assert_eq!(
+            nalgebra::Vector4::from(v),
+            nalgebra::Vector4::new(1.0, 2.0, 3.0, 4.0)
+        );
+        let v = Vector::from(nalgebra::Vector4::new(4.0, 5.0, 6.0, 7.0));
+        lua.load("assert(... == vector.create(4, 5, 6, 7))")
+            .call::<()>(v)?;
+    }
+
+    Ok(())
+}
+
 #[cfg(not(feature = "luau-vector4"))]
 #[test]
 fn test_vector_metatable() -> Result<()> {
@@ -238,6 +293,72 @@ fn test_sandbox_nolibs() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_sandboxed_chunk() -> Result<()> {
+    let lua = Lua::new();
+
+    lua.globals().set("shared", 1)?;
+    let f = lua
+        .load("shared = 2; local_global = true; return shared, math.abs(-3)")
+        .sandboxed(true)
+        .into_function()?;
+    assert_eq!(f.call::<(i32, i32)>(())?, (2, 3));
+    // Globals of the VM are not affected
+    assert_eq!(lua.globals().get::<i32>("shared")?, 1);
+    assert_eq!(lua.globals().get::<Option<bool>>("local_global")?, None);
+    // Each sandboxed chunk has its own environment
+    let shared: i32 = lua.load("return shared").sandboxed(true).eval()?;
+    assert_eq!(shared, 1);
+
+    // Sandbox over a custom environment
+    let env = lua.create_table()?;
+    env.set("value", 10)?;
+    let chunk = lua.load("value = value + 1; return value");
+    let value: i32 = chunk.set_environment(env.clone()).sandboxed(true).eval()?;
+    assert_eq!(value, 11);
+    assert_eq!(env.get::<i32>("value")?, 10);
+
+    // The inherited environment cannot be reached through the environment metatable
+    let escape = lua
+        .load("getmetatable(getfenv()).__index.shared = 4")
+        .sandboxed(true)
+        .exec();
+    assert!(escape.is_err());
+    let escape = lua.load("getfenv(0).shared = 4").sandboxed(true).exec();
+    assert!(escape.is_err());
+    let meta: Value = lua.load("return getmetatable(_G)").sandboxed(true).eval()?;
+    assert_eq!(meta, Value::Boolean(false));
+    lua.load("_G.shared = 4").sandboxed(true).exec()?;
+    assert_eq!(lua.globals().get::<i32>("shared")?, 1);
+
+    // Not sandboxed chunks still modify globals
+    lua.load("shared = 3").exec()?;
+    assert_eq!(lua.globals().get::<i32>("shared")?, 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_sandbox_capabilities() -> Result<()> {
+    let lua = Lua::new();
+    lua.sandbox(true)?;
+    lua.set_capabilities(CapabilityTable::new().grant("value", -5))?;
+
+    // Builtins with a fast path are not reachable when not granted
+    for source in [
+        "return setmetatable({}, {})",
+        "return type(value)",
+        "return math.abs(value)",
+        "local t = {} rawset(t, 1, 1)",
+    ] {
+        let err = lua.load(source).exec().unwrap_err();
+        assert!(err.to_string().contains("not granted"), "{source}: {err}");
+    }
+    assert_eq!(lua.load("return -value").eval::<i32>()?, 5);
+
+    Ok(())
+}
+
 #[test]
 fn test_sandbox_threads() -> Result<()> {
     let lua = Lua::new();
@@ -255,10 +376,167 @@ fn test_sandbox_threads() -> Result<()> {
     // The main state should see the previous `global` value (as the thread is sandboxed)
     assert_eq!(lua.globals().get::<Option<i32>>("global")?, Some(321));
 
-    // Try to reset the (sandboxed) thread
-    co.reset(f)?;
+    // Reset the (sandboxed) thread, it should stay sandboxed
+    co.reset(f.clone())?;
     co.resume::<()>(111)?;
-    assert_eq!(lua.globals().get::<Option<i32>>("global")?, Some(111));
+    assert_eq!(lua.globals().get::<Option<i32>>("global")?, Some(321));
+    assert_eq!(co.globals().get::<Option<i32>>("global")?, Some(111));
+
+    // Preserving globals explicitly is the same as plain reset
+    co.reset_with(f.clone(), ThreadResetOptions::new().preserve_globals(true))?;
+    co.resume::<()>(222)?;
+    assert_eq!(lua.globals().get::<Option<i32>>("global")?, Some(321));
+    assert_eq!(co.globals().get::<Option<i32>>("global")?, Some(222));
+
+    // Drop the sandbox on reset
+    co.reset_with(f, ThreadResetOptions::new().preserve_globals(false))?;
+    assert_eq!(co.globals(), lua.globals());
+    co.resume::<()>(333)?;
+    assert_eq!(lua.globals().get::<Option<i32>>("global")?, Some(333));
+
+    Ok(())
+}
+
+#[test]
+fn test_thread_globals() -> Result<()> {
+    let lua = Lua::new();
+
+    let f = lua.create_function(|lua, v: Value| lua.globals().set("global", v))?;
+
+    // Non-sandboxed threads share globals with the main thread
+    let co = lua.create_thread(f.clone())?;
+    assert_eq!(co.globals(), lua.globals());
+
+    // Sandboxed threads have their own globals
+    let co = lua.create_thread(f.clone())?;
+    co.sandbox()?;
+    co.resume::<()>(123)?;
+    assert_eq!(co.globals().get::<Option<i32>>("global")?, Some(123));
+    assert_eq!(lua.globals().get::<Option<i32>>("global")?, None);
+
+    // Custom globals table
+    let env = lua.create_table()?;
+    let co = lua.create_thread(f)?;
+    co.set_globals(env.clone())?;
+    co.resume::<()>(321)?;
+    assert_eq!(env.get::<Option<i32>>("global")?, Some(321));
+    assert_eq!(lua.globals().get::<Option<i32>>("global")?, None);
+
+    // Cannot change globals of a running thread
+    let f = lua.create_function(|lua, ()| lua.current_thread().set_globals(lua.create_table()?))?;
+    let co = lua.create_thread(f)?;
+    match co.resume::<()>(()) {
+        Err(Error::CallbackError { ref cause, .. }) => {
+            assert!(cause
+                .to_string()
+                .contains("cannot change globals of a running thread"))
+        }
+        r => panic!("expected CallbackError, got {r:?}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_gc_callback() -> Result<()> {
+    let lua = Lua::new();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let events2 = events.clone();
+    lua.set_gc_callback(move |phase, duration| events2.lock().unwrap().push((phase, duration)));
+
+    // Generate enough garbage to run a few incremental cycles
+    lua.load("for i = 1, 200000 do local t = {i} end").exec()?;
+    {
+        let events = events.lock().unwrap();
+        let starts = events
+            .iter()
+            .filter(|(phase, _)| *phase == GcPhase::Start)
+            .count();
+        let ends = events
+            .iter()
+            .filter(|(phase, _)| matches!(phase, GcPhase::End { .. }));
+        assert!(starts >= 2);
+        assert!(ends.clone().count() >= 1);
+        assert!(ends.clone().all(|(_, duration)| *duration > Duration::ZERO));
+        assert!(ends
+            .clone()
+            .any(|(phase, _)| matches!(phase, GcPhase::End { collected } if *collected > 0)));
+        // Phases alternate
+        for pair in events.windows(2) {
+            assert_ne!(pair[0].0 == GcPhase::Start, pair[1].0 == GcPhase::Start);
+        }
+    }
+
+    // Full collection is reported immediately
+    lua.load("garbage = {} for i = 1, 10000 do garbage[i] = {i} end garbage = nil")
+        .exec()?;
+    events.lock().unwrap().clear();
+    lua.gc_collect()?;
+    match events.lock().unwrap().last() {
+        Some((GcPhase::End { collected }, duration)) => {
+            assert!(*collected > 0);
+            assert!(*duration > Duration::ZERO);
+        }
+        r => panic!("expected GcPhase::End, got {r:?}"),
+    }
+
+    // Works together with interrupts
+    let interrupts = Arc::new(AtomicU64::new(0));
+    let interrupts2 = interrupts.clone();
+    lua.set_interrupt(move |_| {
+        interrupts2.fetch_add(1, Ordering::Relaxed);
+        Ok(VmState::Continue)
+    });
+    events.lock().unwrap().clear();
+    lua.load("for i = 1, 200000 do local t = {i} end").exec()?;
+    assert!(interrupts.load(Ordering::Relaxed) > 0);
+    assert!(!events.lock().unwrap().is_empty());
+
+    lua.remove_gc_callback();
+    events.lock().unwrap().clear();
+    lua.load("for i = 1, 200000 do local t = {i} end").exec()?;
+    lua.gc_collect()?;
+    assert!(events.lock().unwrap().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_gc_tuning() -> Result<()> {
+    let lua = Lua::new();
+
+    assert_eq!(lua.gc_goal(), 200);
+    assert_eq!(lua.gc_set_goal(150), 200);
+    assert_eq!(lua.gc_goal(), 150);
+    let step_multiplier = lua.gc_step_multiplier();
+    assert_eq!(lua.gc_set_step_multiplier(300), step_multiplier);
+    assert_eq!(lua.gc_step_multiplier(), 300);
+    let step_size = lua.gc_step_size();
+    assert!(step_size > 0);
+    assert_eq!(lua.gc_set_step_size(4), step_size);
+    assert_eq!(lua.gc_step_size(), 4);
+
+    // Count collection cycles of an allocation heavy script
+    let count_cycles = |goal, step_multiplier, step_size| -> Result<usize> {
+        let lua = Lua::new();
+        lua.gc_set_goal(goal);
+        lua.gc_set_step_multiplier(step_multiplier);
+        lua.gc_set_step_size(step_size);
+        let cycles = Arc::new(AtomicU64::new(0));
+        let cycles2 = cycles.clone();
+        lua.set_gc_callback(move |phase, _| {
+            if phase == GcPhase::Start {
+                cycles2.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+        lua.load("local keep = {} for i = 1, 200000 do local t = {i} if i % 100 == 0 then keep[#keep + 1] = t end end")
+            .exec()?;
+        Ok(cycles.load(Ordering::Relaxed) as usize)
+    };
+    let aggressive = count_cycles(110, 400, 1)?;
+    let lazy = count_cycles(400, 100, 8)?;
+    assert!(aggressive > lazy, "aggressive: {aggressive}, lazy: {lazy}");
 
     Ok(())
 }
@@ -331,9 +609,605 @@ fn test_interrupts() -> Result<()> {
 }
 
 #[test]
-fn test_fflags() {
-    // We cannot really on any particular feature flag to be present
-    assert!(Lua::set_fflag("UnknownFlag", true).is_err());
+fn test_interrupt_interval() -> Result<()> {
+    let lua = Lua::new();
+
+    let count = Arc::new(AtomicU64::new(0));
+    let count2 = count.clone();
+    lua.set_interrupt(move |_| {
+        count2.fetch_add(1, Ordering::Relaxed);
+        Ok(VmState::Continue)
+    });
+    let f = lua
+        .load("local n = 0 for i = 1, 1000 do n += i end return n")
+        .into_function()?;
+
+    f.call::<()>(())?;
+    let every_check = count.swap(0, Ordering::Relaxed);
+    assert!(every_check >= 1000);
+
+    lua.set_interrupt_interval(10);
+    f.call::<()>(())?;
+    assert_eq!(count.swap(0, Ordering::Relaxed), every_check / 10);
+
+    // Zero is the same as the default interval
+    lua.set_interrupt_interval(0);
+    f.call::<()>(())?;
+    assert_eq!(count.load(Ordering::Relaxed), every_check);
+
+    lua.remove_interrupt();
+
+    Ok(())
+}
+
+#[test]
+fn test_breakpoints() -> Result<()> {
+    let lua = Lua::new();
+
+    let func = lua
+        .load(
+            r#"
+        local sum = 0
+        for i = 1, 3 do
+            sum += i
+        end
+        return sum
+    "#,
+        )
+        .into_function()?;
+    let breakpoint = lua.set_breakpoint(&func, 4)?;
+    assert_eq!(breakpoint.line(), 4);
+
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let lines2 = lines.clone();
+    lua.set_debug_callback(move |_, debug| {
+        lines2.lock().unwrap().push(debug.curr_line());
+        Ok(DebugAction::Continue)
+    });
+
+    // Every hit of the breakpoint pauses the thread
+    let co = lua.create_thread(func.clone())?;
+    let mut pauses = 0;
+    loop {
+        let sum: Option<i64> = co.resume(())?;
+        if co.status() == ThreadStatus::Finished {
+            assert_eq!(sum, Some(6));
+            break;
+        }
+        pauses += 1;
+    }
+    assert_eq!(pauses, 3);
+    assert_eq!(*lines.lock().unwrap(), [4, 4, 4]);
+
+    // Threads reusing the address of a collected paused thread are paused again
+    for _ in 0..10 {
+        let co = lua.create_thread(func.clone())?;
+        co.resume::<()>(())?;
+        assert_eq!(co.status(), ThreadStatus::Resumable);
+    }
+    lua.gc_collect()?;
+    for _ in 0..10 {
+        let co = lua.create_thread(func.clone())?;
+        assert_eq!(co.resume::<Option<i64>>(())?, None);
+        assert_eq!(co.status(), ThreadStatus::Resumable);
+    }
+
+    // Without the breakpoint the thread runs to completion
+    lua.remove_breakpoint(breakpoint);
+    let co = lua.create_thread(func.clone())?;
+    assert_eq!(co.resume::<i64>(())?, 6);
+    assert_eq!(co.status(), ThreadStatus::Finished);
+
+    // Breakpoints outside of a coroutine do not pause
+    let breakpoint = lua.set_breakpoint(&func, 2)?;
+    assert_eq!(func.call::<i64>(())?, 6);
+    lua.remove_breakpoint(breakpoint);
+
+    // No code after the last line
+    assert!(lua.set_breakpoint(&func, 100).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_breakpoint_stepping() -> Result<()> {
+    let lua = Lua::new();
+
+    let func = lua
+        .load(
+            r#"
+        local function add(a, b)
+            local c = a + b
+            return c
+        end
+        local x = add(1, 2)
+        local y = add(x, 3)
+        return y
+    "#,
+        )
+        .into_function()?;
+    lua.set_breakpoint(&func, 6)?;
+
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let lines2 = lines.clone();
+    lua.set_debug_callback(move |_, debug| {
+        let mut lines = lines2.lock().unwrap();
+        lines.push(debug.curr_line());
+        Ok(match lines.len() {
+            1 => DebugAction::StepOver, // skip `add` on line 6
+            2 => DebugAction::StepIn,   // enter `add` on line 7
+            3 => DebugAction::StepOver, // line 3 in `add`
+            _ => DebugAction::Continue,
+        })
+    });
+
+    let co = lua.create_thread(func.clone())?;
+    while co.status() == ThreadStatus::Resumable {
+        co.resume::<MultiValue>(())?;
+    }
+    assert_eq!(*lines.lock().unwrap(), [6, 7, 3, 4]);
+
+    // Aborting raises an error
+    lua.set_debug_callback(|_, _| Ok(DebugAction::Abort));
+    let co = lua.create_thread(func)?;
+    match co.resume::<()>(()) {
+        Err(Error::RuntimeError(msg)) => assert_eq!(msg, "execution aborted by debugger"),
+        r => panic!("expected runtime error, got {r:?}"),
+    }
+
+    lua.remove_debug_callback();
+
+    Ok(())
+}
+
+#[test]
+fn test_debug_callbacks() -> Result<()> {
+    let lua = Lua::new();
+
+    let func = lua
+        .load(
+            r#"
+        local x = 1
+        x += 1
+        return x
+    "#,
+        )
+        .into_function()?;
+
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let lines2 = lines.clone();
+    lua.set_debug_step_callback(move |_, debug| {
+        lines2.lock().unwrap().push(debug.curr_line());
+        Ok(VmState::Continue)
+    });
+
+    // The callback is not called without single-step mode
+    let co = lua.create_thread(func.clone())?;
+    assert_eq!(co.resume::<i32>(())?, 2);
+    assert!(lines.lock().unwrap().is_empty());
+
+    let co = lua.create_thread(func.clone())?;
+    co.set_single_step(true);
+    assert_eq!(co.resume::<i32>(())?, 2);
+    let mut lines = lines.lock().unwrap().drain(..).collect::<Vec<_>>();
+    lines.dedup();
+    assert_eq!(lines, [2, 3, 4]);
+
+    // Yielding pauses the thread before the instruction
+    let steps = Arc::new(AtomicU64::new(0));
+    let steps2 = steps.clone();
+    lua.set_debug_step_callback(move |_, _| match steps2.fetch_add(1, Ordering::Relaxed) % 2 {
+        0 => Ok(VmState::Yield),
+        _ => Ok(VmState::Continue),
+    });
+    let co = lua.create_thread(func.clone())?;
+    co.set_single_step(true);
+    let mut pauses = 0;
+    while co.status() == ThreadStatus::Resumable {
+        co.resume::<MultiValue>(())?;
+        pauses += 1;
+    }
+    assert!(pauses > 1);
+
+    // Errors are raised in Lua code
+    lua.set_debug_step_callback(|_, _| Err(Error::runtime("step error")));
+    let co = lua.create_thread(func.clone())?;
+    co.set_single_step(true);
+    match co.resume::<()>(()) {
+        Err(Error::RuntimeError(msg)) => assert_eq!(msg, "step error"),
+        r => panic!("expected runtime error, got {r:?}"),
+    }
+
+    // Breakpoints call the break callback
+    let hits = Arc::new(AtomicU64::new(0));
+    let hits2 = hits.clone();
+    lua.set_debug_break_callback(move |_, debug| {
+        assert_eq!(debug.curr_line(), 3);
+        hits2.fetch_add(1, Ordering::Relaxed);
+        Ok(VmState::Continue)
+    });
+    lua.set_breakpoint(&func, 3)?;
+    assert_eq!(func.call::<i32>(())?, 2);
+    assert_eq!(hits.load(Ordering::Relaxed), 1);
+
+    lua.set_debug_interrupt_callback(|_, _| Ok(()));
+
+    // After removal nothing is called
+    lua.remove_debug_step_callback();
+    lua.remove_debug_break_callback();
+    lua.remove_debug_interrupt_callback();
+    let co = lua.create_thread(func)?;
+    co.set_single_step(true);
+    assert_eq!(co.resume::<i32>(())?, 2);
+    assert_eq!(hits.load(Ordering::Relaxed), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_profiler() -> Result<()> {
+    let lua = Lua::new();
+
+    // Not running
+    assert!(lua.stop_profiling().is_none());
+
+    let interrupts = Arc::new(AtomicU64::new(0));
+    let interrupts2 = interrupts.clone();
+    lua.set_interrupt(move |_| {
+        interrupts2.fetch_add(1, Ordering::Relaxed);
+        Ok(VmState::Continue)
+    });
+
+    lua.start_profiling(ProfilerOptions::new().interval_ticks(10));
+    lua.load(
+        r#"
+        local function hot_a()
+            local x = 0
+            for i = 1, 300000 do x += i end
+            return x
+        end
+
+        local function hot_b()
+            local x = 0
+            for i = 1, 100000 do x += i end
+            return x
+        end
+
+        hot_a()
+        hot_b()
+    "#,
+    )
+    .set_name("profiled")
+    .exec()?;
+    let report = lua.stop_profiling().unwrap();
+
+    // The interrupt callback is still called
+    assert!(interrupts.load(Ordering::Relaxed) > 0);
+
+    let samples = |name: &str| {
+        (report.stacks())
+            .filter(|(stack, _)| stack.rsplit(';').next().unwrap().starts_with(name))
+            .map(|(_, count)| count)
+            .sum::<u64>()
+    };
+    let (a, b) = (samples("hot_a"), samples("hot_b"));
+    assert!(b > 0);
+    assert!(a > b * 2, "hot_a: {a}, hot_b: {b}");
+    assert!(a + b <= report.total_samples());
+
+    let collapsed = report.to_collapsed();
+    for line in collapsed.lines() {
+        let (stack, count) = line.rsplit_once(' ').unwrap();
+        assert!(stack.contains("[string \"profiled\"]:"));
+        assert!(count.parse::<u64>().unwrap() > 0);
+    }
+    assert!(collapsed.contains(";hot_a [string \"profiled\"]:4 "));
+
+    // After stopping nothing is recorded
+    lua.load("for i = 1, 10000 do end").exec()?;
+    assert!(lua.stop_profiling().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_memory_categories() -> Result<()> {
+    let lua = Lua::new();
+
+    let usage = |category| {
+        lua.memory_stats()
+            .into_iter()
+            .find(|&(cat, _)| cat == category)
+            .map(|(_, bytes)| bytes)
+            .unwrap_or(0)
+    };
+
+    assert!(usage(0) > 0);
+    assert_eq!(usage(7), 0);
+    assert_eq!(usage(8), 0);
+
+    let t1 = lua.create_table()?;
+    let t2 = lua.create_table()?;
+    let fill = lua
+        .load("local t = ... for i = 1, 1000 do t[i] = tostring(i) end")
+        .into_function()?;
+    {
+        let _guard = lua.set_memory_category(7);
+        fill.call::<()>(&t1)?;
+        {
+            let _guard = lua.set_memory_category(8);
+            fill.call::<()>(&t2)?;
+        }
+    }
+    lua.gc_collect()?;
+    let (used7, used8) = (usage(7), usage(8));
+    assert!(used7 > 1000, "unexpected usage: {used7}");
+    assert!(used8 > 1000, "unexpected usage: {used8}");
+
+    // Allocations made after switching back are not attributed to the categories
+    lua.load("local t = {} for i = 1, 1000 do t[i] = {} end").exec()?;
+    assert_eq!(usage(7), used7);
+    assert_eq!(usage(8), used8);
+
+    // Memory is released from the category when collected
+    drop(t2);
+    lua.gc_collect()?;
+    assert!(usage(8) < used8);
+
+    // The guard restores the category of the thread it was created on
+    let guard = Arc::new(Mutex::new(None));
+    let guard2 = guard.clone();
+    let enter = lua.create_function(move |lua, ()| {
+        *guard2.lock().unwrap() = Some(lua.set_memory_category(10));
+        Ok(())
+    })?;
+    let thread = lua.create_thread(enter)?;
+    thread.set_memory_category(9)?;
+    thread.resume::<()>(())?;
+    drop(guard.lock().unwrap().take());
+    let used9 = usage(9);
+    lua.load("local t = {} for i = 1, 1000 do t[i] = {} end").exec()?;
+    assert_eq!(usage(9), used9);
+
+    Ok(())
+}
+
+#[test]
+fn test_memory_category_limits() -> Result<()> {
+    let lua = Lua::new();
+
+    let script = lua
+        .load(
+            r#"
+            local t = {}
+            while true do
+                for i = 1, 100 do
+                    table.insert(t, string.rep("x", 100) .. i)
+                end
+                coroutine.yield(#t)
+            end
+            "#,
+        )
+        .into_function()?;
+    let small = lua.create_thread(script.clone())?;
+    small.set_memory_category(1)?;
+    let large = lua.create_thread(script)?;
+    large.set_memory_category(2)?;
+    lua.set_category_limit(1, 64 * 1024)?;
+    lua.set_category_limit(2, 4 * 1024 * 1024)?;
+
+    // The small thread exceeds its quota, while the large one keeps working
+    let mut small_err = None;
+    for _ in 0..100 {
+        if let Err(err) = small.resume::<i64>(()) {
+            small_err = Some(err);
+            break;
+        }
+        large.resume::<i64>(())?;
+    }
+    assert!(
+        matches!(small_err, Some(Error::OutOfMemory { limit, .. }) if limit == 64 * 1024),
+        "{small_err:?}"
+    );
+    assert!(large.resume::<i64>(())? > 1000);
+
+    // Code running outside of the threads is not limited
+    let fill = lua
+        .load("local t = {} for i = 1, 10000 do t[i] = tostring(i) end")
+        .into_function()?;
+    fill.call::<()>(())?;
+
+    // The category of the running thread is checked
+    {
+        let _guard = lua.set_memory_category(1);
+        let res = fill.call::<()>(());
+        assert!(matches!(res, Err(Error::OutOfMemory { .. })), "{res:?}");
+    }
+    fill.call::<()>(())?;
+
+    // Coroutines inherit the category of the thread creating them
+    let spawner = lua
+        .load(
+            "return coroutine.create(function() local t = {} for i = 1, 10000 do t[i] = tostring(i) end end)",
+        )
+        .into_function()?;
+    let spawner = lua.create_thread(spawner)?;
+    spawner.set_memory_category(1)?;
+    let co = spawner.resume::<Thread>(())?;
+    let res = co.resume::<()>(());
+    assert!(matches!(res, Err(Error::OutOfMemory { .. })), "{res:?}");
+    drop((spawner, co));
+
+    // Usage returns to the category when the thread is collected
+    drop(small);
+    lua.gc_collect()?;
+    lua.gc_collect()?;
+    let usage = lua.memory_stats().into_iter().find(|&(cat, _)| cat == 1);
+    assert!(usage.map(|(_, bytes)| bytes).unwrap_or(0) < 64 * 1024);
+
+    Ok(())
+}
+
+#[cfg(feature = "named-closures")]
+#[test]
+fn test_named_closures() -> Result<()> {
+    use mlua::{UserData, UserDataMethods};
+
+    struct Counter;
+
+    impl UserData for Counter {
+        fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+            methods.add_method("fail", |_, _, ()| Err::<(), _>(Error::runtime("method failed")));
+        }
+    }
+
+    let lua = Lua::new();
+
+    // Call through a local variable, so the name is not known from the call site
+    let func = lua.create_function(|_, ()| Err::<(), _>(Error::runtime("function failed")))?;
+    lua.globals().set("func", func)?;
+    let err = lua.load("local f = func; f()").exec().unwrap_err().to_string();
+    assert!(err.contains("test_named_closures::{{closure}}"), "{err}");
+    assert!(err.contains("luau.rs:"), "{err}");
+
+    lua.globals().set("counter", Counter)?;
+    let err = lua
+        .load("local c = counter; c:fail()")
+        .exec()
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("Counter:fail"), "{err}");
+
+    Ok(())
+}
+
+#[test]
+fn test_heap_census() -> Result<()> {
+    let lua = Lua::new();
+
+    let before = lua.heap_census()?;
+    lua.load(
+        r#"
+        items = {}
+        for i = 1, 1000 do
+            items[i] = { name = "item_" .. i }
+        end
+    "#,
+    )
+    .exec()?;
+
+    let census = lua.heap_census()?;
+    let tables = census.tables().count - before.tables().count;
+    let strings = census.strings().count - before.strings().count;
+    assert!(
+        (1001..=1010).contains(&tables),
+        "unexpected number of tables: {tables}"
+    );
+    assert!(
+        (1000..=1010).contains(&strings),
+        "unexpected number of strings: {strings}"
+    );
+    assert!(census.strings().bytes - before.strings().bytes >= 1000 * "item_1".len());
+    assert!(census.total().count > census.tables().count + census.strings().count);
+
+    // Everything is collected once the table is gone
+    lua.globals().set("items", Value::Nil)?;
+    let after = lua.heap_census()?;
+    assert!(after.tables().count <= before.tables().count + 5);
+    assert!(after.strings().count <= before.strings().count + 5);
+
+    // Userdata are split by type name
+    struct Probe;
+    impl mlua::UserData for Probe {}
+    lua.globals().set("probes", vec![Probe, Probe, Probe])?;
+    let census = lua.heap_census()?;
+    let probes = census.userdata_by_type().find(|&(name, _)| name == "Probe");
+    assert_eq!(probes.map(|(_, entry)| entry.count), Some(3));
+
+    Ok(())
+}
+
+#[test]
+fn test_allocation_callback() -> Result<()> {
+    let lua = Lua::new();
+
+    let events = Arc::new(Mutex::new((0, 0, Vec::new())));
+    let events2 = events.clone();
+    lua.set_allocation_callback(move |event: AllocEvent| {
+        let mut events = events2.lock().unwrap();
+        if event.nsize > event.osize {
+            events.0 += 1;
+        } else if event.nsize == 0 {
+            events.1 += 1;
+        }
+        events.2.push(event.memcat);
+    });
+
+    let category = lua.set_memory_category(5);
+    lua.load("local t = {} for i = 1, 1000 do t[i] = tostring(i) end")
+        .exec()?;
+    lua.gc_collect()?;
+
+    // A coroutine resumed from Lua reports its own category
+    let co: Thread = lua
+        .load("coroutine.create(function() local t = {} for i = 1, 100 do t[i] = {} end end)")
+        .eval()?;
+    co.set_memory_category(6)?;
+    lua.load("coroutine.resume(...)").call::<()>(&co)?;
+    drop(category);
+    lua.remove_allocation_callback();
+
+    let (allocs, frees, memcats) = std::mem::take(&mut *events.lock().unwrap());
+    assert!(allocs > 0);
+    assert!(frees > 0);
+    assert!(memcats.contains(&5));
+    assert!(memcats.contains(&6));
+
+    // No events after removing the callback
+    lua.load("local t = {} for i = 1, 100 do t[i] = {} end").exec()?;
+    assert!(events.lock().unwrap().2.is_empty());
+
+    Ok(())
+}
+
+#[cfg(feature = "luau-assert")]
+#[test]
+fn test_assert_handler() -> Result<()> {
+    use mlua::{ffi, AssertAction};
+
+    let failures = Arc::new(Mutex::new(Vec::new()));
+    let failures2 = failures.clone();
+    Lua::set_assert_handler(move |failure| {
+        let msg = format!("{} in {}", failure.expression, failure.function);
+        failures2.lock().unwrap().push((msg, failure.file.to_string()));
+        AssertAction::Panic
+    });
+    assert!(unsafe { ffi::luau_getasserthandler() }.is_some());
+
+    // Luau works normally with the handler installed
+    let lua = Lua::new();
+    assert_eq!(lua.load("1 + 1").eval::<i32>()?, 2);
+
+    // Trigger a real assertion by popping more values than the stack has.
+    // The state must not be used after the panic, so it's leaked.
+    let state = unsafe { ffi::luaL_newstate() };
+    let err = catch_unwind(AssertUnwindSafe(|| unsafe { ffi::lua_settop(state, -10) })).unwrap_err();
+    let msg = err.downcast_ref::<String>().unwrap();
+    assert!(
+        msg.starts_with("Luau assertion failed: -(idx + 1) <= (L->top - L->base)"),
+        "{msg}"
+    );
+    {
+        let failures = failures.lock().unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, "-(idx + 1) <= (L->top - L->base) in lua_settop");
+        assert!(failures[0].1.ends_with("lapi.cpp"), "{}", failures[0].1);
+    }
+
+    Lua::remove_assert_handler();
+    assert!(unsafe { ffi::luau_getasserthandler() }.is_none());
+
+    Ok(())
 }
 
 #[test]
@@ -341,41 +1215,45 @@ fn test_thread_events() -> Result<()> {
     let lua = Lua::new();
 
     let count = Arc::new(AtomicU64::new(0));
-    let thread_data: Arc<(AtomicPtr<c_void>, AtomicBool)> = Arc::new(Default::default());
+    let thread_data: Arc<(Mutex<Option<ThreadId>>, AtomicBool)> = Arc::new(Default::default());
 
     let (count2, thread_data2) = (count.clone(), thread_data.clone());
-    lua.set_thread_creation_callback(move |_, thread| {
+    lua.set_thread_event_callback(move |_, event| {
         count2.fetch_add(1, Ordering::Relaxed);
-        (thread_data2.0).store(thread.to_pointer() as *mut _, Ordering::Relaxed);
-        thread_data2.1.store(false, Ordering::Relaxed);
-        Ok(())
-    });
-    let (count3, thread_data3) = (count.clone(), thread_data.clone());
-    lua.set_thread_collection_callback(move |thread_ptr| {
-        count3.fetch_add(1, Ordering::Relaxed);
-        if thread_data3.0.load(Ordering::Relaxed) == thread_ptr.0 {
-            thread_data3.1.store(true, Ordering::Relaxed);
+        match event {
+            ThreadEvent::Created(thread) => {
+                *thread_data2.0.lock().unwrap() = Some(thread.id());
+                thread_data2.1.store(false, Ordering::Relaxed);
+            }
+            ThreadEvent::Destroyed(id) => {
+                if *thread_data2.0.lock().unwrap() == Some(id) {
+                    thread_data2.1.store(true, Ordering::Relaxed);
+                }
+            }
         }
+        Ok(())
     });
 
     let t = lua.create_thread(lua.load("return 123").into_function()?)?;
     assert_eq!(count.load(Ordering::Relaxed), 1);
-    let t_ptr = t.to_pointer();
-    assert_eq!(t_ptr, thread_data.0.load(Ordering::Relaxed));
+    let t_id = t.id();
+    assert_eq!(*thread_data.0.lock().unwrap(), Some(t_id));
     assert!(!thread_data.1.load(Ordering::Relaxed));
 
     // Thead will be destroyed after GC cycle
     drop(t);
     lua.gc_collect()?;
     assert_eq!(count.load(Ordering::Relaxed), 2);
-    assert_eq!(t_ptr, thread_data.0.load(Ordering::Relaxed));
+    assert_eq!(*thread_data.0.lock().unwrap(), Some(t_id));
     assert!(thread_data.1.load(Ordering::Relaxed));
 
     // Check that recursion is not allowed
     let count4 = count.clone();
-    lua.set_thread_creation_callback(move |lua, _value| {
-        count4.fetch_add(1, Ordering::Relaxed);
-        let _ = lua.create_thread(lua.load("return 123").into_function().unwrap())?;
+    lua.set_thread_event_callback(move |lua, event| {
+        if let ThreadEvent::Created(_) = event {
+            count4.fetch_add(1, Ordering::Relaxed);
+            let _ = lua.create_thread(lua.load("return 123").into_function().unwrap())?;
+        }
         Ok(())
     });
     let t = lua.create_thread(lua.load("return 123").into_function()?)?;
@@ -386,20 +1264,24 @@ fn test_thread_events() -> Result<()> {
     lua.gc_collect()?;
     assert_eq!(count.load(Ordering::Relaxed), 3);
 
-    // Test error inside callback
-    lua.set_thread_creation_callback(move |_, _| Err(Error::runtime("error when processing thread event")));
+    // Test error inside callback (creation can be vetoed)
+    lua.set_thread_event_callback(|_, event| match event {
+        ThreadEvent::Created(_) => Err(Error::runtime("error when processing thread event")),
+        ThreadEvent::Destroyed(_) => Ok(()),
+    });
     let result = lua.create_thread(lua.load("return 123").into_function()?);
-    assert!(result.is_err());
     assert!(
         matches!(result, Err(Error::RuntimeError(err)) if err.contains("error when processing thread event"))
     );
 
     // Test context switch when running Lua script
     let count = Cell::new(0);
-    lua.set_thread_creation_callback(move |_, _| {
-        count.set(count.get() + 1);
-        if count.get() == 2 {
-            return Err(Error::runtime("thread limit exceeded"));
+    lua.set_thread_event_callback(move |_, event| {
+        if let ThreadEvent::Created(_) = event {
+            count.set(count.get() + 1);
+            if count.get() == 2 {
+                return Err(Error::runtime("thread limit exceeded"));
+            }
         }
         Ok(())
     });
@@ -411,9 +1293,87 @@ fn test_thread_events() -> Result<()> {
     "#,
         )
         .exec();
-    assert!(result.is_err());
     assert!(matches!(result, Err(Error::RuntimeError(err)) if err.contains("thread limit exceeded")));
 
+    lua.remove_thread_callbacks();
+    lua.create_thread(lua.load("return 123").into_function()?)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_thread_callbacks_compose() -> Result<()> {
+    let lua = Lua::new();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let events2 = events.clone();
+    lua.set_thread_creation_callback(move |_, _| {
+        events2.lock().unwrap().push("creation");
+        Ok(())
+    });
+    let events2 = events.clone();
+    lua.set_thread_collection_callback(move |_| events2.lock().unwrap().push("collection"));
+    let events2 = events.clone();
+    lua.set_thread_event_callback(move |_, event| {
+        let name = match event {
+            ThreadEvent::Created(_) => "created",
+            ThreadEvent::Destroyed(_) => "destroyed",
+        };
+        events2.lock().unwrap().push(name);
+        Ok(())
+    });
+
+    // Setting one callback does not clear the others
+    let t = lua.create_thread(lua.load("return 123").into_function()?)?;
+    assert_eq!(*events.lock().unwrap(), ["creation", "created"]);
+    drop(t);
+    lua.gc_collect()?;
+    assert_eq!(
+        *events.lock().unwrap(),
+        ["creation", "created", "collection", "destroyed"]
+    );
+
+    // A creation error from any callback fails the thread creation
+    lua.set_thread_creation_callback(|_, _| Err(Error::runtime("vetoed")));
+    events.lock().unwrap().clear();
+    assert!(lua
+        .create_thread(lua.load("return 123").into_function()?)
+        .is_err());
+    assert!(events.lock().unwrap().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_pinned_thread_lifetime() -> Result<()> {
+    let lua = Lua::new();
+
+    let destroyed = Arc::new(Mutex::new(Vec::new()));
+    let destroyed2 = destroyed.clone();
+    lua.set_thread_event_callback(move |_, event| {
+        if let ThreadEvent::Destroyed(id) = event {
+            destroyed2.lock().unwrap().push(id);
+        }
+        Ok(())
+    });
+
+    let thread = lua.create_thread(lua.load("coroutine.yield(1); return 2").into_function()?)?;
+    let thread_id = thread.id();
+    let pinned_id = thread.pin()?;
+
+    // Pinned thread survives GC without a Rust handle
+    drop(thread);
+    lua.gc_collect()?;
+    assert!(!destroyed.lock().unwrap().contains(&thread_id));
+    assert_eq!(lua.resume_pinned::<i32>(pinned_id, ())?, 1);
+    assert_eq!(lua.resume_pinned::<i32>(pinned_id, ())?, 2);
+
+    // Unpinning is idempotent and lets the thread be collected
+    lua.unpin(pinned_id)?;
+    lua.unpin(pinned_id)?;
+    lua.gc_collect()?;
+    assert!(destroyed.lock().unwrap().contains(&thread_id));
+
     Ok(())
 }
 
@@ -449,3 +1409,80 @@ fn test_typeof_error() -> Result<()> {
 
 #[path = "luau/require.rs"]
 mod require;
+
+#[test]
+fn test_type_info() -> Result<()> {
+    let lua = Lua::new();
+
+    let info = lua.type_info(
+        r#"
+        --!strict
+        export type Point = { x: number, y: number }
+        type Callback<T> = (T) -> ()
+
+        local origin: Point = { x = 0, y = 0 }
+        local count = 10
+        local name, tags: { string }? = "a", nil
+
+        local function add(a: Point, b: Point): Point
+            local inner = 1 -- not top-level
+            return { x = a.x + b.x, y = a.y + b.y }
+        end
+
+        function log(fmt: string, ...: any)
+            print(string.format(fmt, ...))
+        end
+
+        function origin.reset<T>(self, value) end
+
+        --[[ version = "ignored" ]]
+        version = "1.0"
+        enabled = count > 5
+        handler = function(cb: Callback<number>) end
+        count = 11
+    "#,
+    )?;
+
+    let list = info
+        .declarations()
+        .iter()
+        .map(|d| (d.name.as_str(), d.kind, d.type_name.as_deref(), d.line))
+        .collect::<Vec<_>>();
+    use mlua::DeclarationKind::*;
+    assert_eq!(
+        list,
+        [
+            ("Point", ExportedType, Some("{ x: number, y: number }"), 3),
+            ("Callback", Type, Some("(T) -> ()"), 4),
+            ("origin", Local, Some("Point"), 6),
+            ("count", Local, Some("number"), 7),
+            ("name", Local, None, 8),
+            ("tags", Local, Some("{ string }?"), 8),
+            ("add", LocalFunction, Some("(a: Point, b: Point) -> Point"), 10),
+            ("log", Function, Some("(fmt: string, ...any) -> ...any"), 15),
+            (
+                "origin.reset",
+                Function,
+                Some("<T>(self: any, value: any) -> ...any"),
+                19
+            ),
+            ("version", Global, Some("string"), 22),
+            ("enabled", Global, None, 23),
+            ("handler", Global, Some("(cb: Callback<number>) -> ...any"), 24),
+        ]
+    );
+    assert_eq!(info.get("count").unwrap().kind, Local);
+    assert!(info.get("inner").is_none());
+
+    // Type errors are not reported
+    let info = lua.type_info("local x: number = \"not a number\"")?;
+    assert_eq!(info.get("x").unwrap().type_name.as_deref(), Some("number"));
+
+    // Syntax errors are reported with location
+    match lua.type_info("local x: number =\nlocal y") {
+        Err(Error::SyntaxError { message, .. }) => assert!(message.starts_with("2:"), "{message}"),
+        r => panic!("expected SyntaxError, got {r:?}"),
+    }
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
-use mlua::{Error, GCMode, Lua, Result, UserData};
+use mlua::{Error, GCMode, Lua, LuaOptions, Result, StdLib, UserData};
 
 #[test]
 fn test_memory_limit() -> Result<()> {
@@ -24,7 +25,7 @@ fn test_memory_limit() -> Result<()> {
 
     lua.set_memory_limit(initial_memory + 10000)?;
     match f.call::<()>(()) {
-        Err(Error::MemoryError(_)) => {}
+        Err(Error::OutOfMemory { .. }) => {}
         something_else => panic!("did not trigger memory error: {:?}", something_else),
     };
 
@@ -37,7 +38,7 @@ fn test_memory_limit() -> Result<()> {
         .load("local t = {}; for i = 1,10000 do t[i] = i end")
         .into_function()
     {
-        Err(Error::MemoryError(_)) => {}
+        Err(Error::OutOfMemory { .. }) => {}
         _ => panic!("did not trigger memory error"),
     };
 
@@ -60,13 +61,145 @@ fn test_memory_limit_thread() -> Result<()> {
     let thread = lua.create_thread(f)?;
     lua.set_memory_limit(lua.used_memory() + 10000)?;
     match thread.resume::<()>(()) {
-        Err(Error::MemoryError(_)) => {}
+        Err(Error::OutOfMemory { .. }) => {}
         something_else => panic!("did not trigger memory error: {:?}", something_else),
     };
 
     Ok(())
 }
 
+#[test]
+fn test_out_of_memory() -> Result<()> {
+    let lua = Lua::new();
+
+    if cfg!(feature = "luajit") && lua.set_memory_limit(0).is_err() {
+        // seems this luajit version does not support memory limit
+        return Ok(());
+    }
+
+    let failures = Arc::new(AtomicUsize::new(0));
+    let failures2 = failures.clone();
+    lua.set_oom_callback(move |err| {
+        if matches!(err, Error::OutOfMemory { .. }) {
+            failures2.fetch_add(1, Ordering::Relaxed);
+        }
+    })?;
+
+    let f = lua
+        .load("local t = {} while true do t[#t + 1] = {} end")
+        .into_function()?;
+    let limit = lua.used_memory() + 16 * 1024;
+    lua.set_memory_limit(limit)?;
+    match f.call::<()>(()) {
+        Err(Error::OutOfMemory {
+            requested,
+            limit: err_limit,
+            used,
+        }) => {
+            assert_eq!(err_limit, limit);
+            assert!(requested > 0);
+            assert!(used + requested > limit);
+        }
+        something_else => panic!("did not trigger out of memory error: {:?}", something_else),
+    };
+    assert!(failures.load(Ordering::Relaxed) > 0);
+
+    // Lua code can catch the error using `pcall`
+    lua.gc_collect()?;
+    lua.globals().set("f", f)?;
+    let (ok, msg): (bool, String) = lua.load("return pcall(f)").eval()?;
+    assert!(!ok);
+    assert!(msg.contains("not enough memory"), "unexpected message: {msg}");
+
+    // The callback is not called after removal
+    lua.remove_oom_callback();
+    let failures_before = failures.load(Ordering::Relaxed);
+    lua.gc_collect()?;
+    assert!(matches!(lua.load("f()").exec(), Err(Error::OutOfMemory { .. })));
+    assert_eq!(failures.load(Ordering::Relaxed), failures_before);
+
+    Ok(())
+}
+
+#[test]
+fn test_size_limits() -> Result<()> {
+    let options = LuaOptions::new().max_string_bytes(1024);
+    #[cfg(feature = "luau")]
+    let options = options.max_table_entries(100);
+    let lua = Lua::new_with(StdLib::ALL_SAFE, options.clone())?;
+
+    // Results within the limits are allowed
+    assert_eq!(lua.load("return #string.rep('ab', 512)").eval::<usize>()?, 1024);
+    #[cfg(any(feature = "lua54", feature = "lua53", feature = "lua52"))]
+    assert_eq!(lua.load("return #('x'):rep(300, ', ')").eval::<usize>()?, 898);
+    let s: String = lua
+        .load("local t = {} for i = 1, 100 do table.insert(t, 'x') end return table.concat(t, '-')")
+        .eval()?;
+    assert_eq!(s.len(), 199);
+
+    let check_error = |code: &str, msg: &str| match lua.load(code).exec() {
+        Err(err) => assert!(err.to_string().contains(msg), "unexpected error: {err}"),
+        Ok(()) => panic!("expected error for `{code}`"),
+    };
+    check_error(
+        "string.rep('ab', 513)",
+        "resulting string is too large (limit is 1024 bytes)",
+    );
+    #[cfg(any(feature = "lua54", feature = "lua53", feature = "lua52"))]
+    check_error("('x'):rep(1000, ', ')", "resulting string is too large");
+    check_error(
+        "local t = {} for i = 1, 100 do t[i] = 12345678901 end table.concat(t)",
+        "resulting string is too large",
+    );
+    #[cfg(feature = "luau")]
+    check_error(
+        "table.create(101, 0)",
+        "table is too large (limit is 100 entries)",
+    );
+
+    // Errors of the original functions are preserved
+    check_error("string.rep({}, 1)", "(string expected, got table)");
+
+    // Other ways to build strings and tables are bounded by the memory limit only
+    lua.load(
+        r#"
+        local s = string.rep("x", 1000)
+        s = s .. s
+        assert(#string.format("%s%s", s, s) == 4000)
+        local t = {}
+        for i = 1, 200 do t[#t + 1] = i end
+        t = {(table.unpack or unpack)(t)}
+        for i = 1, 200 do table.insert(t, i) end
+        assert(#t == 400)
+        "#,
+    )
+    .exec()?;
+    #[cfg(not(feature = "luajit"))]
+    {
+        lua.set_memory_limit(lua.used_memory() + 64 * 1024)?;
+        assert!(matches!(
+            lua.load("local s = 'x' while true do s = s .. s end").exec(),
+            Err(Error::OutOfMemory { .. })
+        ));
+        assert!(matches!(
+            lua.load("local t = {} while true do t[#t + 1] = true end").exec(),
+            Err(Error::OutOfMemory { .. })
+        ));
+        lua.set_memory_limit(0)?;
+    }
+
+    // Limits are applied to libraries loaded later
+    let lua = Lua::new_with(StdLib::NONE, options)?;
+    lua.load_std_libs(StdLib::STRING)?;
+    assert!(lua.load("string.rep('x', 1025)").exec().is_err());
+
+    // No limits by default
+    let lua = Lua::new();
+    assert_eq!(lua.load("return #string.rep('x', 4096)").eval::<usize>()?, 4096);
+
+    Ok(())
+}
+
 #[test]
 fn test_gc_control() -> Result<()> {
     let lua = Lua::new();
@@ -104,6 +237,251 @@ fn test_gc_control() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_gc_step_kbytes() -> Result<()> {
+    let lua = Lua::new();
+
+    lua.gc_collect()?;
+    lua.gc_stop();
+    lua.load("for i = 1, 10000 do local t = {i} end").exec()?;
+    let used_memory = lua.used_memory();
+
+    // Run small steps until the collection cycle finishes
+    let mut steps = 0;
+    while !lua.gc_step_kbytes(1)? {
+        steps += 1;
+        assert!(steps < 100_000, "collection cycle did not finish");
+    }
+    assert!(lua.used_memory() < used_memory);
+    lua.gc_restart();
+
+    Ok(())
+}
+
+#[test]
+fn test_peak_memory() -> Result<()> {
+    let lua = Lua::new();
+
+    lua.load("local t = {} for i = 1, 100000 do t[i] = i end")
+        .exec()?;
+    lua.gc_collect()?;
+    lua.gc_collect()?;
+    assert!(lua.peak_memory() > lua.used_memory());
+
+    lua.reset_peak_memory();
+    assert!(lua.peak_memory() <= lua.used_memory() + 1024);
+
+    // Tracking is disabled
+    let lua = Lua::new_with(StdLib::ALL_SAFE, LuaOptions::new().track_peak_memory(false))?;
+    lua.load("local t = {} for i = 1, 100000 do t[i] = i end")
+        .exec()?;
+    lua.gc_collect()?;
+    assert_eq!(lua.peak_memory(), lua.used_memory());
+
+    Ok(())
+}
+
+#[cfg(not(feature = "luajit"))]
+#[test]
+fn test_custom_allocator() -> Result<()> {
+    use std::alloc::{self, Layout};
+
+    use mlua::{ffi, LuaAllocator};
+
+    #[derive(Default)]
+    struct Counters {
+        allocated: AtomicUsize,
+        freed: AtomicUsize,
+    }
+
+    struct CountingAllocator(Arc<Counters>);
+
+    unsafe impl LuaAllocator for CountingAllocator {
+        fn alloc(&mut self, size: usize) -> *mut u8 {
+            self.0.allocated.fetch_add(size, Ordering::Relaxed);
+            unsafe { alloc::alloc(Layout::from_size_align_unchecked(size, ffi::SYS_MIN_ALIGN)) }
+        }
+
+        unsafe fn realloc(&mut self, ptr: *mut u8, osize: usize, nsize: usize) -> *mut u8 {
+            self.0.allocated.fetch_add(nsize, Ordering::Relaxed);
+            self.0.freed.fetch_add(osize, Ordering::Relaxed);
+            let layout = Layout::from_size_align_unchecked(osize, ffi::SYS_MIN_ALIGN);
+            alloc::realloc(ptr, layout, nsize)
+        }
+
+        unsafe fn free(&mut self, ptr: *mut u8, size: usize) {
+            self.0.freed.fetch_add(size, Ordering::Relaxed);
+            alloc::dealloc(ptr, Layout::from_size_align_unchecked(size, ffi::SYS_MIN_ALIGN));
+        }
+    }
+
+    let counters = Arc::new(Counters::default());
+    let allocator = CountingAllocator(counters.clone());
+    let lua = Lua::new_with_allocator(StdLib::ALL_SAFE, LuaOptions::new(), allocator)?;
+
+    lua.load("local t = {} for i = 1, 1000 do t[i] = tostring(i) end")
+        .exec()?;
+    let live = counters.allocated.load(Ordering::Relaxed) - counters.freed.load(Ordering::Relaxed);
+    assert_eq!(live, lua.used_memory());
+
+    // Memory limit works on top of the allocator
+    lua.set_memory_limit(lua.used_memory() + 10000)?;
+    match lua.load("local t = {} for i = 1, 10000 do t[i] = i end").exec() {
+        Err(Error::OutOfMemory { .. }) => {}
+        something_else => panic!("did not trigger memory error: {:?}", something_else),
+    };
+    lua.set_memory_limit(0)?;
+
+    drop(lua);
+    assert_eq!(Arc::strong_count(&counters), 1);
+    assert_eq!(
+        counters.allocated.load(Ordering::Relaxed),
+        counters.freed.load(Ordering::Relaxed)
+    );
+
+    Ok(())
+}
+
+#[cfg(not(feature = "luajit"))]
+#[test]
+fn test_custom_allocator_failure() {
+    use mlua::LuaAllocator;
+
+    struct FailingAllocator;
+
+    unsafe impl LuaAllocator for FailingAllocator {
+        fn alloc(&mut self, _size: usize) -> *mut u8 {
+            std::ptr::null_mut()
+        }
+
+        unsafe fn realloc(&mut self, _ptr: *mut u8, _osize: usize, _nsize: usize) -> *mut u8 {
+            std::ptr::null_mut()
+        }
+
+        unsafe fn free(&mut self, _ptr: *mut u8, _size: usize) {}
+    }
+
+    match Lua::new_with_allocator(StdLib::ALL_SAFE, LuaOptions::new(), FailingAllocator) {
+        Err(Error::MemoryError(_)) => {}
+        Err(err) => panic!("expected MemoryError, got {err:?}"),
+        Ok(_) => panic!("expected MemoryError"),
+    }
+}
+
+#[cfg(not(feature = "luajit"))]
+#[test]
+fn test_oom_error_cleared() -> Result<()> {
+    use std::alloc::{self, Layout};
+
+    use mlua::{ffi, LuaAllocator};
+
+    // Fails allocations larger than 1 MiB
+    struct SmallAllocator;
+
+    const MAX_SIZE: usize = 1024 * 1024;
+
+    unsafe impl LuaAllocator for SmallAllocator {
+        fn alloc(&mut self, size: usize) -> *mut u8 {
+            if size > MAX_SIZE {
+                return std::ptr::null_mut();
+            }
+            unsafe { alloc::alloc(Layout::from_size_align_unchecked(size, ffi::SYS_MIN_ALIGN)) }
+        }
+
+        unsafe fn realloc(&mut self, ptr: *mut u8, osize: usize, nsize: usize) -> *mut u8 {
+            if nsize > MAX_SIZE {
+                return std::ptr::null_mut();
+            }
+            alloc::realloc(
+                ptr,
+                Layout::from_size_align_unchecked(osize, ffi::SYS_MIN_ALIGN),
+                nsize,
+            )
+        }
+
+        unsafe fn free(&mut self, ptr: *mut u8, size: usize) {
+            alloc::dealloc(ptr, Layout::from_size_align_unchecked(size, ffi::SYS_MIN_ALIGN));
+        }
+    }
+
+    let lua = Lua::new_with_allocator(StdLib::ALL_SAFE, LuaOptions::new(), SmallAllocator)?;
+
+    // Reach the memory limit and catch the error in Lua
+    let f = lua
+        .load("local t = {} while true do t[#t + 1] = {} end")
+        .into_function()?;
+    lua.set_memory_limit(lua.used_memory() + 16 * 1024)?;
+    let ok: bool = lua.load("return (pcall(...))").call(f)?;
+    assert!(!ok);
+    lua.set_memory_limit(0)?;
+    lua.gc_collect()?;
+
+    // A later allocation failure not caused by the limit does not report the stale error
+    match lua.load("return string.rep('x', 4 * 1024 * 1024)").exec() {
+        Err(Error::MemoryError(_)) => {}
+        something_else => panic!("expected MemoryError, got {something_else:?}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_gc_pause_guard() -> Result<()> {
+    let lua = Lua::new();
+
+    {
+        let _outer = lua.gc_pause_guard();
+        {
+            let _inner = lua.gc_pause_guard().step_on_drop(1);
+            #[cfg(any(feature = "lua54", feature = "lua53", feature = "lua52", feature = "luau"))]
+            assert!(!lua.gc_is_running());
+            lua.load("for i = 1, 10000 do local t = {i} end").exec()?;
+        }
+        // The outer guard keeps the collector stopped
+        #[cfg(any(feature = "lua54", feature = "lua53", feature = "lua52", feature = "luau"))]
+        assert!(!lua.gc_is_running());
+    }
+    #[cfg(any(feature = "lua54", feature = "lua53", feature = "lua52", feature = "luau"))]
+    assert!(lua.gc_is_running());
+
+    let used_memory = lua.used_memory();
+    lua.gc_collect()?;
+    assert!(lua.used_memory() < used_memory);
+
+    // The collector stays stopped if it was stopped before
+    #[cfg(any(feature = "lua54", feature = "lua53", feature = "lua52", feature = "luau"))]
+    {
+        lua.gc_stop();
+        drop(lua.gc_pause_guard());
+        assert!(!lua.gc_is_running());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_gc_parameters() -> Result<()> {
+    let lua = Lua::new();
+
+    // Setters return the previous value
+    lua.gc_set_pause(160);
+    assert_eq!(lua.gc_set_pause(240), 160);
+    assert_eq!(lua.gc_set_pause(200), 240);
+
+    lua.gc_set_step_multiplier(120);
+    assert_eq!(lua.gc_set_step_multiplier(400), 120);
+    #[cfg(feature = "lua53")]
+    assert_eq!(lua.gc_set_step_multiplier(10), 400);
+    #[cfg(feature = "lua53")]
+    assert_eq!(lua.gc_set_step_multiplier(200), 40);
+
+    // The collector still works with the new parameters
+    lua.load("local t = {} for i = 1, 1000 do t[i] = {} end").exec()?;
+    lua.gc_collect()?;
+
+    Ok(())
+}
+
 #[cfg(any(feature = "lua53", feature = "lua52"))]
 #[test]
 fn test_gc_error() {
@@ -131,3 +509,20 @@ fn test_gc_error() {
         Ok(()) => panic!("__gc error did not result in error"),
     }
 }
+
+#[test]
+fn test_collect_and_shrink() -> Result<()> {
+    let lua = Lua::new();
+    let initial_memory = lua.used_memory();
+
+    lua.load("big = {} for i = 1, 100000 do big[i] = tostring(i) end")
+        .exec()?;
+    let peak_memory = lua.used_memory();
+    assert!(peak_memory > initial_memory + 1024 * 1024);
+
+    lua.globals().set("big", mlua::Nil)?;
+    lua.collect_and_shrink()?;
+    assert!(lua.used_memory() < initial_memory + 1024 * 1024);
+
+    Ok(())
+}
@@ -741,6 +741,62 @@ fn test_from_value_sorted() -> Result<(), Box<dyn StdError>> {
     Ok(())
 }
 
+#[test]
+fn test_create_table_from_json() -> LuaResult<()> {
+    let lua = Lua::new();
+
+    let table = lua.create_table_from_json(
+        r#"{"name": "fixture", "count": 3, "ratio": 0.5, "items": [1, "two", null], "empty": []}"#,
+    )?;
+    lua.globals().set("fixture", &table)?;
+    lua.globals().set("null", lua.null())?;
+    lua.load(
+        r#"
+        assert(fixture.name == "fixture")
+        assert(fixture.count == 3)
+        assert(fixture.ratio == 0.5)
+        assert(#fixture.items == 3)
+        assert(fixture.items[2] == "two")
+        assert(fixture.items[3] == null)
+    "#,
+    )
+    .exec()?;
+    let array_mt = Some(lua.array_metatable());
+    assert_eq!(table.get::<mlua::Table>("items")?.metatable(), array_mt);
+    assert_eq!(table.get::<mlua::Table>("empty")?.metatable(), array_mt);
+    assert_eq!(table.metatable(), None);
+
+    // Parse errors include the position
+    match lua.create_table_from_json("{\"a\": [1, 2,]}") {
+        Err(Error::DeserializeError(msg)) => assert!(msg.contains("line 1 column"), "{msg}"),
+        r => panic!("expected DeserializeError, got {r:?}"),
+    }
+
+    // String escapes, including surrogate pairs
+    let table = lua.create_table_from_json(r#"["a\"b\n", "\u00e9\ud83d\ude00", 1e2, -0]"#)?;
+    assert_eq!(table.get::<String>(1)?, "a\"b\n");
+    assert_eq!(table.get::<String>(2)?, "\u{e9}\u{1f600}");
+    assert_eq!(table.get::<f64>(3)?, 100.0);
+    let negative_zero = table.get::<f64>(4)?;
+    assert!(negative_zero == 0.0 && negative_zero.is_sign_negative());
+    assert!(matches!(table.get::<Value>(4)?, Value::Number(_)));
+
+    // Deeply nested documents are rejected
+    let nested = format!("{}{}", "[".repeat(1000), "]".repeat(1000));
+    match lua.create_table_from_json(&nested) {
+        Err(Error::DeserializeError(msg)) => assert!(msg.contains("recursion limit"), "{msg}"),
+        r => panic!("expected DeserializeError, got {r:?}"),
+    }
+
+    // Scalars cannot be converted to a table
+    assert!(matches!(
+        lua.create_table_from_json("123"),
+        Err(Error::FromLuaConversionError { .. })
+    ));
+
+    Ok(())
+}
+
 #[test]
 fn test_arbitrary_precision() {
     let lua = Lua::new();
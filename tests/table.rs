@@ -1,4 +1,4 @@
-use mlua::{Error, Lua, ObjectLike, Result, Table, Value};
+use mlua::{Error, Lua, MergeConflict, MergePolicy, ObjectLike, Result, Table, Value};
 
 #[test]
 fn test_globals_set_get() -> Result<()> {
@@ -133,6 +133,98 @@ fn test_table_insert_remove() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_table_insert_sorted() -> Result<()> {
+    let lua = Lua::new();
+
+    // Events ordered by time, `{time, name}`
+    let events = lua.create_table()?;
+    let by_time = |a: &Value, b: &Value| {
+        let time = |v: &Value| v.as_table().unwrap().raw_get::<i64>(1).unwrap();
+        time(a).cmp(&time(b))
+    };
+    let event = |time: i64, name: &str| -> Result<Value> {
+        let event = lua.create_table()?;
+        event.raw_set(1, time)?;
+        event.raw_set(2, name)?;
+        Ok(Value::Table(event))
+    };
+    assert_eq!(events.insert_sorted(event(30, "c")?, by_time)?, 1);
+    assert_eq!(events.insert_sorted(event(10, "a")?, by_time)?, 1);
+    assert_eq!(events.insert_sorted(event(20, "b")?, by_time)?, 2);
+    assert_eq!(events.insert_sorted(event(40, "d")?, by_time)?, 4);
+    // Equal elements keep the insertion order
+    assert_eq!(events.insert_sorted(event(20, "b2")?, by_time)?, 3);
+
+    let names = events
+        .sequence_values::<Table>()
+        .map(|t| t?.raw_get::<String>(2))
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(names, vec!["a", "b", "b2", "c", "d"]);
+
+    // Plain numbers
+    let numbers = lua.create_sequence_from([1, 3, 5, 7])?;
+    let cmp = |a: &Value, b: &Value| a.as_i64().cmp(&b.as_i64());
+    assert_eq!(numbers.insert_sorted(Value::Integer(4), cmp)?, 3);
+    assert_eq!(numbers.insert_sorted(Value::Integer(0), cmp)?, 1);
+    assert_eq!(
+        numbers.sequence_values().collect::<Result<Vec<i64>>>()?,
+        vec![0, 1, 3, 4, 5, 7]
+    );
+
+    #[cfg(feature = "luau")]
+    {
+        numbers.set_readonly(true);
+        let err = numbers.insert_sorted(Value::Integer(2), cmp).unwrap_err();
+        assert!(err.to_string().contains("attempt to modify a readonly table"));
+        assert_eq!(numbers.raw_len(), 6);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_table_swap_remove() -> Result<()> {
+    let lua = Lua::new();
+
+    let t1 = lua.create_sequence_from([1, 2, 3, 4, 5])?;
+    let t2 = lua.create_sequence_from([1, 2, 3, 4, 5])?;
+
+    // Removes the same element as `raw_remove`, but moves the last element into its place
+    let removed = t1.raw_get::<i64>(2)?;
+    t1.raw_remove(2)?;
+    assert_eq!(t2.swap_remove::<i64>(2)?, removed);
+    assert_eq!(t1.raw_len(), t2.raw_len());
+    assert_eq!(t1, [1, 3, 4, 5]);
+    assert_eq!(t2, [1, 5, 3, 4]);
+
+    // Removing the last element is the same as `raw_pop`
+    assert_eq!(t2.swap_remove::<i64>(4)?, 4);
+    assert_eq!(t2, [1, 5, 3]);
+    assert_eq!(t2.swap_remove::<i64>(1)?, 1);
+    assert_eq!(t2.swap_remove::<i64>(2)?, 5);
+    assert_eq!(t2.swap_remove::<Value>(1)?, Value::Integer(3));
+    assert_eq!(t2.raw_len(), 0);
+
+    // Wrong index
+    assert!(t1.swap_remove::<Value>(0).is_err());
+    assert!(t1.swap_remove::<Value>(5).is_err());
+    assert!(t2.swap_remove::<Value>(1).is_err());
+
+    // Check readonly error
+    #[cfg(feature = "luau")]
+    {
+        t1.set_readonly(true);
+        assert!(matches!(
+            t1.swap_remove::<Value>(1),
+            Err(Error::RuntimeError(err)) if err.contains("attempt to modify a readonly table")
+        ));
+        assert_eq!(t1.raw_len(), 4);
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_table_clear() -> Result<()> {
     let lua = Lua::new();
@@ -231,6 +323,163 @@ fn test_table_pairs() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_table_pairs_raw() -> Result<()> {
+    let lua = Lua::new();
+
+    let table = lua.create_table()?;
+    for i in 1..=100 {
+        table.set(format!("key{i}"), i)?;
+    }
+
+    let mut visited = 0;
+    for kv in table.pairs_raw::<String, i64>() {
+        let (k, v) = kv?;
+        assert_eq!(k, format!("key{v}"));
+        visited += 1;
+        // Remove the current and some other keys, insert new keys, force rehash and collection
+        table.set(k, Value::Nil)?;
+        if v % 2 == 0 {
+            table.set(format!("key{}", v + 1), Value::Nil)?;
+        }
+        table.set(format!("new{v}"), v)?;
+        lua.gc_collect()?;
+    }
+    // Removed keys are skipped, new keys are not visited
+    assert!(visited > 0 && visited < 100);
+    assert_eq!(table.pairs::<String, i64>().count(), visited);
+    assert!(table
+        .pairs_raw::<String, i64>()
+        .all(|kv| kv.unwrap().0.starts_with("new")));
+
+    // Conversion errors are returned for individual pairs
+    let table = lua.create_table_from([(1, "a"), (2, "b")])?;
+    let results = table.pairs_raw::<i64, i64>().collect::<Vec<_>>();
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|r| r.is_err()));
+
+    Ok(())
+}
+
+#[test]
+fn test_table_keys_values() -> Result<()> {
+    let lua = Lua::new();
+
+    let table: Table = lua.load(r#"{10, 20, 30, x = 40, y = "s"}"#).eval()?;
+
+    // Array part goes first
+    let keys = table.keys::<Value>().collect::<Result<Vec<_>>>()?;
+    assert_eq!(keys.len(), 5);
+    assert_eq!(
+        keys[..3],
+        [Value::Integer(1), Value::Integer(2), Value::Integer(3)]
+    );
+    let values = table.values::<Value>().collect::<Result<Vec<_>>>()?;
+    assert_eq!(
+        values[..3],
+        [Value::Integer(10), Value::Integer(20), Value::Integer(30)]
+    );
+
+    // Conversion errors do not stop the iteration
+    let keys = table.keys::<i64>().collect::<Vec<_>>();
+    assert_eq!(keys.len(), 5);
+    assert_eq!(keys.iter().filter(|k| k.is_err()).count(), 2);
+    let mut numbers = table.values::<i64>().filter_map(Result::ok).collect::<Vec<_>>();
+    numbers.sort();
+    assert_eq!(numbers, [10, 20, 30, 40]);
+
+    Ok(())
+}
+
+#[test]
+fn test_table_merge() -> Result<()> {
+    let lua = Lua::new();
+
+    let defaults =
+        r#"{ host = "localhost", port = 80, tags = { "a", "b" }, tls = { enabled = false, verify = true } }"#;
+    let overrides: Table = lua
+        .load(r#"{ port = 8080, tags = { "c" }, tls = { enabled = true } }"#)
+        .eval()?;
+
+    // Shallow merge replaces nested tables
+    let config: Table = lua.load(defaults).eval()?;
+    config.merge(&overrides, MergePolicy::new())?;
+    assert_eq!(config.get::<String>("host")?, "localhost");
+    assert_eq!(config.get::<i64>("port")?, 8080);
+    assert_eq!(config.get::<Table>("tls")?, overrides.get::<Table>("tls")?);
+
+    // Keep existing values
+    let config: Table = lua.load(defaults).eval()?;
+    config.merge(
+        &overrides,
+        MergePolicy::new().on_conflict(MergeConflict::KeepExisting),
+    )?;
+    assert_eq!(config.get::<i64>("port")?, 80);
+    assert_eq!(config.get::<Vec<String>>("tags")?, ["a", "b"]);
+
+    // Deep merge with concatenated arrays
+    let config: Table = lua.load(defaults).eval()?;
+    config.deep_merge(&overrides, MergePolicy::new().concat_arrays(true))?;
+    assert_eq!(config.get::<Vec<String>>("tags")?, ["a", "b", "c"]);
+    let tls = config.get::<Table>("tls")?;
+    assert!(tls.get::<bool>("enabled")? && tls.get::<bool>("verify")?);
+
+    // Deep merge replaces arrays by default
+    let config: Table = lua.load(defaults).eval()?;
+    config.deep_merge(&overrides, MergePolicy::new())?;
+    assert_eq!(config.get::<Vec<String>>("tags")?, ["c"]);
+
+    // Deep merge copies nested tables, so merging into the result leaves the source untouched
+    let base: Table = lua.load(defaults).eval()?;
+    let config = lua.create_table()?;
+    config.deep_merge(&base, MergePolicy::new())?;
+    config.deep_merge(&overrides, MergePolicy::new().concat_arrays(true))?;
+    assert!(config.get::<Table>("tls")?.get::<bool>("enabled")?);
+    assert!(!base.get::<Table>("tls")?.get::<bool>("enabled")?);
+    assert_eq!(base.get::<Vec<String>>("tags")?, ["a", "b"]);
+    assert_ne!(config.get::<Table>("tls")?, base.get::<Table>("tls")?);
+
+    // Shared and recursive tables of the source are copied once
+    let source: Table = lua.load("local t = {} t.t = t return { a = t, b = t }").eval()?;
+    let copy = lua.create_table()?;
+    copy.deep_merge(&source, MergePolicy::new())?;
+    let a = copy.get::<Table>("a")?;
+    assert_ne!(a, source.get::<Table>("a")?);
+    assert_eq!(a, copy.get::<Table>("b")?);
+    assert_eq!(a, a.get::<Table>("t")?);
+
+    // Conflicts are reported without modifying the table
+    let config: Table = lua.load(defaults).eval()?;
+    let overrides: Table = lua.load("{ debug = true, tls = { verify = false } }").eval()?;
+    let policy = MergePolicy::new().on_conflict(MergeConflict::Error);
+    match config.deep_merge(&overrides, policy) {
+        Err(Error::RuntimeError(msg)) => assert_eq!(msg, "conflicting values at key 'tls.verify'"),
+        r => panic!("expected RuntimeError, got {r:?}"),
+    }
+    assert_eq!(config.get::<Value>("debug")?, Value::Nil);
+
+    // Top-level arrays
+    let list: Table = lua.load("{ 1, 2 }").eval()?;
+    list.merge(&lua.load("{ 3 }").eval()?, MergePolicy::new().concat_arrays(true))?;
+    assert_eq!(list, [1, 2, 3]);
+
+    // Recursive tables
+    let target: Table = lua.load("local t = {} t.t = t return t").eval()?;
+    let source: Table = lua.load("local t = {} t.t = t return t").eval()?;
+    assert!(target.deep_merge(&source, MergePolicy::new()).is_err());
+
+    #[cfg(feature = "luau")]
+    {
+        let config: Table = lua.load(defaults).eval()?;
+        config.get::<Table>("tls")?.set_readonly(true);
+        let err = config.deep_merge(&overrides, MergePolicy::new()).unwrap_err();
+        assert!(err.to_string().contains("readonly"), "{err}");
+        assert_eq!(config.get::<Value>("debug")?, Value::Nil);
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_table_for_each() -> Result<()> {
     let lua = Lua::new();
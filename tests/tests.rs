@@ -3,12 +3,12 @@ use std::collections::HashMap;
 use std::iter::FromIterator;
 use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::string::String as StdString;
-use std::sync::Arc;
-use std::{error, f32, f64, fmt};
+use std::sync::{Arc, Mutex};
+use std::{error, f32, f64, fmt, io};
 
 use mlua::{
-    ffi, ChunkMode, Error, ExternalError, Function, Lua, LuaOptions, Nil, Result, StdLib, String, Table,
-    UserData, Value, Variadic,
+    ffi, CapabilityTable, ChunkMode, ClockKind, Error, ExternalError, Function, Lua, LuaOptions, MultiValue,
+    Nil, RandomSource, Result, StdLib, String, Table, UserData, Value, Variadic,
 };
 
 #[test]
@@ -29,6 +29,66 @@ fn test_weak_lua_panic() {
     let _ = weak_lua.upgrade();
 }
 
+#[test]
+fn test_stdlib_without_debug() -> Result<()> {
+    let libs = StdLib::ALL_SAFE & !StdLib::DEBUG;
+    assert!(!libs.contains(StdLib::DEBUG));
+    assert!(libs.contains(StdLib::STRING) && libs.contains(StdLib::MATH));
+
+    let lua = Lua::new_with(libs, LuaOptions::default())?;
+    let globals = lua.globals();
+    assert_eq!(globals.get::<Value>("debug")?, Value::Nil);
+    for lib in ["string", "table", "math", "os"] {
+        assert!(globals.get::<Value>(lib)?.is_table(), "`{lib}` is not loaded");
+    }
+    #[cfg(any(feature = "lua54", feature = "lua53", feature = "lua52", feature = "luau"))]
+    assert!(globals.get::<Value>("coroutine")?.is_table());
+    assert!(lua.load("return debug.traceback()").exec().is_err());
+
+    // `debug` is still not available after loading other libraries
+    lua.load_std_libs(StdLib::STRING)?;
+    assert_eq!(globals.get::<Value>("debug")?, Value::Nil);
+
+    Ok(())
+}
+
+#[test]
+fn test_new_sandboxed() -> Result<()> {
+    let lua = Lua::new_sandboxed()?;
+
+    assert_eq!(lua.load("return string.upper('ok')").eval::<String>()?, "OK");
+    let err = lua.load("return missing_global").exec().unwrap_err();
+    assert!(
+        err.to_string().contains("undefined global 'missing_global'"),
+        "{err}"
+    );
+    assert_eq!(lua.globals().raw_get::<Value>("missing_global")?, Value::Nil);
+
+    // Globals assigned by a script are readable
+    lua.load("declared = 1; assert(declared == 1)").exec()?;
+
+    // Libraries and functions with access to the host are not available
+    for name in ["io", "package", "require", "load", "loadfile", "dofile"] {
+        assert_eq!(lua.globals().raw_get::<Value>(name)?, Value::Nil, "{name}");
+    }
+    #[cfg(not(feature = "luau"))]
+    assert_eq!(lua.globals().raw_get::<Value>("os")?, Value::Nil);
+    let meta = lua.load("return getmetatable(_G)").eval::<Value>()?;
+    assert_eq!(meta, Value::Boolean(false));
+
+    // Memory is limited, but the limit can be changed
+    assert!(lua
+        .load("local t = {} for i = 1, 1e8 do t[i] = i end")
+        .exec()
+        .is_err());
+    lua.set_memory_limit(0)?;
+
+    #[cfg(feature = "luau")]
+    assert!(lua.globals().is_readonly());
+
+    Ok(())
+}
+
 #[cfg(not(feature = "luau"))]
 #[test]
 fn test_safety() -> Result<()> {
@@ -500,6 +560,69 @@ fn test_panic() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_panic_backtrace() -> Result<()> {
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    struct PanicPayload {
+        code: i32,
+    }
+
+    let options = LuaOptions::new().capture_panic_backtraces(true);
+    let lua = Lua::new_with(StdLib::ALL_SAFE, options)?;
+    let debug_panic = lua.create_function(|_, ()| -> Result<()> {
+        let payload: Box<dyn fmt::Debug + Send + Sync> = Box::new(PanicPayload { code: 42 });
+        std::panic::panic_any(payload)
+    })?;
+    let opaque_panic =
+        lua.create_function(|_, ()| -> Result<()> { std::panic::panic_any(PanicPayload { code: 1 }) })?;
+    let catch = lua
+        .load(
+            r#"
+        local ok, err = pcall(...)
+        assert(not ok)
+        caught = err
+        return err, tostring(err)
+    "#,
+        )
+        .into_function()?;
+
+    // Payloads with `Debug` are formatted
+    let (err, message) = catch.call::<(Error, StdString)>(debug_panic)?;
+    assert_eq!(message, "PanicPayload { code: 42 }");
+    match &err {
+        Error::RustPanic { message, .. } => assert_eq!(message, "PanicPayload { code: 42 }"),
+        err => panic!("expected RustPanic, got {err:?}"),
+    }
+    assert_eq!(err.to_string(), "rust panic: PanicPayload { code: 42 }");
+    assert!(err.panic_backtrace().is_some());
+
+    // Backtrace is found in wrapped errors
+    let wrapped = Error::CallbackError {
+        traceback: StdString::new(),
+        cause: Arc::new(err.clone()),
+    };
+    assert!(wrapped.panic_backtrace().is_some());
+    assert!(Error::runtime("error").panic_backtrace().is_none());
+
+    // Converting to `Error` does not consume the panic, raising it again resumes it
+    match catch_unwind(AssertUnwindSafe(|| lua.load("error(caught)").exec())) {
+        Ok(r) => panic!("no panic was detected: {r:?}"),
+        Err(p) => {
+            let payload = p.downcast::<Box<dyn fmt::Debug + Send + Sync>>().unwrap();
+            assert_eq!(format!("{payload:?}"), "PanicPayload { code: 42 }");
+        }
+    }
+
+    // Payloads of unknown types
+    let (err, message) = catch.call::<(Error, StdString)>(opaque_panic)?;
+    assert_eq!(message, "<panic>");
+    assert!(matches!(err, Error::RustPanic { ref message, .. } if message == "<panic>"));
+    assert!(err.panic_backtrace().is_some());
+
+    Ok(())
+}
+
 #[cfg(target_pointer_width = "64")]
 #[test]
 fn test_safe_integers() -> Result<()> {
@@ -856,6 +979,58 @@ fn test_registry_value_reuse() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_registry_size() -> Result<()> {
+    let lua = Lua::new();
+    assert_eq!(lua.registry_size(), 0);
+
+    let r1 = lua.create_registry_value("value1")?;
+    let mut r2 = lua.create_registry_value(lua.create_table()?)?;
+    let _r3 = lua.create_registry_value(Nil)?;
+    lua.set_named_registry_value("named", 123)?;
+    assert_eq!(lua.registry_size(), 3);
+
+    let dump = lua.registry_dump();
+    assert_eq!(dump.len(), 3);
+    assert_eq!(dump[0].id, Some(r1.id()));
+    assert_eq!(dump[0].type_name, "string");
+    assert_eq!(dump[1].id, Some(r2.id()));
+    assert_eq!(dump[1].type_name, "table");
+    assert_eq!(dump[2].name.as_deref(), Some("named"));
+    assert_eq!(dump[2].type_name, "number");
+    #[cfg(debug_assertions)]
+    assert!(dump
+        .iter()
+        .all(|e| e.location.unwrap().file().ends_with("tests.rs")));
+
+    // Dropped keys are not counted
+    drop(r1);
+    assert_eq!(lua.registry_size(), 2);
+    lua.expire_registry_values();
+    assert_eq!(lua.registry_size(), 2);
+
+    lua.replace_registry_value(&mut r2, Nil)?;
+    lua.unset_named_registry_value("named")?;
+    assert_eq!(lua.registry_size(), 0);
+    assert!(lua.registry_dump().is_empty());
+
+    // Values stored internally by mlua are not included and cannot be reached by name
+    lua.set_print_handler(|_, _| Ok(()))?;
+    lua.set_clock_source(|_| 0.0)?;
+    let thread = lua.create_thread(lua.create_function(|_, ()| Ok(()))?)?;
+    thread.set_name("worker")?;
+    thread.set_max_resumes(1)?;
+    let _id = thread.pin()?;
+    assert_eq!(lua.registry_size(), 0);
+    assert!(lua.registry_dump().is_empty());
+    assert!(lua.named_registry_value::<Value>("__mlua_print")?.is_nil());
+    assert!(lua
+        .named_registry_value::<Value>("__mlua_pinned_threads")?
+        .is_nil());
+
+    Ok(())
+}
+
 #[test]
 fn test_application_data() -> Result<()> {
     let lua = Lua::new();
@@ -1244,6 +1419,71 @@ fn test_preload_module() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_traceback() -> Result<()> {
+    let lua = Lua::new();
+
+    // Not inside any function
+    assert!(lua.traceback_frames(0).frames().is_empty());
+    assert_eq!(lua.traceback(Some("msg"), 0), "msg\nstack traceback:");
+
+    let enter =
+        lua.create_function(|lua, ()| lua.globals().get::<Function>("inner")?.call::<MultiValue>(()))?;
+    let where_am_i = lua.create_function(|lua, level: usize| {
+        let frames = lua.traceback_frames(level);
+        let frames = (frames.frames().iter())
+            .map(|f| match f.is_native {
+                true => "[native]".to_string(),
+                false => format!("{}:{}", f.source.as_deref().unwrap(), f.line.unwrap()),
+            })
+            .collect::<Vec<_>>();
+        Ok((frames, lua.traceback(Some("here"), level)))
+    })?;
+    lua.globals().set("enter", enter)?;
+    lua.globals().set("where_am_i", where_am_i)?;
+
+    lua.load(
+        r#"
+        function inner()
+            local frames, traceback = where_am_i(LEVEL)
+            return frames, traceback
+        end
+        function outer()
+            local frames, traceback = enter()
+            return frames, traceback
+        end
+    "#,
+    )
+    .set_name("=chunk")
+    .exec()?;
+
+    let outer = lua.globals().get::<Function>("outer")?;
+    lua.globals().set("LEVEL", 0)?;
+    let (frames, traceback) = outer.call::<(Vec<StdString>, StdString)>(())?;
+    assert_eq!(frames, ["[native]", "chunk:3", "[native]", "chunk:7"]);
+    assert!(
+        traceback.starts_with("here\nstack traceback:\n\t[C]: in "),
+        "{traceback}"
+    );
+    assert!(traceback.contains("chunk:3:"), "{traceback}");
+
+    // Skip the callback and the `inner` function
+    lua.globals().set("LEVEL", 2)?;
+    let (frames, traceback) = outer.call::<(Vec<StdString>, StdString)>(())?;
+    assert_eq!(frames, ["[native]", "chunk:7"]);
+    assert!(!traceback.contains("chunk:3:"), "{traceback}");
+    assert!(traceback.contains("chunk:7:"), "{traceback}");
+
+    // Inside a coroutine the traceback is of the coroutine stack
+    lua.globals().set("LEVEL", 0)?;
+    let co = lua.create_thread(outer)?;
+    let (frames, traceback) = co.resume::<(Vec<StdString>, StdString)>(())?;
+    assert_eq!(frames, ["[native]", "chunk:3", "[native]", "chunk:7"]);
+    assert!(traceback.contains("chunk:7:"), "{traceback}");
+
+    Ok(())
+}
+
 #[test]
 fn test_inspect_stack() -> Result<()> {
     let lua = Lua::new();
@@ -1313,6 +1553,74 @@ fn test_inspect_stack() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_inspect_stack_locals() -> Result<()> {
+    let lua = Lua::new();
+
+    let inspect = lua.create_function(|lua, ()| {
+        let debug = lua.inspect_stack(1).unwrap(); // caller
+        let locals = (debug.locals().into_iter())
+            .map(|(name, value)| Ok((name, value.to_string()?)))
+            .collect::<Result<Vec<_>>>()?;
+        let mut upvalues = (debug.upvalues().into_iter())
+            .map(|(name, value)| match value {
+                Value::Function(_) => Ok((name, value.type_name().to_string())),
+                _ => Ok((name, value.to_string()?)),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        upvalues.sort();
+        Ok((format!("{locals:?}"), format!("{upvalues:?}")))
+    })?;
+    lua.globals().set("inspect", inspect)?;
+
+    let chunk = lua.load(
+        r#"
+        local prefix = "hello"
+        local inspect = inspect
+        local function f(arg)
+            local count = 2
+            local label = prefix .. " " .. arg
+            local locals, upvalues = inspect()
+            return locals, upvalues
+        end
+        return f("world")
+    "#,
+    );
+    // Luau keeps local variable names only with full debug information
+    #[cfg(feature = "luau")]
+    let chunk = chunk.set_compiler(mlua::Compiler::new().set_debug_level(2));
+    let (locals, upvalues): (StdString, StdString) = chunk.eval()?;
+    assert_eq!(
+        locals,
+        r#"[("arg", "world"), ("count", "2"), ("label", "hello world")]"#
+    );
+    assert_eq!(upvalues, r#"[("inspect", "function"), ("prefix", "hello")]"#);
+
+    // Without debug information, locals are not available and upvalues have no names
+    #[cfg(feature = "luau")]
+    {
+        let compiler = mlua::Compiler::new().set_debug_level(0);
+        let (locals, upvalues): (StdString, StdString) = lua
+            .load(
+                r#"
+            local prefix = tostring("hello")
+            local inspect = inspect
+            local function f()
+                local locals, upvalues = inspect(prefix)
+                return locals, upvalues
+            end
+            return f()
+        "#,
+            )
+            .set_compiler(compiler)
+            .eval()?;
+        assert_eq!(locals, "[]");
+        assert_eq!(upvalues, r#"[("?", "function"), ("?", "hello")]"#);
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_multi_states() -> Result<()> {
     let lua = Lua::new();
@@ -1504,3 +1812,345 @@ fn test_get_or_init_from_ptr() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_for_loop_semantics() -> Result<()> {
+    use mlua::ForLoopSemantics;
+
+    let lua = Lua::new();
+
+    let semantics = Lua::for_loop_semantics();
+    let last = lua.load("for i = 9007199254740993, 9007199254740993 do return i end");
+    match semantics {
+        ForLoopSemantics::Integer => {
+            assert_eq!(last.eval::<i64>()?, 9007199254740993);
+            let n: i64 = lua
+                .load("local n = 0; for i = math.maxinteger - 1, math.maxinteger do n = n + 1 end; return n")
+                .eval()?;
+            assert_eq!(n, 2);
+        }
+        ForLoopSemantics::IntegerWrapping => {
+            assert_eq!(last.eval::<i64>()?, 9007199254740993);
+            let wrapped: bool = lua
+                .load("for i = math.maxinteger - 1, math.maxinteger do if i < 0 then return true end end")
+                .eval()?;
+            assert!(wrapped);
+        }
+        ForLoopSemantics::Float => {
+            // The loop variable loses precision
+            assert_eq!(last.eval::<f64>()?, 9007199254740992.0);
+        }
+    }
+    assert_eq!(
+        semantics.is_integer(),
+        cfg!(any(feature = "lua54", feature = "lua53"))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_dump_stack() -> Result<()> {
+    let lua = Lua::new();
+
+    let f = lua.create_function(|lua, _: Variadic<Value>| {
+        let stack = lua.dump_stack();
+        // Dumping the stack must not change it
+        assert_eq!(lua.dump_stack().len(), stack.len());
+        for (i, (idx, _, _)) in stack.iter().enumerate() {
+            assert_eq!(*idx as usize, i + 1);
+        }
+        // Arguments are at the top of the callback stack
+        let args = &stack[stack.len() - 4..];
+        let types = args.iter().map(|(_, ty, _)| ty.as_str()).collect::<Vec<_>>();
+        assert_eq!(types, ["number", "string", "table", "nil"]);
+        assert_eq!(args[0].2, Value::Integer(1));
+        assert_eq!(args[1].2.as_string().unwrap(), "abc");
+        Ok(args.len())
+    })?;
+    lua.globals().set("f", f)?;
+    assert_eq!(lua.load("return f(1, 'abc', {}, nil)").eval::<usize>()?, 4);
+
+    Ok(())
+}
+
+#[test]
+fn test_print_handler() -> Result<()> {
+    let lua = Lua::new();
+
+    let original = lua.globals().get::<Function>("print")?;
+    let output = Arc::new(Mutex::new(Vec::new()));
+    let output2 = output.clone();
+    lua.set_print_handler(move |_, line| {
+        output2.lock().unwrap().push(line.to_string());
+        Ok(())
+    })?;
+
+    lua.load(
+        r#"
+        local t = setmetatable({}, {__tostring = function() return "custom" end})
+        print("a", 1, 1.5, true, nil, t)
+        print()
+    "#,
+    )
+    .exec()?;
+    assert_eq!(*output.lock().unwrap(), ["a\t1\t1.5\ttrue\tnil\tcustom", ""]);
+
+    // Errors from the handler are raised in Lua
+    lua.set_print_handler(|_, _| Err(Error::runtime("output is closed")))?;
+    let err = lua.load("print('x')").exec().unwrap_err();
+    assert!(err.to_string().contains("output is closed"), "{err}");
+
+    lua.remove_print_handler()?;
+    assert_eq!(lua.globals().get::<Function>("print")?, original);
+    lua.remove_print_handler()?;
+
+    Ok(())
+}
+
+#[test]
+fn test_capabilities() -> Result<()> {
+    let lua = Lua::new();
+
+    // Loaded before restricting the environment
+    let get_os = lua.load("return function() return os end").eval::<Function>()?;
+
+    let caps = CapabilityTable::new()
+        .grant_global("string")
+        .grant_global("getmetatable")
+        .grant_global("_G")
+        .grant("LIMIT", 10)
+        .grant_function("double", |_, n: i64| Ok(n * 2));
+    lua.set_capabilities(caps)?;
+
+    let result = lua
+        .load("x = double(LIMIT) return string.format('%d', x)")
+        .eval::<StdString>()?;
+    assert_eq!(result, "20");
+    // Scripts can define globals
+    assert_eq!(lua.globals().get::<i64>("x")?, 20);
+
+    for err in [
+        lua.load("return os").exec().unwrap_err(),
+        get_os.call::<()>(()).unwrap_err(),
+    ] {
+        match err {
+            Error::CallbackError { cause, .. } => match cause.as_ref() {
+                Error::RuntimeError(msg) => assert_eq!(msg, "capability 'os' not granted"),
+                cause => panic!("unexpected cause: {cause:?}"),
+            },
+            err => panic!("expected CallbackError, got {err:?}"),
+        }
+    }
+    assert!(lua.load("print('hello')").exec().is_err());
+    // The globals metatable is locked
+    let meta = lua.load("return getmetatable(_G)").eval::<Value>()?;
+    assert_eq!(meta, Value::Boolean(false));
+
+    // Granting a missing global fails without changing the environment
+    let err = lua
+        .set_capabilities(CapabilityTable::new().grant_global("os"))
+        .unwrap_err();
+    assert_eq!(err.to_string(), "runtime error: global 'os' does not exist");
+    assert_eq!(lua.globals().get::<i64>("LIMIT")?, 10);
+
+    Ok(())
+}
+
+#[test]
+fn test_io_handler() -> Result<()> {
+    type Files = Arc<Mutex<HashMap<StdString, Vec<u8>>>>;
+
+    struct MemoryFs(Files);
+
+    struct MemoryFile {
+        files: Files,
+        path: StdString,
+        data: Vec<u8>,
+        pos: usize,
+    }
+
+    impl mlua::Vfs for MemoryFs {
+        fn open(&self, path: &str, mode: &str) -> io::Result<Box<dyn mlua::VfsFile>> {
+            let mut files = self.0.lock().unwrap();
+            if mode.starts_with('w') {
+                files.insert(path.to_string(), Vec::new());
+            }
+            let data = match files.get(path) {
+                Some(data) => data.clone(),
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        "No such file or directory",
+                    ))
+                }
+            };
+            let pos = if mode.starts_with('a') { data.len() } else { 0 };
+            let (files, path) = (self.0.clone(), path.to_string());
+            Ok(Box::new(MemoryFile {
+                files,
+                path,
+                data,
+                pos,
+            }))
+        }
+    }
+
+    impl mlua::VfsFile for MemoryFile {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min(self.data.len() - self.pos);
+            buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+            self.pos += n;
+            Ok(n)
+        }
+
+        fn write(&mut self, buf: &[u8]) -> io::Result<()> {
+            self.data.truncate(self.pos);
+            self.data.extend_from_slice(buf);
+            self.pos = self.data.len();
+            Ok(())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            let mut files = self.files.lock().unwrap();
+            files.insert(self.path.clone(), self.data.clone());
+            Ok(())
+        }
+    }
+
+    let lua = Lua::new();
+    let files = Files::default();
+    files
+        .lock()
+        .unwrap()
+        .insert("data.txt".into(), b"line 1\nline 2\n12345".to_vec());
+    lua.set_io_handler(MemoryFs(files.clone()))?;
+
+    lua.load(
+        r#"
+        local f = assert(io.open("data.txt"))
+        assert(io.type(f) == "file")
+        assert(f:read() == "line 1")
+        assert(f:read("L") == "line 2\n")
+        assert(f:read(3) == "123")
+        assert(f:read("a") == "45")
+        assert(f:read() == nil)
+        assert(f:close() == true)
+        assert(io.type(f) == "closed file")
+        assert(io.type(1) == nil)
+
+        local out = assert(io.open("out.txt", "w"))
+        assert(out:write("a", 1, "\n"):write("b\n") == out)
+        out:close()
+
+        local lines = {}
+        for line in io.lines("out.txt") do
+            table.insert(lines, line)
+        end
+        assert(#lines == 2 and lines[1] == "a1" and lines[2] == "b")
+
+        local ok, msg, errno = io.open("missing.txt")
+        assert(ok == nil and msg == "missing.txt: No such file or directory" and errno == 0)
+        assert(not pcall(io.open, "data.txt", "x"))
+        assert(not pcall(f.read, f))
+    "#,
+    )
+    .exec()?;
+    assert_eq!(files.lock().unwrap()["out.txt"], b"a1\nb\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_clock_source() -> Result<()> {
+    let lua = Lua::new();
+
+    let now = Arc::new(Mutex::new(86400.75));
+    let now2 = now.clone();
+    lua.set_clock_source(move |kind| match kind {
+        ClockKind::Time => *now2.lock().unwrap(),
+        ClockKind::Cpu => 1.5,
+    })?;
+
+    assert_eq!(lua.load("os.time()").eval::<i64>()?, 86400);
+    assert_eq!(lua.load("os.clock()").eval::<f64>()?, 1.5);
+    assert_eq!(
+        lua.load("os.date('!%Y-%m-%d')").eval::<StdString>()?,
+        "1970-01-02"
+    );
+    *now.lock().unwrap() = 2.0 * 86400.0;
+    assert_eq!(lua.load("os.date('!*t').day").eval::<i64>()?, 3);
+
+    // Explicit times are handled by the original functions
+    assert_eq!(lua.load("os.date('!%Y', 0)").eval::<StdString>()?, "1970");
+    let t = lua.load("os.time({year = 2000, month = 1, day = 1, hour = 12})");
+    assert!(t.eval::<i64>()? > 900_000_000);
+
+    // A new source replaces the previous one
+    lua.set_clock_source(|_| 10.0)?;
+    assert_eq!(
+        lua.load("os.time(), os.clock()").eval::<(i64, f64)>()?,
+        (10, 10.0)
+    );
+
+    let lua = Lua::new_with(StdLib::NONE, LuaOptions::default())?;
+    assert!(lua.set_clock_source(|_| 0.0).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_random_source() -> Result<()> {
+    struct SplitMix64(u64);
+
+    impl RandomSource for SplitMix64 {
+        fn next_u64(&mut self) -> u64 {
+            self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+            let mut z = self.0;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+            z ^ (z >> 31)
+        }
+
+        fn set_seed(&mut self, seed: u64) {
+            self.0 = seed;
+        }
+    }
+
+    let sample = r#"
+        local t = {}
+        for i = 1, 100 do
+            local a, b, c = math.random(), math.random(6), math.random(-3, 3)
+            assert(a >= 0 and a < 1 and b >= 1 and b <= 6 and c >= -3 and c <= 3)
+            table.insert(t, b)
+            table.insert(t, c)
+        end
+        return t
+    "#;
+
+    let lua = Lua::new();
+    lua.set_random_source(SplitMix64(42))?;
+    let first = lua.load(sample).eval::<Vec<i64>>()?;
+    lua.set_random_source(SplitMix64(42))?;
+    assert_eq!(lua.load(sample).eval::<Vec<i64>>()?, first);
+
+    // Reseeding from Lua
+    let seeded = lua
+        .load("math.randomseed(7) return math.random(1000000)")
+        .into_function()?;
+    assert_eq!(seeded.call::<i64>(())?, seeded.call::<i64>(())?);
+
+    // Bounds
+    assert_eq!(lua.load("math.random(5, 5)").eval::<i64>()?, 5);
+    let err = lua.load("math.random(2, 1)").exec().unwrap_err();
+    assert!(err.to_string().contains("interval is empty"), "{err}");
+
+    // Closures can be used as sources
+    lua.set_random_source(|| 0)?;
+    assert_eq!(
+        lua.load("math.random(), math.random(10)").eval::<(f64, i64)>()?,
+        (0.0, 1)
+    );
+
+    Ok(())
+}
@@ -1,6 +1,10 @@
+use std::collections::HashSet;
 use std::panic::catch_unwind;
+use std::string::String as StdString;
 
-use mlua::{Error, Function, Lua, Result, Thread, ThreadStatus};
+use mlua::{
+    Error, Function, Lua, MultiValue, Result, ResumeOutcome, Thread, ThreadPool, ThreadStatus, Value,
+};
 
 #[test]
 fn test_thread() -> Result<()> {
@@ -173,6 +177,658 @@ fn test_thread_reset() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_thread_resume_raw() -> Result<()> {
+    let lua = Lua::new();
+
+    // Pass yielded values from a producer to a consumer
+    let producer = lua.create_thread(
+        lua.load(
+            r#"
+            function()
+                local mt = {}
+                for i = 1, 3 do
+                    coroutine.yield(i, setmetatable({}, mt), mt, 4, 5, 6, 7, 8, 9, 10)
+                end
+            end
+        "#,
+        )
+        .eval()?,
+    )?;
+    let consumer = lua.create_thread(
+        lua.load(
+            r#"
+            function(...)
+                local sum = 0
+                local function consume(...)
+                    local n = select('#', ...)
+                    if n == 0 then
+                        return false
+                    end
+                    assert(n == 10)
+                    local v, t, mt = ...
+                    assert(getmetatable(t) == mt)
+                    sum = sum + v
+                    return true
+                end
+                local more = consume(...)
+                while more do
+                    more = consume(coroutine.yield(sum))
+                end
+                return sum
+            end
+        "#,
+        )
+        .eval()?,
+    )?;
+    let result = loop {
+        let values = producer.resume_raw(MultiValue::new())?;
+        if producer.status() != ThreadStatus::Resumable {
+            break consumer.resume_raw(values)?;
+        }
+        assert_eq!(values.len(), 10);
+        consumer.resume_raw(values)?;
+    };
+    assert_eq!(consumer.status(), ThreadStatus::Finished);
+    assert_eq!(result.into_iter().collect::<Vec<_>>(), vec![Value::Integer(6)]);
+
+    // Errors are the same as in `resume`
+    let func = lua
+        .load("function() coroutine.yield() error('boom') end")
+        .eval::<Function>()?;
+    let (thread1, thread2) = (lua.create_thread(func.clone())?, lua.create_thread(func)?);
+    thread1.resume::<()>(())?;
+    thread2.resume_raw(MultiValue::new())?;
+    let err1 = thread1.resume::<MultiValue>(()).unwrap_err();
+    let err2 = thread2.resume_raw(MultiValue::new()).unwrap_err();
+    assert!(err2.to_string().contains("stack traceback:"));
+    assert_eq!(err1.to_string(), err2.to_string());
+    assert!(matches!(
+        thread2.resume_raw(MultiValue::new()),
+        Err(Error::CoroutineUnresumable)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_resume_many() -> Result<()> {
+    let lua = Lua::new();
+
+    let func = lua
+        .load("function(n) while true do n = coroutine.yield(n * 2) end end")
+        .eval::<Function>()?;
+    let workers = (0..3)
+        .map(|_| lua.create_thread(func.clone()))
+        .collect::<Result<Vec<_>>>()?;
+    let failing = lua.create_thread(lua.load("function() error('boom') end").eval()?)?;
+    let finished = lua.create_thread(lua.load("function(a, b) return a + b end").eval()?)?;
+
+    let mut batch = workers
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (t.clone(), MultiValue::from_vec(vec![Value::Integer(i as _)])))
+        .collect::<Vec<_>>();
+    batch.insert(1, (failing.clone(), MultiValue::new()));
+    batch.push((
+        finished,
+        MultiValue::from_vec(vec![Value::Integer(1), Value::Integer(2)]),
+    ));
+
+    let outcomes = lua.resume_many(&batch);
+    assert_eq!(outcomes.len(), 5);
+    // A failure does not stop other threads
+    assert!(outcomes[1].as_ref().unwrap_err().to_string().contains("boom"));
+    assert_eq!(failing.status(), ThreadStatus::Error);
+    for (idx, expected) in [(0, 0), (2, 2), (3, 4)] {
+        match &outcomes[idx] {
+            Ok(ResumeOutcome::Yielded(values)) => assert_eq!(values[0], Value::Integer(expected)),
+            outcome => panic!("unexpected outcome {outcome:?}"),
+        }
+    }
+    match &outcomes[4] {
+        Ok(ResumeOutcome::Finished(values)) => assert_eq!(values[0], Value::Integer(3)),
+        outcome => panic!("unexpected outcome {outcome:?}"),
+    }
+
+    // Threads of another Lua instance are rejected
+    let lua2 = Lua::new();
+    let foreign = lua2.create_thread(lua2.load("function() end").eval()?)?;
+    let outcomes = lua.resume_many(&[(foreign.clone(), MultiValue::new())]);
+    assert!(outcomes[0].is_err());
+    assert_eq!(foreign.status(), ThreadStatus::Resumable);
+
+    Ok(())
+}
+
+#[test]
+fn test_thread_pin() -> Result<()> {
+    let lua = Lua::new();
+
+    let thread = lua.create_thread(lua.load("return coroutine.yield(...) + 1").into_function()?)?;
+    let id = thread.pin()?;
+    drop(thread);
+    lua.gc_collect()?;
+
+    assert_eq!(lua.resume_pinned::<i64>(id, 1)?, 1);
+    assert_eq!(lua.resume_pinned::<i64>(id, 2)?, 3);
+    assert!(lua.resume_pinned::<i64>(id, ()).is_err());
+
+    lua.unpin(id)?;
+    lua.unpin(id)?;
+    match lua.resume_pinned::<()>(id, ()) {
+        Err(Error::RuntimeError(msg)) => assert!(msg.contains("is not pinned")),
+        r => panic!("expected RuntimeError, got {r:?}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_thread_resume_until_done() -> Result<()> {
+    let lua = Lua::new();
+
+    let generator = lua
+        .load(
+            r#"
+            function(n)
+                for i = 1, n do
+                    coroutine.yield(i * 10)
+                end
+                return -1
+            end
+        "#,
+        )
+        .eval::<Function>()?;
+    let thread = lua.create_thread(generator)?;
+    let (yields, last) = thread.resume_until_done::<i64>(5, None)?;
+    assert_eq!(yields, vec![10, 20, 30, 40, 50]);
+    assert_eq!(last, -1);
+    assert_eq!(thread.status(), ThreadStatus::Finished);
+
+    // Infinite generator with a cap
+    let infinite = lua
+        .load("function() local i = 0 while true do i = i + 1 coroutine.yield(i) end end")
+        .eval::<Function>()?;
+    let thread = lua.create_thread(infinite.clone())?;
+    match thread.resume_until_done::<i64>((), Some(10)) {
+        Err(Error::TooManyYields(10)) => {}
+        r => panic!("expected TooManyYields error, got {r:?}"),
+    }
+    assert_eq!(thread.status(), ThreadStatus::Resumable);
+    assert_eq!(thread.resume::<i64>(())?, 12);
+
+    // Budget hook
+    let thread = lua.create_thread(infinite)?;
+    let mut calls = 0;
+    let result = thread.resume_until_done_with::<i64, _>((), None, |n| {
+        calls += 1;
+        if n >= 3 {
+            return Err(Error::runtime("budget exceeded"));
+        }
+        Ok(())
+    });
+    assert!(matches!(result, Err(Error::RuntimeError(msg)) if msg == "budget exceeded"));
+    assert_eq!(calls, 3);
+
+    // Errors are propagated
+    let thread = lua.create_thread(
+        lua.load("function() coroutine.yield(1) error('boom') end")
+            .eval()?,
+    )?;
+    assert!(thread.resume_until_done::<i64>((), None).is_err());
+    assert_eq!(thread.status(), ThreadStatus::Error);
+
+    Ok(())
+}
+
+#[test]
+fn test_coroutine_function() -> Result<()> {
+    let lua = Lua::new();
+
+    let generator = lua
+        .load("function(n) for i = 1, n do coroutine.yield(i) end end")
+        .eval::<Function>()?;
+    let func = lua.create_coroutine_function(generator)?;
+    lua.globals().set("next_value", &func)?;
+
+    // Use as iterator in a generic-for (first call receives the argument)
+    let values: Vec<i64> = lua
+        .load(
+            r#"
+            local values = {}
+            for v in function() return next_value(3) end do
+                table.insert(values, v)
+            end
+            return values
+        "#,
+        )
+        .eval()?;
+    assert_eq!(values, vec![1, 2, 3]);
+
+    // Calling after exhaustion
+    match func.call::<()>(()) {
+        Err(err) => assert!(err.to_string().contains("cannot resume dead coroutine")),
+        r => panic!("expected error, got {r:?}"),
+    }
+    let (ok, msg): (bool, StdString) = lua
+        .load("local ok, err = pcall(next_value); return ok, tostring(err)")
+        .eval()?;
+    assert!(!ok);
+    assert!(msg.contains("cannot resume dead coroutine"));
+
+    // Errors are propagated
+    let failing = lua
+        .load("function() coroutine.yield(1); error('boom') end")
+        .eval::<Function>()?;
+    let func = lua.create_coroutine_function(failing)?;
+    assert_eq!(func.call::<i64>(())?, 1);
+    match func.call::<()>(()) {
+        Err(err) => assert!(err.to_string().contains("boom")),
+        r => panic!("expected error, got {r:?}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_thread_max_resumes() -> Result<()> {
+    let lua = Lua::new();
+
+    let trampoline = lua
+        .load("function() while true do coroutine.yield() end end")
+        .eval::<Function>()?;
+
+    // Resumes from Rust
+    let thread = lua.create_thread(trampoline.clone())?;
+    assert_eq!(thread.resume_count(), 0);
+    thread.set_max_resumes(3)?;
+    for _ in 0..3 {
+        thread.resume::<()>(())?;
+    }
+    assert_eq!(thread.resume_count(), 3);
+    match thread.resume::<()>(()) {
+        Err(Error::ResumeLimitExceeded(3)) => {}
+        r => panic!("expected ResumeLimitExceeded error, got {r:?}"),
+    }
+    assert_eq!(thread.status(), ThreadStatus::Resumable);
+
+    // Setting a new limit resets the counter
+    thread.set_max_resumes(1)?;
+    thread.resume::<()>(())?;
+    assert!(thread.resume::<()>(()).is_err());
+
+    // Resumes from Lua are not counted
+    let thread = lua.create_thread(trampoline)?;
+    thread.set_max_resumes(1)?;
+    let count: i64 = lua
+        .load(
+            r#"
+            local co = ...
+            for i = 1, 5 do
+                assert(coroutine.resume(co))
+            end
+            return 5
+        "#,
+        )
+        .call(&thread)?;
+    assert_eq!(count, 5);
+    assert_eq!(thread.resume_count(), 0);
+    thread.resume::<()>(())?;
+    assert!(matches!(
+        thread.resume::<()>(()),
+        Err(Error::ResumeLimitExceeded(1))
+    ));
+
+    // The `coroutine` library is not modified
+    let (ok, msg) = lua
+        .load(
+            "local co = coroutine.create(function() end); coroutine.resume(co); return coroutine.resume(co)",
+        )
+        .eval::<(bool, StdString)>()?;
+    assert!(!ok);
+    assert!(msg.contains("dead coroutine"));
+
+    Ok(())
+}
+
+#[test]
+fn test_thread_name() -> Result<()> {
+    let lua = Lua::new();
+
+    let thread = lua.create_thread(lua.load("coroutine.yield(); error('boom')").into_function()?)?;
+    assert_eq!(thread.name(), None);
+    thread.set_name("worker")?;
+    assert_eq!(thread.name().as_deref(), Some("worker"));
+    assert!(format!("{thread:?}").contains("\"worker\""));
+
+    thread.resume::<()>(())?;
+    let err = thread.resume::<()>(()).unwrap_err().to_string();
+    assert!(err.contains("boom"));
+    assert!(err.contains("in thread 'worker'\nstack traceback:"), "{err}");
+
+    // Errors from Rust callbacks include the name too, names survive reset
+    let fail = lua.create_function(|_, ()| Err::<(), _>(Error::runtime("rust failure")))?;
+    let thread = lua.create_thread(lua.load("return").into_function()?)?;
+    thread.set_name("job")?;
+    thread.resume::<()>(())?;
+    thread.reset(fail.clone())?;
+    assert_eq!(thread.name().as_deref(), Some("job"));
+    let err = thread.resume::<()>(()).unwrap_err().to_string();
+    assert!(err.contains("rust failure"));
+    assert!(err.contains("in thread 'job'\nstack traceback:"), "{err}");
+
+    // Unnamed threads have no name in traceback
+    let thread = lua.create_thread(fail)?;
+    let err = thread.resume::<()>(()).unwrap_err().to_string();
+    assert!(!err.contains("in thread"));
+
+    Ok(())
+}
+
+#[test]
+fn test_thread_last_error() -> Result<()> {
+    let lua = Lua::new();
+
+    let func: Function = lua
+        .load(r#"function() coroutine.yield() error("boom") end"#)
+        .eval()?;
+    let thread = lua.create_thread(func.clone())?;
+    assert!(thread.last_error().is_none());
+
+    thread.resume::<()>(())?;
+    assert_eq!(thread.status(), ThreadStatus::Resumable);
+    assert!(thread.last_error().is_none());
+
+    let err = thread.resume::<()>(()).unwrap_err();
+    assert_eq!(thread.status(), ThreadStatus::Error);
+    // The error can be retrieved multiple times
+    for _ in 0..2 {
+        let last_error = thread.last_error().expect("expected stored error");
+        assert_eq!(last_error.to_string(), err.to_string());
+        assert!(last_error.to_string().contains("boom"));
+    }
+
+    // Resetting the thread clears the error
+    #[cfg(feature = "lua54")]
+    {
+        // Lua 5.4 returns the original error when closing the failed thread
+        let reset_err = thread.reset(func.clone()).unwrap_err();
+        assert!(reset_err.to_string().contains("boom"), "{reset_err}");
+        assert_eq!(thread.status(), ThreadStatus::Finished);
+        assert!(thread.last_error().is_none());
+        thread.reset(func)?;
+        assert_eq!(thread.status(), ThreadStatus::Resumable);
+        assert!(thread.last_error().is_none());
+    }
+    #[cfg(feature = "luau")]
+    {
+        thread.reset(func)?;
+        assert_eq!(thread.status(), ThreadStatus::Resumable);
+        assert!(thread.last_error().is_none());
+    }
+
+    // Threads failed when resumed from Lua do not keep the error
+    let thread: Thread = lua
+        .load("coroutine.create(function() error('boom') end)")
+        .eval()?;
+    lua.load("coroutine.resume(...)").call::<()>(&thread)?;
+    assert_eq!(thread.status(), ThreadStatus::Error);
+    assert!(thread.last_error().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_thread_stack_values() -> Result<()> {
+    let lua = Lua::new();
+    // Local variable names are available only with full debug information
+    #[cfg(feature = "luau")]
+    lua.set_compiler(mlua::Compiler::new().set_debug_level(2));
+
+    let thread = lua.create_thread(
+        lua.load(
+            r#"
+            function(a)
+                local function inner(x)
+                    local y = "two"
+                    coroutine.yield(x, y)
+                    return x
+                end
+                inner(a)
+                local z = a + 1
+                coroutine.yield(z)
+            end
+            "#,
+        )
+        .eval()?,
+    )?;
+    assert!(thread.stack_values()?.is_empty());
+    assert_eq!(thread.depth(), 0);
+
+    let (x, y) = thread.resume::<(i64, StdString)>(1)?;
+    assert_eq!((x, y.as_str()), (1, "two"));
+    // Read the locals of the suspended function without resuming it
+    let values = thread.stack_values()?;
+    assert_eq!(values.len(), 2);
+    assert_eq!(values[0], Value::Integer(1));
+    assert_eq!(values[1].to_string()?, "two");
+    let inner_depth = thread.depth();
+    assert!(inner_depth > 0);
+
+    assert_eq!(thread.resume::<i64>(())?, 2);
+    let values = thread.stack_values()?;
+    assert_eq!(values[0], Value::Integer(1));
+    assert_eq!(values.last(), Some(&Value::Integer(2)));
+    assert!(thread.depth() < inner_depth);
+
+    thread.resume::<()>(())?;
+    assert_eq!(thread.status(), ThreadStatus::Finished);
+    assert!(thread.stack_values()?.is_empty());
+    assert_eq!(thread.depth(), 0);
+
+    // Running threads cannot be inspected
+    let thread = lua.create_thread(lua.create_function(|lua, ()| {
+        lua.current_thread().stack_values()?;
+        Ok(())
+    })?)?;
+    match thread.resume::<()>(()) {
+        Err(Error::CallbackError { ref cause, .. }) => {
+            assert!(cause
+                .to_string()
+                .contains("cannot inspect stack of a running thread"))
+        }
+        r => panic!("expected CallbackError, got {r:?}"),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_thread_close() -> Result<()> {
+    let lua = Lua::new();
+
+    // New and finished threads can always be closed
+    let thread = lua.create_thread(lua.load("return 1").into_function()?)?;
+    thread.close()?;
+    assert_eq!(thread.status(), ThreadStatus::Finished);
+    assert!(matches!(
+        thread.resume::<()>(()),
+        Err(Error::CoroutineUnresumable)
+    ));
+    thread.close()?;
+
+    // Try to close running thread
+    let thread = lua.create_thread(lua.create_function(|lua, ()| lua.current_thread().close())?)?;
+    let result = thread.resume::<()>(());
+    assert!(
+        matches!(result, Err(Error::CallbackError{ ref cause, ..})
+            if matches!(cause.as_ref(), Error::RuntimeError(err)
+                if err == "cannot close a running thread")
+        ),
+        "unexpected result: {result:?}",
+    );
+
+    // Close yielded thread
+    let thread: Thread = lua
+        .load(
+            r#"
+            coroutine.create(function()
+                local t = {}
+                for i = 1, 100000 do t[i] = tostring(i) end
+                coroutine.yield(#t)
+                return #t
+            end)
+        "#,
+        )
+        .eval()?;
+    assert_eq!(thread.resume::<i64>(())?, 100000);
+    assert_eq!(thread.status(), ThreadStatus::Resumable);
+    lua.gc_collect()?;
+    let used_memory = lua.used_memory();
+
+    #[cfg(any(feature = "lua54", feature = "luau"))]
+    {
+        thread.close()?;
+        assert_eq!(thread.status(), ThreadStatus::Finished);
+        lua.gc_collect()?;
+        assert!(lua.used_memory() < used_memory / 2);
+        assert!(matches!(
+            thread.resume::<i64>(()),
+            Err(Error::CoroutineUnresumable)
+        ));
+    }
+    #[cfg(not(any(feature = "lua54", feature = "luau")))]
+    {
+        assert!(thread.close().is_err());
+        assert_eq!(thread.status(), ThreadStatus::Resumable);
+        let _ = used_memory;
+    }
+
+    // Close errored thread
+    let thread = lua.create_thread(lua.load("error('boom')").into_function()?)?;
+    assert!(thread.resume::<()>(()).is_err());
+    #[cfg(any(feature = "lua54", feature = "luau"))]
+    {
+        // The original error is not returned again
+        thread.close()?;
+        assert_eq!(thread.status(), ThreadStatus::Finished);
+    }
+    #[cfg(not(any(feature = "lua54", feature = "luau")))]
+    assert!(thread.close().is_err());
+
+    // Errors from closing methods are returned
+    #[cfg(feature = "lua54")]
+    {
+        let thread: Thread = lua
+            .load(
+                r#"
+                coroutine.create(function()
+                    local x <close> = setmetatable({}, {__close = function() error("close failed") end})
+                    error("boom")
+                end)
+            "#,
+            )
+            .eval()?;
+        assert!(thread.resume::<()>(()).is_err());
+        let err = thread.close().unwrap_err();
+        assert!(err.to_string().contains("close failed"), "{err}");
+        assert_eq!(thread.status(), ThreadStatus::Finished);
+
+        let thread: Thread = lua
+            .load(
+                r#"
+                coroutine.create(function()
+                    local x <close> = setmetatable({}, {__close = function() error("close failed") end})
+                    coroutine.yield()
+                end)
+            "#,
+            )
+            .eval()?;
+        thread.resume::<()>(())?;
+        let err = thread.close().unwrap_err();
+        assert!(err.to_string().contains("close failed"), "{err}");
+        assert_eq!(thread.status(), ThreadStatus::Finished);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_thread_pool() -> Result<()> {
+    let lua = Lua::new();
+
+    let pool = ThreadPool::new(&lua, 8);
+    assert_eq!(pool.max_size(), 8);
+    let add: Function = lua
+        .load("function(a, b) coroutine.yield(a) return a + b end")
+        .eval()?;
+
+    // Run 1000 short jobs, up to 8 at a time
+    let mut pointers = HashSet::new();
+    for batch in 0..125 {
+        let threads = (0..8)
+            .map(|i| pool.spawn(&add, batch * 8 + i))
+            .collect::<Result<Vec<_>>>()?;
+        for (i, thread) in threads.iter().enumerate() {
+            let n = batch * 8 + i as i64;
+            pointers.insert(thread.to_pointer());
+            assert_eq!(thread.resume::<i64>(1)?, n);
+            assert_eq!(thread.resume::<i64>(())?, n + 1);
+            assert_eq!(thread.status(), ThreadStatus::Finished);
+        }
+    }
+    assert!(pointers.len() <= 8);
+    assert_eq!(pool.idle_count(), 8);
+
+    // Suspended threads are not returned to the pool
+    let thread = pool.spawn(&add, 1)?;
+    thread.resume::<()>(2)?;
+    drop(thread);
+    assert_eq!(pool.idle_count(), 7);
+
+    pool.trim();
+    assert_eq!(pool.idle_count(), 0);
+
+    // Errored threads are discarded by default
+    let fail: Function = lua.load("function() error('boom') end").eval()?;
+    let thread = pool.spawn(&fail, ())?;
+    assert!(thread.resume::<()>(()).is_err());
+    drop(thread);
+    assert_eq!(pool.idle_count(), 0);
+
+    #[cfg(any(feature = "lua54", feature = "luau"))]
+    {
+        let pool = ThreadPool::new(&lua, 8).reuse_errored(true);
+        let thread = pool.spawn(&fail, ())?;
+        assert!(thread.resume::<()>(()).is_err());
+        drop(thread);
+        assert_eq!(pool.idle_count(), 1);
+        let thread = pool.spawn(&add, (1, 2))?;
+        assert_eq!(thread.resume::<i64>(())?, 1);
+        assert_eq!(thread.resume::<i64>(())?, 3);
+    }
+
+    // Sandboxed threads do not share globals between jobs
+    #[cfg(feature = "luau")]
+    {
+        let pool = ThreadPool::new(&lua, 1).sandbox(true);
+        let job = lua.create_function(|lua, v: i64| {
+            let old = lua.globals().get::<Option<i64>>("value")?;
+            lua.globals().set("value", v)?;
+            Ok(old)
+        })?;
+        let thread = pool.spawn(&job, 1)?;
+        assert_eq!(thread.resume::<Option<i64>>(())?, None);
+        drop(thread);
+        let thread = pool.spawn(&job, 2)?;
+        assert_eq!(thread.resume::<Option<i64>>(())?, None);
+        assert_eq!(lua.globals().get::<Option<i64>>("value")?, None);
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_coroutine_from_closure() -> Result<()> {
     let lua = Lua::new();
@@ -250,6 +906,21 @@ fn test_thread_resume_error() -> Result<()> {
     let status = thread.resume_error::<String>("myerror").unwrap();
     assert_eq!(status, "success");
 
+    // Unhandled error is returned to Rust
+    let thread = lua
+        .load(r#"coroutine.create(function() coroutine.yield() end)"#)
+        .eval::<Thread>()?;
+    thread.resume::<()>(())?;
+    match thread.resume_error::<()>("cancelled") {
+        Err(Error::RuntimeError(err)) => assert!(err.contains("cancelled"), "unexpected error: {err}"),
+        r => panic!("expected RuntimeError, got {r:?}"),
+    }
+    assert_eq!(thread.status(), ThreadStatus::Error);
+    assert!(matches!(
+        thread.resume_error::<()>("again"),
+        Err(Error::CoroutineUnresumable)
+    ));
+
     Ok(())
 }
 
@@ -209,6 +209,59 @@ fn test_metamethods() -> Result<()> {
     Ok(())
 }
 
+#[cfg(feature = "luau")]
+#[test]
+fn test_metamethod_iter() -> Result<()> {
+    struct Inventory(Vec<(&'static str, i64)>);
+
+    impl UserData for Inventory {
+        fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+            methods.add_meta_function(MetaMethod::Iter, |lua, this: AnyUserData| {
+                let next = lua.create_function(|_, (this, i): (UserDataRef<Inventory>, usize)| {
+                    let item = this.0.get(i).copied();
+                    Ok((
+                        item.map(|_| i + 1),
+                        item.map(|(name, _)| name),
+                        item.map(|(_, n)| n),
+                    ))
+                })?;
+                Ok((next, this, 0))
+            });
+        }
+    }
+
+    let lua = Lua::new();
+    let inventory = Inventory(vec![("apple", 3), ("pear", 5), ("plum", 0)]);
+    lua.globals().set("inventory", inventory)?;
+
+    let items = lua
+        .load(
+            r#"
+        local items = {}
+        for i, name, count in inventory do
+            table.insert(items, `{i}:{name}={count}`)
+        end
+        return table.concat(items, ",")
+    "#,
+        )
+        .eval::<StdString>()?;
+    assert_eq!(items, "1:apple=3,2:pear=5,3:plum=0");
+
+    // Breaking out of the loop works as usual
+    let first = lua
+        .load("for _, name in inventory do return name end")
+        .eval::<StdString>()?;
+    assert_eq!(first, "apple");
+
+    // Iterating over userdata without `__iter` is an error
+    struct Opaque;
+    impl UserData for Opaque {}
+    lua.globals().set("opaque", Opaque)?;
+    assert!(lua.load("for _ in opaque do end").exec().is_err());
+
+    Ok(())
+}
+
 #[cfg(feature = "lua54")]
 #[test]
 fn test_metamethod_close() -> Result<()> {
@@ -589,6 +642,110 @@ fn test_fields() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_userdata_type_with_name() -> Result<()> {
+    struct PlayerData;
+    impl UserData for PlayerData {}
+
+    struct Custom;
+    impl UserData for Custom {
+        fn add_fields<F: UserDataFields<Self>>(fields: &mut F) {
+            fields.add_meta_field(MetaMethod::Type, "CustomType");
+        }
+    }
+
+    let lua = Lua::new();
+    lua.register_userdata_type_with_name::<PlayerData>("Player")?;
+    lua.globals().set("player", PlayerData)?;
+
+    let ud = lua.globals().get::<AnyUserData>("player")?;
+    assert_eq!(ud.metatable()?.get::<StdString>(MetaMethod::Type)?, "Player");
+    #[cfg(any(feature = "lua54", feature = "lua53", feature = "luau"))]
+    lua.load(r#"assert(tostring(player):sub(1, 7) == "Player:")"#)
+        .exec()?;
+    #[cfg(feature = "luau")]
+    lua.load(r#"assert(typeof(player) == "Player")"#).exec()?;
+
+    // Error messages use the custom name
+    let take = lua.create_function(|_, _: UserDataRef<PlayerData>| Ok(()))?;
+    let err = take.call::<()>(123).unwrap_err().to_string();
+    assert!(err.contains("expected userdata of type 'Player'"), "{err}");
+    let take = lua.create_function_spec("take", |spec| {
+        spec.arg::<UserDataRef<PlayerData>>("player").build(|_, _| Ok(()))
+    })?;
+    let err = take.call::<()>(true).unwrap_err().to_string();
+    assert!(err.contains("(Player expected, got boolean)"), "{err}");
+
+    // Renaming updates the existing metatable
+    lua.register_userdata_type_with_name::<PlayerData>("Hero")?;
+    assert_eq!(ud.metatable()?.get::<StdString>(MetaMethod::Type)?, "Hero");
+
+    // Name provided by the type itself is kept
+    let ud = lua.create_userdata(Custom)?;
+    lua.register_userdata_type_with_name::<Custom>("Other")?;
+    assert_eq!(ud.metatable()?.get::<StdString>(MetaMethod::Type)?, "CustomType");
+
+    // Types with the same Rust name are named independently
+    mod a {
+        pub struct Data;
+        impl mlua::UserData for Data {}
+    }
+    mod b {
+        pub struct Data;
+        impl mlua::UserData for Data {}
+    }
+    lua.register_userdata_type_with_name::<a::Data>("AData")?;
+    let b_data = lua.create_userdata(b::Data)?;
+    assert_eq!(b_data.metatable()?.get::<StdString>(MetaMethod::Type)?, "Data");
+    let take = lua.create_function(|_, _: UserDataRef<b::Data>| Ok(()))?;
+    let err = take.call::<()>(123).unwrap_err().to_string();
+    assert!(err.contains("expected userdata of type 'Data'"), "{err}");
+
+    Ok(())
+}
+
+#[test]
+fn test_userdata_limit() -> Result<()> {
+    struct Handle;
+    impl UserData for Handle {}
+
+    let lua = Lua::new();
+    lua.globals()
+        .set("Handle", lua.create_function(|_, ()| Ok(Handle))?)?;
+    assert_eq!(lua.userdata_count::<Handle>(), 0);
+
+    lua.set_userdata_limit::<Handle>(2);
+    let h1 = lua.create_userdata(Handle)?;
+    lua.load("h2 = Handle()").exec()?;
+    assert_eq!(lua.userdata_count::<Handle>(), 2);
+
+    match lua.create_userdata(Handle) {
+        Err(Error::RuntimeError(msg)) => assert_eq!(msg, "userdata limit exceeded for type 'Handle'"),
+        r => panic!("expected RuntimeError, got {r:?}"),
+    }
+    let err = lua.load("Handle()").exec().unwrap_err();
+    assert!(err.to_string().contains("userdata limit exceeded"), "{err}");
+
+    // Other types are not affected
+    lua.create_any_userdata(0i32)?;
+
+    // Collected instances free their slots (Lua 5.1 needs up to three cycles)
+    drop(h1);
+    lua.globals().set("h2", Nil)?;
+    lua.gc_collect()?;
+    lua.gc_collect()?;
+    lua.gc_collect()?;
+    assert_eq!(lua.userdata_count::<Handle>(), 0);
+    lua.create_userdata(Handle)?;
+    assert_eq!(lua.userdata_count::<Handle>(), 1);
+
+    // Raising the limit keeps the count
+    lua.set_userdata_limit::<Handle>(3);
+    assert_eq!(lua.userdata_count::<Handle>(), 1);
+
+    Ok(())
+}
+
 #[test]
 fn test_metatable() -> Result<()> {
     #[derive(Copy, Clone)]
@@ -1298,3 +1455,190 @@ fn test_userdata_wrappers() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_trait_object_userdata() -> Result<()> {
+    // Plugins are exposed to Lua as `Box<dyn Plugin>`, which is a single concrete type
+    trait Plugin: Send {
+        fn name(&self) -> StdString;
+        fn run(&mut self, input: i64) -> i64;
+    }
+
+    struct Doubler;
+    struct Counter(i64);
+
+    impl Plugin for Doubler {
+        fn name(&self) -> StdString {
+            "doubler".into()
+        }
+        fn run(&mut self, input: i64) -> i64 {
+            input * 2
+        }
+    }
+
+    impl Plugin for Counter {
+        fn name(&self) -> StdString {
+            format!("counter({})", self.0)
+        }
+        fn run(&mut self, input: i64) -> i64 {
+            self.0 += input;
+            self.0
+        }
+    }
+
+    impl UserData for Box<dyn Plugin> {
+        fn add_methods<M: UserDataMethods<Self>>(methods: &mut M) {
+            methods.add_method("name", |_, this, ()| Ok(this.name()));
+            methods.add_method_mut("run", |_, this, input: i64| Ok(this.run(input)));
+        }
+    }
+
+    let lua = Lua::new();
+    let plugins: Vec<Box<dyn Plugin>> = vec![Box::new(Doubler), Box::new(Counter(10))];
+    let plugins = (plugins.into_iter())
+        .map(|plugin| lua.create_userdata(plugin))
+        .collect::<Result<Vec<_>>>()?;
+    lua.globals().set("plugins", plugins)?;
+    lua.globals().set(
+        "plugin_name",
+        lua.create_function(|_, plugin: UserDataRef<Box<dyn Plugin>>| Ok(plugin.name()))?,
+    )?;
+
+    lua.load(
+        r#"
+        assert(plugins[1]:name() == "doubler")
+        assert(plugins[1]:run(21) == 42)
+        assert(plugins[2]:run(5) == 15)
+        assert(plugin_name(plugins[2]) == "counter(15)")
+    "#,
+    )
+    .exec()?;
+
+    // The same works without implementing `UserData`, by registering the trait object type
+    trait Greeter: Send {
+        fn greet(&self) -> StdString;
+    }
+
+    struct English;
+    impl Greeter for English {
+        fn greet(&self) -> StdString {
+            "hello".into()
+        }
+    }
+
+    lua.register_userdata_type::<Box<dyn Greeter>>(|reg| {
+        reg.add_method("greet", |_, this, ()| Ok(this.greet()));
+    })?;
+    let greeter: Box<dyn Greeter> = Box::new(English);
+    let greeter = lua.create_any_userdata(greeter)?;
+    assert_eq!(greeter.call_method::<StdString>("greet", ())?, "hello");
+    assert_eq!(greeter.borrow::<Box<dyn Greeter>>()?.greet(), "hello");
+
+    Ok(())
+}
+
+#[cfg(feature = "checked-int")]
+#[test]
+fn test_checked_int() -> Result<()> {
+    use mlua::CheckedInt;
+
+    let lua = Lua::new();
+    lua.globals().set("int", CheckedInt::create_constructor(&lua)?)?;
+
+    lua.load(
+        r#"
+        local a, b = int(7), int(-2)
+        assert(a + b == int(5) and a - b == int(9) and a * b == int(-14))
+        assert(a + 1 == int(8) and 10 - a == int(3))
+        assert(a / b == int(-4) and a % b == int(-1))
+        assert(-a == int(-7) and int(2) ^ 10 == int(1024))
+        assert(b < a and a <= a and not (a < b))
+        assert(tostring(a * b) == "-14")
+    "#,
+    )
+    .exec()?;
+
+    let err = lua.load("int(2^52) * int(2^52)").exec().unwrap_err();
+    assert!(err.to_string().contains("integer overflow"), "{err}");
+    let err = lua.load("int(1) / 0").exec().unwrap_err();
+    assert!(err.to_string().contains("attempt to perform '1 / 0'"), "{err}");
+    let err = lua.load("int(2) ^ -1").exec().unwrap_err();
+    assert!(err.to_string().contains("negative exponent"), "{err}");
+    assert!(lua.load("int(1.5)").exec().is_err());
+
+    // `math.mininteger % -1` is zero, while the division overflows
+    let min = CheckedInt(i64::MIN);
+    let rem: CheckedInt = lua.load("local n = ...; return n % int(-1)").call(min)?;
+    assert_eq!(i64::from(rem), 0);
+    let err = lua
+        .load("local n = ...; return n / int(-1)")
+        .call::<CheckedInt>(min)
+        .unwrap_err();
+    assert!(err.to_string().contains("integer overflow"), "{err}");
+
+    // Conversion to and from Rust
+    let max: CheckedInt = lua.load("return ...").call(CheckedInt(i64::MAX))?;
+    assert_eq!(i64::from(max), i64::MAX);
+    let err = lua
+        .load("local n = ...; return n + 1")
+        .call::<CheckedInt>(max)
+        .unwrap_err();
+    assert!(err.to_string().contains("integer overflow"), "{err}");
+    assert_eq!(lua.load("42").eval::<CheckedInt>()?, CheckedInt(42));
+
+    Ok(())
+}
+
+#[test]
+fn test_userdata_finalization_priority() -> Result<()> {
+    use std::sync::Mutex;
+
+    struct Resource(&'static str, Arc<Mutex<Vec<&'static str>>>);
+
+    impl Drop for Resource {
+        fn drop(&mut self) {
+            self.1.lock().unwrap().push(self.0);
+        }
+    }
+
+    let lua = Lua::new();
+    let order = Arc::new(Mutex::new(Vec::new()));
+
+    let conn = lua.create_any_userdata(Resource("conn", order.clone()))?;
+    conn.set_finalization_priority(10);
+    let stmt1 = lua.create_any_userdata(Resource("stmt1", order.clone()))?;
+    stmt1.set_finalization_priority(1);
+    let stmt2 = lua.create_any_userdata(Resource("stmt2", order.clone()))?;
+    stmt2.set_finalization_priority(1);
+    drop((stmt2, conn, stmt1));
+    lua.gc_collect()?;
+    lua.gc_collect()?;
+    let finalized = order.lock().unwrap().clone();
+    assert_eq!(finalized.len(), 3);
+    assert_eq!(finalized[2], "conn");
+
+    // Values collected outside of `gc_collect` are dropped when returning to Rust
+    order.lock().unwrap().clear();
+    let ud = lua.create_any_userdata(Resource("auto", order.clone()))?;
+    ud.set_finalization_priority(1);
+    drop(ud);
+    lua.load("collectgarbage('collect')").exec()?;
+    assert_eq!(*order.lock().unwrap(), vec!["auto"]);
+
+    // Explicitly destroyed userdata is dropped immediately
+    order.lock().unwrap().clear();
+    let ud = lua.create_any_userdata(Resource("explicit", order.clone()))?;
+    ud.set_finalization_priority(5);
+    ud.destroy()?;
+    assert_eq!(*order.lock().unwrap(), vec!["explicit"]);
+
+    // Deferred values are dropped with the Lua state
+    order.lock().unwrap().clear();
+    let ud = lua.create_any_userdata(Resource("last", order.clone()))?;
+    ud.set_finalization_priority(1);
+    drop(ud);
+    drop(lua);
+    assert_eq!(*order.lock().unwrap(), vec!["last"]);
+
+    Ok(())
+}
@@ -286,6 +286,15 @@ fn test_value_conversions() -> Result<()> {
         Some(&"hello")
     );
 
+    #[cfg(feature = "luau")]
+    {
+        let v = mlua::Vector::zero();
+        assert!(Value::Vector(v).is_vector());
+        assert_eq!(Value::Vector(v).as_vector(), Some(v));
+        assert!(!Value::Nil.is_vector());
+        assert_eq!(Value::Integer(1).as_vector(), None);
+    }
+
     assert!(Value::Error(Box::new(Error::runtime("some error"))).is_error());
     assert_eq!(
         (Value::Error(Box::new(Error::runtime("some error"))).as_error())
@@ -297,6 +306,35 @@ fn test_value_conversions() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_value_detach() -> Result<()> {
+    let lua = Lua::new();
+
+    let table = lua.create_table_from([("a", 1)])?;
+    let owned = Value::Table(table.clone()).detach(&lua)?;
+    drop(table);
+    lua.gc_collect()?;
+
+    // The detached value keeps the object alive
+    let value = owned.attach(&lua)?;
+    assert_eq!(value.as_table().unwrap().get::<i32>("a")?, 1);
+    assert_eq!(value, owned.attach(&lua)?);
+
+    // Primitive values
+    assert_eq!(Value::Nil.detach(&lua)?.attach(&lua)?, Value::Nil);
+    assert_eq!(Value::Integer(5).detach(&lua)?.attach(&lua)?, Value::Integer(5));
+
+    // Can be sent to other threads
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+    assert_send_sync(&owned);
+
+    // Cannot be attached to another Lua state
+    let lua2 = Lua::new();
+    assert!(matches!(owned.attach(&lua2), Err(Error::MismatchedRegistryKey)));
+
+    Ok(())
+}
+
 #[test]
 fn test_value_exhaustive_match() {
     match Value::Nil {